@@ -0,0 +1,33 @@
+use indexer_base::chunks::ChunkIndex;
+use neon::prelude::*;
+use std::path::PathBuf;
+
+/// Looks up the byte range of rows in the chunk index of an indexed file
+/// Returns `[start, end]` of the chunks that contain the rows or `null`
+/// if the rows are not in the index
+///
+/// # Arguments
+///
+/// * `file` - path of the indexed file
+/// * `first_row` - first row of the range
+/// * `last_row` - last row of the range (inclusive)
+pub fn byte_range_for_rows(mut cx: FunctionContext) -> JsResult<JsValue> {
+    let file = PathBuf::from(cx.argument::<JsString>(0)?.value());
+    let first_row = cx.argument::<JsNumber>(1)?.value() as usize;
+    let last_row = cx.argument::<JsNumber>(2)?.value() as usize;
+    let index = match ChunkIndex::load(&file) {
+        Ok(index) => index,
+        Err(e) => return cx.throw_error(format!("could not load chunk index: {}", e)),
+    };
+    match index.byte_range(first_row, last_row) {
+        Some((start, end)) => {
+            let range = JsArray::new(&mut cx, 2);
+            let start = cx.number(start as f64);
+            let end = cx.number(end as f64);
+            range.set(&mut cx, 0, start)?;
+            range.set(&mut cx, 1, end)?;
+            Ok(range.upcast())
+        }
+        None => Ok(cx.null().upcast()),
+    }
+}
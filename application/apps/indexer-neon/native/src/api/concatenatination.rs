@@ -4,7 +4,7 @@ use indexer_base::{
     chunks::ChunkResults,
     progress::{IndexingProgress, Notification, Severity},
 };
-use merging::concatenator::{concat_files, ConcatenatorInput};
+use merging::concatenator::{concat_files, ConcatOutput, ConcatenatorInput};
 use neon::prelude::*;
 use std::{path, thread};
 
@@ -56,6 +56,7 @@ fn concatenate_with_progress(
         &out_path,
         append,
         chunk_size,
+        ConcatOutput::Text,
         update_channel.clone(),
         shutdown_receiver,
     ) {
@@ -72,7 +73,7 @@ fn concatenate_with_progress(
             }
             let _ = update_channel.send(Ok(IndexingProgress::Stopped));
         }
-        Ok(()) => trace!("concatenation done"),
+        Ok(result) => trace!("concatenation done: {:?}", result),
     }
 }
 // interface of the Rust code for js, exposes the `poll` and `shutdown` methods
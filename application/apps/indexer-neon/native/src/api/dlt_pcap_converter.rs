@@ -1,5 +1,8 @@
 use crate::channels::EventEmitterTask;
-use indexer_base::chunks::VoidResults;
+use indexer_base::{
+    chunks::VoidResults,
+    redaction::{self, Redactor},
+};
 
 use crossbeam_channel as cc;
 use neon::prelude::*;
@@ -19,6 +22,7 @@ impl PcapDltConverterEventEmitter {
         chunk_result_sender: cc::Sender<VoidResults>,
         pcap_file_path: path::PathBuf,
         out_file_path: path::PathBuf,
+        mut redactor: Option<Redactor>,
     ) {
         info!("start_converting_to_dlt_in_thread");
         let dlt_filter_config = None;
@@ -37,12 +41,21 @@ impl PcapDltConverterEventEmitter {
                     chunk_result_sender,
                     shutdown_rx,
                     None,
+                    redactor.as_mut(),
                 )
                 .await
                 {
                     Ok(_) => info!("Conversion was ok"),
                     Err(e) => warn!("Conversion error: {}", e),
                 }
+                if let Some(redactor) = redactor {
+                    if let Err(e) = redactor
+                        .report()
+                        .write_to(&redaction::report_path(&out_file_path))
+                    {
+                        warn!("could not write redaction report: {}", e);
+                    }
+                }
                 debug!("Back after DLT pcap indexing finished!");
             });
         }));
@@ -57,6 +70,16 @@ declare_types! {
 
             let file_path = path::PathBuf::from(cx.argument::<JsString>(0)?.value().as_str());
             let out_path = path::PathBuf::from(cx.argument::<JsString>(1)?.value().as_str());
+            let redactor = match cx.argument_opt(2) {
+                Some(arg) if arg.is_a::<JsString>() => {
+                    let config = arg.downcast::<JsString>().or_throw(&mut cx)?.value();
+                    match Redactor::from_file(path::Path::new(&config)) {
+                        Ok(redactor) => Some(redactor),
+                        Err(e) => return cx.throw_error(format!("{}", e)),
+                    }
+                }
+                _ => None,
+            };
 
             let (shutdown_tx, shutdown_rx) = sync::mpsc::channel(1);
             let (tx, rx): (cc::Sender<VoidResults>, cc::Receiver<VoidResults>) = cc::unbounded();
@@ -71,6 +94,7 @@ declare_types! {
                 tx,
                 file_path,
                 out_path,
+                redactor,
             );
             Ok(emitter)
         }
@@ -1,8 +1,20 @@
 use crate::channels::EventEmitterTask;
 use anyhow::{Error, *};
 use crossbeam_channel as cc;
-use indexer_base::{chunks::ChunkResults, config::SectionConfig, export::export_file_line_based};
+use dlt::fibex_cache::gather_fibex_data_cached;
+use dlt_core::fibex::FibexConfig;
+use indexer_base::{
+    chunks::ChunkResults,
+    config::SectionConfig,
+    export::export_file_line_based,
+    redaction::{self, Redactor},
+};
 use neon::{context::Context, prelude::*};
+use processor::{
+    dlt_source::message_slots,
+    export::{export_rows, read_search_result, ExportFormat},
+    grabber::read_metadata,
+};
 use std::{path, thread};
 use tokio::sync;
 
@@ -24,6 +36,8 @@ impl ExporterEventEmitter {
         destination_path: path::PathBuf,
         sections_config: SectionConfig,
         was_session_file: bool,
+        metadata: Option<path::PathBuf>,
+        redaction_config: Option<path::PathBuf>,
         // TODO react on shutdown event
         _shutdown_rx: sync::mpsc::Receiver<()>,
         chunk_result_sender: cc::Sender<ChunkResults>,
@@ -33,6 +47,12 @@ impl ExporterEventEmitter {
             source_type, sections_config
         );
 
+        let mut redactor = match redaction_config {
+            Some(config) => Some(Redactor::from_file(&config)?),
+            None => None,
+        };
+        let report_path = redaction::report_path(&destination_path);
+
         // Spawn a thread to continue running after this method has returned.
         if source_type == DLT_SESSION_ID {
             let session_id = source;
@@ -54,13 +74,25 @@ impl ExporterEventEmitter {
             let dlt_file_path = path::PathBuf::from(source);
 
             self.task_thread = Some(thread::spawn(move || {
-                match dlt::dlt_file::export_as_dlt_file(
+                let slots = match metadata
+                    .map(|path| read_metadata(path).and_then(|metadata| message_slots(&metadata)))
+                {
+                    Some(Ok(slots)) => slots,
+                    Some(Err(e)) => {
+                        warn!("could not use metadata for export: {}", e);
+                        vec![]
+                    }
+                    None => vec![],
+                };
+                match dlt::dlt_file::export_as_dlt_file_with_slots(
                     dlt_file_path,
                     destination_path,
                     sections_config,
+                    slots,
+                    redactor.as_mut(),
                     chunk_result_sender,
                 ) {
-                    Ok(_) => {}
+                    Ok(_) => write_redaction_report(redactor, &report_path),
                     Err(e) => warn!("error exporting dlt messages: {}", e),
                 }
                 debug!("back after DLT export finished!");
@@ -75,9 +107,10 @@ impl ExporterEventEmitter {
                     destination_path,
                     sections_config,
                     was_session_file,
+                    redactor.as_mut(),
                     chunk_result_sender,
                 ) {
-                    Ok(_) => {}
+                    Ok(_) => write_redaction_report(redactor, &report_path),
                     Err(e) => warn!("error exporting lines: {}", e),
                 }
                 debug!("back after line export finished!");
@@ -89,6 +122,63 @@ impl ExporterEventEmitter {
     }
 }
 
+fn write_redaction_report(redactor: Option<Redactor>, report_path: &path::Path) {
+    if let Some(redactor) = redactor {
+        if let Err(e) = redactor.report().write_to(report_path) {
+            warn!("could not write redaction report: {}", e);
+        }
+    }
+}
+
+pub struct SearchExporterEventEmitter {
+    pub event_receiver: cc::Receiver<ChunkResults>,
+    pub shutdown_sender: sync::mpsc::Sender<()>,
+    pub task_thread: Option<std::thread::JoinHandle<()>>,
+}
+impl SearchExporterEventEmitter {
+    /// export the rows of a search result file (as written by the search)
+    #[allow(clippy::too_many_arguments)]
+    pub fn start_exporting_search_result_in_thread(
+        &mut self,
+        source: path::PathBuf,
+        destination_path: path::PathBuf,
+        search_result: path::PathBuf,
+        context: usize,
+        format: ExportFormat,
+        fibex: Option<String>,
+        chunk_result_sender: cc::Sender<ChunkResults>,
+    ) {
+        info!(
+            "start_exporting_search_result_in_thread: {:?} ({:?})",
+            search_result, format
+        );
+        self.task_thread = Some(thread::spawn(move || {
+            let fibex_metadata = fibex.and_then(|fibex| {
+                gather_fibex_data_cached(
+                    FibexConfig {
+                        fibex_file_paths: vec![fibex],
+                    },
+                    &chunk_result_sender,
+                )
+            });
+            match read_search_result(&search_result).and_then(|rows| {
+                export_rows(
+                    &source,
+                    &destination_path,
+                    &rows,
+                    context,
+                    format,
+                    fibex_metadata.as_ref(),
+                    chunk_result_sender,
+                )
+            }) {
+                Ok(exported) => debug!("exported {} rows of search result", exported),
+                Err(e) => warn!("error exporting search result: {}", e),
+            }
+        }));
+    }
+}
+
 // interface of the Rust code for js, exposes the `poll` and `shutdown` methods
 declare_types! {
     pub class JsExporterEventEmitter for ExporterEventEmitter {
@@ -105,6 +195,20 @@ declare_types! {
             let sections_conf: SectionConfig = neon_serde::from_value(&mut cx, arg_sections_conf)?;
             i += 1;
             let was_session_file = cx.argument::<JsBoolean>(i)?.value();
+            i += 1;
+            let metadata = match cx.argument_opt(i) {
+                Some(arg) if arg.is_a::<JsString>() => Some(path::PathBuf::from(
+                    arg.downcast::<JsString>().or_throw(&mut cx)?.value(),
+                )),
+                _ => None,
+            };
+            i += 1;
+            let redaction_config = match cx.argument_opt(i) {
+                Some(arg) if arg.is_a::<JsString>() => Some(path::PathBuf::from(
+                    arg.downcast::<JsString>().or_throw(&mut cx)?.value(),
+                )),
+                _ => None,
+            };
 
             let shutdown_channel = sync::mpsc::channel(1);
             let (tx, rx): (cc::Sender<ChunkResults>, cc::Receiver<ChunkResults>) = cc::unbounded();
@@ -120,6 +224,8 @@ declare_types! {
                 destination_path,
                 sections_conf,
                 was_session_file,
+                metadata,
+                redaction_config,
                 shutdown_channel.1,
                 tx,
             ) {
@@ -165,3 +271,60 @@ declare_types! {
         }
     }
 }
+
+// interface of the Rust code for js, exposes the `poll` and `shutdown` methods
+declare_types! {
+    pub class JsSearchExporterEventEmitter for SearchExporterEventEmitter {
+        init(mut cx) {
+            trace!("Rust: JsSearchExporterEventEmitter");
+            let source = path::PathBuf::from(cx.argument::<JsString>(0)?.value().as_str());
+            let destination_path = path::PathBuf::from(cx.argument::<JsString>(1)?.value().as_str());
+            let search_result = path::PathBuf::from(cx.argument::<JsString>(2)?.value().as_str());
+            let context = cx.argument::<JsNumber>(3)?.value() as usize;
+            let format: ExportFormat = match cx.argument::<JsString>(4)?.value().parse() {
+                Ok(format) => format,
+                Err(e) => return cx.throw_error(format!("{}", e)),
+            };
+            let fibex = match cx.argument_opt(5) {
+                Some(arg) if arg.is_a::<JsString>() => {
+                    Some(arg.downcast::<JsString>().or_throw(&mut cx)?.value())
+                }
+                _ => None,
+            };
+
+            let shutdown_channel = sync::mpsc::channel(1);
+            let (tx, rx): (cc::Sender<ChunkResults>, cc::Receiver<ChunkResults>) = cc::unbounded();
+            let mut emitter = SearchExporterEventEmitter {
+                event_receiver: rx,
+                shutdown_sender: shutdown_channel.0,
+                task_thread: None,
+            };
+            emitter.start_exporting_search_result_in_thread(
+                source,
+                destination_path,
+                search_result,
+                context,
+                format,
+                fibex,
+                tx,
+            );
+            Ok(emitter)
+        }
+
+        // will be called by JS to receive data in a loop, but care should be taken to only call it once at a time.
+        method poll(mut cx) {
+            let cb = cx.argument::<JsFunction>(0)?;
+            let this = cx.this();
+            let events = cx.borrow(&this, |emitter| emitter.event_receiver.clone());
+            let emitter = EventEmitterTask::new(events);
+            emitter.schedule(cb);
+            Ok(JsUndefined::new().upcast())
+        }
+
+        // exporting a search result can not be stopped
+        method shutdown(mut cx) {
+            trace!("shutdown called");
+            Ok(JsUndefined::new().upcast())
+        }
+    }
+}
@@ -20,6 +20,7 @@ impl IndexingEventEmitter {
         chunk_result_sender: cc::Sender<ChunkResults>,
         append: bool,
         chunk_size: usize,
+        multiline: bool,
         thread_conf: IndexingThreadConfig,
     ) {
         info!("call event_thread with chunk size: {}", chunk_size);
@@ -39,6 +40,7 @@ impl IndexingEventEmitter {
                     watch: false,
                 },
                 thread_conf.timestamps,
+                multiline,
                 chunk_result_sender.clone(),
                 Some(shutdown_rx),
             );
@@ -54,6 +56,7 @@ impl IndexingEventEmitter {
 async fn index_file_with_progress(
     config: IndexingConfig,
     timestamps: bool,
+    multiline: bool,
     tx: cc::Sender<ChunkResults>,
     shutdown_receiver: Option<cc::Receiver<()>>,
 ) {
@@ -74,6 +77,7 @@ async fn index_file_with_progress(
         config,
         source_file_size,
         timestamps,
+        multiline,
         tx.clone(),
         shutdown_receiver,
     )
@@ -101,6 +105,7 @@ declare_types! {
             let append: bool = cx.argument::<JsBoolean>(3)?.value();
             let timestamps: bool = cx.argument::<JsBoolean>(4)?.value();
             let chunk_size: usize = cx.argument::<JsNumber>(5)?.value() as usize;
+            let multiline: bool = cx.argument::<JsBoolean>(6)?.value();
             let (shutdown_sender, shutdown_receiver) = cc::unbounded();
 
             let file_path = path::PathBuf::from(file);
@@ -114,6 +119,7 @@ declare_types! {
                 chunk_result_sender,
                 append,
                 chunk_size,
+                multiline,
                 IndexingThreadConfig {
                     in_file: file_path,
                     out_path,
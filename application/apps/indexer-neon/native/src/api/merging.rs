@@ -1,18 +1,33 @@
 use crate::channels::EventEmitterTask;
 use crossbeam_channel as cc;
 use indexer_base::{
-    chunks::ChunkResults,
-    progress::{IndexingProgress, Notification, Severity},
+    chunks::{Chunk, ChunkResults},
+    progress::{IndexingProgress, IndexingResults, Notification, Severity},
+};
+use merging::{
+    merger::{merge_files_use_config, FileMergeOptions},
+    report::InputReport,
 };
-use merging::merger::{merge_files_use_config, FileMergeOptions};
 use neon::prelude::*;
+use serde::Serialize;
 use std::{
     path::{Path, PathBuf},
     thread,
 };
 
+/// What the merge sends to js: the chunks of the merged file and, before
+/// the merge is finished, the reports of all inputs
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+pub enum MergeItem {
+    Chunk(Chunk),
+    Reports { reports: Vec<InputReport> },
+}
+
+pub type MergeResults = IndexingResults<MergeItem>;
+
 pub struct MergerEmitter {
-    pub event_receiver: cc::Receiver<ChunkResults>,
+    pub event_receiver: cc::Receiver<MergeResults>,
     pub shutdown_sender: cc::Sender<()>,
     pub task_thread: Option<std::thread::JoinHandle<()>>,
 }
@@ -24,7 +39,7 @@ impl MergerEmitter {
         out_path: &Path,
         append: bool,
         chunk_size: usize, // used for mapping line numbers to byte positions
-        update_channel: cc::Sender<ChunkResults>,
+        update_channel: cc::Sender<MergeResults>,
         shutdown_rx: cc::Receiver<()>,
     ) {
         let out = PathBuf::from(out_path);
@@ -48,18 +63,43 @@ fn merge_with_progress(
     out_path: &Path,
     append: bool,
     chunk_size: usize, // used for mapping line numbers to byte positions
-    update_channel: cc::Sender<ChunkResults>,
+    update_channel: cc::Sender<MergeResults>,
     shutdown_receiver: Option<cc::Receiver<()>>,
 ) {
-    match merge_files_use_config(
-        options,
-        out_path,
-        append,
-        chunk_size,
-        update_channel.clone(),
-        shutdown_receiver,
-    ) {
-        Err(why) => {
+    let (chunk_tx, chunk_rx): (cc::Sender<ChunkResults>, cc::Receiver<ChunkResults>) =
+        cc::unbounded();
+    let out = PathBuf::from(out_path);
+    let merging = thread::spawn(move || {
+        merge_files_use_config(
+            options,
+            &out,
+            append,
+            chunk_size,
+            chunk_tx,
+            shutdown_receiver,
+        )
+    });
+    // the reports have to reach js before the merge is finished
+    let mut finished = false;
+    for result in chunk_rx.iter() {
+        let forwarded = match result {
+            Ok(IndexingProgress::Finished) => {
+                finished = true;
+                continue;
+            }
+            Ok(IndexingProgress::GotItem { item }) => Ok(IndexingProgress::GotItem {
+                item: MergeItem::Chunk(item),
+            }),
+            Ok(IndexingProgress::Progress { ticks }) => Ok(IndexingProgress::Progress { ticks }),
+            Ok(IndexingProgress::Stopped) => Ok(IndexingProgress::Stopped),
+            Err(notification) => Err(notification),
+        };
+        if update_channel.send(forwarded).is_err() {
+            warn!("could not communicate merge progress to js");
+        }
+    }
+    match merging.join() {
+        Ok(Err(why)) => {
             let err_msg = format!("couldn't merge_with_progress: {}", why);
             error!("{}", err_msg);
             match update_channel.send(Err(Notification {
@@ -72,7 +112,21 @@ fn merge_with_progress(
             }
             let _ = update_channel.send(Ok(IndexingProgress::Stopped));
         }
-        Ok(()) => trace!("merge done"),
+        Ok(Ok(reports)) => {
+            trace!("merge done: {:?}", reports);
+            if update_channel
+                .send(Ok(IndexingProgress::GotItem {
+                    item: MergeItem::Reports { reports },
+                }))
+                .is_err()
+            {
+                warn!("could not communicate merge reports to js");
+            }
+        }
+        Err(_) => error!("merging thread panicked"),
+    }
+    if finished {
+        let _ = update_channel.send(Ok(IndexingProgress::Finished));
     }
 }
 
@@ -90,7 +144,7 @@ pub class JsMergerEmitter for MergerEmitter {
         trace!("out_path: {:?}", out_path);
         trace!("append: {:?}", append);
 
-        let (result_tx, result_rx): (cc::Sender<ChunkResults>, cc::Receiver<ChunkResults>) = cc::unbounded();
+        let (result_tx, result_rx): (cc::Sender<MergeResults>, cc::Receiver<MergeResults>) = cc::unbounded();
         let shutdown_channel = cc::unbounded();
         let mut emitter = MergerEmitter{
             event_receiver: result_rx,
@@ -1,3 +1,4 @@
+pub mod chunk_index;
 pub mod concatenatination;
 pub mod dlt_indexing;
 pub mod dlt_pcap;
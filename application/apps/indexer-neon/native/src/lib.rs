@@ -20,11 +20,13 @@ use crate::api::dlt_pcap_converter::JsDltPcapConverterEventEmitter;
 use logging::init_logging;
 
 use api::{
-    concatenatination::JsConcatenatorEmitter, dlt_indexing::JsDltIndexerEventEmitter,
-    dlt_pcap::JsDltPcapEventEmitter, dlt_sockets::JsDltSocketEventEmitter,
-    dlt_stats::JsDltStatsEventEmitter, exporting::JsExporterEventEmitter,
-    format_verification::JsFormatVerificationEmitter, indexing::JsIndexerEventEmitter,
-    merging::JsMergerEmitter, timestamp_detector::detect_timestamp_in_string,
+    chunk_index::byte_range_for_rows, concatenatination::JsConcatenatorEmitter,
+    dlt_indexing::JsDltIndexerEventEmitter, dlt_pcap::JsDltPcapEventEmitter,
+    dlt_sockets::JsDltSocketEventEmitter, dlt_stats::JsDltStatsEventEmitter,
+    exporting::{JsExporterEventEmitter, JsSearchExporterEventEmitter},
+    format_verification::JsFormatVerificationEmitter,
+    indexing::JsIndexerEventEmitter, merging::JsMergerEmitter,
+    timestamp_detector::detect_timestamp_in_string,
     timestamp_detector::JsTimestampFormatDetectionEmitter,
     timestamp_extractor::JsTimestampExtractEmitter,
 };
@@ -41,6 +43,7 @@ register_module!(mut cx, {
 
     // expose synchronous API functions
     cx.export_function("detectTimestampInString", detect_timestamp_in_string)?;
+    cx.export_function("byteRangeForRows", byte_range_for_rows)?;
 
     // expose asynchronous APIs (event emitters)
     cx.export_class::<JsIndexerEventEmitter>("RustIndexerEventEmitter")?;
@@ -54,6 +57,7 @@ register_module!(mut cx, {
     cx.export_class::<JsConcatenatorEmitter>("RustConcatenatorEmitter")?;
     cx.export_class::<JsMergerEmitter>("RustMergerEmitter")?;
     cx.export_class::<JsExporterEventEmitter>("RustExporterEventEmitter")?;
+    cx.export_class::<JsSearchExporterEventEmitter>("RustSearchExporterEventEmitter")?;
     cx.export_class::<JsFormatVerificationEmitter>("RustFormatVerificationEmitter")?;
 
     Ok(())
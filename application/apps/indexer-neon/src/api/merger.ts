@@ -37,11 +37,32 @@ export interface IMergeFilesOptionsChecked {
     chunk_size: number,
 }
 
-export type TMergeFilesEvents = 'result' | 'progress' | 'notification';
+/**
+ * What the merger saw in one of its inputs, sent once before the merge is finished
+ */
+export interface IMergeInputReport {
+    path: string;
+    tag: string;
+    format: string | null;
+    error: string | null;
+    entries: number;
+    without_timestamp: number;
+    year_missing: number;
+    min_time: number | null;
+    max_time: number | null;
+    out_of_order: number;
+    reordered: number;
+}
+interface INeonMergeReports {
+    reports: IMergeInputReport[];
+}
+
+export type TMergeFilesEvents = 'result' | 'progress' | 'notification' | 'reports';
 export type TMergeFilesEventResult = (event: IChunk) => void;
 export type TMergeFilesEventProgress = (event: ITicks) => void;
 export type TMergeFilesEventNotification = (event: INeonNotification) => void;
-export type TMergeFilesEventObject = TMergeFilesEventResult | TMergeFilesEventProgress | TMergeFilesEventNotification;
+export type TMergeFilesEventReports = (event: IMergeInputReport[]) => void;
+export type TMergeFilesEventObject = TMergeFilesEventResult | TMergeFilesEventProgress | TMergeFilesEventNotification | TMergeFilesEventReports;
 
 export function mergeFilesAsync(
     config: Array<IMergerItemOptions>,
@@ -59,7 +80,7 @@ export function mergeFilesAsync(
                 opt.append,
                 opt.chunk_size,
             );
-            const computation = new NativeComputationManager<INeonTransferChunk>(channel);
+            const computation = new NativeComputationManager<INeonTransferChunk | INeonMergeReports>(channel);
             // Add cancel callback
             refCancelCB(() => {
                 // Cancelation is started, but not canceled
@@ -67,7 +88,11 @@ export function mergeFilesAsync(
                 computation.requestShutdown();
             });
             let totalTicks = 1;
-            computation.onItem((c: INeonTransferChunk) => {
+            computation.onItem((c: INeonTransferChunk | INeonMergeReports) => {
+                if ('reports' in c) {
+                    self.emit('reports', c.reports);
+                    return;
+                }
                 self.emit('result', {
                     bytesStart: c.b[0],
                     bytesEnd: c.b[1],
@@ -11,12 +11,14 @@ export interface IIndexOptions {
 	chunkSize?: number;
 	append?: boolean;
 	timestamps?: boolean;
+	multiline?: boolean;
 }
 
 export interface IIndexOptionsChecked {
 	chunkSize: number;
 	append: boolean;
 	timestamps: boolean;
+	multiline: boolean;
 }
 
 export type TIndexAsyncEvents = 'chunk' | 'progress' | 'notification';
@@ -47,7 +49,8 @@ export function indexAsync(
 				outPath,
 				opt.append,
 				opt.timestamps,
-				opt.chunkSize
+				opt.chunkSize,
+				opt.multiline
 			);
 			const computation = new NativeComputationManager<INeonTransferChunk>(channel);
 			// Add cancel callback
@@ -106,5 +109,6 @@ function getDefaultProcessorOptions(options: IIndexOptions | undefined): IIndexO
 	options.append = typeof options.append === 'boolean' ? options.append : false;
 	options.timestamps = typeof options.timestamps === 'boolean' ? options.timestamps : false;
 	options.chunkSize = typeof options.chunkSize === 'number' ? options.chunkSize : 5000;
+	options.multiline = typeof options.multiline === 'boolean' ? options.multiline : false;
 	return options as IIndexOptionsChecked;
 }
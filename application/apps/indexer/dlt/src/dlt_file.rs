@@ -9,16 +9,22 @@
 // Dissemination of this information or reproduction of this material
 // is strictly forbidden unless prior written permission is obtained
 // from E.S.R.Labs.
+use crate::{
+    dlt_redaction::redact_message,
+    dlt_timeline::{TimelineCollector, TimelineResults},
+    fibex_cache::gather_fibex_data_cached,
+};
 use buf_redux::{policy::MinBuffered, BufReader as ReduxReader};
+use byteorder::{ByteOrder, LittleEndian};
 use crossbeam_channel as cc;
 use dlt_core::{
     dlt::{LogLevel, Message},
-    fibex::{gather_fibex_data, FibexConfig, FibexMetadata},
+    fibex::{FibexConfig, FibexMetadata},
     filtering,
     fmt::{FormatOptions, FormattableMessage},
     parse::{
         dlt_consume_msg, dlt_message, forward_to_next_storage_header, skip_storage_header,
-        DltParseError, ParsedMessage,
+        DltParseError, ParsedMessage, DLT_PATTERN,
     },
     statistics::{
         dlt_statistic_row_info, IdMap, LevelDistribution, StatisticInfo, StatisticRowInfo,
@@ -26,8 +32,12 @@ use dlt_core::{
 };
 use indexer_base::{
     chunks::{ChunkFactory, ChunkResults},
+    compression::{CompressedPosition, InputFile},
     config::*,
+    container::RowWriter,
     progress::*,
+    redaction::{Redaction, Redactor},
+    timedline::TimedLine,
     utils,
 };
 use rustc_hash::FxHashMap;
@@ -51,7 +61,11 @@ pub enum Error {
 pub(crate) const STOP_CHECK_LINE_THRESHOLD: usize = 250_000;
 pub(crate) const DLT_READER_CAPACITY: usize = 10 * 1024 * 1024;
 pub(crate) const DLT_MIN_BUFFER_SPACE: usize = 10 * 1024;
+/// the biggest message including its storage header
+const REDACTION_MIN_BUFFER_SPACE: usize = u16::MAX as usize + STORAGE_HEADER_LENGTH;
 pub(crate) const DLT_PATTERN_SIZE: usize = 4;
+/// size of the storage header in front of each message of a dlt file
+pub const STORAGE_HEADER_LENGTH: usize = 16;
 
 pub type StatisticsResults = std::result::Result<IndexingProgress<StatisticInfo>, Notification>;
 
@@ -61,14 +75,14 @@ pub async fn parse_dlt_file(
     fibex: Option<FibexConfig>,
 ) -> Result<Vec<Message>, DltParseError> {
     trace!("parse_dlt_file");
-    let fibex_metadata: Option<FibexMetadata> = fibex.map(gather_fibex_data).flatten();
-    let source_file_size = fs::metadata(&in_file)?.len();
     let (update_channel, _rx): (cc::Sender<ChunkResults>, cc::Receiver<ChunkResults>) =
         cc::unbounded();
-    let mut progress_reporter = ProgressReporter::new(source_file_size, update_channel.clone());
+    let fibex_metadata: Option<FibexMetadata> =
+        fibex.and_then(|fibex| gather_fibex_data_cached(fibex, &update_channel));
+    let source_file_size = fs::metadata(&in_file)?.len();
     let mut messages: Vec<Message> = Vec::new();
 
-    let f = match fs::File::open(&in_file) {
+    let f = match InputFile::open(&in_file) {
         Ok(file) => file,
         Err(e) => {
             warn!("could not open {:?}", in_file);
@@ -83,6 +97,8 @@ pub async fn parse_dlt_file(
             )));
         }
     };
+    let mut progress_reporter = ProgressReporter::new(source_file_size, update_channel.clone())
+        .with_compressed_position(f.compressed_position());
     let mut message_stream = FileMessageProducer::new(f, filter_config, true, fibex_metadata);
     // type Item = Result<Option<Message>, DltParseError>;
     while let Some(msg_result) = tokio_stream::StreamExt::next(&mut message_stream).await {
@@ -116,9 +132,10 @@ pub fn create_index_and_mapping_dlt(
     trace!("create_index_and_mapping_dlt");
     let filter_config: Option<filtering::ProcessedDltFilterConfig> =
         dlt_filter.map(filtering::process_filter_config);
-    let fibex_metadata: Option<FibexMetadata> = fibex.map(gather_fibex_data).flatten();
+    let fibex_metadata: Option<FibexMetadata> =
+        fibex.and_then(|fibex| gather_fibex_data_cached(fibex, update_channel));
 
-    let f = fs::File::open(&config.in_file)?;
+    let f = InputFile::open(&config.in_file)?;
     //     Ok(file) => file,
     //     Err(e) => {
     //         warn!("could not open {:?}", config.in_file);
@@ -130,10 +147,12 @@ pub fn create_index_and_mapping_dlt(
     //         return Err(anyhow!(format!("could not open file ({})", e)));
     //     }
     // };
+    let compressed_position = f.compressed_position();
     let mut message_producer = FileMessageProducer::new(f, filter_config, true, fibex_metadata);
     index_dlt_content(
         config,
         source_file_size,
+        compressed_position,
         update_channel,
         shutdown_receiver,
         &mut message_producer,
@@ -148,7 +167,7 @@ pub struct MessageStats {
 }
 pub struct FileMessageProducer<R>
 where
-    R: Read + Seek + Unpin,
+    R: Read + Unpin,
 {
     reader: ReduxReader<R, MinBuffered>,
     filter_config: Option<filtering::ProcessedDltFilterConfig>,
@@ -159,7 +178,7 @@ where
 
 impl<R> FileMessageProducer<R>
 where
-    R: Read + Seek + Unpin,
+    R: Read + Unpin,
 {
     pub fn new(
         input: R,
@@ -187,6 +206,14 @@ where
 
     // Produces the next message in a file or None if the EOF was reached
     fn produce_next_message(&mut self) -> (usize, Result<Option<ParsedMessage>, DltParseError>) {
+        let result = self.parse_next_message();
+        self.reader.consume(result.0);
+        trace!("produce_next_message (consumed {})", result.0);
+        result
+    }
+
+    // Parses the next message, the consumed bytes are still at the start of the buffer
+    fn parse_next_message(&mut self) -> (usize, Result<Option<ParsedMessage>, DltParseError>) {
         #[allow(clippy::never_loop)]
         let consume_and_parse_result: (
             usize,
@@ -263,18 +290,13 @@ where
                 }
             }
         };
-        self.reader.consume(consume_and_parse_result.0);
-        trace!(
-            "produce_next_message (consumed {})",
-            consume_and_parse_result.0
-        );
         consume_and_parse_result
     }
 }
 
 impl<R> Iterator for FileMessageProducer<R>
 where
-    R: Read + Seek + Unpin,
+    R: Read + Unpin,
 {
     type Item = ParsedMessage;
     fn next(&mut self) -> Option<ParsedMessage> {
@@ -287,7 +309,7 @@ where
 
 impl<R> tokio_stream::Stream for FileMessageProducer<R>
 where
-    R: Read + Seek + Unpin,
+    R: Read + Unpin,
 {
     type Item = Result<(usize, Option<Message>), DltParseError>;
     fn poll_next(
@@ -309,11 +331,163 @@ where
     }
 }
 
+/// Time of a dlt message in ms.
+///
+/// Without `ecu_time_reference` this is the time of the storage header. With a reference,
+/// the ECU timestamp of the message (0.1 ms ticks since the ECU was started) is added
+/// to it. Messages without an ECU timestamp fall back to the storage header time.
+pub fn message_timestamp(msg: &Message, ecu_time_reference: Option<i64>) -> Option<i64> {
+    let ecu_time = match (ecu_time_reference, msg.header.timestamp) {
+        (Some(reference), Some(ticks)) => Some(reference + ticks as i64 / 10),
+        _ => None,
+    };
+    ecu_time.or_else(|| {
+        msg.storage_header
+            .as_ref()
+            .map(|h| h.timestamp.seconds as i64 * 1000 + h.timestamp.microseconds as i64 / 1000)
+    })
+}
+
+pub(crate) fn message_to_timed_line(
+    msg: Message,
+    tag: &str,
+    consumed: usize,
+    timestamp: i64,
+    line_nr: usize,
+) -> TimedLine {
+    let formattable_msg = FormattableMessage {
+        message: msg,
+        fibex_metadata: None,
+        options: None,
+    };
+    TimedLine {
+        timestamp,
+        content: formattable_msg.to_string(),
+        tag: tag.to_string(),
+        original_length: consumed,
+        year_was_missing: false,
+        line_nr,
+    }
+}
+
+/// Iterator over the messages of a dlt file together with the number of bytes each
+/// message took in the file. Content that can not be parsed is skipped, its bytes are
+/// added to the next message.
+/// Stops at the first unrecoverable parse error.
+pub fn dlt_messages<R: Read + Unpin>(
+    input: R,
+    with_storage_header: bool,
+) -> impl Iterator<Item = (usize, Message)> {
+    map_dlt_messages(input, with_storage_header, |msg, _| msg)
+}
+
+/// Like `dlt_messages` but every message comes with its bytes as they are in the file
+/// (without skipped content before it)
+pub fn dlt_messages_with_bytes<R: Read + Unpin>(
+    input: R,
+    with_storage_header: bool,
+) -> impl Iterator<Item = (usize, Message, Vec<u8>)> {
+    map_dlt_messages(input, with_storage_header, move |msg, consumed| {
+        let length = msg.header.overall_length() as usize
+            + if with_storage_header {
+                STORAGE_HEADER_LENGTH
+            } else {
+                0
+            };
+        let bytes = consumed[consumed.len().saturating_sub(length)..].to_vec();
+        (msg, bytes)
+    })
+    .map(|(consumed, (msg, bytes))| (consumed, msg, bytes))
+}
+
+/// `map` gets each message with the bytes that were consumed for it
+fn map_dlt_messages<R, T, F>(
+    input: R,
+    with_storage_header: bool,
+    mut map: F,
+) -> impl Iterator<Item = (usize, T)>
+where
+    R: Read + Unpin,
+    F: FnMut(Message, &[u8]) -> T,
+{
+    let mut producer = FileMessageProducer::new(input, None, with_storage_header, None);
+    // bytes of skipped content are added to the next message
+    let mut skipped = 0usize;
+    std::iter::from_fn(move || loop {
+        let (consumed, next) = producer.parse_next_message();
+        match next {
+            Ok(Some(ParsedMessage::Item(msg))) => {
+                let item = map(msg, &producer.reader.buffer()[..consumed]);
+                producer.reader.consume(consumed);
+                let message = (skipped + consumed, item);
+                skipped = 0;
+                return Some(message);
+            }
+            Ok(None) => return None,
+            Ok(Some(_)) | Err(DltParseError::ParsingHickup(_)) if consumed > 0 => {
+                producer.reader.consume(consumed);
+                skipped += consumed;
+            }
+            Ok(Some(_)) | Err(_) => {
+                if let Err(e) = next {
+                    warn!("stop reading dlt messages: {}", e);
+                }
+                return None;
+            }
+        }
+    })
+}
+
+/// Iterator over the messages of a dlt file (with storage headers) as `TimedLine`s,
+/// used for merging. See `message_timestamp` for how messages are timed, messages
+/// without any time get the time of the previous message.
+/// Stops at the first unrecoverable parse error.
+pub fn dlt_timed_lines<R: Read + Unpin>(
+    input: R,
+    tag: String,
+    ecu_time_reference: Option<i64>,
+    current_line_nr: usize,
+) -> impl Iterator<Item = TimedLine> {
+    let mut last_timestamp = 0i64;
+    dlt_messages(input, true)
+        .enumerate()
+        .map(move |(index, (consumed, msg))| {
+            let timestamp = message_timestamp(&msg, ecu_time_reference).unwrap_or(last_timestamp);
+            last_timestamp = timestamp;
+            message_to_timed_line(msg, &tag, consumed, timestamp, current_line_nr + index)
+        })
+}
+
+/// Check if a file contains dlt messages.
+///
+/// Returns `Some(true)` for files that start with a storage header. Files with a `.dlt`
+/// extension (before a compression extension) that start with a message without storage
+/// header give `Some(false)`. `None` means the file does not look like dlt.
+pub fn detect_dlt_file(path: &Path) -> Result<Option<bool>, DltParseError> {
+    let mut start = vec![];
+    InputFile::open(path)?
+        .take(DLT_MIN_BUFFER_SPACE as u64)
+        .read_to_end(&mut start)?;
+    if start.starts_with(DLT_PATTERN) {
+        return Ok(Some(true));
+    }
+    let named_dlt = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_lowercase())
+        .is_some_and(|name| name.ends_with(".dlt") || name.contains(".dlt."));
+    if named_dlt {
+        if let Ok((_, ParsedMessage::Item(_))) = dlt_message(&start, None, false) {
+            return Ok(Some(false));
+        }
+    }
+    Ok(None)
+}
+
 /// count how many recognizable DLT messages are stored in a file
 /// each message needs to be equiped with a storage header
 pub fn count_dlt_messages(input: &Path) -> Result<u64, DltParseError> {
     if input.exists() {
-        let f = fs::File::open(&input)?;
+        let f = InputFile::open(input)?;
 
         let mut reader = ReduxReader::with_capacity(DLT_READER_CAPACITY, f)
             .set_policy(MinBuffered(DLT_MIN_BUFFER_SPACE));
@@ -352,10 +526,12 @@ pub fn count_dlt_messages(input: &Path) -> Result<u64, DltParseError> {
 
 /// create index for a dlt file
 /// source_file_size: if progress updates should be made, add this value
+/// compressed_position: for compressed files, progress is relative to the compressed size
 #[allow(clippy::cognitive_complexity)]
-pub fn index_dlt_content<R: Read + Seek + Unpin>(
+pub fn index_dlt_content<R: Read + Unpin>(
     config: IndexingConfig,
     source_file_size: u64,
+    compressed_position: Option<CompressedPosition>,
     update_channel: &cc::Sender<ChunkResults>,
     shutdown_receiver: Option<cc::Receiver<()>>,
     message_producer: &mut FileMessageProducer<R>,
@@ -367,7 +543,8 @@ pub fn index_dlt_content<R: Read + Seek + Unpin>(
 
     let mut chunk_count = 0usize;
     let mut last_byte_index = 0usize;
-    let mut chunk_factory = ChunkFactory::new(config.chunk_size, current_out_file_size);
+    let mut chunk_factory =
+        ChunkFactory::new(config.chunk_size, current_out_file_size).with_index(&config.out_path)?;
     let mut line_nr = if config.append {
         utils::next_line_nr(&config.out_path)?
     } else {
@@ -375,9 +552,14 @@ pub fn index_dlt_content<R: Read + Seek + Unpin>(
     };
     // let tmp_file = create_dlt_tmp_file("file")?;
     // let mut tmp_writer = BufWriter::with_capacity(DLT_READER_CAPACITY, tmp_file);
-    let mut buf_writer = BufWriter::with_capacity(DLT_READER_CAPACITY, out_file);
+    let mut row_writer = RowWriter::new(
+        BufWriter::with_capacity(DLT_READER_CAPACITY, out_file),
+        &config.out_path,
+        current_out_file_size,
+    )?;
 
-    let mut progress_reporter = ProgressReporter::new(source_file_size, update_channel.clone());
+    let mut progress_reporter = ProgressReporter::new(source_file_size, update_channel.clone())
+        .with_compressed_position(compressed_position);
 
     let mut stopped = false;
     let mut skipped = 0usize;
@@ -400,13 +582,8 @@ pub fn index_dlt_content<R: Read + Seek + Unpin>(
                     fibex_metadata: message_producer.fibex(),
                     options: fmt_options.as_ref(),
                 };
-                let written_bytes_len = utils::create_tagged_line_d(
-                    &config.tag,
-                    &mut buf_writer,
-                    &formattable_msg,
-                    line_nr,
-                    true,
-                )?;
+                let written_bytes_len =
+                    row_writer.write_row_d(&config.tag, &formattable_msg, line_nr, true)?;
                 // tmp_writer.write_all(&msg.as_bytes())?;
                 line_nr += 1;
                 if let Some(chunk) = chunk_factory.add_bytes(line_nr, written_bytes_len) {
@@ -417,7 +594,7 @@ pub fn index_dlt_content<R: Read + Seek + Unpin>(
                     chunk_count += 1;
                     last_byte_index = chunk.b.1;
                     let _ = update_channel.send(Ok(IndexingProgress::GotItem { item: chunk }));
-                    buf_writer.flush()?;
+                    row_writer.flush()?;
                 }
             }
             Ok(Some(ParsedMessage::Invalid)) => {
@@ -451,8 +628,8 @@ pub fn index_dlt_content<R: Read + Seek + Unpin>(
         }
     }
 
-    trace!("buf_writer.flush()");
-    buf_writer.flush()?;
+    trace!("row_writer.flush()");
+    row_writer.flush()?;
     // tmp_writer.flush()?;
     if let Some(chunk) = chunk_factory.create_last_chunk(line_nr, chunk_count == 0) {
         trace!("send chunk {:?}", chunk);
@@ -505,11 +682,50 @@ pub fn export_session_file(
     )
 }
 
+/// Position of a run of messages in a dlt file, e.g. taken from the slots
+/// of the grabber metadata
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MessageSlot {
+    /// index of the first message of the slot
+    pub first_message: u64,
+    pub message_count: u64,
+    /// byte offset of the first message of the slot
+    pub offset: u64,
+}
+
+impl MessageSlot {
+    fn contains(&self, message: u64) -> bool {
+        message >= self.first_message && message - self.first_message < self.message_count
+    }
+}
+
 pub fn export_as_dlt_file(
     dlt_file_path: PathBuf,
     destination_path: PathBuf,
     sections: SectionConfig,
     update_channel: cc::Sender<ChunkResults>,
+) -> Result<(), Error> {
+    export_as_dlt_file_with_slots(
+        dlt_file_path,
+        destination_path,
+        sections,
+        vec![],
+        None,
+        update_channel,
+    )
+}
+
+/// like `export_as_dlt_file`, but with known slots the file is not scanned from
+/// the beginning: for every section we seek to the slot that contains its first
+/// message and only parse from there
+/// with a `redactor` the exported messages are redacted, see `redact_message`
+pub fn export_as_dlt_file_with_slots(
+    dlt_file_path: PathBuf,
+    destination_path: PathBuf,
+    sections: SectionConfig,
+    slots: Vec<MessageSlot>,
+    mut redactor: Option<&mut Redactor>,
+    update_channel: cc::Sender<ChunkResults>,
 ) -> Result<(), Error> {
     trace!(
         "export_as_dlt_file {:?} to file: {:?}, exporting {:?}",
@@ -523,14 +739,19 @@ pub fn export_as_dlt_file(
         let mut reader = &mut std::io::BufReader::new(f);
         let out_file = std::fs::File::create(destination_path)?;
         trace!("created out_file: {:?}", &out_file);
-        let partitioner = FilePartitioner::new(&dlt_file_path, sections)?;
+        let partitioner = FilePartitioner::new(&dlt_file_path, sections, slots)?;
         let mut out_writer = BufWriter::new(out_file);
 
         for part in partitioner.get_parts() {
             trace!("copy part {:?}", part);
-            reader.seek(std::io::SeekFrom::Start(part.offset as u64))?;
-            let mut take = reader.take(part.length as u64);
-            std::io::copy(&mut take, &mut out_writer)?;
+            reader.seek(std::io::SeekFrom::Start(part.offset))?;
+            let mut take = reader.take(part.length);
+            match redactor.as_deref_mut() {
+                Some(redactor) => copy_redacted(&mut take, &mut out_writer, redactor)?,
+                None => {
+                    std::io::copy(&mut take, &mut out_writer)?;
+                }
+            }
             reader = take.into_inner();
             out_writer.flush()?;
         }
@@ -547,6 +768,48 @@ pub fn export_as_dlt_file(
     }
 }
 
+/// copy the messages (with storage header) of `input`, messages that were not changed
+/// by the redaction keep their original bytes. Content that is no valid message is left out.
+fn copy_redacted<R: Read, W: Write>(
+    input: R,
+    out: &mut W,
+    redactor: &mut Redactor,
+) -> Result<(), Error> {
+    // a complete message always fits into the buffer
+    let mut reader = ReduxReader::with_capacity(DLT_READER_CAPACITY, input)
+        .set_policy(MinBuffered(REDACTION_MIN_BUFFER_SPACE));
+    loop {
+        let content = reader.fill_buf()?;
+        if content.is_empty() {
+            break;
+        }
+        let consumed = match dlt_message(content, None, true) {
+            Ok((rest, ParsedMessage::Item(mut msg))) => {
+                let consumed = content.len() - rest.len();
+                match redact_message(redactor, &mut msg) {
+                    Redaction::Unchanged => out.write_all(&content[..consumed])?,
+                    Redaction::Changed(()) => out.write_all(&msg.as_bytes())?,
+                    Redaction::Drop => (),
+                }
+                consumed
+            }
+            Ok((rest, _)) => {
+                warn!("left out content that was no valid message");
+                content.len() - rest.len()
+            }
+            Err(e) => {
+                warn!("stop redacting, could not parse message: {}", e);
+                break;
+            }
+        };
+        if consumed == 0 {
+            break;
+        }
+        reader.consume(consumed);
+    }
+    Ok(())
+}
+
 pub(crate) fn session_file_path(session_id: &str) -> Option<PathBuf> {
     let home_dir = dirs::home_dir()?;
     let tmp_file_name = format!("{}.dlt", session_id);
@@ -567,19 +830,47 @@ struct FilePartitioner {
     reader: ReduxReader<fs::File, MinBuffered>,
     offset: u64,
     section_config: SectionConfig,
+    /// sorted by `first_message`
+    slots: Vec<MessageSlot>,
     file_size: u64,
 }
 impl FilePartitioner {
-    fn new(in_path: &Path, c: SectionConfig) -> Result<Self, std::io::Error> {
-        let f = fs::File::open(&in_path)?;
+    fn new(
+        in_path: &Path,
+        c: SectionConfig,
+        mut slots: Vec<MessageSlot>,
+    ) -> Result<Self, std::io::Error> {
+        slots.sort_unstable_by_key(|slot| slot.first_message);
+        let f = fs::File::open(in_path)?;
         Ok(FilePartitioner {
             reader: ReduxReader::with_capacity(DLT_READER_CAPACITY, f)
                 .set_policy(MinBuffered(DLT_MIN_BUFFER_SPACE)),
             offset: 0,
             section_config: c,
+            slots,
             file_size: fs::metadata(in_path)?.len(),
         })
     }
+
+    /// continue at the slot that contains `message` if it lies ahead,
+    /// returns the index of the message we continue with
+    fn skip_to_slot(&mut self, message: u64, current: u64) -> std::io::Result<u64> {
+        let slots = &self.slots;
+        let following = slots.partition_point(|slot| slot.first_message <= message);
+        match following
+            .checked_sub(1)
+            .map(|index| &slots[index])
+            .filter(|slot| slot.contains(message))
+        {
+            Some(slot) if slot.first_message > current => {
+                trace!("skip to slot {:?}", slot);
+                self.reader.seek(std::io::SeekFrom::Start(slot.offset))?;
+                self.offset = slot.offset;
+                Ok(slot.first_message)
+            }
+            _ => Ok(current),
+        }
+    }
     fn get_parts(mut self) -> Vec<FilePart> {
         if self.section_config.sections.is_empty() {
             return vec![FilePart {
@@ -607,7 +898,15 @@ impl FilePartitioner {
                 self.bytes_in_section = 0;
             }
         }
-        for section in self.section_config.sections {
+        let sections = std::mem::take(&mut self.section_config.sections);
+        for section in sections {
+            match self.skip_to_slot(section.first_line as u64, state.index as u64) {
+                Ok(index) => state.index = index as usize,
+                Err(_e) => {
+                    warn!("error in FilePartitioner seek: {}", _e);
+                    break;
+                }
+            }
             loop {
                 trace!("next[{:?}] (current section {:?})", state.index, section);
                 match self.reader.fill_buf() {
@@ -615,6 +914,14 @@ impl FilePartitioner {
                         trace!("Ok(content (len {}))", content.len());
                         if content.is_empty() {
                             trace!("0, Ok(ParsedMessage::Invalid)");
+                            // a section that reaches past the end of the file
+                            if state.in_section {
+                                result_vec.push(FilePart {
+                                    offset: state.section_offset,
+                                    length: state.bytes_in_section,
+                                });
+                                state.reset_section();
+                            }
                             break;
                         }
                         match skip_storage_header(content) {
@@ -631,7 +938,7 @@ impl FilePartitioner {
                                     state.section_offset = self.offset;
                                 }
                                 if state.in_section {
-                                    state.bytes_in_section += consumed as u64;
+                                    state.bytes_in_section += consumed;
                                 }
                                 if state.index == section.last_line {
                                     trace!("<--- leaving section: {:?}) ({:?})", section, state);
@@ -680,12 +987,27 @@ pub fn get_dlt_file_info(
     update_channel: &cc::Sender<StatisticsResults>,
     shutdown_receiver: Option<crossbeam_channel::Receiver<()>>,
 ) -> Result<(), DltParseError> {
-    let f = fs::File::open(in_file)?;
+    get_dlt_file_info_with_timeline(in_file, update_channel, None, shutdown_receiver)
+}
 
-    let source_file_size = fs::metadata(&in_file)?.len();
+/// Collect the statistics of a dlt file like `get_dlt_file_info`. With a timeline the
+/// messages are also counted in buckets of `bucket_ms` in the same pass, each bucket is
+/// sent to the timeline channel once it is complete.
+pub fn get_dlt_file_info_with_timeline(
+    in_file: &Path,
+    update_channel: &cc::Sender<StatisticsResults>,
+    timeline: Option<(u64, cc::Sender<TimelineResults>)>,
+    shutdown_receiver: Option<crossbeam_channel::Receiver<()>>,
+) -> Result<(), DltParseError> {
+    let f = InputFile::open(in_file)?;
+    let compressed_position = f.compressed_position();
+
+    let source_file_size = fs::metadata(in_file)?.len();
     let mut reader = ReduxReader::with_capacity(DLT_READER_CAPACITY, f)
         .set_policy(MinBuffered(DLT_MIN_BUFFER_SPACE));
 
+    let mut timeline = timeline
+        .map(|(bucket_ms, timeline_channel)| TimelineCollector::new(bucket_ms, timeline_channel));
     let mut app_ids: IdMap = FxHashMap::default();
     let mut context_ids: IdMap = FxHashMap::default();
     let mut ecu_ids: IdMap = FxHashMap::default();
@@ -694,17 +1016,27 @@ pub fn get_dlt_file_info(
     let mut contained_non_verbose = false;
     loop {
         match read_one_dlt_message_info(&mut reader, true) {
-            Ok(Some((
-                consumed,
-                StatisticRowInfo {
-                    app_id_context_id: Some((app_id, context_id)),
+            Ok(Some((consumed, info))) => {
+                if let Some(timeline) = &mut timeline {
+                    // garbage before the storage header is not part of the message
+                    if let Some((_, message)) =
+                        forward_to_next_storage_header(&reader.buffer()[..consumed as usize])
+                    {
+                        if let Some(timestamp) = storage_header_time(message) {
+                            timeline.add(timestamp as i64, message.len() as u64, &info);
+                        }
+                    }
+                }
+                let StatisticRowInfo {
+                    app_id_context_id,
                     ecu_id: ecu,
                     level,
                     verbose,
-                },
-            ))) => {
+                } = info;
                 contained_non_verbose = contained_non_verbose || !verbose;
                 reader.consume(consumed as usize);
+                let (app_id, context_id) =
+                    app_id_context_id.unwrap_or_else(|| ("NONE".to_string(), "NONE".to_string()));
                 add_for_level(level, &mut app_ids, app_id);
                 add_for_level(level, &mut context_ids, context_id);
                 match ecu {
@@ -713,25 +1045,6 @@ pub fn get_dlt_file_info(
                 };
                 processed_bytes += consumed;
             }
-            Ok(Some((
-                consumed,
-                StatisticRowInfo {
-                    app_id_context_id: None,
-                    ecu_id: ecu,
-                    level,
-                    verbose,
-                },
-            ))) => {
-                contained_non_verbose = contained_non_verbose || !verbose;
-                reader.consume(consumed as usize);
-                add_for_level(level, &mut app_ids, "NONE".to_string());
-                add_for_level(level, &mut context_ids, "NONE".to_string());
-                match ecu {
-                    Some(id) => add_for_level(level, &mut ecu_ids, id),
-                    None => add_for_level(level, &mut ecu_ids, "NONE".to_string()),
-                };
-                processed_bytes += consumed;
-            }
             Ok(None) => {
                 break;
             }
@@ -777,14 +1090,17 @@ pub fn get_dlt_file_info(
             }
         }
         index += 1;
-        if index % STOP_CHECK_LINE_THRESHOLD == 0 {
+        if index.is_multiple_of(STOP_CHECK_LINE_THRESHOLD) {
             if utils::check_if_stop_was_requested(shutdown_receiver.as_ref(), "dlt stats producer")
             {
                 let _ = update_channel.send(Ok(IndexingProgress::Stopped));
                 break;
             }
+            let position = compressed_position
+                .as_ref()
+                .map_or(processed_bytes, CompressedPosition::get);
             let _ = update_channel.send(Ok(IndexingProgress::Progress {
-                ticks: (processed_bytes, source_file_size),
+                ticks: (position, source_file_size),
             }));
         }
     }
@@ -801,11 +1117,24 @@ pub fn get_dlt_file_info(
         contained_non_verbose,
     };
 
+    if let Some(timeline) = timeline {
+        timeline.finish();
+    }
     let _ = update_channel.send(Ok(IndexingProgress::GotItem { item: res }));
     let _ = update_channel.send(Ok(IndexingProgress::Finished));
     Ok(())
 }
 
+/// time of the storage header in ms, for a message that starts with a storage header
+pub(crate) fn storage_header_time(message: &[u8]) -> Option<u64> {
+    if message.len() < 12 || &message[..4] != DLT_PATTERN {
+        return None;
+    }
+    let seconds = LittleEndian::read_u32(&message[4..8]) as u64;
+    let microseconds = LittleEndian::read_u32(&message[8..12]) as u64;
+    Some(seconds * 1000 + microseconds / 1000)
+}
+
 fn add_for_level(level: Option<LogLevel>, ids: &mut IdMap, id: String) {
     if let Some(n) = ids.get_mut(&id) {
         match level {
@@ -863,7 +1192,7 @@ fn add_for_level(level: Option<LogLevel>, ids: &mut IdMap, id: String) {
     }
 }
 
-fn read_one_dlt_message_info<T: Read>(
+pub(crate) fn read_one_dlt_message_info<T: Read>(
     reader: &mut ReduxReader<T, MinBuffered>,
     with_storage_header: bool,
 ) -> Result<Option<(u64, StatisticRowInfo)>, DltParseError> {
@@ -0,0 +1,391 @@
+// Copyright (c) 2019 E.S.R.Labs. All rights reserved.
+//
+// NOTICE:  All information contained herein is, and remains
+// the property of E.S.R.Labs and its suppliers, if any.
+// The intellectual and technical concepts contained herein are
+// proprietary to E.S.R.Labs and its suppliers and may be covered
+// by German and Foreign Patents, patents in process, and are protected
+// by trade secret or copyright law.
+// Dissemination of this information or reproduction of this material
+// is strictly forbidden unless prior written permission is obtained
+// from E.S.R.Labs.
+use crate::dlt_file::{
+    message_timestamp, Error, DLT_PATTERN_SIZE, DLT_READER_CAPACITY, STOP_CHECK_LINE_THRESHOLD,
+};
+use buf_redux::{policy::MinBuffered, BufReader as ReduxReader};
+use crossbeam_channel as cc;
+use dlt_core::{
+    dlt::Message,
+    parse::{dlt_message, DltParseError, ParsedMessage, DLT_PATTERN},
+};
+use indexer_base::{
+    compression::{CompressedPosition, InputFile},
+    progress::*,
+    utils,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::{BufRead, Read},
+    path::Path,
+};
+
+/// the biggest message including its storage header, a shorter rest of the file
+/// that can't be parsed completely is a truncated message
+const HEALTH_MIN_BUFFER_SPACE: usize = u16::MAX as usize + 16;
+/// of each kind of issue only the first ones are listed
+pub const MAX_LISTED_ISSUES: usize = 1000;
+/// default for the difference between storage header time and ECU time that is accepted
+pub const DEFAULT_CLOCK_TOLERANCE_MS: u64 = 1000;
+
+/// Issues of one kind, `count` can be bigger than the listed issues
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Issues<T> {
+    pub count: usize,
+    pub listed: Vec<T>,
+}
+
+impl<T> Default for Issues<T> {
+    fn default() -> Self {
+        Issues {
+            count: 0,
+            listed: vec![],
+        }
+    }
+}
+
+impl<T> Issues<T> {
+    fn add(&mut self, issue: T) {
+        self.count += 1;
+        if self.listed.len() < MAX_LISTED_ISSUES {
+            self.listed.push(issue);
+        }
+    }
+}
+
+/// Bytes that are not part of any message
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CorruptedRegion {
+    pub offset: u64,
+    pub length: u64,
+    /// where the next message starts, `None` if the file ends in the region
+    pub resync_offset: Option<u64>,
+}
+
+/// Messages of an ECU whose counter did not follow the previous one
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CounterGap {
+    pub ecu_id: String,
+    pub offset: u64,
+    pub message_index: usize,
+    pub expected: u8,
+    pub actual: u8,
+    /// number of messages that are missing if the counter only wrapped around once
+    pub missing: usize,
+}
+
+/// A message with an older storage header time than the message before
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TimeJump {
+    pub offset: u64,
+    pub message_index: usize,
+    pub previous_ms: i64,
+    pub timestamp_ms: i64,
+}
+
+/// The storage header time and the ECU time of an ECU drifted apart by more than
+/// the tolerance since the last check point
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ClockDisagreement {
+    pub ecu_id: String,
+    pub offset: u64,
+    pub message_index: usize,
+    pub storage_ms: i64,
+    /// ECU timestamp in ms since the ECU was started
+    pub ecu_ms: i64,
+    pub drift_ms: i64,
+}
+
+/// The last message of the file is incomplete
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TruncatedMessage {
+    pub offset: u64,
+    pub available: usize,
+    pub needed: Option<usize>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct EcuHealth {
+    pub messages: usize,
+    pub counter_gaps: usize,
+    pub missing_messages: usize,
+    pub clock_disagreements: usize,
+    /// the ECU timestamp went backwards, usually a restart of the ECU
+    pub ecu_restarts: usize,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct DltHealthReport {
+    pub file_size: u64,
+    pub messages: usize,
+    pub corrupted_bytes: u64,
+    pub corrupted_regions: Issues<CorruptedRegion>,
+    pub counter_gaps: Issues<CounterGap>,
+    pub time_jumps: Issues<TimeJump>,
+    pub clock_disagreements: Issues<ClockDisagreement>,
+    pub truncated_message: Option<TruncatedMessage>,
+    pub ecus: BTreeMap<String, EcuHealth>,
+}
+
+pub type HealthResults = std::result::Result<IndexingProgress<DltHealthReport>, Notification>;
+
+#[derive(Default)]
+struct EcuState {
+    counter: Option<u8>,
+    ecu_time: Option<u32>,
+    /// storage header time - ECU time at the last check point
+    clock_offset: Option<i64>,
+}
+
+struct HealthCollector {
+    report: DltHealthReport,
+    clock_tolerance: i64,
+    open_region: Option<CorruptedRegion>,
+    previous_time: Option<i64>,
+    ecus: BTreeMap<String, EcuState>,
+}
+
+impl HealthCollector {
+    fn add_corruption(&mut self, offset: u64, length: u64) {
+        self.report.corrupted_bytes += length;
+        match &mut self.open_region {
+            Some(region) if region.offset + region.length == offset => region.length += length,
+            _ => {
+                self.close_region(None);
+                self.open_region = Some(CorruptedRegion {
+                    offset,
+                    length,
+                    resync_offset: None,
+                });
+            }
+        }
+    }
+
+    fn close_region(&mut self, resync_offset: Option<u64>) {
+        if let Some(mut region) = self.open_region.take() {
+            region.resync_offset = resync_offset;
+            self.report.corrupted_regions.add(region);
+        }
+    }
+
+    fn add_message(&mut self, offset: u64, msg: &Message) {
+        self.close_region(Some(offset));
+        let message_index = self.report.messages;
+        self.report.messages += 1;
+        let ecu_id = msg
+            .header
+            .ecu_id
+            .clone()
+            .or_else(|| msg.storage_header.as_ref().map(|h| h.ecu_id.clone()))
+            .filter(|id| !id.is_empty())
+            .unwrap_or_else(|| "NONE".to_string());
+        let storage_ms = message_timestamp(msg, None);
+
+        if let (Some(previous_ms), Some(timestamp_ms)) = (self.previous_time, storage_ms) {
+            if timestamp_ms < previous_ms {
+                self.report.time_jumps.add(TimeJump {
+                    offset,
+                    message_index,
+                    previous_ms,
+                    timestamp_ms,
+                });
+            }
+        }
+        self.previous_time = storage_ms.or(self.previous_time);
+
+        let state = self.ecus.entry(ecu_id.clone()).or_default();
+        let ecu_health = self.report.ecus.entry(ecu_id.clone()).or_default();
+        ecu_health.messages += 1;
+
+        let actual = msg.header.message_counter;
+        if let Some(previous) = state.counter {
+            let expected = previous.wrapping_add(1);
+            if actual != expected {
+                let missing = actual.wrapping_sub(expected) as usize;
+                ecu_health.counter_gaps += 1;
+                ecu_health.missing_messages += missing;
+                self.report.counter_gaps.add(CounterGap {
+                    ecu_id: ecu_id.clone(),
+                    offset,
+                    message_index,
+                    expected,
+                    actual,
+                    missing,
+                });
+            }
+        }
+        state.counter = Some(actual);
+
+        if let (Some(storage_ms), Some(ticks)) = (storage_ms, msg.header.timestamp) {
+            if state.ecu_time.is_some_and(|previous| ticks < previous) {
+                ecu_health.ecu_restarts += 1;
+                state.clock_offset = None;
+            }
+            state.ecu_time = Some(ticks);
+            let ecu_ms = ticks as i64 / 10;
+            let clock_offset = storage_ms - ecu_ms;
+            match state.clock_offset {
+                Some(reference) if (clock_offset - reference).abs() > self.clock_tolerance => {
+                    ecu_health.clock_disagreements += 1;
+                    self.report.clock_disagreements.add(ClockDisagreement {
+                        ecu_id,
+                        offset,
+                        message_index,
+                        storage_ms,
+                        ecu_ms,
+                        drift_ms: clock_offset - reference,
+                    });
+                    state.clock_offset = Some(clock_offset);
+                }
+                Some(_) => (),
+                None => state.clock_offset = Some(clock_offset),
+            }
+        }
+    }
+}
+
+/// Check how trustworthy a dlt file (with storage headers) is.
+///
+/// Unlike the normal parsing nothing is skipped silently: bytes that do not belong to
+/// a message are reported as corrupted regions together with the offset where parsing
+/// could continue. For each ECU the message counter is checked for gaps and the ECU
+/// timestamps are compared with the storage header time, differences of more than
+/// `clock_tolerance_ms` are reported.
+pub fn analyze_dlt_health(
+    in_file: &Path,
+    clock_tolerance_ms: u64,
+    update_channel: &cc::Sender<HealthResults>,
+    shutdown_receiver: Option<cc::Receiver<()>>,
+) -> Result<DltHealthReport, Error> {
+    let f = InputFile::open(in_file)?;
+    let compressed_position = f.compressed_position();
+    let source_file_size = fs::metadata(in_file)?.len();
+    let mut reader = ReduxReader::with_capacity(DLT_READER_CAPACITY, f)
+        .set_policy(MinBuffered(HEALTH_MIN_BUFFER_SPACE));
+    let mut collector = HealthCollector {
+        report: DltHealthReport {
+            file_size: source_file_size,
+            ..Default::default()
+        },
+        clock_tolerance: clock_tolerance_ms as i64,
+        open_region: None,
+        previous_time: None,
+        ecus: BTreeMap::new(),
+    };
+
+    let mut offset = 0u64;
+    let mut index = 0usize;
+    loop {
+        let consumed = match next_health_entry(&mut reader)? {
+            HealthEntry::End => break,
+            HealthEntry::Message(consumed, msg) => {
+                collector.add_message(offset, &msg);
+                consumed
+            }
+            HealthEntry::Corrupted(consumed) => {
+                collector.add_corruption(offset, consumed as u64);
+                consumed
+            }
+            HealthEntry::Truncated(available, needed) => {
+                collector.report.truncated_message = Some(TruncatedMessage {
+                    offset,
+                    available,
+                    needed,
+                });
+                break;
+            }
+            HealthEntry::Failed(cause) => {
+                warn!("cannot continue checking the dlt file: {}", cause);
+                let _ = update_channel.send(Err(Notification {
+                    severity: Severity::ERROR,
+                    content: format!("error parsing dlt file: {}", cause),
+                    line: None,
+                }));
+                break;
+            }
+        };
+        reader.consume(consumed);
+        offset += consumed as u64;
+        index += 1;
+        if index.is_multiple_of(STOP_CHECK_LINE_THRESHOLD) {
+            if utils::check_if_stop_was_requested(shutdown_receiver.as_ref(), "dlt health") {
+                let _ = update_channel.send(Ok(IndexingProgress::Stopped));
+                break;
+            }
+            let position = compressed_position
+                .as_ref()
+                .map_or(offset, CompressedPosition::get);
+            let _ = update_channel.send(Ok(IndexingProgress::Progress {
+                ticks: (position, source_file_size),
+            }));
+        }
+    }
+    collector.close_region(None);
+    let report = collector.report;
+    let _ = update_channel.send(Ok(IndexingProgress::GotItem {
+        item: report.clone(),
+    }));
+    let _ = update_channel.send(Ok(IndexingProgress::Finished));
+    Ok(report)
+}
+
+enum HealthEntry {
+    End,
+    Message(usize, Message),
+    /// bytes that don't belong to a message
+    Corrupted(usize),
+    /// available bytes and how many were needed
+    Truncated(usize, Option<usize>),
+    Failed(String),
+}
+
+fn next_health_entry<T: Read>(
+    reader: &mut ReduxReader<T, MinBuffered>,
+) -> Result<HealthEntry, Error> {
+    let content = reader.fill_buf()?;
+    if content.is_empty() {
+        return Ok(HealthEntry::End);
+    }
+    let available = content.len();
+    if !content.starts_with(DLT_PATTERN) {
+        let garbage = if available < DLT_PATTERN_SIZE {
+            available
+        } else {
+            content
+                .windows(DLT_PATTERN_SIZE)
+                .position(|window| window == DLT_PATTERN)
+                // keep a pattern that might start at the end of the buffer
+                .unwrap_or(available - (DLT_PATTERN_SIZE - 1))
+        };
+        return Ok(HealthEntry::Corrupted(garbage));
+    }
+    Ok(match dlt_message(content, None, true) {
+        Ok((rest, ParsedMessage::Item(msg))) => HealthEntry::Message(available - rest.len(), msg),
+        Ok((rest, _)) => HealthEntry::Corrupted(available - rest.len()),
+        // a broken length in the middle of the file looks the same, but then another
+        // message follows
+        Err(DltParseError::IncompleteParse { needed })
+            if available < HEALTH_MIN_BUFFER_SPACE
+                && !content[DLT_PATTERN_SIZE..]
+                    .windows(DLT_PATTERN_SIZE)
+                    .any(|window| window == DLT_PATTERN) =>
+        {
+            HealthEntry::Truncated(available, needed.map(|n| n.get()))
+        }
+        Err(DltParseError::IncompleteParse { .. }) | Err(DltParseError::ParsingHickup(_)) => {
+            HealthEntry::Corrupted(DLT_PATTERN_SIZE)
+        }
+        Err(DltParseError::Unrecoverable(cause)) => HealthEntry::Failed(cause),
+    })
+}
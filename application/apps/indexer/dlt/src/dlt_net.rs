@@ -1,10 +1,10 @@
 extern crate dirs;
-use crate::dlt_file::create_dlt_session_file;
+use crate::{dlt_file::create_dlt_session_file, fibex_cache::gather_fibex_data_cached};
 use bytes::BytesMut;
 use crossbeam_channel as cc;
 use dlt_core::{
     dlt::*,
-    fibex::{gather_fibex_data, FibexConfig, FibexMetadata},
+    fibex::{FibexConfig, FibexMetadata},
     filtering,
     fmt::FormattableMessage,
     parse::{dlt_message, *},
@@ -12,6 +12,7 @@ use dlt_core::{
 use indexer_base::{
     chunks::{Chunk, ChunkFactory, ChunkResults},
     config::SocketConfig,
+    container::RowWriter,
     progress::*,
     utils,
 };
@@ -85,7 +86,8 @@ pub async fn index_from_socket(
         }
     };
     debug!("Binding socket within: {}", bind_addr_and_port);
-    let fibex_metadata: Option<FibexMetadata> = fibex.map(gather_fibex_data).flatten();
+    let fibex_metadata: Option<FibexMetadata> =
+        fibex.and_then(|fibex| gather_fibex_data_cached(fibex, &update_channel));
     match socket_config.udp_connection_info {
         None => {
             index_from_socket_tcp(
@@ -371,7 +373,7 @@ pub async fn create_index_and_mapping_dlt_from_socket(
 pub(crate) struct SessionProcessor {
     tmp_writer: BufWriter<std::fs::File>,
     chunk_factory: ChunkFactory,
-    buf_writer: BufWriter<std::fs::File>,
+    row_writer: RowWriter<BufWriter<std::fs::File>>,
     line_nr: usize,
     tag: String,
     update_channel: cc::Sender<ChunkResults>,
@@ -399,8 +401,12 @@ impl SessionProcessor {
             })?;
         Ok(SessionProcessor {
             tmp_writer: BufWriter::new(tmp_dlt_file),
-            chunk_factory: ChunkFactory::new(0, current_out_file_size),
-            buf_writer: BufWriter::with_capacity(10 * 1024 * 1024, out_file),
+            chunk_factory: ChunkFactory::new(0, current_out_file_size).with_index(out_path)?,
+            row_writer: RowWriter::new(
+                BufWriter::with_capacity(10 * 1024 * 1024, out_file),
+                out_path,
+                current_out_file_size,
+            )?,
             line_nr: initial_line_nr,
             tag: tag.to_owned(),
             update_channel,
@@ -422,9 +428,8 @@ impl SessionProcessor {
                         fibex_metadata,
                         options: Some(fmt_options),
                     };
-                    let written_bytes_len = utils::create_tagged_line_d(
+                    let written_bytes_len = self.row_writer.write_row_d(
                         &self.tag,
-                        &mut self.buf_writer,
                         &formattable_msg,
                         self.line_nr,
                         true,
@@ -434,7 +439,7 @@ impl SessionProcessor {
                         .chunk_factory
                         .add_bytes(self.line_nr, written_bytes_len)
                     {
-                        self.buf_writer.flush()?;
+                        self.row_writer.flush()?;
                         let _ = self
                             .update_channel
                             .send(Ok(IndexingProgress::GotItem { item: chunk }));
@@ -0,0 +1,225 @@
+// Copyright (c) 2019 E.S.R.Labs. All rights reserved.
+//
+// NOTICE:  All information contained herein is, and remains
+// the property of E.S.R.Labs and its suppliers, if any.
+// The intellectual and technical concepts contained herein are
+// proprietary to E.S.R.Labs and its suppliers and may be covered
+// by German and Foreign Patents, patents in process, and are protected
+// by trade secret or copyright law.
+// Dissemination of this information or reproduction of this material
+// is strictly forbidden unless prior written permission is obtained
+// from E.S.R.Labs.
+use crate::{
+    dlt_file::{read_one_dlt_message_info, Error, DLT_READER_CAPACITY, STOP_CHECK_LINE_THRESHOLD},
+    fibex_cache::gather_fibex_data_cached,
+};
+use buf_redux::{policy::MinBuffered, BufReader as ReduxReader};
+use crossbeam_channel as cc;
+use dlt_core::{
+    dlt::{Message, PayloadContent},
+    fibex::{FibexConfig, FibexMetadata},
+    fmt::FormattableMessage,
+    parse::{dlt_message, DltParseError, ParsedMessage},
+};
+use indexer_base::{
+    compression::{CompressedPosition, InputFile},
+    progress::*,
+    utils,
+};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fmt::Write, fs, io::BufRead, path::Path};
+
+/// the biggest message including its storage header
+const NON_VERBOSE_MIN_BUFFER_SPACE: usize = u16::MAX as usize + 16;
+/// separates the arguments in a formatted message (not exported by dlt-core)
+const DLT_ARGUMENT_SENTINAL: char = '\u{0005}';
+/// payloads that are kept of each unresolved message id
+pub const MAX_PAYLOAD_SAMPLES: usize = 3;
+/// bytes that are kept of a payload sample
+pub const MAX_SAMPLE_BYTES: usize = 32;
+
+/// A non-verbose message id as it was seen in the file
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct NonVerboseId {
+    /// `None` for messages without extended header
+    pub app_id: Option<String>,
+    pub context_id: Option<String>,
+    pub message_id: u32,
+    pub count: usize,
+    /// the fibex metadata describes the message
+    pub resolved: bool,
+    /// the first payloads (hex, cut to `MAX_SAMPLE_BYTES`) of unresolved messages
+    pub samples: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct NonVerboseReport {
+    pub fibex_loaded: bool,
+    pub messages: usize,
+    pub resolved_messages: usize,
+    pub unresolved_messages: usize,
+    /// ordered by app id, context id and message id
+    pub ids: Vec<NonVerboseId>,
+}
+
+pub type NonVerboseResults = std::result::Result<IndexingProgress<NonVerboseReport>, Notification>;
+
+type NonVerboseKey = (Option<String>, Option<String>, u32);
+
+fn hex_sample(data: &[u8]) -> String {
+    data.iter()
+        .take(MAX_SAMPLE_BYTES)
+        .map(|b| format!("{:02x}", b))
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+/// Whether formatting with the fibex metadata decoded the message. Messages that
+/// are not described are formatted with their id in brackets. A message that is
+/// described but can't be decoded from its payload counts as resolved.
+/// A frame without any PDUs is formatted like a message that is not described,
+/// so it is reported as unresolved: the frames of the fibex metadata can't be
+/// looked up directly.
+fn resolved_by_fibex(message: Message, message_id: u32, fibex: Option<&FibexMetadata>) -> bool {
+    let fibex = match fibex {
+        Some(fibex) => fibex,
+        None => return false,
+    };
+    let mut formatted = String::new();
+    let result = write!(
+        formatted,
+        "{}",
+        FormattableMessage {
+            message,
+            fibex_metadata: Some(fibex),
+            options: None,
+        }
+    );
+    match result {
+        Ok(()) => !formatted.contains(&format!(
+            "{}[{}]{}",
+            DLT_ARGUMENT_SENTINAL, message_id, DLT_ARGUMENT_SENTINAL
+        )),
+        Err(_) => true,
+    }
+}
+
+/// Collect every non-verbose message id of a dlt file (with storage headers) and
+/// check if the fibex files describe them.
+///
+/// Only non-verbose messages are parsed completely, the rest is skipped with the
+/// cheap header parsing that is also used for the statistics.
+pub fn get_non_verbose_report(
+    in_file: &Path,
+    fibex: Option<FibexConfig>,
+    update_channel: &cc::Sender<NonVerboseResults>,
+    shutdown_receiver: Option<cc::Receiver<()>>,
+) -> Result<NonVerboseReport, Error> {
+    let fibex_metadata: Option<FibexMetadata> =
+        fibex.and_then(|fibex| gather_fibex_data_cached(fibex, update_channel));
+    let f = InputFile::open(in_file)?;
+    let compressed_position = f.compressed_position();
+    let source_file_size = fs::metadata(in_file)?.len();
+    let mut reader = ReduxReader::with_capacity(DLT_READER_CAPACITY, f)
+        .set_policy(MinBuffered(NON_VERBOSE_MIN_BUFFER_SPACE));
+
+    let mut ids: BTreeMap<NonVerboseKey, NonVerboseId> = BTreeMap::new();
+    let mut index = 0usize;
+    let mut processed_bytes = 0u64;
+    loop {
+        match read_one_dlt_message_info(&mut reader, true) {
+            Ok(Some((consumed, info))) => {
+                if !info.verbose {
+                    if let Ok((_, ParsedMessage::Item(message))) =
+                        dlt_message(reader.buffer(), None, true)
+                    {
+                        if let PayloadContent::NonVerbose(message_id, data) = &message.payload {
+                            let message_id = *message_id;
+                            let (app_id, context_id) = match &message.extended_header {
+                                Some(ext) => (
+                                    Some(ext.application_id.clone()),
+                                    Some(ext.context_id.clone()),
+                                ),
+                                None => (None, None),
+                            };
+                            let sample = hex_sample(data);
+                            let key = (app_id.clone(), context_id.clone(), message_id);
+                            let id = ids.entry(key).or_insert_with(|| NonVerboseId {
+                                app_id,
+                                context_id,
+                                message_id,
+                                count: 0,
+                                resolved: resolved_by_fibex(
+                                    message,
+                                    message_id,
+                                    fibex_metadata.as_ref(),
+                                ),
+                                samples: vec![],
+                            });
+                            id.count += 1;
+                            if !id.resolved
+                                && id.samples.len() < MAX_PAYLOAD_SAMPLES
+                                && !id.samples.contains(&sample)
+                            {
+                                id.samples.push(sample);
+                            }
+                        }
+                    }
+                }
+                reader.consume(consumed as usize);
+                processed_bytes += consumed;
+            }
+            Ok(None) => break,
+            Err(DltParseError::ParsingHickup(reason)) => {
+                // we couldn't parse the message. try to skip it and find the next.
+                reader.consume(4); // at least skip the magic DLT pattern
+                debug!(
+                    "error parsing 1 dlt message, try to continue parsing: {}",
+                    reason
+                );
+            }
+            Err(e) => {
+                warn!("cannot continue collecting non-verbose ids: {}", e);
+                let _ = update_channel.send(Err(Notification {
+                    severity: Severity::ERROR,
+                    content: format!("error parsing dlt file: {}", e),
+                    line: None,
+                }));
+                break;
+            }
+        }
+        index += 1;
+        if index.is_multiple_of(STOP_CHECK_LINE_THRESHOLD) {
+            if utils::check_if_stop_was_requested(shutdown_receiver.as_ref(), "dlt non-verbose") {
+                let _ = update_channel.send(Ok(IndexingProgress::Stopped));
+                break;
+            }
+            let position = compressed_position
+                .as_ref()
+                .map_or(processed_bytes, CompressedPosition::get);
+            let _ = update_channel.send(Ok(IndexingProgress::Progress {
+                ticks: (position, source_file_size),
+            }));
+        }
+    }
+
+    let ids: Vec<NonVerboseId> = ids.into_values().collect();
+    let count = |resolved: bool| -> usize {
+        ids.iter()
+            .filter(|id| id.resolved == resolved)
+            .map(|id| id.count)
+            .sum()
+    };
+    let report = NonVerboseReport {
+        fibex_loaded: fibex_metadata.is_some(),
+        messages: ids.iter().map(|id| id.count).sum(),
+        resolved_messages: count(true),
+        unresolved_messages: count(false),
+        ids,
+    };
+    let _ = update_channel.send(Ok(IndexingProgress::GotItem {
+        item: report.clone(),
+    }));
+    let _ = update_channel.send(Ok(IndexingProgress::Finished));
+    Ok(report)
+}
@@ -1,20 +1,29 @@
+use crate::{
+    dlt_file::{message_timestamp, message_to_timed_line},
+    dlt_redaction::redact_message,
+    fibex_cache::gather_fibex_data_cached,
+};
 use crossbeam_channel as cc;
 use dlt_core::{
     dlt,
-    fibex::{gather_fibex_data, FibexConfig, FibexMetadata},
+    fibex::{FibexConfig, FibexMetadata},
     filtering,
     fmt::FormattableMessage,
     parse::*,
 };
 use indexer_base::{
     chunks::{ChunkFactory, ChunkResults, VoidResults},
+    compression::InputFile,
     config::IndexingConfig,
+    container::RowWriter,
     progress::*,
+    redaction::{Redaction, Redactor},
+    timedline::TimedLine,
     utils,
 };
 use pcap_parser::{traits::PcapReaderIterator, PcapBlockOwned, PcapError, PcapNGReader};
 use std::{
-    fs::*,
+    collections::VecDeque,
     io::{BufWriter, Write},
     time::{SystemTime, UNIX_EPOCH},
 };
@@ -33,7 +42,7 @@ pub enum Error {
 }
 
 struct PcapMessageProducer {
-    reader: PcapNGReader<File>,
+    reader: PcapNGReader<InputFile>,
     index: usize,
     fibex_metadata: Option<FibexMetadata>,
     filter_config: Option<filtering::ProcessedDltFilterConfig>,
@@ -46,8 +55,15 @@ impl PcapMessageProducer {
         fibex_metadata: Option<FibexMetadata>,
         filter_config: Option<filtering::ProcessedDltFilterConfig>,
     ) -> Result<Self, DltParseError> {
-        let pcap_file = File::open(&pcap_path)?;
-        match PcapNGReader::new(65536, pcap_file) {
+        Self::from_input(InputFile::open(pcap_path)?, fibex_metadata, filter_config)
+    }
+
+    pub fn from_input(
+        input: InputFile,
+        fibex_metadata: Option<FibexMetadata>,
+        filter_config: Option<filtering::ProcessedDltFilterConfig>,
+    ) -> Result<Self, DltParseError> {
+        match PcapNGReader::new(65536, input) {
             Ok(reader) => Ok(PcapMessageProducer {
                 reader,
                 index: 0,
@@ -116,12 +132,8 @@ enum MessageStreamItem {
     Empty,
     Done,
 }
-impl Stream for PcapMessageProducer {
-    type Item = (usize, Result<MessageStreamItem, DltParseError>);
-    fn poll_next(
-        mut self: std::pin::Pin<&mut Self>,
-        _cx: &mut std::task::Context,
-    ) -> core::task::Poll<Option<Self::Item>> {
+impl PcapMessageProducer {
+    fn produce_next(&mut self) -> (usize, Result<MessageStreamItem, DltParseError>) {
         let mut consumed = 0usize;
         let filter_config = self.filter_config.clone();
         self.index += 1;
@@ -150,13 +162,13 @@ impl Stream for PcapMessageProducer {
                 };
                 if let Some(payload) = data {
                     match etherparse::SlicedPacket::from_ethernet(payload) {
-                        Err(value) => core::task::Poll::Ready(Some((
+                        Err(value) => (
                             consumed,
                             Err(DltParseError::ParsingHickup(format!(
                                 "error trying to extract data from ethernet frame: {}",
                                 value
                             ))),
-                        ))),
+                        ),
                         Ok(value) => {
                             let mut input_slice = value.payload;
                             let mut found_dlt_messages = vec![];
@@ -188,46 +200,37 @@ impl Stream for PcapMessageProducer {
                             }
                             if found_dlt_messages.is_empty() {
                                 if skipped > 0 {
-                                    core::task::Poll::Ready(Some((
-                                        consumed,
-                                        Ok(MessageStreamItem::Skipped),
-                                    )))
+                                    (consumed, Ok(MessageStreamItem::Skipped))
                                 } else {
-                                    core::task::Poll::Ready(Some((
-                                        consumed,
-                                        Ok(MessageStreamItem::Empty),
-                                    )))
+                                    (consumed, Ok(MessageStreamItem::Empty))
                                 }
                             } else {
-                                core::task::Poll::Ready(Some((
-                                    consumed,
-                                    Ok(MessageStreamItem::Item(found_dlt_messages)),
-                                )))
+                                (consumed, Ok(MessageStreamItem::Item(found_dlt_messages)))
                             }
                         }
                     }
                 } else {
-                    core::task::Poll::Ready(Some((consumed, Ok(MessageStreamItem::Empty))))
+                    (consumed, Ok(MessageStreamItem::Empty))
                 }
             }
             Err(PcapError::Eof) => {
                 trace!("Pcap: EOF");
-                core::task::Poll::Ready(Some((consumed, Ok(MessageStreamItem::Done))))
+                (consumed, Ok(MessageStreamItem::Done))
             }
             Err(PcapError::Incomplete) => {
                 trace!("Pcap: Incomplete");
                 let _ = self.reader.refill();
-                core::task::Poll::Ready(Some((consumed, Ok(MessageStreamItem::Incomplete))))
+                (consumed, Ok(MessageStreamItem::Incomplete))
             }
             Err(e) => {
                 warn!("Pcap: error {:?}", e);
-                core::task::Poll::Ready(Some((
+                (
                     consumed,
                     Err(DltParseError::Unrecoverable(format!(
                         "error reading pcap: {:?}",
                         e
                     ))),
-                )))
+                )
             }
         };
         self.reader.consume(consumed);
@@ -235,7 +238,62 @@ impl Stream for PcapMessageProducer {
     }
 }
 
+impl Stream for PcapMessageProducer {
+    type Item = (usize, Result<MessageStreamItem, DltParseError>);
+    fn poll_next(
+        mut self: std::pin::Pin<&mut Self>,
+        _cx: &mut std::task::Context,
+    ) -> core::task::Poll<Option<Self::Item>> {
+        core::task::Poll::Ready(Some(self.produce_next()))
+    }
+}
+
+/// Iterator over the dlt messages contained in a PCAPNG file as `TimedLine`s, used
+/// for merging. Messages are timed like in `dlt_file::message_timestamp`, the storage
+/// header holds the time the packet was captured.
+/// Stops at the first unrecoverable parse error.
+pub fn pcap_timed_lines(
+    input: InputFile,
+    tag: String,
+    ecu_time_reference: Option<i64>,
+    current_line_nr: usize,
+) -> Result<impl Iterator<Item = TimedLine>, DltParseError> {
+    let mut producer = PcapMessageProducer::from_input(input, None, None)?;
+    let mut line_nr = current_line_nr;
+    let mut last_timestamp = 0i64;
+    // messages of the current packet, the consumed bytes are attributed to the first one
+    let mut pending: VecDeque<(usize, dlt::Message)> = VecDeque::new();
+    let mut skipped = 0usize;
+    Ok(std::iter::from_fn(move || loop {
+        if let Some((consumed, msg)) = pending.pop_front() {
+            let timestamp = message_timestamp(&msg, ecu_time_reference).unwrap_or(last_timestamp);
+            last_timestamp = timestamp;
+            let timed_line = message_to_timed_line(msg, &tag, consumed, timestamp, line_nr);
+            line_nr += 1;
+            return Some(timed_line);
+        }
+        let (consumed, next) = producer.produce_next();
+        match next {
+            Ok(MessageStreamItem::Item(msgs)) => {
+                let mut consumed = skipped + consumed;
+                skipped = 0;
+                for msg in msgs {
+                    pending.push_back((consumed, msg));
+                    consumed = 0;
+                }
+            }
+            Ok(MessageStreamItem::Done) => return None,
+            Ok(_) | Err(DltParseError::ParsingHickup(_)) => skipped += consumed,
+            Err(e) => {
+                warn!("stop reading pcap messages: {}", e);
+                return None;
+            }
+        }
+    }))
+}
+
 /// convert a PCAPNG file to a dlt file
+/// with a `redactor` the messages are redacted before they are written
 #[allow(clippy::too_many_arguments)]
 pub async fn pcap_to_dlt(
     pcap_path: &std::path::Path,
@@ -244,12 +302,14 @@ pub async fn pcap_to_dlt(
     update_channel: cc::Sender<VoidResults>,
     shutdown_receiver: sync::mpsc::Receiver<()>,
     fibex: Option<FibexConfig>,
+    mut redactor: Option<&mut Redactor>,
 ) -> Result<(), Error> {
     trace!("Starting pcap_to_dlt");
 
     let filter_config: Option<filtering::ProcessedDltFilterConfig> =
         dlt_filter.map(filtering::process_filter_config);
-    let fibex_metadata: Option<FibexMetadata> = fibex.map(gather_fibex_data).flatten();
+    let fibex_metadata: Option<FibexMetadata> =
+        fibex.and_then(|fibex| gather_fibex_data_cached(fibex, &update_channel));
     let pcap_file_size = pcap_path.metadata()?.len();
     let progress = |consumed: usize| {
         let _ = update_channel.send(Ok(IndexingProgress::Progress {
@@ -290,7 +350,12 @@ pub async fn pcap_to_dlt(
                                     processed_bytes += consumed;
                                     progress(processed_bytes);
                                 }
-                                for msg in msgs {
+                                for mut msg in msgs {
+                                    if let Some(redactor) = redactor.as_deref_mut() {
+                                        if redact_message(redactor, &mut msg) == Redaction::Drop {
+                                            continue;
+                                        }
+                                    }
                                     let msg_with_storage_header = match msg.storage_header {
                                         Some(_) => msg,
                                         None => msg.add_storage_header(None),
@@ -383,12 +448,18 @@ pub async fn index_from_pcap(
     fibex: Option<FibexConfig>,
 ) -> Result<(), Error> {
     trace!("index_from_pcap for  conf: {:?}", config);
-    let fibex_metadata: Option<FibexMetadata> = fibex.map(gather_fibex_data).flatten();
+    let fibex_metadata: Option<FibexMetadata> =
+        fibex.and_then(|fibex| gather_fibex_data_cached(fibex, &update_channel));
     let (out_file, current_out_file_size) =
         utils::get_out_file_and_size(config.append, &config.out_path)?;
-    let mut chunk_factory = ChunkFactory::new(config.chunk_size, current_out_file_size);
+    let mut chunk_factory =
+        ChunkFactory::new(config.chunk_size, current_out_file_size).with_index(&config.out_path)?;
     let mut line_nr = initial_line_nr;
-    let mut buf_writer = BufWriter::with_capacity(10 * 1024 * 1024, out_file);
+    let mut row_writer = RowWriter::new(
+        BufWriter::with_capacity(10 * 1024 * 1024, out_file),
+        &config.out_path,
+        current_out_file_size,
+    )?;
     let pcap_file_size = config.in_file.metadata().map(|md| md.len())?;
     let progress = |consumed: usize| {
         let _ = update_channel.send(Ok(IndexingProgress::Progress {
@@ -433,16 +504,15 @@ pub async fn index_from_pcap(
 
                                 for msg in msgs {
                                     let formattable_msg = FormattableMessage { message: msg, fibex_metadata: pcap_msg_producer.fibex(), options: None };
-                                    let written_bytes_len = utils::create_tagged_line_d(
+                                    let written_bytes_len = row_writer.write_row_d(
                                         &config.tag,
-                                        &mut buf_writer,
                                         &formattable_msg,
                                         line_nr,
                                         true,
                                     )?;
                                     line_nr += 1;
                                     if let Some(chunk) = chunk_factory.add_bytes(line_nr, written_bytes_len) {
-                                        buf_writer.flush()?;
+                                        row_writer.flush()?;
                                         chunk_count += 1;
                                         let _ =
                                             update_channel.send(Ok(IndexingProgress::GotItem { item: chunk }));
@@ -460,7 +530,7 @@ pub async fn index_from_pcap(
                             }
                             Ok(MessageStreamItem::Done) => {
                                 trace!("MessageStreamItem::Done received");
-                                buf_writer.flush()?;
+                                row_writer.flush()?;
                                 if let Some(chunk) = chunk_factory.create_last_chunk(line_nr, chunk_count == 0)
                                 {
                                     trace!("index: add last chunk {:?}", chunk);
@@ -0,0 +1,66 @@
+// Copyright (c) 2019 E.S.R.Labs. All rights reserved.
+//
+// NOTICE:  All information contained herein is, and remains
+// the property of E.S.R.Labs and its suppliers, if any.
+// The intellectual and technical concepts contained herein are
+// proprietary to E.S.R.Labs and its suppliers and may be covered
+// by German and Foreign Patents, patents in process, and are protected
+// by trade secret or copyright law.
+// Dissemination of this information or reproduction of this material
+// is strictly forbidden unless prior written permission is obtained
+// from E.S.R.Labs.
+use byteorder::{BigEndian, LittleEndian};
+use dlt_core::dlt::{Endianness, Message, PayloadContent, Value};
+use indexer_base::redaction::{Redaction, Redactor};
+
+/// Redact the verbose string arguments of a message in place.
+///
+/// A changed message gets a new payload length so it can be written with `as_bytes`.
+/// Non-verbose and control messages can't be redacted and are reported as unchecked.
+pub fn redact_message(redactor: &mut Redactor, msg: &mut Message) -> Redaction<()> {
+    let big_endian = msg.header.endianness == Endianness::Big;
+    let args = match &mut msg.payload {
+        PayloadContent::Verbose(args) => args,
+        _ => {
+            redactor.record_unchecked_entry();
+            return Redaction::Unchanged;
+        }
+    };
+    let mut outcome = Redaction::Unchanged;
+    for arg in args.iter_mut() {
+        if let Value::StringVal(s) = &mut arg.value {
+            match redactor.redact_text(s) {
+                Redaction::Unchanged => (),
+                Redaction::Changed(redacted) => {
+                    *s = redacted;
+                    outcome = Redaction::Changed(());
+                }
+                Redaction::Drop => {
+                    outcome = Redaction::Drop;
+                    break;
+                }
+            }
+        }
+    }
+    if outcome == Redaction::Changed(()) {
+        let payload_length: usize = args
+            .iter()
+            .map(|arg| {
+                if big_endian {
+                    arg.as_bytes::<BigEndian>().len()
+                } else {
+                    arg.as_bytes::<LittleEndian>().len()
+                }
+            })
+            .sum();
+        let header_length = (msg.header.overall_length() - msg.header.payload_length) as usize;
+        if header_length + payload_length > u16::MAX as usize {
+            warn!("redacted message does not fit into a dlt message, dropping it");
+            outcome = Redaction::Drop;
+        } else {
+            msg.header.payload_length = payload_length as u16;
+        }
+    }
+    redactor.record_entry(&outcome);
+    outcome
+}
@@ -0,0 +1,327 @@
+// Copyright (c) 2019 E.S.R.Labs. All rights reserved.
+//
+// NOTICE:  All information contained herein is, and remains
+// the property of E.S.R.Labs and its suppliers, if any.
+// The intellectual and technical concepts contained herein are
+// proprietary to E.S.R.Labs and its suppliers and may be covered
+// by German and Foreign Patents, patents in process, and are protected
+// by trade secret or copyright law.
+// Dissemination of this information or reproduction of this material
+// is strictly forbidden unless prior written permission is obtained
+// from E.S.R.Labs.
+use crate::dlt_file::{
+    read_one_dlt_message_info, storage_header_time, Error, DLT_PATTERN_SIZE, DLT_READER_CAPACITY,
+    STOP_CHECK_LINE_THRESHOLD, STORAGE_HEADER_LENGTH,
+};
+use buf_redux::{policy::MinBuffered, BufReader as ReduxReader};
+use byteorder::{BigEndian, ByteOrder};
+use chrono::NaiveDateTime;
+use crossbeam_channel as cc;
+use dlt_core::{
+    parse::{forward_to_next_storage_header, DltParseError, DLT_PATTERN},
+    statistics::StatisticRowInfo,
+};
+use indexer_base::{
+    compression::{CompressedPosition, InputFile},
+    progress::*,
+    utils,
+};
+use rustc_hash::FxHashMap;
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    io::{BufRead, BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+/// the biggest message including its storage header, messages are copied as a whole.
+/// The pattern of the following storage header has to be visible as well.
+const SPLIT_MIN_BUFFER_SPACE: usize = u16::MAX as usize + STORAGE_HEADER_LENGTH + DLT_PATTERN_SIZE;
+/// more open files are closed and reopened for appending when needed
+const MAX_OPEN_FILES: usize = 64;
+
+/// How the messages of a dlt file are distributed over the split files
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum SplitBy {
+    Ecu,
+    AppId,
+    /// one file per time window of the storage header timestamps, in ms
+    TimeWindow(u64),
+    /// a new file when the current one would get bigger than this many bytes
+    Size(u64),
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SplitFile {
+    pub path: PathBuf,
+    pub messages: usize,
+    pub bytes: u64,
+}
+
+pub type SplitResults = std::result::Result<IndexingProgress<Vec<SplitFile>>, Notification>;
+
+/// Writes the messages into `<out_dir>/<stem>_<key>.dlt`
+struct SplitWriter {
+    out_dir: PathBuf,
+    stem: String,
+    files: Vec<SplitFile>,
+    file_index: FxHashMap<String, usize>,
+    open: FxHashMap<String, BufWriter<fs::File>>,
+}
+
+impl SplitWriter {
+    fn new(out_dir: &Path, stem: String) -> Self {
+        SplitWriter {
+            out_dir: out_dir.to_path_buf(),
+            stem,
+            files: vec![],
+            file_index: FxHashMap::default(),
+            open: FxHashMap::default(),
+        }
+    }
+
+    fn write(&mut self, key: &str, message: &[u8]) -> Result<(), Error> {
+        let name = file_name_part(key);
+        if !self.open.contains_key(&name) {
+            if self.open.len() >= MAX_OPEN_FILES {
+                self.close_all()?;
+            }
+            let file = match self.file_index.get(&name) {
+                Some(&i) => fs::OpenOptions::new()
+                    .append(true)
+                    .open(&self.files[i].path)?,
+                None => {
+                    let path = self.out_dir.join(format!("{}_{}.dlt", self.stem, name));
+                    let file = fs::File::create(&path)?;
+                    self.file_index.insert(name.clone(), self.files.len());
+                    self.files.push(SplitFile {
+                        path,
+                        messages: 0,
+                        bytes: 0,
+                    });
+                    file
+                }
+            };
+            self.open.insert(name.clone(), BufWriter::new(file));
+        }
+        if let Some(out) = self.open.get_mut(&name) {
+            out.write_all(message)?;
+        }
+        if let Some(&i) = self.file_index.get(&name) {
+            self.files[i].messages += 1;
+            self.files[i].bytes += message.len() as u64;
+        }
+        Ok(())
+    }
+
+    fn close_all(&mut self) -> Result<(), Error> {
+        for (_, mut out) in self.open.drain() {
+            out.flush()?;
+        }
+        Ok(())
+    }
+
+    fn finish(mut self) -> Result<Vec<SplitFile>, Error> {
+        self.close_all()?;
+        Ok(self.files)
+    }
+}
+
+/// ids can contain anything, only keep what is safe in a file name
+fn file_name_part(key: &str) -> String {
+    let name: String = key
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '.' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if name.is_empty() {
+        "NONE".to_string()
+    } else {
+        name
+    }
+}
+
+fn window_name(window_start: u64) -> String {
+    NaiveDateTime::from_timestamp(
+        (window_start / 1000) as i64,
+        (window_start % 1000) as u32 * 1_000_000,
+    )
+    .format("%Y%m%dT%H%M%S%.3f")
+    .to_string()
+}
+
+/// Start of the message in the `consumed` bytes, content before its storage header
+/// is skipped. `None` if the length of the message does not fit: it differs from its
+/// header or the message runs into the storage header of the next message
+/// (`following` are the bytes after the message).
+fn message_start(consumed: &[u8], following: &[u8]) -> Option<usize> {
+    let (skipped, message) = forward_to_next_storage_header(consumed)?;
+    let length_field = message.get(STORAGE_HEADER_LENGTH + 2..STORAGE_HEADER_LENGTH + 4)?;
+    let length = STORAGE_HEADER_LENGTH + BigEndian::read_u16(length_field) as usize;
+    let next_message_follows = following.is_empty() || following.starts_with(DLT_PATTERN);
+    let contains_next_message =
+        forward_to_next_storage_header(&message[DLT_PATTERN_SIZE..]).is_some();
+    if message.len() == length && (next_message_follows || !contains_next_message) {
+        Some(skipped as usize)
+    } else {
+        None
+    }
+}
+
+/// Split a dlt file (with storage headers) into several dlt files in one pass.
+///
+/// Messages are copied unchanged, only the headers are parsed to find the file
+/// a message belongs to. Messages without an ECU or app id go to the `NONE` file,
+/// messages of a size split are never cut. Content between messages is dropped,
+/// messages with a length that does not fit are skipped.
+/// The written files are sent as item and returned, also when the split was stopped.
+pub fn split_dlt_file(
+    in_file: &Path,
+    out_dir: &Path,
+    split_by: &SplitBy,
+    update_channel: &cc::Sender<SplitResults>,
+    shutdown_receiver: Option<cc::Receiver<()>>,
+) -> Result<Vec<SplitFile>, Error> {
+    match split_by {
+        SplitBy::TimeWindow(0) => return Err(Error::Config("time window of 0 ms".to_string())),
+        SplitBy::Size(0) => return Err(Error::Config("split size of 0 bytes".to_string())),
+        _ => (),
+    }
+    let f = InputFile::open(in_file)?;
+    let compressed_position = f.compressed_position();
+    let source_file_size = fs::metadata(in_file)?.len();
+    let mut reader = ReduxReader::with_capacity(DLT_READER_CAPACITY, f)
+        .set_policy(MinBuffered(SPLIT_MIN_BUFFER_SPACE));
+
+    let stem = in_file
+        .file_stem()
+        .map(|stem| stem.to_string_lossy())
+        .unwrap_or_default();
+    let stem = stem.trim_end_matches(".dlt").to_string();
+    let mut writer = SplitWriter::new(out_dir, stem);
+
+    let mut index = 0usize;
+    let mut processed_bytes = 0u64;
+    let mut part = 0usize;
+    let mut part_size = 0u64;
+    let mut invalid_messages = 0usize;
+    loop {
+        match read_one_dlt_message_info(&mut reader, true) {
+            Ok(Some((consumed, info))) => {
+                let buffer = reader.buffer();
+                let start =
+                    match message_start(&buffer[..consumed as usize], &buffer[consumed as usize..])
+                    {
+                        Some(start) => start,
+                        None => {
+                            // continue searching for a message after this storage header
+                            let skip = forward_to_next_storage_header(&buffer[..consumed as usize])
+                                .map_or(consumed as usize, |(skipped, _)| {
+                                    skipped as usize + DLT_PATTERN_SIZE
+                                });
+                            reader.consume(skip);
+                            processed_bytes += skip as u64;
+                            invalid_messages += 1;
+                            continue;
+                        }
+                    };
+                let message = &buffer[start..consumed as usize];
+                let key = match split_by {
+                    SplitBy::Ecu => info.ecu_id.unwrap_or_default(),
+                    SplitBy::AppId => match info {
+                        StatisticRowInfo {
+                            app_id_context_id: Some((app_id, _)),
+                            ..
+                        } => app_id,
+                        _ => String::new(),
+                    },
+                    SplitBy::TimeWindow(window) => match storage_header_time(message) {
+                        Some(time) => window_name(time - time % window),
+                        None => String::new(),
+                    },
+                    SplitBy::Size(max_size) => {
+                        let size = message.len() as u64;
+                        if part_size > 0 && part_size + size > *max_size {
+                            part += 1;
+                            part_size = 0;
+                        }
+                        part_size += size;
+                        format!("{:04}", part)
+                    }
+                };
+                writer.write(&key, message)?;
+                reader.consume(consumed as usize);
+                processed_bytes += consumed;
+            }
+            Ok(None) => {
+                break;
+            }
+            Err(e) => match e {
+                DltParseError::ParsingHickup(reason) => {
+                    // we couldn't parse the message. try to skip it and find the next.
+                    reader.consume(4); // at least skip the magic DLT pattern
+                    debug!(
+                        "error parsing 1 dlt message, try to continue splitting: {}",
+                        reason
+                    );
+                }
+                DltParseError::Unrecoverable(cause) => {
+                    warn!("cannot continue splitting: {}", cause);
+                    let _ = update_channel.send(Err(Notification {
+                        severity: Severity::ERROR,
+                        content: format!("error parsing dlt file: {}", cause),
+                        line: None,
+                    }));
+                    break;
+                }
+                DltParseError::IncompleteParse { needed } => {
+                    warn!(
+                        "cannot continue splitting, parse was incomplete: {:?}",
+                        needed
+                    );
+                    let _ = update_channel.send(Err(Notification {
+                        severity: Severity::ERROR,
+                        content: format!("parse was incomplete: {:?}", needed),
+                        line: None,
+                    }));
+                    break;
+                }
+            },
+        }
+        index += 1;
+        if index.is_multiple_of(STOP_CHECK_LINE_THRESHOLD) {
+            if utils::check_if_stop_was_requested(shutdown_receiver.as_ref(), "dlt split") {
+                let _ = update_channel.send(Ok(IndexingProgress::Stopped));
+                break;
+            }
+            let position = compressed_position
+                .as_ref()
+                .map_or(processed_bytes, CompressedPosition::get);
+            let _ = update_channel.send(Ok(IndexingProgress::Progress {
+                ticks: (position, source_file_size),
+            }));
+        }
+    }
+    if invalid_messages > 0 {
+        let _ = update_channel.send(Err(Notification {
+            severity: Severity::WARNING,
+            content: format!(
+                "{} messages with invalid length were skipped",
+                invalid_messages
+            ),
+            line: None,
+        }));
+    }
+    let files = writer.finish()?;
+    let _ = update_channel.send(Ok(IndexingProgress::GotItem {
+        item: files.clone(),
+    }));
+    let _ = update_channel.send(Ok(IndexingProgress::Finished));
+    Ok(files)
+}
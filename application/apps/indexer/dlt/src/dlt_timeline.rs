@@ -0,0 +1,238 @@
+// Copyright (c) 2019 E.S.R.Labs. All rights reserved.
+//
+// NOTICE:  All information contained herein is, and remains
+// the property of E.S.R.Labs and its suppliers, if any.
+// The intellectual and technical concepts contained herein are
+// proprietary to E.S.R.Labs and its suppliers and may be covered
+// by German and Foreign Patents, patents in process, and are protected
+// by trade secret or copyright law.
+// Dissemination of this information or reproduction of this material
+// is strictly forbidden unless prior written permission is obtained
+// from E.S.R.Labs.
+use crossbeam_channel as cc;
+use dlt_core::{dlt::LogLevel, statistics::StatisticRowInfo};
+use indexer_base::progress::{IndexingProgress, Notification};
+use serde::{Deserialize, Serialize};
+use std::{borrow::Cow, collections::BTreeMap, io::Write};
+
+/// buckets are sent once messages this many buckets later were seen,
+/// messages that come later than that are sent in an additional bucket
+const LATE_BUCKETS: i64 = 2;
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq)]
+pub struct Throughput {
+    pub messages: usize,
+    pub bytes: u64,
+}
+
+impl Throughput {
+    fn add(&mut self, other: &Throughput) {
+        self.messages += other.messages;
+        self.bytes += other.bytes;
+    }
+}
+
+/// Messages and bytes of a time window (storage header time), in total and
+/// by ECU, app id, context id and log level
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct TimelineBucket {
+    /// posix time in ms
+    pub start: i64,
+    pub duration_ms: u64,
+    pub total: Throughput,
+    pub ecu_ids: BTreeMap<String, Throughput>,
+    pub app_ids: BTreeMap<String, Throughput>,
+    pub context_ids: BTreeMap<String, Throughput>,
+    /// `non_log` for messages that are not log messages
+    pub levels: BTreeMap<String, Throughput>,
+}
+
+impl TimelineBucket {
+    fn add(&mut self, other: &TimelineBucket) {
+        self.total.add(&other.total);
+        for (mine, theirs) in [
+            (&mut self.ecu_ids, &other.ecu_ids),
+            (&mut self.app_ids, &other.app_ids),
+            (&mut self.context_ids, &other.context_ids),
+            (&mut self.levels, &other.levels),
+        ] {
+            for (id, throughput) in theirs {
+                mine.entry(id.clone()).or_default().add(throughput);
+            }
+        }
+    }
+}
+
+pub type TimelineResults = std::result::Result<IndexingProgress<TimelineBucket>, Notification>;
+
+fn level_name(level: Option<LogLevel>) -> &'static str {
+    match level {
+        Some(LogLevel::Fatal) => "fatal",
+        Some(LogLevel::Error) => "error",
+        Some(LogLevel::Warn) => "warning",
+        Some(LogLevel::Info) => "info",
+        Some(LogLevel::Debug) => "debug",
+        Some(LogLevel::Verbose) => "verbose",
+        Some(LogLevel::Invalid(_)) => "invalid",
+        None => "non_log",
+    }
+}
+
+/// Counts the messages of a statistics pass into buckets of `bucket_ms` and sends
+/// every bucket as `GotItem` as soon as it is complete.
+pub struct TimelineCollector {
+    bucket_ms: u64,
+    buckets: BTreeMap<i64, TimelineBucket>,
+    newest: i64,
+    update_channel: cc::Sender<TimelineResults>,
+}
+
+impl TimelineCollector {
+    pub fn new(bucket_ms: u64, update_channel: cc::Sender<TimelineResults>) -> Self {
+        TimelineCollector {
+            bucket_ms: bucket_ms.max(1),
+            buckets: BTreeMap::new(),
+            newest: i64::MIN,
+            update_channel,
+        }
+    }
+
+    /// add a message of `bytes` (with storage header) that was logged at `timestamp` (ms)
+    pub fn add(&mut self, timestamp: i64, bytes: u64, info: &StatisticRowInfo) {
+        let bucket_ms = self.bucket_ms as i64;
+        let start = timestamp - timestamp.rem_euclid(bucket_ms);
+        let throughput = Throughput { messages: 1, bytes };
+        let bucket = self.buckets.entry(start).or_insert_with(|| TimelineBucket {
+            start,
+            duration_ms: bucket_ms as u64,
+            ..Default::default()
+        });
+        bucket.total.add(&throughput);
+        let ecu_id = info.ecu_id.clone().unwrap_or_else(|| "NONE".to_string());
+        bucket.ecu_ids.entry(ecu_id).or_default().add(&throughput);
+        let (app_id, context_id) = info
+            .app_id_context_id
+            .clone()
+            .unwrap_or_else(|| ("NONE".to_string(), "NONE".to_string()));
+        bucket.app_ids.entry(app_id).or_default().add(&throughput);
+        bucket
+            .context_ids
+            .entry(context_id)
+            .or_default()
+            .add(&throughput);
+        bucket
+            .levels
+            .entry(level_name(info.level).to_string())
+            .or_default()
+            .add(&throughput);
+
+        if start > self.newest {
+            self.newest = start;
+            self.send_older_than(start.saturating_sub(LATE_BUCKETS * bucket_ms));
+        }
+    }
+
+    fn send_older_than(&mut self, start: i64) {
+        let newer = self.buckets.split_off(&start);
+        let complete = std::mem::replace(&mut self.buckets, newer);
+        for (_, bucket) in complete {
+            let _ = self
+                .update_channel
+                .send(Ok(IndexingProgress::GotItem { item: bucket }));
+        }
+    }
+
+    /// send the remaining buckets
+    pub fn finish(mut self) {
+        self.send_older_than(i64::MAX);
+        let _ = self.update_channel.send(Ok(IndexingProgress::Finished));
+    }
+}
+
+/// Sort buckets by time and add up buckets of the same time (sent again because of
+/// late messages).
+pub fn combine_buckets(buckets: Vec<TimelineBucket>) -> Vec<TimelineBucket> {
+    let mut combined: BTreeMap<i64, TimelineBucket> = BTreeMap::new();
+    for bucket in buckets {
+        match combined.get_mut(&bucket.start) {
+            Some(existing) => existing.add(&bucket),
+            None => {
+                combined.insert(bucket.start, bucket);
+            }
+        }
+    }
+    combined.into_values().collect()
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum TimelineFormat {
+    /// one row per bucket and id with rates per second
+    Csv,
+    /// the buckets as json array
+    Json,
+}
+
+/// ids come from the messages, so they are quoted if needed
+fn csv_field(field: &str) -> Cow<'_, str> {
+    if field.contains([',', '"', '\n', '\r']) {
+        Cow::Owned(format!("\"{}\"", field.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(field)
+    }
+}
+
+fn write_csv_row<W: Write>(
+    out: &mut W,
+    bucket: &TimelineBucket,
+    kind: &str,
+    id: &str,
+    throughput: &Throughput,
+) -> std::io::Result<()> {
+    let seconds = bucket.duration_ms as f64 / 1000.0;
+    writeln!(
+        out,
+        "{},{},{},{},{},{},{:.3},{:.3}",
+        bucket.start,
+        bucket.duration_ms,
+        kind,
+        csv_field(id),
+        throughput.messages,
+        throughput.bytes,
+        throughput.messages as f64 / seconds,
+        throughput.bytes as f64 / seconds
+    )
+}
+
+pub fn write_timeline<W: Write>(
+    buckets: &[TimelineBucket],
+    format: TimelineFormat,
+    mut out: W,
+) -> std::io::Result<()> {
+    match format {
+        TimelineFormat::Json => {
+            serde_json::to_writer_pretty(&mut out, buckets)?;
+            writeln!(out)
+        }
+        TimelineFormat::Csv => {
+            writeln!(
+                out,
+                "start,duration_ms,kind,id,messages,bytes,messages_per_second,bytes_per_second"
+            )?;
+            for bucket in buckets {
+                write_csv_row(&mut out, bucket, "total", "", &bucket.total)?;
+                for (kind, ids) in [
+                    ("ecu", &bucket.ecu_ids),
+                    ("app_id", &bucket.app_ids),
+                    ("context_id", &bucket.context_ids),
+                    ("level", &bucket.levels),
+                ] {
+                    for (id, throughput) in ids {
+                        write_csv_row(&mut out, bucket, kind, id, throughput)?;
+                    }
+                }
+            }
+            Ok(())
+        }
+    }
+}
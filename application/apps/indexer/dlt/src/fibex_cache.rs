@@ -0,0 +1,484 @@
+// Copyright (c) 2019 E.S.R.Labs. All rights reserved.
+//
+// NOTICE:  All information contained herein is, and remains
+// the property of E.S.R.Labs and its suppliers, if any.
+// The intellectual and technical concepts contained herein are
+// proprietary to E.S.R.Labs and its suppliers and may be covered
+// by German and Foreign Patents, patents in process, and are protected
+// by trade secret or copyright law.
+// Dissemination of this information or reproduction of this material
+// is strictly forbidden unless prior written permission is obtained
+// from E.S.R.Labs.
+use crossbeam_channel as cc;
+use dlt_core::fibex::{gather_fibex_data, FibexConfig, FibexMetadata};
+use indexer_base::progress::{IndexingProgress, Notification, Severity};
+use lazy_static::lazy_static;
+use quick_xml::{
+    events::{BytesDecl, BytesEnd, BytesStart, Event},
+    Reader, Writer,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap},
+    fmt, fs,
+    hash::{Hash, Hasher},
+    io::{self, BufRead, BufWriter, Write},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::UNIX_EPOCH,
+};
+use thiserror::Error;
+
+/// the elements of a fibex file that are used to decode non-verbose messages
+const KEPT_ELEMENTS: [&[u8]; 4] = [b"FRAME", b"PDU", b"SIGNAL", b"CODING"];
+const CACHE_ROOT: &[u8] = b"CACHED-FIBEX";
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("IO error: {0:?}")]
+    Io(#[from] io::Error),
+    #[error("XML error: {0}")]
+    Xml(String),
+    #[error("Cache manifest error: {0}")]
+    Manifest(#[from] serde_json::Error),
+    #[error("Could not read fibex data from {0:?}")]
+    Fibex(Vec<String>),
+}
+
+impl From<quick_xml::Error> for Error {
+    fn from(e: quick_xml::Error) -> Self {
+        match e {
+            quick_xml::Error::Io(e) => Error::Io(e),
+            e => Error::Xml(e.to_string()),
+        }
+    }
+}
+
+/// Identifies the version of a fibex file that was read
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct FibexFileStamp {
+    pub path: PathBuf,
+    /// nanoseconds since the epoch
+    pub modified: u64,
+    pub size: u64,
+}
+
+impl FibexFileStamp {
+    fn of(path: &Path) -> Result<Self, Error> {
+        let path = fs::canonicalize(path)?;
+        let metadata = fs::metadata(&path)?;
+        let modified = metadata
+            .modified()?
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos() as u64)
+            .unwrap_or(0);
+        Ok(FibexFileStamp {
+            path,
+            modified,
+            size: metadata.len(),
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum FibexElement {
+    Frame,
+    Pdu,
+    Signal,
+    Coding,
+}
+
+/// An element that is defined differently in two places. Like dlt-core does for
+/// frames and PDUs we use the first definition and ignore the later one. dlt-core
+/// would use the last definition of signals and codings, the condensed file only
+/// keeps the first one of those as well.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct FibexConflict {
+    pub element: FibexElement,
+    pub id: String,
+    pub used_file: PathBuf,
+    pub ignored_file: PathBuf,
+}
+
+impl fmt::Display for FibexConflict {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let element = match self.element {
+            FibexElement::Frame => "frame",
+            FibexElement::Pdu => "PDU",
+            FibexElement::Signal => "signal",
+            FibexElement::Coding => "coding",
+        };
+        write!(
+            f,
+            "{} {} in {:?} conflicts with the definition in {:?} (which is used)",
+            element, self.id, self.ignored_file, self.used_file
+        )
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct CacheManifest {
+    files: Vec<FibexFileStamp>,
+    conflicts: Vec<FibexConflict>,
+}
+
+#[derive(Debug)]
+pub struct CachedFibex {
+    pub metadata: FibexMetadata,
+    pub conflicts: Vec<FibexConflict>,
+}
+
+/// Keeps fibex metadata in memory by the paths and modification times of the
+/// fibex files.
+///
+/// With a cache directory the frames, PDUs, signals and codings of the files are
+/// also written to one condensed fibex file, which is read instead of the
+/// original files the next time the same versions are requested.
+pub struct FibexCache {
+    cache_dir: Option<PathBuf>,
+    loaded: HashMap<Vec<FibexFileStamp>, Arc<CachedFibex>>,
+}
+
+impl FibexCache {
+    pub fn new(cache_dir: Option<PathBuf>) -> Self {
+        FibexCache {
+            cache_dir,
+            loaded: HashMap::new(),
+        }
+    }
+
+    pub fn load(&mut self, fibex: &FibexConfig) -> Result<Arc<CachedFibex>, Error> {
+        let stamps = fibex
+            .fibex_file_paths
+            .iter()
+            .map(|p| FibexFileStamp::of(Path::new(p)))
+            .collect::<Result<Vec<FibexFileStamp>, Error>>()?;
+        if let Some(cached) = self.loaded.get(&stamps) {
+            return Ok(cached.clone());
+        }
+        let cached = Arc::new(match &self.cache_dir {
+            Some(dir) => match read_cache_entry(dir, &stamps) {
+                Some(cached) => cached,
+                None => create_cache_entry(dir, &stamps)?,
+            },
+            None => {
+                let paths: Vec<PathBuf> = stamps.iter().map(|s| s.path.clone()).collect();
+                let condensed = tempfile::Builder::new()
+                    .suffix(".xml")
+                    .tempfile()?
+                    .into_temp_path();
+                let (metadata, conflicts) = condense_and_read(&paths, &condensed)?;
+                CachedFibex {
+                    metadata,
+                    conflicts,
+                }
+            }
+        });
+        self.loaded.insert(stamps, cached.clone());
+        Ok(cached)
+    }
+}
+
+pub fn default_cache_dir() -> Option<PathBuf> {
+    dirs::home_dir().map(|home_dir| home_dir.join(".chipmunk").join("fibex_cache"))
+}
+
+lazy_static! {
+    static ref FIBEX_CACHE: Mutex<FibexCache> = Mutex::new(FibexCache::new(default_cache_dir()));
+}
+
+/// Replace the process wide cache. With `None` the fibex metadata is only kept in
+/// memory, otherwise the condensed files are written to `cache_dir` instead of
+/// the `default_cache_dir`.
+pub fn init_fibex_cache(cache_dir: Option<PathBuf>) {
+    match FIBEX_CACHE.lock() {
+        Ok(mut cache) => *cache = FibexCache::new(cache_dir),
+        Err(poisoned) => *poisoned.into_inner() = FibexCache::new(cache_dir),
+    }
+}
+
+/// Drop-in for `gather_fibex_data` that goes through the process wide cache.
+/// Conflicting definitions are reported as warnings.
+pub fn gather_fibex_data_cached<T>(
+    fibex: FibexConfig,
+    update_channel: &cc::Sender<Result<IndexingProgress<T>, Notification>>,
+) -> Option<FibexMetadata> {
+    if fibex.fibex_file_paths.is_empty() {
+        return None;
+    }
+    let loaded = match FIBEX_CACHE.lock() {
+        Ok(mut cache) => cache.load(&fibex),
+        Err(poisoned) => poisoned.into_inner().load(&fibex),
+    };
+    match loaded {
+        Ok(cached) => {
+            for conflict in &cached.conflicts {
+                warn!("{}", conflict);
+                let _ = update_channel.send(Err(Notification {
+                    severity: Severity::WARNING,
+                    content: conflict.to_string(),
+                    line: None,
+                }));
+            }
+            Some(cached.metadata.clone())
+        }
+        Err(e) => {
+            warn!("fibex cache not usable ({}), reading fibex files", e);
+            gather_fibex_data(fibex)
+        }
+    }
+}
+
+fn read_fibex_files(paths: &[PathBuf]) -> Result<FibexMetadata, Error> {
+    let fibex_file_paths: Vec<String> = paths
+        .iter()
+        .map(|p| p.to_string_lossy().to_string())
+        .collect();
+    gather_fibex_data(FibexConfig {
+        fibex_file_paths: fibex_file_paths.clone(),
+    })
+    .ok_or(Error::Fibex(fibex_file_paths))
+}
+
+fn entry_name(stamps: &[FibexFileStamp]) -> String {
+    let mut hasher = DefaultHasher::new();
+    stamps.hash(&mut hasher);
+    format!("{:016x}", hasher.finish())
+}
+
+fn read_cache_entry(dir: &Path, stamps: &[FibexFileStamp]) -> Option<CachedFibex> {
+    let name = entry_name(stamps);
+    let manifest: CacheManifest =
+        serde_json::from_reader(fs::File::open(dir.join(format!("{}.json", name))).ok()?).ok()?;
+    if manifest.files != stamps {
+        return None;
+    }
+    let condensed = dir.join(format!("{}.xml", name));
+    match read_fibex_files(&[condensed]) {
+        Ok(metadata) => {
+            debug!("using cached fibex {}", name);
+            Some(CachedFibex {
+                metadata,
+                conflicts: manifest.conflicts,
+            })
+        }
+        Err(e) => {
+            warn!("cached fibex {} not readable: {}", name, e);
+            None
+        }
+    }
+}
+
+/// Write the condensed file of the original files and its manifest, the metadata
+/// is read from the condensed file only. Entries for older versions of the same
+/// files are removed.
+fn create_cache_entry(dir: &Path, stamps: &[FibexFileStamp]) -> Result<CachedFibex, Error> {
+    let paths: Vec<PathBuf> = stamps.iter().map(|s| s.path.clone()).collect();
+    fs::create_dir_all(dir)?;
+    remove_outdated_entries(dir, stamps);
+    let name = entry_name(stamps);
+    let condensed = dir.join(format!("{}.xml", name));
+    let tmp_condensed = dir.join(format!("{}.xml.tmp", name));
+    let (metadata, conflicts) = match condense_and_read(&paths, &tmp_condensed) {
+        Ok(condensed) => condensed,
+        Err(e) => {
+            let _ = fs::remove_file(&tmp_condensed);
+            return Err(e);
+        }
+    };
+    fs::rename(&tmp_condensed, &condensed)?;
+    let manifest = CacheManifest {
+        files: stamps.to_vec(),
+        conflicts,
+    };
+    let tmp_manifest = dir.join(format!("{}.json.tmp", name));
+    serde_json::to_writer(fs::File::create(&tmp_manifest)?, &manifest)?;
+    fs::rename(&tmp_manifest, dir.join(format!("{}.json", name)))?;
+    Ok(CachedFibex {
+        metadata,
+        conflicts: manifest.conflicts,
+    })
+}
+
+/// Condense `paths` into the file `condensed` and read the metadata from it, so
+/// the original files are only parsed once.
+fn condense_and_read(
+    paths: &[PathBuf],
+    condensed: &Path,
+) -> Result<(FibexMetadata, Vec<FibexConflict>), Error> {
+    let mut writer = BufWriter::new(fs::File::create(condensed)?);
+    let conflicts = condense_fibex_files(paths, &mut writer)?;
+    writer.flush()?;
+    drop(writer);
+    Ok((read_fibex_files(&[condensed.to_path_buf()])?, conflicts))
+}
+
+fn remove_outdated_entries(dir: &Path, stamps: &[FibexFileStamp]) {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return,
+    };
+    for manifest_path in entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+    {
+        let outdated = fs::File::open(&manifest_path)
+            .ok()
+            .and_then(|f| serde_json::from_reader::<_, CacheManifest>(f).ok())
+            .is_some_and(|manifest| {
+                manifest.files.len() == stamps.len()
+                    && manifest
+                        .files
+                        .iter()
+                        .zip(stamps)
+                        .all(|(cached, current)| cached.path == current.path)
+            });
+        if outdated {
+            debug!("removing outdated fibex cache entry {:?}", manifest_path);
+            let _ = fs::remove_file(manifest_path.with_extension("xml"));
+            let _ = fs::remove_file(&manifest_path);
+        }
+    }
+}
+
+/// Write the elements that dlt-core reads from all `files` (in order) into one
+/// fibex file and return the elements that are defined differently in more than
+/// one place. Only the first definition of an element is written.
+pub fn condense_fibex_files<W: Write>(
+    files: &[PathBuf],
+    out: W,
+) -> Result<Vec<FibexConflict>, Error> {
+    let mut writer = Writer::new(out);
+    writer.write_event(Event::Decl(BytesDecl::new(b"1.0", Some(b"UTF-8"), None)))?;
+    writer.write_event(Event::Start(BytesStart::borrowed_name(CACHE_ROOT)))?;
+    let mut definitions: HashMap<(FibexElement, String), (usize, u64)> = HashMap::new();
+    let mut conflicts = vec![];
+    for (file_index, path) in files.iter().enumerate() {
+        let mut reader = Reader::from_file(path)?;
+        let mut buf = vec![];
+        // the root element is kept for its namespace declarations
+        let mut root: Option<Vec<u8>> = None;
+        loop {
+            match reader.read_event(&mut buf)? {
+                Event::Start(e) if root.is_none() => {
+                    root = Some(e.name().to_vec());
+                    writer.write_event(Event::Start(e))?;
+                }
+                Event::Start(e) if KEPT_ELEMENTS.contains(&e.local_name()) => {
+                    let element = match e.local_name() {
+                        b"FRAME" => Some(FibexElement::Frame),
+                        b"PDU" => Some(FibexElement::Pdu),
+                        b"SIGNAL" => Some(FibexElement::Signal),
+                        b"CODING" => Some(FibexElement::Coding),
+                        _ => None,
+                    };
+                    let id = id_attribute(&e);
+                    let e = e.into_owned();
+                    let mut copy = Writer::new(vec![]);
+                    let definition = copy_element(&mut reader, &mut copy, e, path)?;
+                    let first = match (element, id) {
+                        (Some(element), Some(id)) => {
+                            match definitions.get(&(element, id.clone())) {
+                                Some((first_index, first_definition)) => {
+                                    if *first_definition != definition {
+                                        conflicts.push(FibexConflict {
+                                            element,
+                                            id,
+                                            used_file: files[*first_index].clone(),
+                                            ignored_file: path.clone(),
+                                        });
+                                    }
+                                    false
+                                }
+                                None => {
+                                    definitions.insert((element, id), (file_index, definition));
+                                    true
+                                }
+                            }
+                        }
+                        _ => true,
+                    };
+                    if first {
+                        writer.inner().write_all(&copy.into_inner())?;
+                    }
+                }
+                Event::Eof => break,
+                _ => {}
+            }
+            buf.clear();
+        }
+        if let Some(root) = root {
+            writer.write_event(Event::End(BytesEnd::owned(root)))?;
+        }
+    }
+    writer.write_event(Event::End(BytesEnd::borrowed(CACHE_ROOT)))?;
+    writer.into_inner().flush()?;
+    Ok(conflicts)
+}
+
+fn id_attribute(e: &BytesStart) -> Option<String> {
+    e.attributes()
+        .with_checks(false)
+        .filter_map(Result::ok)
+        .find(|a| a.key == b"ID")
+        .map(|a| String::from_utf8_lossy(&a.value).to_string())
+}
+
+/// hash an element without its namespace prefixes
+fn hash_tag(local_name: &[u8], attributes: Option<&BytesStart>, hasher: &mut impl Hasher) {
+    local_name.hash(hasher);
+    if let Some(start) = attributes {
+        for attribute in start.attributes().with_checks(false).filter_map(Result::ok) {
+            if attribute.key.starts_with(b"xmlns") {
+                continue;
+            }
+            let key = attribute.key;
+            let local_key = key
+                .iter()
+                .position(|b| *b == b':')
+                .map_or(key, |i| &key[i + 1..]);
+            local_key.hash(hasher);
+            attribute.value.hash(hasher);
+        }
+    }
+}
+
+/// Copy the element that was started with `start` and return a hash of its
+/// content that ignores formatting.
+fn copy_element<B: BufRead, W: Write>(
+    reader: &mut Reader<B>,
+    writer: &mut Writer<W>,
+    start: BytesStart<'static>,
+    path: &Path,
+) -> Result<u64, Error> {
+    let mut hasher = DefaultHasher::new();
+    hash_tag(start.local_name(), Some(&start), &mut hasher);
+    writer.write_event(Event::Start(start))?;
+    let mut depth = 1usize;
+    let mut buf = vec![];
+    while depth > 0 {
+        let event = reader.read_event(&mut buf)?;
+        match &event {
+            Event::Start(e) => {
+                depth += 1;
+                hash_tag(e.local_name(), Some(e), &mut hasher);
+            }
+            Event::Empty(e) => hash_tag(e.local_name(), Some(e), &mut hasher),
+            Event::End(e) => {
+                depth -= 1;
+                hash_tag(e.local_name(), None, &mut hasher);
+            }
+            Event::Text(e) | Event::CData(e) => e.escaped().trim_ascii().hash(&mut hasher),
+            Event::Eof => {
+                return Err(Error::Xml(format!("unexpected end of {:?}", path)));
+            }
+            _ => {}
+        }
+        if !matches!(event, Event::Comment(_)) {
+            writer.write_event(event)?;
+        }
+        buf.clear();
+    }
+    Ok(hasher.finish())
+}
@@ -16,8 +16,14 @@ extern crate lazy_static;
 extern crate log;
 
 pub mod dlt_file;
+pub mod dlt_health;
 pub mod dlt_net;
+pub mod dlt_non_verbose;
 pub mod dlt_pcap;
+pub mod dlt_redaction;
+pub mod dlt_split;
+pub mod dlt_timeline;
+pub mod fibex_cache;
 
 #[cfg(test)]
 mod tests;
@@ -10,9 +10,8 @@ mod tests {
         let in_path = PathBuf::from("..")
             .join("dlt/test_samples")
             .join("lukas_crash.dlt");
-        let out_path = PathBuf::from("..")
-            .join("dlt/test_samples")
-            .join("lukas_crash.dlt.out");
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let out_path = tmp_dir.path().join("lukas_crash.dlt.out");
 
         let source_file_size = std::fs::metadata(&in_path).unwrap().len();
         let (tx, _rx): (cc::Sender<ChunkResults>, cc::Receiver<ChunkResults>) = cc::unbounded();
@@ -32,6 +31,38 @@ mod tests {
             &tx,
             None,
             None,
+            None,
         );
     }
+
+    #[test]
+    fn test_dlt_timed_lines() {
+        let in_path = PathBuf::from("..")
+            .join("dlt/test_samples")
+            .join("timed_messages.dlt");
+        // storage header times 2019-05-22T12:36:10, 12:36:20.5 and 12:36:50
+        let file = std::fs::File::open(&in_path).unwrap();
+        let lines: Vec<_> = dlt_timed_lines(file, "TAG".to_string(), None, 5).collect();
+        assert_eq!(
+            vec![1_558_528_570_000, 1_558_528_580_500, 1_558_528_610_000],
+            lines.iter().map(|l| l.timestamp).collect::<Vec<i64>>()
+        );
+        assert_eq!(
+            vec![5, 6, 7],
+            lines.iter().map(|l| l.line_nr).collect::<Vec<usize>>()
+        );
+        assert!(lines.iter().all(|l| l.tag == "TAG"));
+        assert_eq!(
+            std::fs::metadata(&in_path).unwrap().len() as usize,
+            lines.iter().map(|l| l.original_length).sum::<usize>()
+        );
+        assert!(lines[1].content.contains("dlt message 1"));
+
+        // ECU timestamps are 1000, 1001 and 1002 ticks of 0.1 ms
+        let file = std::fs::File::open(&in_path).unwrap();
+        let timestamps: Vec<i64> = dlt_timed_lines(file, "TAG".to_string(), Some(1_000_000), 0)
+            .map(|l| l.timestamp)
+            .collect();
+        assert_eq!(vec![1_000_100, 1_000_100, 1_000_100], timestamps);
+    }
 }
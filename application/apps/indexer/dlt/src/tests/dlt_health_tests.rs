@@ -0,0 +1,92 @@
+#[cfg(test)]
+mod tests {
+    use crate::dlt_health::*;
+    use crossbeam_channel as cc;
+    use pretty_assertions::assert_eq;
+    use std::path::{Path, PathBuf};
+
+    /// size of each message in timed_messages.dlt
+    const MESSAGE_SIZE: usize = 58;
+
+    fn sample() -> Vec<u8> {
+        std::fs::read(
+            PathBuf::from("..")
+                .join("dlt/test_samples")
+                .join("timed_messages.dlt"),
+        )
+        .unwrap()
+    }
+
+    fn analyze(path: &Path) -> DltHealthReport {
+        let (tx, _rx): (cc::Sender<HealthResults>, cc::Receiver<HealthResults>) = cc::unbounded();
+        analyze_dlt_health(path, DEFAULT_CLOCK_TOLERANCE_MS, &tx, None).unwrap()
+    }
+
+    #[test]
+    fn test_health_of_intact_file() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let path = tmp_dir.path().join("intact.dlt");
+        std::fs::write(&path, sample()).unwrap();
+        let report = analyze(&path);
+        assert_eq!(3, report.messages);
+        assert_eq!(0, report.corrupted_bytes);
+        assert_eq!(0, report.counter_gaps.count);
+        assert_eq!(0, report.time_jumps.count);
+        assert_eq!(None, report.truncated_message);
+        // the ECU time only advances 0.1 ms per message, the storage time seconds
+        assert_eq!(2, report.clock_disagreements.count);
+        assert_eq!(10_500, report.clock_disagreements.listed[0].drift_ms);
+    }
+
+    #[test]
+    fn test_health_of_damaged_file() {
+        let original = sample();
+        let first = &original[..MESSAGE_SIZE];
+        let last = &original[2 * MESSAGE_SIZE..];
+        let mut damaged = first.to_vec();
+        damaged.extend_from_slice(b"xxxxxxxxxx");
+        damaged.extend_from_slice(last);
+        damaged.extend_from_slice(first);
+        damaged.extend_from_slice(&first[..30]);
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let path = tmp_dir.path().join("damaged.dlt");
+        std::fs::write(&path, &damaged).unwrap();
+
+        let report = analyze(&path);
+        assert_eq!(3, report.messages);
+        assert_eq!(10, report.corrupted_bytes);
+        assert_eq!(
+            vec![CorruptedRegion {
+                offset: MESSAGE_SIZE as u64,
+                length: 10,
+                resync_offset: Some(MESSAGE_SIZE as u64 + 10),
+            }],
+            report.corrupted_regions.listed
+        );
+        let gaps: Vec<(u8, u8, usize)> = report
+            .counter_gaps
+            .listed
+            .iter()
+            .map(|gap| (gap.expected, gap.actual, gap.missing))
+            .collect();
+        assert_eq!(vec![(1, 2, 1), (3, 0, 253)], gaps);
+        assert_eq!(
+            vec![TimeJump {
+                offset: 2 * MESSAGE_SIZE as u64 + 10,
+                message_index: 2,
+                previous_ms: 1_558_528_610_000,
+                timestamp_ms: 1_558_528_570_000,
+            }],
+            report.time_jumps.listed
+        );
+        assert_eq!(
+            Some(3 * MESSAGE_SIZE as u64 + 10),
+            report.truncated_message.as_ref().map(|t| t.offset)
+        );
+        let ecu = &report.ecus["ECU1"];
+        assert_eq!(
+            (3, 254, 1),
+            (ecu.messages, ecu.missing_messages, ecu.ecu_restarts)
+        );
+    }
+}
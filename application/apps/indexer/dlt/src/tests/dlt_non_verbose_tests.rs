@@ -0,0 +1,143 @@
+#[cfg(test)]
+mod tests {
+    use crate::dlt_non_verbose::*;
+    use crossbeam_channel as cc;
+    use dlt_core::{
+        dlt::{
+            DltTimeStamp, Endianness, ExtendedHeaderConfig, LogLevel, Message, MessageConfig,
+            MessageType, PayloadContent, StorageHeader,
+        },
+        fibex::FibexConfig,
+    };
+    use pretty_assertions::assert_eq;
+    use std::path::{Path, PathBuf};
+
+    fn non_verbose(app_id: &str, message_id: u32, data: Vec<u8>) -> Vec<u8> {
+        Message::new(
+            MessageConfig {
+                version: 1,
+                counter: 0,
+                endianness: Endianness::Little,
+                ecu_id: Some("ECU1".to_string()),
+                session_id: None,
+                timestamp: None,
+                payload: PayloadContent::NonVerbose(message_id, data),
+                extended_header_info: Some(ExtendedHeaderConfig {
+                    message_type: MessageType::Log(LogLevel::Warn),
+                    app_id: app_id.to_string(),
+                    context_id: "CTX1".to_string(),
+                }),
+            },
+            Some(StorageHeader {
+                timestamp: DltTimeStamp::from_ms(1000),
+                ecu_id: "ECU1".to_string(),
+            }),
+        )
+        .as_bytes()
+    }
+
+    fn write_sample(dir: &Path) -> PathBuf {
+        let mut content = vec![];
+        content.extend(non_verbose("DR", 65, vec![0; 24]));
+        content.extend(non_verbose("APP1", 99, vec![1, 2, 3]));
+        content.extend(non_verbose("DR", 65, vec![0; 24]));
+        content.extend(non_verbose("APP1", 99, vec![4, 5]));
+        content.extend(non_verbose("APP1", 99, vec![1, 2, 3]));
+        content.extend(std::fs::read("../dlt/test_samples/timed_messages.dlt").unwrap());
+        let path = dir.join("non_verbose.dlt");
+        std::fs::write(&path, content).unwrap();
+        path
+    }
+
+    fn report(path: &Path, fibex: Option<FibexConfig>) -> NonVerboseReport {
+        let (tx, _rx): (
+            cc::Sender<NonVerboseResults>,
+            cc::Receiver<NonVerboseResults>,
+        ) = cc::unbounded();
+        get_non_verbose_report(path, fibex, &tx, None).unwrap()
+    }
+
+    #[test]
+    fn test_non_verbose_report_with_fibex() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let path = write_sample(tmp_dir.path());
+        let fibex = FibexConfig {
+            fibex_file_paths: vec!["../dlt/tests/dlt-messages.xml".to_string()],
+        };
+        let report = report(&path, Some(fibex));
+        assert!(report.fibex_loaded);
+        assert_eq!(
+            (5, 2, 3),
+            (
+                report.messages,
+                report.resolved_messages,
+                report.unresolved_messages
+            )
+        );
+        assert_eq!(
+            vec![
+                NonVerboseId {
+                    app_id: Some("APP1".to_string()),
+                    context_id: Some("CTX1".to_string()),
+                    message_id: 99,
+                    count: 3,
+                    resolved: false,
+                    samples: vec!["01 02 03".to_string(), "04 05".to_string()],
+                },
+                NonVerboseId {
+                    app_id: Some("DR".to_string()),
+                    context_id: Some("CTX1".to_string()),
+                    message_id: 65,
+                    count: 2,
+                    resolved: true,
+                    samples: vec![],
+                },
+            ],
+            report.ids
+        );
+    }
+
+    #[test]
+    fn test_non_verbose_report_frame_without_pdus_is_unresolved() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let fibex_path = tmp_dir.path().join("no_pdus.xml");
+        let empty_frame = r#"<fx:FRAME ID="ID_70">
+                <ho:SHORT-NAME>empty</ho:SHORT-NAME>
+                <fx:BYTE-LENGTH>0</fx:BYTE-LENGTH>
+                <fx:FRAME-TYPE>OTHER</fx:FRAME-TYPE>
+                <fx:PDU-INSTANCES>
+                </fx:PDU-INSTANCES>
+                <fx:MANUFACTURER-EXTENSION>
+                    <APPLICATION_ID>DR</APPLICATION_ID>
+                    <CONTEXT_ID>CTX1</CONTEXT_ID>
+                </fx:MANUFACTURER-EXTENSION>
+            </fx:FRAME>
+        </fx:FRAMES>"#;
+        std::fs::write(
+            &fibex_path,
+            std::fs::read_to_string("../dlt/tests/dlt-messages.xml")
+                .unwrap()
+                .replace("</fx:FRAMES>", empty_frame),
+        )
+        .unwrap();
+        let path = tmp_dir.path().join("empty_frame.dlt");
+        std::fs::write(&path, non_verbose("DR", 70, vec![])).unwrap();
+        let fibex = FibexConfig {
+            fibex_file_paths: vec![fibex_path.to_string_lossy().to_string()],
+        };
+        let report = report(&path, Some(fibex));
+        assert!(report.fibex_loaded);
+        assert_eq!(1, report.unresolved_messages);
+        assert!(!report.ids[0].resolved);
+    }
+
+    #[test]
+    fn test_non_verbose_report_without_fibex() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let path = write_sample(tmp_dir.path());
+        let report = report(&path, None);
+        assert!(!report.fibex_loaded);
+        assert_eq!(5, report.unresolved_messages);
+        assert!(report.ids.iter().all(|id| !id.resolved));
+    }
+}
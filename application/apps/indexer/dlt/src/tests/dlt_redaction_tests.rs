@@ -0,0 +1,136 @@
+#[cfg(test)]
+mod tests {
+    use crate::dlt_file::*;
+    use crossbeam_channel as cc;
+    use dlt_core::fmt::FormattableMessage;
+    use indexer_base::{
+        chunks::ChunkResults,
+        config::SectionConfig,
+        redaction::{RedactionAction, RedactionConfig, RedactionPattern, RedactionRule, Redactor},
+    };
+    use pretty_assertions::assert_eq;
+    use std::path::PathBuf;
+
+    fn rule(name: &str, pattern: &str, action: RedactionAction) -> RedactionRule {
+        RedactionRule {
+            name: name.to_string(),
+            pattern: RedactionPattern::Regex {
+                pattern: pattern.to_string(),
+            },
+            action,
+        }
+    }
+
+    #[test]
+    fn test_export_redacted_dlt_file() {
+        let in_path = PathBuf::from("..")
+            .join("dlt/test_samples")
+            .join("timed_messages.dlt");
+        let original = std::fs::read(&in_path).unwrap();
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let out_path = tmp_dir.path().join("redacted.dlt");
+        let mut redactor = Redactor::new(RedactionConfig {
+            salt: String::new(),
+            rules: vec![
+                rule("mask", "message 1", RedactionAction::Mask),
+                rule("drop", "message 2", RedactionAction::Drop),
+            ],
+        })
+        .unwrap();
+        let (tx, _rx): (cc::Sender<ChunkResults>, cc::Receiver<ChunkResults>) = cc::unbounded();
+        export_as_dlt_file_with_slots(
+            in_path,
+            out_path.clone(),
+            SectionConfig { sections: vec![] },
+            vec![],
+            Some(&mut redactor),
+            tx,
+        )
+        .unwrap();
+
+        let redacted = std::fs::read(&out_path).unwrap();
+        let messages: Vec<(usize, String)> = dlt_messages(redacted.as_slice(), true)
+            .map(|(consumed, message)| {
+                let formatted = FormattableMessage {
+                    message,
+                    fibex_metadata: None,
+                    options: None,
+                };
+                (consumed, formatted.to_string())
+            })
+            .collect();
+        assert_eq!(2, messages.len());
+        assert_eq!(redacted.len(), messages.iter().map(|m| m.0).sum::<usize>());
+        // the first message is copied as is
+        assert_eq!(original[..messages[0].0], redacted[..messages[0].0]);
+        assert!(messages[0].1.contains("dlt message 0"));
+        assert!(messages[1].1.contains("dlt *********"));
+
+        let report = redactor.report();
+        assert_eq!(
+            (3, 1, 1, 0),
+            (
+                report.entries,
+                report.changed_entries,
+                report.dropped_entries,
+                report.unchecked_entries
+            )
+        );
+        assert_eq!(1, report.rules["mask"].matches);
+        assert_eq!(1, report.rules["drop"].entries);
+    }
+
+    #[test]
+    fn test_export_hashed_dlt_file_changes_payload_length() {
+        let in_path = PathBuf::from("..")
+            .join("dlt/test_samples")
+            .join("timed_messages.dlt");
+        let original_lengths: Vec<usize> =
+            dlt_messages(std::fs::File::open(&in_path).unwrap(), true)
+                .map(|(consumed, _)| consumed)
+                .collect();
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let out_path = tmp_dir.path().join("hashed.dlt");
+        let mut redactor = Redactor::new(RedactionConfig {
+            salt: String::new(),
+            rules: vec![rule("hash", "message 1", RedactionAction::Hash)],
+        })
+        .unwrap();
+        let (tx, _rx): (cc::Sender<ChunkResults>, cc::Receiver<ChunkResults>) = cc::unbounded();
+        export_as_dlt_file_with_slots(
+            in_path,
+            out_path.clone(),
+            SectionConfig { sections: vec![] },
+            vec![],
+            Some(&mut redactor),
+            tx,
+        )
+        .unwrap();
+
+        let redacted = std::fs::read(&out_path).unwrap();
+        let messages: Vec<(usize, String)> = dlt_messages(redacted.as_slice(), true)
+            .map(|(consumed, message)| {
+                let formatted = FormattableMessage {
+                    message,
+                    fibex_metadata: None,
+                    options: None,
+                };
+                (consumed, formatted.to_string())
+            })
+            .collect();
+        assert_eq!(3, messages.len());
+        // "message 1" (9 bytes) is replaced by '#' and 16 hex digits
+        assert_eq!(
+            vec![
+                original_lengths[0],
+                original_lengths[1] + 8,
+                original_lengths[2]
+            ],
+            messages.iter().map(|m| m.0).collect::<Vec<usize>>()
+        );
+        assert_eq!(redacted.len(), messages.iter().map(|m| m.0).sum::<usize>());
+        assert!(messages[1].1.contains("dlt #"));
+        assert!(messages[2].1.contains("dlt message 2"));
+        assert_eq!(1, redactor.report().changed_entries);
+    }
+}
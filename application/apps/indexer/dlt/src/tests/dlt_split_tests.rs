@@ -0,0 +1,121 @@
+#[cfg(test)]
+mod tests {
+    use crate::{dlt_file::dlt_messages_with_bytes, dlt_split::*};
+    use crossbeam_channel as cc;
+    use pretty_assertions::assert_eq;
+    use std::path::PathBuf;
+
+    fn split(split_by: SplitBy, out_dir: &std::path::Path) -> Vec<SplitFile> {
+        let in_path = PathBuf::from("..")
+            .join("dlt/test_samples")
+            .join("timed_messages.dlt");
+        let (tx, _rx): (cc::Sender<SplitResults>, cc::Receiver<SplitResults>) = cc::unbounded();
+        split_dlt_file(&in_path, out_dir, &split_by, &tx, None).unwrap()
+    }
+
+    fn names_and_counts(files: &[SplitFile]) -> Vec<(String, usize)> {
+        files
+            .iter()
+            .map(|f| {
+                (
+                    f.path.file_name().unwrap().to_string_lossy().to_string(),
+                    f.messages,
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_split_by_ecu_keeps_all_messages() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let files = split(SplitBy::Ecu, tmp_dir.path());
+        assert_eq!(
+            vec![("timed_messages_ECU1.dlt".to_string(), 3)],
+            names_and_counts(&files)
+        );
+        let original = std::fs::read("../dlt/test_samples/timed_messages.dlt").unwrap();
+        assert_eq!(original, std::fs::read(&files[0].path).unwrap());
+        assert_eq!(original.len() as u64, files[0].bytes);
+    }
+
+    #[test]
+    fn test_split_by_time_window() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let files = split(SplitBy::TimeWindow(30_000), tmp_dir.path());
+        assert_eq!(
+            vec![
+                ("timed_messages_20190522T123600.000.dlt".to_string(), 2),
+                ("timed_messages_20190522T123630.000.dlt".to_string(), 1)
+            ],
+            names_and_counts(&files)
+        );
+    }
+
+    #[test]
+    fn test_split_by_size() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let files = split(SplitBy::Size(120), tmp_dir.path());
+        assert_eq!(
+            vec![
+                ("timed_messages_0000.dlt".to_string(), 2),
+                ("timed_messages_0001.dlt".to_string(), 1)
+            ],
+            names_and_counts(&files)
+        );
+        for file in files {
+            assert_eq!(file.bytes, std::fs::metadata(&file.path).unwrap().len());
+        }
+    }
+
+    #[test]
+    fn test_split_skips_garbage_and_invalid_messages() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let messages: Vec<Vec<u8>> = dlt_messages_with_bytes(
+            std::fs::File::open("../dlt/test_samples/timed_messages.dlt").unwrap(),
+            true,
+        )
+        .map(|(_, _, bytes)| bytes)
+        .collect();
+        // garbage between the messages and a message that was cut off
+        let mut content = messages[0].clone();
+        content.extend_from_slice(b"garbage");
+        content.extend_from_slice(&messages[1][..messages[1].len() - 5]);
+        content.extend_from_slice(&messages[2]);
+        let in_path = tmp_dir.path().join("broken.dlt");
+        std::fs::write(&in_path, content).unwrap();
+
+        let (tx, rx): (cc::Sender<SplitResults>, cc::Receiver<SplitResults>) = cc::unbounded();
+        let files = split_dlt_file(&in_path, tmp_dir.path(), &SplitBy::Ecu, &tx, None).unwrap();
+        assert_eq!(
+            vec![("broken_ECU1.dlt".to_string(), 2)],
+            names_and_counts(&files)
+        );
+        assert_eq!(
+            [messages[0].clone(), messages[2].clone()].concat(),
+            std::fs::read(&files[0].path).unwrap()
+        );
+        assert!(rx.try_iter().any(|result| matches!(
+            result,
+            Err(notification) if notification.content.starts_with("1 messages with invalid length")
+        )));
+
+        // the time windows are taken from the storage headers, not from the garbage
+        let window_dir = tmp_dir.path().join("windows");
+        std::fs::create_dir(&window_dir).unwrap();
+        let files = split_dlt_file(
+            &in_path,
+            &window_dir,
+            &SplitBy::TimeWindow(30_000),
+            &tx,
+            None,
+        )
+        .unwrap();
+        assert_eq!(
+            vec![
+                ("broken_20190522T123600.000.dlt".to_string(), 1),
+                ("broken_20190522T123630.000.dlt".to_string(), 1)
+            ],
+            names_and_counts(&files)
+        );
+    }
+}
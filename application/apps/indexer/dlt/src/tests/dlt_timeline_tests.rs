@@ -0,0 +1,99 @@
+#[cfg(test)]
+mod tests {
+    use crate::{dlt_file::*, dlt_timeline::*};
+    use crossbeam_channel as cc;
+    use indexer_base::progress::IndexingProgress;
+    use pretty_assertions::assert_eq;
+    use std::path::{Path, PathBuf};
+
+    fn sample_path() -> PathBuf {
+        PathBuf::from("..")
+            .join("dlt/test_samples")
+            .join("timed_messages.dlt")
+    }
+
+    fn timeline_of_sample(bucket_ms: u64) -> Vec<TimelineBucket> {
+        timeline_of(&sample_path(), bucket_ms)
+    }
+
+    fn timeline_of(in_path: &Path, bucket_ms: u64) -> Vec<TimelineBucket> {
+        let (tx, _rx): (
+            cc::Sender<StatisticsResults>,
+            cc::Receiver<StatisticsResults>,
+        ) = cc::unbounded();
+        let (timeline_tx, timeline_rx): (
+            cc::Sender<TimelineResults>,
+            cc::Receiver<TimelineResults>,
+        ) = cc::unbounded();
+        get_dlt_file_info_with_timeline(in_path, &tx, Some((bucket_ms, timeline_tx)), None)
+            .unwrap();
+        let buckets = timeline_rx
+            .try_iter()
+            .filter_map(|item| match item {
+                Ok(IndexingProgress::GotItem { item }) => Some(item),
+                _ => None,
+            })
+            .collect();
+        combine_buckets(buckets)
+    }
+
+    #[test]
+    fn test_timeline_buckets() {
+        let buckets = timeline_of_sample(30_000);
+        let counts: Vec<(i64, usize, u64)> = buckets
+            .iter()
+            .map(|b| (b.start, b.total.messages, b.total.bytes))
+            .collect();
+        assert_eq!(
+            vec![(1_558_528_560_000, 2, 116), (1_558_528_590_000, 1, 58)],
+            counts
+        );
+        assert_eq!(2, buckets[0].app_ids["APP1"].messages);
+        assert_eq!(2, buckets[0].levels["info"].messages);
+    }
+
+    #[test]
+    fn test_timeline_as_csv() {
+        let buckets = timeline_of_sample(60_000);
+        let mut out = vec![];
+        write_timeline(&buckets, TimelineFormat::Csv, &mut out).unwrap();
+        let csv = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = csv.lines().take(3).collect();
+        assert_eq!(
+            vec![
+                "start,duration_ms,kind,id,messages,bytes,messages_per_second,bytes_per_second",
+                "1558528560000,60000,total,,3,174,0.050,2.900",
+                "1558528560000,60000,ecu,ECU1,3,174,0.050,2.900",
+            ],
+            lines
+        );
+    }
+
+    #[test]
+    fn test_timeline_skips_garbage_before_messages() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let in_path = tmp_dir.path().join("garbage.dlt");
+        let mut content = vec![];
+        for (_, _, bytes) in
+            dlt_messages_with_bytes(std::fs::File::open(sample_path()).unwrap(), true)
+        {
+            content.extend_from_slice(b"garbage");
+            content.extend(bytes);
+        }
+        std::fs::write(&in_path, content).unwrap();
+        assert_eq!(timeline_of_sample(30_000), timeline_of(&in_path, 30_000));
+    }
+
+    #[test]
+    fn test_timeline_csv_quotes_ids() {
+        let mut buckets = timeline_of_sample(60_000);
+        let throughput = buckets[0].app_ids["APP1"];
+        buckets[0].app_ids.insert("A,\"B".to_string(), throughput);
+        let mut out = vec![];
+        write_timeline(&buckets, TimelineFormat::Csv, &mut out).unwrap();
+        let csv = String::from_utf8(out).unwrap();
+        assert!(csv
+            .lines()
+            .any(|line| line.starts_with("1558528560000,60000,app_id,\"A,\"\"B\",3,")));
+    }
+}
@@ -0,0 +1,171 @@
+#[cfg(test)]
+mod tests {
+    use crate::fibex_cache::*;
+    use dlt_core::fibex::{gather_fibex_data, FibexConfig};
+    use pretty_assertions::assert_eq;
+    use std::path::{Path, PathBuf};
+
+    const FIBEX: &str = "../dlt/tests/dlt-messages.xml";
+
+    fn config(paths: &[&Path]) -> FibexConfig {
+        FibexConfig {
+            fibex_file_paths: paths
+                .iter()
+                .map(|p| p.to_string_lossy().to_string())
+                .collect(),
+        }
+    }
+
+    fn cache_entries(dir: &Path, extension: &str) -> Vec<PathBuf> {
+        std::fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == extension))
+            .collect()
+    }
+
+    #[test]
+    fn test_fibex_cache_on_disk() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let fibex_path = tmp_dir.path().join("messages.xml");
+        std::fs::copy(FIBEX, &fibex_path).unwrap();
+        let cache_dir = tmp_dir.path().join("cache");
+        let original = gather_fibex_data(config(&[&fibex_path])).unwrap();
+
+        let loaded = FibexCache::new(Some(cache_dir.clone()))
+            .load(&config(&[&fibex_path]))
+            .unwrap();
+        assert_eq!(original, loaded.metadata);
+        assert!(loaded.conflicts.is_empty());
+        let condensed = cache_entries(&cache_dir, "xml");
+        assert_eq!(1, condensed.len());
+        assert_eq!(
+            original,
+            gather_fibex_data(config(&[&condensed[0]])).unwrap()
+        );
+
+        // a new cache (like in a new session) reads the condensed file
+        let from_disk = FibexCache::new(Some(cache_dir.clone()))
+            .load(&config(&[&fibex_path]))
+            .unwrap();
+        assert_eq!(original, from_disk.metadata);
+
+        // a changed file replaces the outdated entry
+        let changed = std::fs::read_to_string(FIBEX).unwrap().replace(
+            "<ho:SHORT-NAME>direction</ho:SHORT-NAME>",
+            "<ho:SHORT-NAME>dir</ho:SHORT-NAME>",
+        );
+        std::fs::write(&fibex_path, changed).unwrap();
+        let reloaded = FibexCache::new(Some(cache_dir.clone()))
+            .load(&config(&[&fibex_path]))
+            .unwrap();
+        assert_eq!(
+            gather_fibex_data(config(&[&fibex_path])).unwrap(),
+            reloaded.metadata
+        );
+        assert!(original != reloaded.metadata);
+        assert_eq!(1, cache_entries(&cache_dir, "json").len());
+        assert_eq!(1, cache_entries(&cache_dir, "xml").len());
+    }
+
+    #[test]
+    fn test_fibex_conflicts() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let fibex = std::fs::read_to_string(FIBEX).unwrap();
+        let first = tmp_dir.path().join("first.xml");
+        let same = tmp_dir.path().join("same.xml");
+        let conflicting = tmp_dir.path().join("conflicting.xml");
+        std::fs::write(&first, &fibex).unwrap();
+        // formatting differences are no conflict
+        std::fs::write(&same, fibex.replace("    ", "\t")).unwrap();
+        std::fs::write(
+            &conflicting,
+            fibex
+                .replace(
+                    "<ho:DESC>speed: </ho:DESC>",
+                    "<ho:DESC>velocity: </ho:DESC>",
+                )
+                .replace(
+                    "<ho:SHORT-NAME>direction</ho:SHORT-NAME>",
+                    "<ho:SHORT-NAME>dir</ho:SHORT-NAME>",
+                ),
+        )
+        .unwrap();
+
+        let mut cache = FibexCache::new(None);
+        assert!(cache
+            .load(&config(&[&first, &same]))
+            .unwrap()
+            .conflicts
+            .is_empty());
+        let loaded = cache.load(&config(&[&first, &conflicting])).unwrap();
+        assert_eq!(
+            gather_fibex_data(config(&[&first, &conflicting])).unwrap(),
+            loaded.metadata
+        );
+        let first = std::fs::canonicalize(first).unwrap();
+        let conflicting = std::fs::canonicalize(conflicting).unwrap();
+        assert_eq!(
+            vec![
+                FibexConflict {
+                    element: FibexElement::Pdu,
+                    id: "ID_4012".to_string(),
+                    used_file: first.clone(),
+                    ignored_file: conflicting.clone(),
+                },
+                FibexConflict {
+                    element: FibexElement::Frame,
+                    id: "ID_64".to_string(),
+                    used_file: first,
+                    ignored_file: conflicting,
+                },
+            ],
+            loaded.conflicts
+        );
+    }
+
+    #[test]
+    fn test_signal_and_coding_conflicts_keep_first_definition() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let fibex = |signal_coding: &str, base_type: &str| {
+            format!(
+                r#"<?xml version="1.0" encoding="UTF-8"?>
+<fx:FIBEX xmlns:fx="http://www.asam.net/xml/fbx" xmlns:ho="http://www.asam.net/xml">
+    <fx:SIGNAL ID="S_TEST">
+        <fx:CODING-REF ID-REF="{}"/>
+    </fx:SIGNAL>
+    <fx:CODING ID="C_TEST">
+        <ho:CODED-TYPE ho:BASE-DATA-TYPE="{}"/>
+    </fx:CODING>
+</fx:FIBEX>"#,
+                signal_coding, base_type
+            )
+        };
+        let first = tmp_dir.path().join("first.xml");
+        let second = tmp_dir.path().join("second.xml");
+        std::fs::write(&first, fibex("C_TEST", "A_UINT32")).unwrap();
+        std::fs::write(&second, fibex("C_OTHER", "A_INT64")).unwrap();
+
+        let mut condensed = vec![];
+        let conflicts =
+            condense_fibex_files(&[first.clone(), second.clone()], &mut condensed).unwrap();
+        assert_eq!(
+            vec![
+                (FibexElement::Signal, "S_TEST".to_string()),
+                (FibexElement::Coding, "C_TEST".to_string()),
+            ],
+            conflicts
+                .iter()
+                .map(|c| (c.element, c.id.clone()))
+                .collect::<Vec<_>>()
+        );
+        assert!(conflicts
+            .iter()
+            .all(|c| c.used_file == first && c.ignored_file == second));
+        let condensed = String::from_utf8(condensed).unwrap();
+        assert_eq!(1, condensed.matches("<fx:SIGNAL ").count());
+        assert_eq!(1, condensed.matches("<fx:CODING ").count());
+        assert!(condensed.contains("A_UINT32"));
+        assert!(!condensed.contains("A_INT64"));
+    }
+}
@@ -1,3 +1,9 @@
 #[macro_use]
 mod dlt_file_tests;
+mod dlt_health_tests;
 mod dlt_net_tests;
+mod dlt_non_verbose_tests;
+mod dlt_redaction_tests;
+mod dlt_split_tests;
+mod dlt_timeline_tests;
+mod fibex_cache_tests;
@@ -11,10 +11,19 @@
 // from E.S.R.Labs.
 use crate::progress::{IndexingProgress, Notification};
 use serde::{Deserialize, Serialize};
-use std::fs;
+use std::{
+    fs,
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
+};
 use thiserror::Error;
 
 pub type ChunkResults = std::result::Result<IndexingProgress<Chunk>, Notification>;
+/// extension of the chunk index that is written next to an indexed output
+pub const CHUNK_INDEX_EXTENSION: &str = "chunks";
+/// rows and bytes of a chunk as 4 little endian `u64`
+const CHUNK_RECORD_LENGTH: usize = 4 * 8;
+
 pub type VoidResults = std::result::Result<IndexingProgress<()>, Notification>;
 
 #[derive(Error, Debug)]
@@ -36,12 +45,79 @@ pub fn serialize_chunks(chunks: &[Chunk], out_file_name: &std::path::Path) -> Re
     fs::write(out_file_name, j)?;
     Ok(())
 }
+
+/// path of the chunk index that belongs to an indexed output
+pub fn chunk_index_path(out_path: &Path) -> PathBuf {
+    let mut name = out_path.as_os_str().to_owned();
+    name.push(format!(".{}", CHUNK_INDEX_EXTENSION));
+    PathBuf::from(name)
+}
+
+/// The chunks of an indexed output as they were persisted by the `ChunkFactory`
+#[derive(Debug, Default)]
+pub struct ChunkIndex {
+    chunks: Vec<Chunk>,
+}
+
+impl ChunkIndex {
+    /// load the chunk index of `out_path`, an incomplete chunk at the end
+    /// (the index is still written) is ignored
+    pub fn load(out_path: &Path) -> Result<ChunkIndex, Error> {
+        let mut bytes = vec![];
+        fs::File::open(chunk_index_path(out_path))?.read_to_end(&mut bytes)?;
+        let chunks = bytes
+            .chunks_exact(CHUNK_RECORD_LENGTH)
+            .map(|record| {
+                let value = |i: usize| {
+                    let mut number = [0u8; 8];
+                    number.copy_from_slice(&record[i * 8..(i + 1) * 8]);
+                    u64::from_le_bytes(number) as usize
+                };
+                Chunk {
+                    r: (value(0), value(1)),
+                    b: (value(2), value(3)),
+                }
+            })
+            .collect();
+        Ok(ChunkIndex { chunks })
+    }
+
+    pub fn chunks(&self) -> &[Chunk] {
+        &self.chunks
+    }
+
+    /// number of rows covered by the index
+    pub fn rows(&self) -> usize {
+        self.chunks.last().map(|c| c.r.1 + 1).unwrap_or(0)
+    }
+
+    /// Byte range of the chunks that contain the rows `first_row..=last_row`.
+    /// Rows after the last chunk are not part of the range, `None` if `first_row`
+    /// is not covered by the index.
+    pub fn byte_range(&self, first_row: usize, last_row: usize) -> Option<(usize, usize)> {
+        if first_row > last_row {
+            return None;
+        }
+        let first = self.chunk_for_row(first_row)?;
+        let last = self
+            .chunk_for_row(last_row)
+            .or_else(|| self.chunks.last())?;
+        Some((first.b.0, last.b.1))
+    }
+
+    fn chunk_for_row(&self, row: usize) -> Option<&Chunk> {
+        let index = self.chunks.partition_point(|c| c.r.1 < row);
+        self.chunks.get(index).filter(|c| c.r.0 <= row)
+    }
+}
+
 pub struct ChunkFactory {
     pub chunk_size: usize, // how many lines in one chunk?
     pub start_of_chunk_byte_index: usize,
     last_line_current_chunk: usize,
     current_byte_index: usize,
     lines_in_chunk: usize,
+    index: Option<fs::File>,
 }
 
 impl ChunkFactory {
@@ -52,6 +128,47 @@ impl ChunkFactory {
             current_byte_index: start_of_chunk_byte_index,
             last_line_current_chunk: 0,
             lines_in_chunk: 0,
+            index: None,
+        }
+    }
+
+    /// Persist every chunk that is created in the chunk index of `out_path`.
+    /// If the output is appended to (`start_of_chunk_byte_index` > 0) the
+    /// existing index is continued, otherwise it is started from scratch.
+    pub fn with_index(mut self, out_path: &Path) -> io::Result<ChunkFactory> {
+        let path = chunk_index_path(out_path);
+        let index = if self.start_of_chunk_byte_index > 0 {
+            let index = fs::OpenOptions::new()
+                .append(true)
+                .create(true)
+                .open(path)?;
+            // drop an incomplete chunk that was not written to the end
+            let len = index.metadata()?.len();
+            index.set_len(len - len % CHUNK_RECORD_LENGTH as u64)?;
+            index
+        } else {
+            fs::File::create(path)?
+        };
+        self.index = Some(index);
+        Ok(self)
+    }
+
+    fn persist(&mut self, chunk: &Chunk) {
+        if chunk.r.1 < chunk.r.0 {
+            return;
+        }
+        if let Some(index) = &mut self.index {
+            let mut record = [0u8; CHUNK_RECORD_LENGTH];
+            for (i, value) in [chunk.r.0, chunk.r.1, chunk.b.0, chunk.b.1]
+                .iter()
+                .enumerate()
+            {
+                record[i * 8..(i + 1) * 8].copy_from_slice(&(*value as u64).to_le_bytes());
+            }
+            if let Err(e) = index.write_all(&record) {
+                warn!("could not persist chunk {:?}: {}", chunk, e);
+                self.index = None;
+            }
         }
     }
     pub fn get_current_byte_index(&self) -> usize {
@@ -73,6 +190,7 @@ impl ChunkFactory {
 
             self.start_of_chunk_byte_index = self.current_byte_index + 1;
             self.lines_in_chunk = 0;
+            self.persist(&chunk);
             return Some(chunk);
         }
         None
@@ -90,6 +208,7 @@ impl ChunkFactory {
                     ),
                     b: (self.start_of_chunk_byte_index, self.current_byte_index),
                 };
+                self.persist(&chunk);
                 return Some(chunk);
             }
         }
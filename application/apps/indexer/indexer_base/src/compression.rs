@@ -0,0 +1,891 @@
+// Copyright (c) 2019 E.S.R.Labs. All rights reserved.
+//
+// NOTICE:  All information contained herein is, and remains
+// the property of E.S.R.Labs and its suppliers, if any.
+// The intellectual and technical concepts contained herein are
+// proprietary to E.S.R.Labs and its suppliers and may be covered
+// by German and Foreign Patents, patents in process, and are protected
+// by trade secret or copyright law.
+// Dissemination of this information or reproduction of this material
+// is strictly forbidden unless prior written permission is obtained
+// from E.S.R.Labs.
+use miniz_oxide::inflate::{
+    core::{decompress, inflate_flags, BlockBoundaryState, DecompressorOxide, TINFL_LZ_DICT_SIZE},
+    TINFLStatus,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    io::{self, BufRead, BufReader, Read, Seek, SeekFrom},
+    ops::Range,
+    path::Path,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+const XZ_MAGIC: &[u8] = &[0xfd, 0x37, 0x7a, 0x58, 0x5a, 0x00];
+const COMPRESSED_READER_CAPACITY: usize = 64 * 1024;
+/// minimal distance (in decompressed bytes) between two checkpoints
+pub const CHECKPOINT_INTERVAL: u64 = 4 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Compression {
+    Gzip,
+    Zstd,
+    Xz,
+}
+
+impl Compression {
+    /// identify the compression by the magic bytes at the start of the content
+    pub fn from_magic(start: &[u8]) -> Option<Compression> {
+        if start.starts_with(GZIP_MAGIC) {
+            Some(Compression::Gzip)
+        } else if start.starts_with(ZSTD_MAGIC) {
+            Some(Compression::Zstd)
+        } else if start.starts_with(XZ_MAGIC) {
+            Some(Compression::Xz)
+        } else {
+            None
+        }
+    }
+}
+
+/// check if a file is compressed with one of the supported formats
+pub fn detect_compression(path: &Path) -> io::Result<Option<Compression>> {
+    let mut start = vec![];
+    fs::File::open(path)?
+        .take(XZ_MAGIC.len() as u64)
+        .read_to_end(&mut start)?;
+    Ok(Compression::from_magic(&start))
+}
+
+/// When several files are processed together and at least one of them is compressed,
+/// progress has to be tracked with a shared position for all of them.
+pub fn shared_position_if_compressed<P: AsRef<Path>>(paths: &[P]) -> Option<CompressedPosition> {
+    let any_compressed = paths
+        .iter()
+        .any(|p| matches!(detect_compression(p.as_ref()), Ok(Some(_))));
+    if any_compressed {
+        Some(CompressedPosition::default())
+    } else {
+        None
+    }
+}
+
+/// A position in a compressed file where decoding can be started.
+///
+/// Most checkpoints are at the start of a gzip member, a zstd frame or a xz stream.
+/// Large members also get checkpoints inside of them: between two deflate blocks of
+/// a gzip member and at the blocks of a xz stream that have their size in the block
+/// header (as written by multi-threaded encoders). zstd frames can only be decoded
+/// from their start.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Checkpoint {
+    pub compressed: u64,
+    pub decompressed: u64,
+    /// decoder state for checkpoints inside of a member
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub state: Option<MemberState>,
+}
+
+/// What is needed to start decoding inside of a member
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum MemberState {
+    /// Between two deflate blocks of a gzip member. The next block can start
+    /// with bits of the last consumed byte and can refer to the preceding
+    /// 32 KiB of decompressed content (stored deflate compressed in `window`).
+    Deflate {
+        num_bits: u8,
+        bit_buf: u8,
+        window: Vec<u8>,
+    },
+    /// start of a block of a xz stream with the flags of the stream
+    XzBlock { stream_flags: [u8; 2] },
+}
+
+/// Shared counter of the bytes that were consumed from files on disk.
+/// Used to report progress relative to the size of the (compressed) files.
+/// Several inputs can share one counter, e.g. when merging.
+#[derive(Debug, Clone, Default)]
+pub struct CompressedPosition(Arc<AtomicU64>);
+
+impl CompressedPosition {
+    pub fn get(&self) -> u64 {
+        self.0.load(Ordering::Relaxed)
+    }
+    fn advance(&self, amount: usize) {
+        self.0.fetch_add(amount as u64, Ordering::Relaxed);
+    }
+}
+
+struct CountingReader<R: BufRead> {
+    inner: R,
+    /// bytes consumed by this reader
+    consumed: u64,
+    position: CompressedPosition,
+}
+
+impl<R: BufRead> CountingReader<R> {
+    fn advance(&mut self, amount: usize) {
+        self.consumed += amount as u64;
+        self.position.advance(amount);
+    }
+}
+
+impl<R: BufRead> Read for CountingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.advance(n);
+        Ok(n)
+    }
+}
+
+impl<R: BufRead> BufRead for CountingReader<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.inner.fill_buf()
+    }
+    fn consume(&mut self, amt: usize) {
+        self.advance(amt);
+        self.inner.consume(amt)
+    }
+}
+
+/// The checkpoints of a decoder, members add their checkpoints while they are decoded
+struct Checkpoints {
+    list: Vec<Checkpoint>,
+    interval: u64,
+    /// compressed offset where the decoding was started
+    compressed_start: u64,
+}
+
+impl Checkpoints {
+    /// a checkpoint is due when the last one is at least `interval` bytes behind
+    fn is_due(&self, decompressed: u64) -> bool {
+        self.list.last().is_none_or(|last| {
+            decompressed > last.decompressed && decompressed - last.decompressed >= self.interval
+        })
+    }
+
+    /// `compressed` is relative to the start of the decoding
+    fn add(&mut self, compressed: u64, decompressed: u64, state: Option<MemberState>) {
+        self.list.push(Checkpoint {
+            compressed: self.compressed_start + compressed,
+            decompressed,
+            state,
+        });
+    }
+}
+
+fn skip<R: Read>(reader: &mut R, amount: u64) -> io::Result<()> {
+    if io::copy(&mut reader.take(amount), &mut io::sink())? == amount {
+        Ok(())
+    } else {
+        Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "premature end of compressed content",
+        ))
+    }
+}
+
+fn read_byte<R: Read>(reader: &mut R) -> io::Result<u8> {
+    let mut byte = [0];
+    reader.read_exact(&mut byte)?;
+    Ok(byte[0])
+}
+
+fn invalid_data(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+const GZIP_FHCRC: u8 = 0x02;
+const GZIP_FEXTRA: u8 = 0x04;
+const GZIP_FNAME: u8 = 0x08;
+const GZIP_FCOMMENT: u8 = 0x10;
+const DEFLATE_WINDOW_SIZE: usize = TINFL_LZ_DICT_SIZE;
+
+fn read_gzip_header<R: Read>(reader: &mut R) -> io::Result<()> {
+    let mut header = [0; 10];
+    reader.read_exact(&mut header)?;
+    if !header.starts_with(GZIP_MAGIC) || header[2] != 8 {
+        return Err(invalid_data("invalid gzip header"));
+    }
+    let flags = header[3];
+    if flags & GZIP_FEXTRA != 0 {
+        let mut len = [0; 2];
+        reader.read_exact(&mut len)?;
+        skip(reader, u16::from_le_bytes(len) as u64)?;
+    }
+    for flag in &[GZIP_FNAME, GZIP_FCOMMENT] {
+        if flags & flag != 0 {
+            while read_byte(reader)? != 0 {}
+        }
+    }
+    if flags & GZIP_FHCRC != 0 {
+        skip(reader, 2)?;
+    }
+    Ok(())
+}
+
+/// Decodes a single gzip member. The deflate stream is inflated block by block
+/// so the state between two blocks can be kept as checkpoint.
+struct GzipMember<R: BufRead> {
+    reader: CountingReader<R>,
+    inflater: Box<DecompressorOxide>,
+    /// ring buffer for the decompressed content, the last 32 KiB of it are
+    /// needed to inflate the following blocks
+    window: Vec<u8>,
+    window_pos: usize,
+    /// range of the window that was inflated but not yet returned
+    pending: Range<usize>,
+    /// checksum of the content, unknown if decoding started inside of the member
+    crc: Option<flate2::Crc>,
+    /// decompressed offset after the inflated content
+    decompressed: u64,
+    done: bool,
+}
+
+impl<R: BufRead> GzipMember<R> {
+    fn start(mut reader: CountingReader<R>, decompressed: u64) -> io::Result<Self> {
+        read_gzip_header(&mut reader)?;
+        Ok(Self {
+            reader,
+            inflater: Box::default(),
+            window: vec![0; DEFLATE_WINDOW_SIZE],
+            window_pos: 0,
+            pending: 0..0,
+            crc: Some(flate2::Crc::new()),
+            decompressed,
+            done: false,
+        })
+    }
+
+    fn resume(
+        reader: CountingReader<R>,
+        num_bits: u8,
+        bit_buf: u8,
+        window: &[u8],
+        decompressed: u64,
+    ) -> io::Result<Self> {
+        let window = miniz_oxide::inflate::decompress_to_vec(window)
+            .ok()
+            .filter(|window| window.len() == DEFLATE_WINDOW_SIZE)
+            .ok_or_else(|| invalid_data("invalid checkpoint window"))?;
+        let state = BlockBoundaryState {
+            num_bits,
+            bit_buf,
+            ..Default::default()
+        };
+        Ok(Self {
+            reader,
+            inflater: Box::new(DecompressorOxide::from_block_boundary_state(&state)),
+            window,
+            window_pos: 0,
+            pending: 0..0,
+            crc: None,
+            decompressed,
+            done: false,
+        })
+    }
+
+    fn read(&mut self, buf: &mut [u8], checkpoints: &mut Checkpoints) -> io::Result<usize> {
+        loop {
+            if !self.pending.is_empty() {
+                let n = self.pending.len().min(buf.len());
+                let start = self.pending.start;
+                buf[..n].copy_from_slice(&self.window[start..start + n]);
+                self.pending.start += n;
+                return Ok(n);
+            }
+            if self.done {
+                return Ok(0);
+            }
+            self.inflate(checkpoints)?;
+        }
+    }
+
+    fn inflate(&mut self, checkpoints: &mut Checkpoints) -> io::Result<()> {
+        let input = self.reader.fill_buf()?;
+        let flags = if input.is_empty() {
+            inflate_flags::TINFL_FLAG_STOP_ON_BLOCK_BOUNDARY
+        } else {
+            inflate_flags::TINFL_FLAG_STOP_ON_BLOCK_BOUNDARY
+                | inflate_flags::TINFL_FLAG_HAS_MORE_INPUT
+        };
+        let (status, consumed, written) = decompress(
+            &mut self.inflater,
+            input,
+            &mut self.window,
+            self.window_pos,
+            flags,
+        );
+        self.reader.consume(consumed);
+        self.pending = self.window_pos..self.window_pos + written;
+        if let Some(crc) = &mut self.crc {
+            crc.update(&self.window[self.pending.clone()]);
+        }
+        self.window_pos = (self.window_pos + written) % DEFLATE_WINDOW_SIZE;
+        self.decompressed += written as u64;
+        match status {
+            TINFLStatus::Done => {
+                self.read_trailer()?;
+                self.done = true;
+            }
+            TINFLStatus::BlockBoundary => {
+                if checkpoints.is_due(self.decompressed) {
+                    self.add_checkpoint(checkpoints);
+                }
+            }
+            TINFLStatus::NeedsMoreInput | TINFLStatus::HasMoreOutput => (),
+            TINFLStatus::FailedCannotMakeProgress => {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "premature end of gzip member",
+                ))
+            }
+            _ => return Err(invalid_data("corrupt deflate stream")),
+        }
+        Ok(())
+    }
+
+    fn add_checkpoint(&self, checkpoints: &mut Checkpoints) {
+        if let Some(state) = self.inflater.block_boundary_state() {
+            let mut window = self.window[self.window_pos..].to_vec();
+            window.extend_from_slice(&self.window[..self.window_pos]);
+            checkpoints.add(
+                self.reader.consumed,
+                self.decompressed,
+                Some(MemberState::Deflate {
+                    num_bits: state.num_bits,
+                    bit_buf: state.bit_buf,
+                    window: miniz_oxide::deflate::compress_to_vec(&window, 1),
+                }),
+            );
+        }
+    }
+
+    fn read_trailer(&mut self) -> io::Result<()> {
+        let mut trailer = [0; 8];
+        self.reader.read_exact(&mut trailer)?;
+        if let Some(crc) = &self.crc {
+            let sum = u32::from_le_bytes([trailer[0], trailer[1], trailer[2], trailer[3]]);
+            let size = u32::from_le_bytes([trailer[4], trailer[5], trailer[6], trailer[7]]);
+            if sum != crc.sum() || size != crc.amount() {
+                return Err(invalid_data("gzip checksum mismatch"));
+            }
+        }
+        Ok(())
+    }
+}
+
+const XZ_HEADER_SIZE: usize = 12;
+const XZ_FOOTER_SIZE: u64 = 12;
+
+/// size of the check at the end of each xz block for the check type in the stream flags
+fn xz_check_size(stream_flags: [u8; 2]) -> u64 {
+    match stream_flags[1] & 0x0f {
+        0 => 0,
+        1..=3 => 4,
+        4..=6 => 8,
+        7..=9 => 16,
+        10..=12 => 32,
+        _ => 64,
+    }
+}
+
+/// variable length integer as used in the xz format
+fn read_xz_vli<R: Read>(reader: &mut R) -> io::Result<u64> {
+    let mut value = 0;
+    for i in 0..9 {
+        let byte = read_byte(reader)?;
+        value |= ((byte & 0x7f) as u64) << (i * 7);
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(invalid_data("invalid xz integer"))
+}
+
+/// Size of a xz block including header, padding and check.
+/// `None` if the header does not contain the compressed size.
+fn xz_block_size(header: &[u8], stream_flags: [u8; 2]) -> io::Result<Option<u64>> {
+    const COMPRESSED_SIZE_PRESENT: u8 = 0x40;
+    if header.len() < 2 || header[1] & COMPRESSED_SIZE_PRESENT == 0 {
+        return Ok(None);
+    }
+    let compressed = read_xz_vli(&mut &header[2..])?;
+    let unpadded = header.len() as u64 + compressed;
+    Ok(Some(unpadded.div_ceil(4) * 4 + xz_check_size(stream_flags)))
+}
+
+/// Decodes a single xz stream. `xz2::bufread::XzDecoder` fails if more content
+/// follows the end of the stream, so we drive the stream ourselves.
+///
+/// The stream and block headers are read here and passed on to the decoder, that
+/// way the blocks are found. A block can be decoded on it's own by a new stream
+/// decoder that gets a copy of the stream header followed by the block. The index
+/// at the end of the stream is skipped then.
+struct XzMember<R: BufRead> {
+    reader: CountingReader<R>,
+    stream: xz2::stream::Stream,
+    stream_flags: [u8; 2],
+    /// headers that were read but not yet passed to the stream
+    headers: Vec<u8>,
+    /// compressed offset of the next block, unknown if a block header has no size
+    next_block: Option<u64>,
+    /// decoding started at a block, the stream decoder can't handle the index then
+    resumed: bool,
+    decompressed: u64,
+    done: bool,
+}
+
+impl<R: BufRead> XzMember<R> {
+    fn start(mut reader: CountingReader<R>, decompressed: u64) -> io::Result<Self> {
+        let mut header = vec![0; XZ_HEADER_SIZE];
+        reader.read_exact(&mut header)?;
+        let stream_flags = [header[6], header[7]];
+        Self::new(reader, header, stream_flags, false, decompressed)
+    }
+
+    fn resume(
+        reader: CountingReader<R>,
+        stream_flags: [u8; 2],
+        decompressed: u64,
+    ) -> io::Result<Self> {
+        let mut crc = flate2::Crc::new();
+        crc.update(&stream_flags);
+        let mut header = XZ_MAGIC.to_vec();
+        header.extend_from_slice(&stream_flags);
+        header.extend_from_slice(&crc.sum().to_le_bytes());
+        Self::new(reader, header, stream_flags, true, decompressed)
+    }
+
+    fn new(
+        reader: CountingReader<R>,
+        headers: Vec<u8>,
+        stream_flags: [u8; 2],
+        resumed: bool,
+        decompressed: u64,
+    ) -> io::Result<Self> {
+        Ok(Self {
+            next_block: Some(reader.consumed),
+            reader,
+            stream: xz2::stream::Stream::new_stream_decoder(u64::MAX, 0)?,
+            stream_flags,
+            headers,
+            resumed,
+            decompressed,
+            done: false,
+        })
+    }
+
+    fn read(&mut self, buf: &mut [u8], checkpoints: &mut Checkpoints) -> io::Result<usize> {
+        while !self.done {
+            let from_headers = !self.headers.is_empty();
+            let until_next_block = self.next_block.map(|next| next - self.reader.consumed);
+            if !from_headers && until_next_block == Some(0) {
+                self.start_block(checkpoints)?;
+                continue;
+            }
+            let input = if from_headers {
+                &self.headers[..]
+            } else {
+                let input = self.reader.fill_buf()?;
+                match until_next_block {
+                    Some(limit) if (limit as usize) < input.len() => &input[..limit as usize],
+                    _ => input,
+                }
+            };
+            let eof = input.is_empty();
+            let (before_in, before_out) = (self.stream.total_in(), self.stream.total_out());
+            let action = if eof {
+                xz2::stream::Action::Finish
+            } else {
+                xz2::stream::Action::Run
+            };
+            let status = self.stream.process(input, buf, action);
+            let consumed = (self.stream.total_in() - before_in) as usize;
+            let read = (self.stream.total_out() - before_out) as usize;
+            if from_headers {
+                self.headers.drain(..consumed);
+            } else {
+                self.reader.consume(consumed);
+            }
+            match status? {
+                xz2::stream::Status::StreamEnd => self.done = true,
+                _ if read == 0 && eof => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::UnexpectedEof,
+                        "premature end of xz stream",
+                    ))
+                }
+                _ if read == 0 && consumed == 0 => return Err(invalid_data("corrupt xz stream")),
+                _ => (),
+            }
+            if read > 0 {
+                self.decompressed += read as u64;
+                return Ok(read);
+            }
+        }
+        Ok(0)
+    }
+
+    /// read the header of the next block (or the start of the index)
+    fn start_block(&mut self, checkpoints: &mut Checkpoints) -> io::Result<()> {
+        let block_start = self.reader.consumed;
+        let header_size = read_byte(&mut self.reader)?;
+        if header_size == 0 {
+            self.next_block = None;
+            if self.resumed {
+                self.skip_index()?;
+                self.done = true;
+            } else {
+                self.headers.push(header_size);
+            }
+            return Ok(());
+        }
+        let mut header = vec![0; (header_size as usize + 1) * 4];
+        header[0] = header_size;
+        self.reader.read_exact(&mut header[1..])?;
+        self.next_block = xz_block_size(&header, self.stream_flags)?.map(|size| block_start + size);
+        if self.next_block.is_some() && checkpoints.is_due(self.decompressed) {
+            checkpoints.add(
+                block_start,
+                self.decompressed,
+                Some(MemberState::XzBlock {
+                    stream_flags: self.stream_flags,
+                }),
+            );
+        }
+        self.headers.extend_from_slice(&header);
+        Ok(())
+    }
+
+    /// skip the rest of the index (after the index indicator) and the stream footer
+    fn skip_index(&mut self) -> io::Result<()> {
+        let index_start = self.reader.consumed - 1;
+        let records = read_xz_vli(&mut self.reader)?;
+        for _ in 0..records * 2 {
+            read_xz_vli(&mut self.reader)?;
+        }
+        let padding = (4 - (self.reader.consumed - index_start) % 4) % 4;
+        skip(&mut self.reader, padding + 4 + XZ_FOOTER_SIZE)
+    }
+}
+
+enum Member<R: BufRead> {
+    Gzip(GzipMember<R>),
+    Zstd(zstd::stream::read::Decoder<'static, CountingReader<R>>),
+    Xz(XzMember<R>),
+}
+
+impl<R: BufRead> Member<R> {
+    fn start(
+        compression: Compression,
+        reader: CountingReader<R>,
+        decompressed: u64,
+    ) -> io::Result<Self> {
+        Ok(match compression {
+            Compression::Gzip => Member::Gzip(GzipMember::start(reader, decompressed)?),
+            Compression::Zstd => {
+                Member::Zstd(zstd::stream::read::Decoder::with_buffer(reader)?.single_frame())
+            }
+            Compression::Xz => Member::Xz(XzMember::start(reader, decompressed)?),
+        })
+    }
+
+    /// continue decoding inside of a member
+    fn resume(
+        compression: Compression,
+        reader: CountingReader<R>,
+        state: &MemberState,
+        decompressed: u64,
+    ) -> io::Result<Self> {
+        match (compression, state) {
+            (
+                Compression::Gzip,
+                MemberState::Deflate {
+                    num_bits,
+                    bit_buf,
+                    window,
+                },
+            ) => Ok(Member::Gzip(GzipMember::resume(
+                reader,
+                *num_bits,
+                *bit_buf,
+                window,
+                decompressed,
+            )?)),
+            (Compression::Xz, MemberState::XzBlock { stream_flags }) => Ok(Member::Xz(
+                XzMember::resume(reader, *stream_flags, decompressed)?,
+            )),
+            _ => Err(invalid_data("checkpoint does not match the compression")),
+        }
+    }
+
+    fn read(&mut self, buf: &mut [u8], checkpoints: &mut Checkpoints) -> io::Result<usize> {
+        match self {
+            Member::Gzip(d) => d.read(buf, checkpoints),
+            Member::Zstd(d) => d.read(buf),
+            Member::Xz(d) => d.read(buf, checkpoints),
+        }
+    }
+
+    fn into_inner(self) -> CountingReader<R> {
+        match self {
+            Member::Gzip(d) => d.reader,
+            Member::Zstd(d) => d.finish(),
+            Member::Xz(d) => d.reader,
+        }
+    }
+}
+
+enum DecoderState<R: BufRead> {
+    BetweenMembers(CountingReader<R>),
+    InMember(Member<R>),
+    Failed,
+}
+
+/// Streaming decoder for compressed content that consists of one or more
+/// members (gzip members, zstd frames or xz streams).
+///
+/// Every member is decoded on it's own so the decoder can remember where
+/// members start. These checkpoints (and the ones inside of large members)
+/// allow to start decoding in the middle of a file later on (see `Decoder::resume`).
+pub struct Decoder<R: BufRead> {
+    compression: Compression,
+    state: DecoderState<R>,
+    position: CompressedPosition,
+    decompressed: u64,
+    checkpoints: Checkpoints,
+}
+
+impl<R: BufRead> Decoder<R> {
+    pub fn new(compression: Compression, reader: R) -> Self {
+        Self::starting_at(
+            compression,
+            reader,
+            &Checkpoint::default(),
+            CompressedPosition::default(),
+        )
+    }
+
+    fn starting_at(
+        compression: Compression,
+        reader: R,
+        start: &Checkpoint,
+        position: CompressedPosition,
+    ) -> Self {
+        Self {
+            compression,
+            state: DecoderState::BetweenMembers(CountingReader {
+                inner: reader,
+                consumed: 0,
+                position: position.clone(),
+            }),
+            position,
+            decompressed: start.decompressed,
+            checkpoints: Checkpoints {
+                list: vec![],
+                interval: CHECKPOINT_INTERVAL,
+                compressed_start: start.compressed,
+            },
+        }
+    }
+
+    /// change the minimal distance between two checkpoints
+    pub fn checkpoint_interval(mut self, interval: u64) -> Self {
+        self.checkpoints.interval = interval;
+        self
+    }
+
+    /// the checkpoints for all members that were started so far. Members that
+    /// follow each other closely are combined so there is not more than
+    /// one checkpoint every `CHECKPOINT_INTERVAL` bytes (see `checkpoint_interval`)
+    pub fn checkpoints(&self) -> &[Checkpoint] {
+        &self.checkpoints.list
+    }
+
+    /// counter of the compressed bytes consumed since the decoder was started
+    pub fn compressed_position(&self) -> CompressedPosition {
+        self.position.clone()
+    }
+}
+
+impl<R: BufRead> Read for Decoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        loop {
+            match std::mem::replace(&mut self.state, DecoderState::Failed) {
+                DecoderState::BetweenMembers(mut reader) => {
+                    if reader.fill_buf()?.is_empty() {
+                        self.state = DecoderState::BetweenMembers(reader);
+                        return Ok(0);
+                    }
+                    if self.checkpoints.is_due(self.decompressed) {
+                        self.checkpoints
+                            .add(reader.consumed, self.decompressed, None);
+                    }
+                    self.state = DecoderState::InMember(Member::start(
+                        self.compression,
+                        reader,
+                        self.decompressed,
+                    )?);
+                }
+                DecoderState::InMember(mut member) => {
+                    let n = member.read(buf, &mut self.checkpoints)?;
+                    if n == 0 {
+                        self.state = DecoderState::BetweenMembers(member.into_inner());
+                    } else {
+                        self.state = DecoderState::InMember(member);
+                        self.decompressed += n as u64;
+                        return Ok(n);
+                    }
+                }
+                DecoderState::Failed => {
+                    return Err(io::Error::other("decoder failed on previous read"))
+                }
+            }
+        }
+    }
+}
+
+impl Decoder<BufReader<fs::File>> {
+    /// start decoding a file at a checkpoint
+    pub fn resume(
+        path: &Path,
+        compression: Compression,
+        checkpoint: &Checkpoint,
+    ) -> io::Result<Self> {
+        let mut f = fs::File::open(path)?;
+        f.seek(SeekFrom::Start(checkpoint.compressed))?;
+        let mut decoder = Self::starting_at(
+            compression,
+            BufReader::with_capacity(COMPRESSED_READER_CAPACITY, f),
+            checkpoint,
+            CompressedPosition::default(),
+        );
+        if let Some(state) = &checkpoint.state {
+            if let DecoderState::BetweenMembers(reader) =
+                std::mem::replace(&mut decoder.state, DecoderState::Failed)
+            {
+                decoder.state = DecoderState::InMember(Member::resume(
+                    compression,
+                    reader,
+                    state,
+                    checkpoint.decompressed,
+                )?);
+            }
+        }
+        Ok(decoder)
+    }
+}
+
+/// A file that is transparently decoded if it is compressed
+pub enum InputFile {
+    Plain {
+        file: fs::File,
+        position: Option<CompressedPosition>,
+    },
+    Compressed(Box<Decoder<BufReader<fs::File>>>),
+}
+
+impl InputFile {
+    /// open a file for reading, compressed files are detected by their magic bytes
+    pub fn open(path: &Path) -> io::Result<Self> {
+        Self::open_with_position(path, None)
+    }
+
+    /// Like `open` but if a `position` is given all bytes read from the file on disk
+    /// are counted in it, for plain files as well as for compressed ones.
+    pub fn open_with_position(
+        path: &Path,
+        position: Option<CompressedPosition>,
+    ) -> io::Result<Self> {
+        let file = fs::File::open(path)?;
+        Ok(match detect_compression(path)? {
+            Some(compression) => InputFile::Compressed(Box::new(Decoder::starting_at(
+                compression,
+                BufReader::with_capacity(COMPRESSED_READER_CAPACITY, file),
+                &Checkpoint::default(),
+                position.unwrap_or_default(),
+            ))),
+            None => InputFile::Plain { file, position },
+        })
+    }
+
+    /// the counter for the bytes consumed from the file on disk. `None` for plain files
+    /// that were opened without a position
+    pub fn compressed_position(&self) -> Option<CompressedPosition> {
+        match self {
+            InputFile::Plain { position, .. } => position.clone(),
+            InputFile::Compressed(decoder) => Some(decoder.compressed_position()),
+        }
+    }
+
+    pub fn checkpoints(&self) -> Vec<Checkpoint> {
+        match self {
+            InputFile::Plain { .. } => vec![],
+            InputFile::Compressed(decoder) => decoder.checkpoints().to_vec(),
+        }
+    }
+}
+
+impl Read for InputFile {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            InputFile::Plain { file, position } => {
+                let n = file.read(buf)?;
+                if let Some(position) = position {
+                    position.advance(n);
+                }
+                Ok(n)
+            }
+            InputFile::Compressed(decoder) => decoder.read(buf),
+        }
+    }
+}
+
+/// Read `length` bytes at the decompressed `offset` of a file.
+///
+/// For compressed files decoding starts at the closest checkpoint before `offset`,
+/// without checkpoints the file is decoded from the start.
+pub fn read_at(
+    path: &Path,
+    checkpoints: &[Checkpoint],
+    offset: u64,
+    length: usize,
+) -> io::Result<Vec<u8>> {
+    let mut buf = vec![0; length];
+    match detect_compression(path)? {
+        None => {
+            let mut f = fs::File::open(path)?;
+            f.seek(SeekFrom::Start(offset))?;
+            f.read_exact(&mut buf)?;
+        }
+        Some(compression) => {
+            let checkpoint = checkpoints
+                .iter()
+                .rev()
+                .find(|c| c.decompressed <= offset)
+                .cloned()
+                .unwrap_or_default();
+            let mut decoder = Decoder::resume(path, compression, &checkpoint)?;
+            let to_skip = offset - checkpoint.decompressed;
+            let skipped = io::copy(&mut (&mut decoder).take(to_skip), &mut io::sink())?;
+            if skipped != to_skip {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    format!("offset {} beyond decompressed content", offset),
+                ));
+            }
+            decoder.read_exact(&mut buf)?;
+        }
+    }
+    Ok(buf)
+}
@@ -68,10 +68,7 @@ pub struct SocketConfig {
 impl SocketConfig {
     pub fn socket_addr(&self) -> Result<SocketAddr, Error> {
         // Touch IPv4
-        let addr: Option<SocketAddr> = match format!("{}:{}", self.bind_addr, self.port).parse() {
-            Ok(addr) => Some(addr),
-            Err(_) => None,
-        };
+        let addr: Option<SocketAddr> = format!("{}:{}", self.bind_addr, self.port).parse().ok();
         if let Some(addr) = addr {
             Ok(addr)
         } else {
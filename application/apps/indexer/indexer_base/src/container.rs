@@ -0,0 +1,325 @@
+// Copyright (c) 2019 E.S.R.Labs. All rights reserved.
+//
+// NOTICE:  All information contained herein is, and remains
+// the property of E.S.R.Labs and its suppliers, if any.
+// The intellectual and technical concepts contained herein are
+// proprietary to E.S.R.Labs and its suppliers and may be covered
+// by German and Foreign Patents, patents in process, and are protected
+// by trade secret or copyright law.
+// Dissemination of this information or reproduction of this material
+// is strictly forbidden unless prior written permission is obtained
+// from E.S.R.Labs.
+use crate::utils::{self, ENTRY_NEWLINE_SENTINAL};
+use std::{
+    convert::{TryFrom, TryInto},
+    fmt::Display,
+    fs,
+    io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+};
+
+/// extension of output files that are written as container
+pub const CONTAINER_EXTENSION: &str = "rows";
+/// extension of the line index that is written next to a container
+pub const INDEX_EXTENSION: &str = "idx";
+
+const FLAG_TIMESTAMP: u8 = 0b01;
+const FLAG_NEWLINE: u8 = 0b10;
+/// flags, source row, timestamp and tag length
+const RECORD_HEADER_LENGTH: usize = 1 + 8 + 8 + 2;
+const INDEX_ENTRY_LENGTH: u64 = 8;
+
+/// How the rows of an indexed output file are stored.
+///
+/// `Tagged` is the text format where tag and row number are appended to each line
+/// using sentinels. `Container` stores every row as a length-prefixed record:
+///
+/// `u32 length | u8 flags | u64 source row | i64 timestamp | u16 tag length | tag | content`
+///
+/// all numbers little endian, `length` counts the bytes after it. The content is
+/// stored as is, so it may contain newlines or any of the sentinels. Next to the
+/// container a line index (`<out>.idx`) holds the offset of every record as `u64`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    Tagged,
+    Container,
+}
+
+impl OutputFormat {
+    /// files with the `.rows` extension are containers, everything else is tagged
+    pub fn for_path(path: &Path) -> Self {
+        match path.extension() {
+            Some(ext) if ext == CONTAINER_EXTENSION => OutputFormat::Container,
+            _ => OutputFormat::Tagged,
+        }
+    }
+}
+
+pub fn is_container(path: &Path) -> bool {
+    OutputFormat::for_path(path) == OutputFormat::Container
+}
+
+/// path of the line index that belongs to a container
+pub fn index_path(container: &Path) -> PathBuf {
+    let mut name = container.as_os_str().to_owned();
+    name.push(format!(".{}", INDEX_EXTENSION));
+    PathBuf::from(name)
+}
+
+/// One row of a container
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Record {
+    pub tag: String,
+    pub source_row: usize,
+    pub timestamp: Option<i64>,
+    /// the row was written with a trailing newline in the tagged format
+    pub had_newline: bool,
+    pub content: Vec<u8>,
+}
+
+impl Record {
+    pub fn content_str(&self) -> std::borrow::Cow<'_, str> {
+        String::from_utf8_lossy(&self.content)
+    }
+}
+
+struct LineIndex {
+    writer: BufWriter<fs::File>,
+    position: u64,
+}
+
+/// Writes the rows of an indexed output in the format that fits the output path.
+///
+/// In the tagged format the rows are written exactly like `utils::write_tagged_line`
+/// does. In the container format the newline sentinels of multi-line entries are
+/// turned back into newlines since the records don't need to fit into one line.
+pub struct RowWriter<W: Write> {
+    out: W,
+    index: Option<LineIndex>,
+}
+
+impl<W: Write> RowWriter<W> {
+    /// `current_out_file_size` is the size of the output before anything is appended
+    pub fn new(out: W, out_path: &Path, current_out_file_size: usize) -> io::Result<Self> {
+        let index = match OutputFormat::for_path(out_path) {
+            OutputFormat::Tagged => None,
+            OutputFormat::Container => {
+                let index_file = fs::OpenOptions::new()
+                    .create(true)
+                    .write(true)
+                    .append(current_out_file_size > 0)
+                    .truncate(current_out_file_size == 0)
+                    .open(index_path(out_path))?;
+                Some(LineIndex {
+                    writer: BufWriter::new(index_file),
+                    position: current_out_file_size as u64,
+                })
+            }
+        };
+        Ok(RowWriter { out, index })
+    }
+
+    pub fn format(&self) -> OutputFormat {
+        match self.index {
+            Some(_) => OutputFormat::Container,
+            None => OutputFormat::Tagged,
+        }
+    }
+
+    /// write one row, returns the number of bytes that were added to the output
+    pub fn write_row(
+        &mut self,
+        tag: &str,
+        content: &str,
+        row: usize,
+        with_newline: bool,
+        timestamp: Option<i64>,
+    ) -> io::Result<usize> {
+        match self.index {
+            None => {
+                utils::write_tagged_line(tag, &mut self.out, content, row, with_newline, timestamp)
+            }
+            Some(_) => self.write_record(tag, content, row, with_newline, timestamp),
+        }
+    }
+
+    fn write_record(
+        &mut self,
+        tag: &str,
+        content: &str,
+        row: usize,
+        with_newline: bool,
+        timestamp: Option<i64>,
+    ) -> io::Result<usize> {
+        let content = content.replace(ENTRY_NEWLINE_SENTINAL, "\n");
+        let tag_len = u16::try_from(tag.len())
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "tag is too long"))?;
+        let length = u32::try_from(RECORD_HEADER_LENGTH + tag.len() + content.len())
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "row is too long"))?;
+        let mut flags = 0u8;
+        if timestamp.is_some() {
+            flags |= FLAG_TIMESTAMP;
+        }
+        if with_newline {
+            flags |= FLAG_NEWLINE;
+        }
+        self.out.write_all(&length.to_le_bytes())?;
+        self.out.write_all(&[flags])?;
+        self.out.write_all(&(row as u64).to_le_bytes())?;
+        self.out.write_all(&timestamp.unwrap_or(0).to_le_bytes())?;
+        self.out.write_all(&tag_len.to_le_bytes())?;
+        self.out.write_all(tag.as_bytes())?;
+        self.out.write_all(content.as_bytes())?;
+        let written = 4 + length as usize;
+        if let Some(index) = &mut self.index {
+            index.writer.write_all(&index.position.to_le_bytes())?;
+            index.position += written as u64;
+        }
+        Ok(written)
+    }
+
+    /// the underlying writer, for output that is not written as rows
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.out
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()?;
+        if let Some(index) = &mut self.index {
+            index.writer.flush()?;
+        }
+        Ok(())
+    }
+}
+
+impl RowWriter<BufWriter<fs::File>> {
+    /// like `write_row` for content that still needs to be formatted,
+    /// the tagged format is written with `utils::create_tagged_line_d`
+    pub fn write_row_d<T: Display>(
+        &mut self,
+        tag: &str,
+        content: T,
+        row: usize,
+        with_newline: bool,
+    ) -> io::Result<usize> {
+        match self.index {
+            None => utils::create_tagged_line_d(tag, &mut self.out, content, row, with_newline),
+            Some(_) => self.write_record(tag, &content.to_string(), row, with_newline, None),
+        }
+    }
+}
+
+/// read the next record, `None` at the end of the container
+pub fn read_record<R: Read>(reader: &mut R) -> io::Result<Option<Record>> {
+    let mut length = [0u8; 4];
+    match reader.read_exact(&mut length) {
+        Ok(()) => (),
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e),
+    }
+    let length = u32::from_le_bytes(length) as usize;
+    if length < RECORD_HEADER_LENGTH {
+        return Err(invalid_data(format!(
+            "record of {} bytes is too short",
+            length
+        )));
+    }
+    let mut buf = vec![0u8; length];
+    reader.read_exact(&mut buf)?;
+    let flags = buf[0];
+    let source_row = u64::from_le_bytes(buf[1..9].try_into().unwrap()) as usize;
+    let timestamp = i64::from_le_bytes(buf[9..17].try_into().unwrap());
+    let tag_len = u16::from_le_bytes(buf[17..19].try_into().unwrap()) as usize;
+    if RECORD_HEADER_LENGTH + tag_len > length {
+        return Err(invalid_data(format!(
+            "tag of {} bytes does not fit into record of {} bytes",
+            tag_len, length
+        )));
+    }
+    let tag = String::from_utf8(buf[RECORD_HEADER_LENGTH..RECORD_HEADER_LENGTH + tag_len].to_vec())
+        .map_err(|e| invalid_data(format!("tag is not valid utf8: {}", e)))?;
+    let content = buf.split_off(RECORD_HEADER_LENGTH + tag_len);
+    Ok(Some(Record {
+        tag,
+        source_row,
+        timestamp: if flags & FLAG_TIMESTAMP != 0 {
+            Some(timestamp)
+        } else {
+            None
+        },
+        had_newline: flags & FLAG_NEWLINE != 0,
+        content,
+    }))
+}
+
+fn invalid_data(reason: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, reason)
+}
+
+/// Reads the records of a container, using the line index to seek to rows
+pub struct ContainerReader {
+    reader: BufReader<fs::File>,
+    index: fs::File,
+    rows: usize,
+}
+
+impl ContainerReader {
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let index = fs::File::open(index_path(path))?;
+        let rows = (index.metadata()?.len() / INDEX_ENTRY_LENGTH) as usize;
+        Ok(ContainerReader {
+            reader: BufReader::new(fs::File::open(path)?),
+            index,
+            rows,
+        })
+    }
+
+    /// number of rows in the container
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    /// continue reading at `row` (counted from 0)
+    pub fn seek_row(&mut self, row: usize) -> io::Result<()> {
+        if row >= self.rows {
+            self.reader.seek(SeekFrom::End(0))?;
+            return Ok(());
+        }
+        let mut offset = [0u8; INDEX_ENTRY_LENGTH as usize];
+        self.index
+            .seek(SeekFrom::Start(row as u64 * INDEX_ENTRY_LENGTH))?;
+        self.index.read_exact(&mut offset)?;
+        self.reader
+            .seek(SeekFrom::Start(u64::from_le_bytes(offset)))?;
+        Ok(())
+    }
+
+    /// the record at `row`, reading continues after it
+    pub fn record_at(&mut self, row: usize) -> io::Result<Option<Record>> {
+        self.seek_row(row)?;
+        read_record(&mut self.reader)
+    }
+}
+
+impl Iterator for ContainerReader {
+    type Item = io::Result<Record>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        read_record(&mut self.reader).transpose()
+    }
+}
+
+/// the row that follows the last record of a container, 0 for an empty container
+pub fn next_source_row(path: &Path) -> io::Result<usize> {
+    if !path.exists() || fs::metadata(path)?.len() == 0 {
+        return Ok(0);
+    }
+    let mut reader = ContainerReader::open(path)?;
+    match reader.rows().checked_sub(1) {
+        Some(last) => Ok(reader
+            .record_at(last)?
+            .map(|record| record.source_row + 1)
+            .unwrap_or(0)),
+        None => Ok(0),
+    }
+}
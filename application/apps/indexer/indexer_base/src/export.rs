@@ -1,7 +1,9 @@
 use crate::{
     chunks::ChunkResults,
     config::SectionConfig,
+    container::{self, ContainerReader},
     progress::{IndexingProgress, Notification, Severity},
+    redaction::{Redaction, Redactor},
     utils::restore_line,
 };
 use crossbeam_channel as cc;
@@ -10,7 +12,7 @@ use std::{
     io::{BufRead, BufWriter, Write},
 };
 
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -23,11 +25,13 @@ pub enum Error {
 
 /// will save sections of a file that is based on lines (newlines)
 /// and remove session file data if required (if `was_session_file` is true)
+/// with a `redactor` every exported line is redacted before it is written
 pub fn export_file_line_based(
     file_path: PathBuf,
     destination_path: PathBuf,
     sections: SectionConfig,
     was_session_file: bool,
+    mut redactor: Option<&mut Redactor>,
     update_channel: cc::Sender<ChunkResults>,
 ) -> Result<(), Error> {
     trace!(
@@ -43,19 +47,33 @@ pub fn export_file_line_based(
     );
     if file_path.exists() {
         trace!("found file to export: {:?}", &file_path);
+        if container::is_container(&file_path) {
+            export_container(&file_path, destination_path, sections, redactor)?;
+            let _ = update_channel.send(Ok(IndexingProgress::Finished));
+            return Ok(());
+        }
         let f = fs::File::open(&file_path)?;
         let reader = &mut std::io::BufReader::new(f);
         let out_file = std::fs::File::create(destination_path)?;
         let lines_iter = &mut reader.lines();
         let mut out_writer = BufWriter::new(out_file);
+        let mut write_line = |line: &str| -> Result<(), Error> {
+            let line = if was_session_file {
+                restore_line(line)
+            } else {
+                std::borrow::Cow::Borrowed(line)
+            };
+            match redactor.as_deref_mut().map(|r| r.redact_line(&line)) {
+                Some(Redaction::Drop) => (),
+                Some(Redaction::Changed(redacted)) => writeln!(out_writer, "{}", redacted)?,
+                Some(Redaction::Unchanged) | None => writeln!(out_writer, "{}", line)?,
+            }
+            Ok(())
+        };
         // check if we have to export the whole file
         if sections.sections.is_empty() {
             for elem in lines_iter {
-                if was_session_file {
-                    out_writer.write_fmt(format_args!("{}\n", restore_line(&elem?)))?;
-                } else {
-                    out_writer.write_fmt(format_args!("{}\n", elem?))?;
-                }
+                write_line(&elem?)?;
             }
         } else {
             let mut index = 0usize;
@@ -65,16 +83,13 @@ pub fn export_file_line_based(
                 let section_size = section.last_line - section.first_line + 1;
                 let elem_iter = lines_iter.skip(forward).take(section_size);
                 for elem in elem_iter {
-                    if was_session_file {
-                        out_writer.write_fmt(format_args!("{}\n", restore_line(&elem?)))?;
-                    } else {
-                        out_writer.write_fmt(format_args!("{}\n", elem?))?;
-                    }
+                    write_line(&elem?)?;
                 }
                 index += forward;
                 index += section_size;
             }
         }
+        out_writer.flush()?;
 
         let _ = update_channel.send(Ok(IndexingProgress::Finished));
         Ok(())
@@ -88,3 +103,43 @@ pub fn export_file_line_based(
         Err(Error::Export(reason))
     }
 }
+
+/// the content of every record is written followed by a newline,
+/// sections are looked up in the line index of the container
+fn export_container(
+    file_path: &Path,
+    destination_path: PathBuf,
+    sections: SectionConfig,
+    mut redactor: Option<&mut Redactor>,
+) -> Result<(), Error> {
+    let mut reader = ContainerReader::open(file_path)?;
+    let mut out_writer = BufWriter::new(std::fs::File::create(destination_path)?);
+    let mut write_record = |record: container::Record| -> Result<(), Error> {
+        match redactor
+            .as_deref_mut()
+            .map(|r| r.redact_line(&record.content_str()))
+        {
+            Some(Redaction::Drop) => return Ok(()),
+            Some(Redaction::Changed(redacted)) => out_writer.write_all(redacted.as_bytes())?,
+            Some(Redaction::Unchanged) | None => out_writer.write_all(&record.content)?,
+        }
+        out_writer.write_all(b"\n")?;
+        Ok(())
+    };
+    if sections.sections.is_empty() {
+        for record in reader {
+            write_record(record?)?;
+        }
+    } else {
+        for section in sections.sections {
+            reader.seek_row(section.first_line)?;
+            /* since section [1,2] is 2 lines, we have to add 1 here */
+            let section_size = section.last_line - section.first_line + 1;
+            for record in (&mut reader).take(section_size) {
+                write_record(record?)?;
+            }
+        }
+    }
+    out_writer.flush()?;
+    Ok(())
+}
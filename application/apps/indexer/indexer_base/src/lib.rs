@@ -10,10 +10,13 @@
 // is strictly forbidden unless prior written permission is obtained
 // from E.S.R.Labs.
 pub mod chunks;
+pub mod compression;
 pub mod config;
+pub mod container;
 pub mod error_reporter;
 pub mod export;
 pub mod progress;
+pub mod redaction;
 pub mod timedline;
 pub mod utils;
 
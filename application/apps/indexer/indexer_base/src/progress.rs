@@ -1,3 +1,4 @@
+use crate::compression::CompressedPosition;
 use crossbeam_channel as cc;
 use serde::{Deserialize, Serialize};
 
@@ -85,6 +86,7 @@ pub struct ProgressReporter<T> {
     processed_bytes: u64,
     progress_percentage: u64,
     total: u64,
+    compressed_position: Option<CompressedPosition>,
 }
 
 impl<T> ProgressReporter<T> {
@@ -97,10 +99,21 @@ impl<T> ProgressReporter<T> {
             processed_bytes: 0,
             progress_percentage: 0,
             total,
+            compressed_position: None,
         }
     }
+    /// For compressed inputs the total is the size of the compressed file.
+    /// The progress is then taken from the position in the compressed
+    /// content instead of the consumed (decompressed) bytes.
+    pub fn with_compressed_position(mut self, position: Option<CompressedPosition>) -> Self {
+        self.compressed_position = position;
+        self
+    }
     pub fn make_progress(&mut self, consumed: usize) {
-        self.processed_bytes += consumed as u64;
+        self.processed_bytes = match &self.compressed_position {
+            Some(position) => position.get().min(self.total),
+            None => self.processed_bytes + consumed as u64,
+        };
         let new_progress_percentage: u64 =
             (self.processed_bytes as f64 / self.total as f64 * 100.0).round() as u64;
         if new_progress_percentage != self.progress_percentage {
@@ -0,0 +1,286 @@
+// Copyright (c) 2019 E.S.R.Labs. All rights reserved.
+//
+// NOTICE:  All information contained herein is, and remains
+// the property of E.S.R.Labs and its suppliers, if any.
+// The intellectual and technical concepts contained herein are
+// proprietary to E.S.R.Labs and its suppliers and may be covered
+// by German and Foreign Patents, patents in process, and are protected
+// by trade secret or copyright law.
+// Dissemination of this information or reproduction of this material
+// is strictly forbidden unless prior written permission is obtained
+// from E.S.R.Labs.
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+use thiserror::Error;
+
+/// extension of the report that is written next to a redacted export
+pub const REPORT_EXTENSION: &str = "redaction.json";
+
+const VIN_PATTERN: &str = r"\b[A-HJ-NPR-Z0-9]{17}\b";
+const MAC_PATTERN: &str = r"\b[0-9A-Fa-f]{2}(?:[:-][0-9A-Fa-f]{2}){5}\b";
+const IP_PATTERN: &str = r"\b(?:(?:25[0-5]|2[0-4]\d|1?\d?\d)\.){3}(?:25[0-5]|2[0-4]\d|1?\d?\d)\b|\b(?:[0-9A-Fa-f]{1,4}:){7}[0-9A-Fa-f]{1,4}\b";
+const GPS_PATTERN: &str = r"-?\b\d{1,2}\.\d{4,}\s*,\s*-?\d{1,3}\.\d{4,}\b";
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("Invalid redaction config: {0}")]
+    Config(String),
+    #[error("IO error: {0:?}")]
+    Io(#[from] std::io::Error),
+}
+
+/// What happens to text that matches a rule
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum RedactionAction {
+    /// every character is replaced by `*`
+    Mask,
+    /// replaced by a hash, so the same value always gets the same replacement
+    Hash,
+    /// the whole entry (line or message) is left out
+    Drop,
+}
+
+/// What a rule looks for. Besides plain regular expressions there are
+/// rules for the kinds of data we usually need to scrub.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "lowercase", tag = "type")]
+pub enum RedactionPattern {
+    Regex {
+        pattern: String,
+    },
+    /// vehicle identification numbers
+    Vin,
+    /// MAC addresses, separated by `:` or `-`
+    Mac,
+    /// IPv4 and (uncompressed) IPv6 addresses
+    Ip,
+    /// decimal coordinate pairs like `48.1374, 11.5755`
+    Gps,
+    /// the given user names, ignoring case
+    Names {
+        names: Vec<String>,
+    },
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RedactionRule {
+    /// used to report the matches of this rule
+    pub name: String,
+    #[serde(flatten)]
+    pub pattern: RedactionPattern,
+    pub action: RedactionAction,
+}
+
+/// e.g.
+/// `{"salt": "x", "rules": [{"name": "vin", "type": "vin", "action": "hash"}]}`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RedactionConfig {
+    /// added to every hashed value so hashes can't be looked up across exports
+    #[serde(default)]
+    pub salt: String,
+    pub rules: Vec<RedactionRule>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct RuleReport {
+    /// how often the rule matched
+    pub matches: usize,
+    /// number of entries the rule matched in
+    pub entries: usize,
+}
+
+/// What was redacted, per rule and for the entries (lines or messages)
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct RedactionReport {
+    pub rules: BTreeMap<String, RuleReport>,
+    pub entries: usize,
+    pub changed_entries: usize,
+    pub dropped_entries: usize,
+    /// entries that could not be checked, e.g. non-verbose dlt messages
+    pub unchecked_entries: usize,
+}
+
+impl RedactionReport {
+    pub fn write_to(&self, path: &Path) -> Result<(), Error> {
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| Error::Config(format!("could not serialize report: {}", e)))?;
+        fs::write(path, json)?;
+        Ok(())
+    }
+}
+
+/// path of the report that belongs to a redacted export
+pub fn report_path(destination: &Path) -> PathBuf {
+    let mut name = destination.as_os_str().to_owned();
+    name.push(format!(".{}", REPORT_EXTENSION));
+    PathBuf::from(name)
+}
+
+/// Outcome of redacting an entry or a part of it
+#[derive(Debug, Clone, PartialEq)]
+pub enum Redaction<T> {
+    Unchanged,
+    Changed(T),
+    Drop,
+}
+
+struct CompiledRule {
+    name: String,
+    regex: Regex,
+    action: RedactionAction,
+}
+
+pub struct Redactor {
+    rules: Vec<CompiledRule>,
+    salt: String,
+    report: RedactionReport,
+    /// rules that matched in the current entry
+    matched_in_entry: Vec<bool>,
+}
+
+impl Redactor {
+    pub fn new(config: RedactionConfig) -> Result<Self, Error> {
+        let rules = config
+            .rules
+            .into_iter()
+            .map(|rule| {
+                let pattern = match &rule.pattern {
+                    RedactionPattern::Regex { pattern } => pattern.clone(),
+                    RedactionPattern::Vin => VIN_PATTERN.to_string(),
+                    RedactionPattern::Mac => MAC_PATTERN.to_string(),
+                    RedactionPattern::Ip => IP_PATTERN.to_string(),
+                    RedactionPattern::Gps => GPS_PATTERN.to_string(),
+                    RedactionPattern::Names { names } => {
+                        if names.is_empty() {
+                            return Err(Error::Config(format!("rule {} has no names", rule.name)));
+                        }
+                        let names: Vec<String> = names.iter().map(|n| regex::escape(n)).collect();
+                        format!(r"(?i)\b(?:{})\b", names.join("|"))
+                    }
+                };
+                let regex = Regex::new(&pattern).map_err(|e| {
+                    Error::Config(format!("invalid pattern for rule {}: {}", rule.name, e))
+                })?;
+                Ok(CompiledRule {
+                    name: rule.name,
+                    regex,
+                    action: rule.action,
+                })
+            })
+            .collect::<Result<Vec<CompiledRule>, Error>>()?;
+        let report = RedactionReport {
+            rules: rules
+                .iter()
+                .map(|rule| (rule.name.clone(), RuleReport::default()))
+                .collect(),
+            ..Default::default()
+        };
+        Ok(Redactor {
+            matched_in_entry: vec![false; rules.len()],
+            rules,
+            salt: config.salt,
+            report,
+        })
+    }
+
+    pub fn from_file(path: &Path) -> Result<Self, Error> {
+        let content = fs::read_to_string(path)?;
+        let config: RedactionConfig = serde_json::from_str(&content)
+            .map_err(|e| Error::Config(format!("could not parse {:?}: {}", path, e)))?;
+        Redactor::new(config)
+    }
+
+    /// Redact one part of an entry, e.g. one string argument of a dlt message.
+    /// Matches are counted, the entry itself is counted with `record_entry`.
+    pub fn redact_text(&mut self, text: &str) -> Redaction<String> {
+        let mut redacted: Option<String> = None;
+        for (i, rule) in self.rules.iter().enumerate() {
+            let current = redacted.as_deref().unwrap_or(text);
+            let matches = rule.regex.find_iter(current).count();
+            if matches == 0 {
+                continue;
+            }
+            let rule_report = self.report.rules.entry(rule.name.clone()).or_default();
+            rule_report.matches += matches;
+            self.matched_in_entry[i] = true;
+            let replaced = match rule.action {
+                RedactionAction::Drop => return Redaction::Drop,
+                RedactionAction::Mask => rule
+                    .regex
+                    .replace_all(current, |caps: &regex::Captures| {
+                        "*".repeat(caps[0].chars().count())
+                    })
+                    .into_owned(),
+                RedactionAction::Hash => {
+                    let salt = &self.salt;
+                    rule.regex
+                        .replace_all(current, |caps: &regex::Captures| {
+                            format!("#{:016x}", fnv_hash(salt, &caps[0]))
+                        })
+                        .into_owned()
+                }
+            };
+            redacted = Some(replaced);
+        }
+        match redacted {
+            Some(text) => Redaction::Changed(text),
+            None => Redaction::Unchanged,
+        }
+    }
+
+    /// count an entry (a line or a message) with the outcome of all its parts
+    pub fn record_entry<T>(&mut self, outcome: &Redaction<T>) {
+        self.report.entries += 1;
+        match outcome {
+            Redaction::Unchanged => (),
+            Redaction::Changed(_) => self.report.changed_entries += 1,
+            Redaction::Drop => self.report.dropped_entries += 1,
+        }
+        for (rule, matched) in self.rules.iter().zip(self.matched_in_entry.iter_mut()) {
+            if *matched {
+                self.report
+                    .rules
+                    .entry(rule.name.clone())
+                    .or_default()
+                    .entries += 1;
+                *matched = false;
+            }
+        }
+    }
+
+    /// count an entry that could not be redacted
+    pub fn record_unchecked_entry(&mut self) {
+        self.report.entries += 1;
+        self.report.unchecked_entries += 1;
+    }
+
+    /// redact a line of text as one entry
+    pub fn redact_line(&mut self, line: &str) -> Redaction<String> {
+        let outcome = self.redact_text(line);
+        self.record_entry(&outcome);
+        outcome
+    }
+
+    pub fn report(&self) -> &RedactionReport {
+        &self.report
+    }
+}
+
+/// FNV-1a, stable across runs and platforms so that hashed values can be
+/// compared between exports
+fn fnv_hash(salt: &str, value: &str) -> u64 {
+    salt.bytes()
+        .chain(value.bytes())
+        .fold(FNV_OFFSET_BASIS, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(FNV_PRIME)
+        })
+}
@@ -0,0 +1,69 @@
+#[cfg(test)]
+mod tests {
+    use crate::chunks::*;
+    use pretty_assertions::assert_eq;
+    use std::{fs, io::Write, path::Path};
+    use tempdir::TempDir;
+
+    /// rows of 10 bytes each, returns the chunks that were sent
+    fn create_chunks(out_path: &Path, start_row: usize, rows: usize) -> Vec<Chunk> {
+        let current_out_file_size = start_row * 10;
+        let mut factory = ChunkFactory::new(3, current_out_file_size)
+            .with_index(out_path)
+            .unwrap();
+        let mut chunks = vec![];
+        for line_nr in start_row + 1..=start_row + rows {
+            chunks.extend(factory.add_bytes(line_nr, 10));
+        }
+        chunks.extend(factory.create_last_chunk(start_row + rows, chunks.is_empty()));
+        chunks
+    }
+
+    fn as_tuples(chunks: &[Chunk]) -> Vec<((usize, usize), (usize, usize))> {
+        chunks.iter().map(|c| (c.r, c.b)).collect()
+    }
+
+    #[test]
+    fn test_persisted_chunks_are_the_sent_chunks() {
+        let tmp_dir = TempDir::new("chunks").expect("could not create temp dir");
+        let out_path = tmp_dir.path().join("out.out");
+        let sent = create_chunks(&out_path, 0, 7);
+        let index = ChunkIndex::load(&out_path).unwrap();
+        assert_eq!(as_tuples(&sent), as_tuples(index.chunks()));
+        assert_eq!(7, index.rows());
+
+        assert_eq!(Some((0, 30)), index.byte_range(0, 2));
+        assert_eq!(Some((0, 60)), index.byte_range(1, 4));
+        assert_eq!(Some((31, 70)), index.byte_range(5, 6));
+        assert_eq!(Some((61, 70)), index.byte_range(6, 100));
+        assert_eq!(None, index.byte_range(7, 8));
+        assert_eq!(None, index.byte_range(4, 3));
+    }
+
+    #[test]
+    fn test_appending_continues_the_index() {
+        let tmp_dir = TempDir::new("chunks").expect("could not create temp dir");
+        let out_path = tmp_dir.path().join("out.out");
+        create_chunks(&out_path, 0, 6);
+        // an incomplete chunk that was not finished
+        fs::OpenOptions::new()
+            .append(true)
+            .open(chunk_index_path(&out_path))
+            .unwrap()
+            .write_all(&[1, 2, 3])
+            .unwrap();
+        assert_eq!(6, ChunkIndex::load(&out_path).unwrap().rows());
+
+        create_chunks(&out_path, 6, 2);
+        let index = ChunkIndex::load(&out_path).unwrap();
+        assert_eq!(
+            vec![((0, 2), (0, 30)), ((3, 5), (31, 60)), ((6, 7), (60, 80))],
+            as_tuples(index.chunks())
+        );
+        assert_eq!(Some((31, 80)), index.byte_range(4, 7));
+
+        // without appending the index starts from scratch
+        create_chunks(&out_path, 0, 2);
+        assert_eq!(2, ChunkIndex::load(&out_path).unwrap().rows());
+    }
+}
@@ -0,0 +1,256 @@
+#[cfg(test)]
+mod tests {
+    use crate::compression::*;
+    use pretty_assertions::assert_eq;
+    use std::{
+        fs,
+        io::{BufReader, Read, Write},
+        path::Path,
+    };
+    use tempdir::TempDir;
+
+    fn compress(compression: Compression, content: &[u8]) -> Vec<u8> {
+        match compression {
+            Compression::Gzip => {
+                let mut encoder =
+                    flate2::write::GzEncoder::new(vec![], flate2::Compression::default());
+                encoder.write_all(content).unwrap();
+                encoder.finish().unwrap()
+            }
+            Compression::Zstd => zstd::encode_all(content, 0).unwrap(),
+            Compression::Xz => {
+                let mut encoder = xz2::write::XzEncoder::new(vec![], 6);
+                encoder.write_all(content).unwrap();
+                encoder.finish().unwrap()
+            }
+        }
+    }
+
+    /// every part is compressed as separate member and the members are concatenated
+    fn write_members(path: &Path, compression: Compression, parts: &[&str]) {
+        let content: Vec<u8> = parts
+            .iter()
+            .flat_map(|part| compress(compression, part.as_bytes()))
+            .collect();
+        fs::write(path, content).expect("testfile could not be written");
+    }
+
+    #[test]
+    fn test_detect_compression_by_magic() {
+        assert_eq!(
+            Some(Compression::Gzip),
+            Compression::from_magic(&compress(Compression::Gzip, b"a"))
+        );
+        assert_eq!(
+            Some(Compression::Zstd),
+            Compression::from_magic(&compress(Compression::Zstd, b"a"))
+        );
+        assert_eq!(
+            Some(Compression::Xz),
+            Compression::from_magic(&compress(Compression::Xz, b"a"))
+        );
+        assert_eq!(None, Compression::from_magic(b"2019-05-02 line"));
+        assert_eq!(None, Compression::from_magic(&[0x1f]));
+    }
+
+    #[test]
+    fn test_decode_multiple_members() {
+        let tmp_dir = TempDir::new("compression").expect("could not create temp dir");
+        for compression in &[Compression::Gzip, Compression::Zstd, Compression::Xz] {
+            let path = tmp_dir.path().join("members");
+            write_members(&path, *compression, &["first\n", "second\n", "third\n"]);
+            let mut input = InputFile::open(&path).unwrap();
+            let position = input.compressed_position().expect("no compressed position");
+            let mut decoded = String::new();
+            input.read_to_string(&mut decoded).unwrap();
+            assert_eq!("first\nsecond\nthird\n", decoded, "{:?}", compression);
+            assert_eq!(fs::metadata(&path).unwrap().len(), position.get());
+        }
+    }
+
+    #[test]
+    fn test_plain_file_with_shared_position() {
+        let tmp_dir = TempDir::new("compression").expect("could not create temp dir");
+        let plain = tmp_dir.path().join("plain.log");
+        fs::write(&plain, "plain line\n").unwrap();
+        let packed = tmp_dir.path().join("packed.log.gz");
+        write_members(&packed, Compression::Gzip, &["packed line\n"]);
+
+        assert!(InputFile::open(&plain)
+            .unwrap()
+            .compressed_position()
+            .is_none());
+        let position = shared_position_if_compressed(&[&plain, &packed]).expect("no position");
+        for path in &[&plain, &packed] {
+            let mut input = InputFile::open_with_position(path, Some(position.clone())).unwrap();
+            input.read_to_end(&mut vec![]).unwrap();
+        }
+        let on_disk = fs::metadata(&plain).unwrap().len() + fs::metadata(&packed).unwrap().len();
+        assert_eq!(on_disk, position.get());
+        assert!(shared_position_if_compressed(&[&plain]).is_none());
+    }
+
+    #[test]
+    fn test_read_at_checkpoints() {
+        let tmp_dir = TempDir::new("compression").expect("could not create temp dir");
+        let path = tmp_dir.path().join("members.gz");
+        let parts = ["0123456789\n", "abcdefghij\n", "ABCDEFGHIJ\n"];
+        write_members(&path, Compression::Gzip, &parts);
+
+        let mut decoder = Decoder::new(
+            Compression::Gzip,
+            BufReader::new(fs::File::open(&path).unwrap()),
+        )
+        .checkpoint_interval(0);
+        decoder.read_to_end(&mut vec![]).unwrap();
+        let checkpoints = decoder.checkpoints().to_vec();
+        assert_eq!(
+            vec![0, 11, 22],
+            checkpoints
+                .iter()
+                .map(|c| c.decompressed)
+                .collect::<Vec<u64>>()
+        );
+        assert_eq!(0, checkpoints[0].compressed);
+        assert_eq!(
+            compress(Compression::Gzip, parts[0].as_bytes()).len() as u64,
+            checkpoints[1].compressed
+        );
+
+        assert_eq!(
+            b"CDE".to_vec(),
+            read_at(&path, &checkpoints, 24, 3).unwrap()
+        );
+        assert_eq!(
+            b"j\nAB".to_vec(),
+            read_at(&path, &checkpoints, 20, 4).unwrap()
+        );
+        // without checkpoints everything is decoded from the start
+        assert_eq!(b"CDE".to_vec(), read_at(&path, &[], 24, 3).unwrap());
+        assert!(read_at(&path, &checkpoints, 40, 3).is_err());
+
+        let plain = tmp_dir.path().join("plain.log");
+        fs::write(&plain, parts.concat()).unwrap();
+        assert_eq!(b"CDE".to_vec(), read_at(&plain, &[], 24, 3).unwrap());
+    }
+
+    /// lines with varying content so the compressed members consist of many blocks
+    fn large_content(lines: u64) -> Vec<u8> {
+        let mut value: u64 = 7;
+        (0..lines)
+            .flat_map(|i| {
+                value = value.wrapping_mul(6364136223846793005).wrapping_add(1);
+                format!("{:08} value {:x}\n", i, value >> 40).into_bytes()
+            })
+            .collect()
+    }
+
+    /// decode the file starting from the last checkpoint before `offset`, returns
+    /// the bytes at `offset` and how many compressed bytes were consumed for them
+    fn resume_before(
+        path: &Path,
+        compression: Compression,
+        checkpoints: &[Checkpoint],
+        offset: u64,
+    ) -> (Vec<u8>, u64) {
+        let checkpoint = checkpoints
+            .iter()
+            .rev()
+            .find(|c| c.decompressed <= offset)
+            .unwrap();
+        let mut decoder = Decoder::resume(path, compression, checkpoint).unwrap();
+        let position = decoder.compressed_position();
+        let mut skipped = vec![0; (offset - checkpoint.decompressed) as usize];
+        decoder.read_exact(&mut skipped).unwrap();
+        let mut buf = vec![0; 20];
+        decoder.read_exact(&mut buf).unwrap();
+        (buf, position.get())
+    }
+
+    #[test]
+    fn test_checkpoints_inside_of_gzip_member() {
+        let tmp_dir = TempDir::new("compression").expect("could not create temp dir");
+        let path = tmp_dir.path().join("large.gz");
+        let content = large_content(100_000);
+        fs::write(&path, compress(Compression::Gzip, &content)).unwrap();
+
+        let interval = 64 * 1024;
+        let mut decoder = Decoder::new(
+            Compression::Gzip,
+            BufReader::new(fs::File::open(&path).unwrap()),
+        )
+        .checkpoint_interval(interval);
+        let mut decoded = vec![];
+        decoder.read_to_end(&mut decoded).unwrap();
+        assert_eq!(content, decoded);
+        let checkpoints = decoder.checkpoints().to_vec();
+        assert!(checkpoints.len() > 10);
+        assert!(checkpoints[0].state.is_none());
+        assert!(checkpoints[1..].iter().all(|c| c.state.is_some()));
+
+        // grabbing close to the end only decodes from a checkpoint nearby
+        let offset = content.len() as u64 - 100;
+        let (bytes, consumed) = resume_before(&path, Compression::Gzip, &checkpoints, offset);
+        assert_eq!(&content[offset as usize..offset as usize + 20], &bytes[..]);
+        assert!(consumed < fs::metadata(&path).unwrap().len() / 10);
+        assert_eq!(
+            content[offset as usize..offset as usize + 20].to_vec(),
+            read_at(&path, &checkpoints, offset, 20).unwrap()
+        );
+        // the checksum of the member is not known to a resumed decoder
+        let mut resumed =
+            Decoder::resume(&path, Compression::Gzip, checkpoints.last().unwrap()).unwrap();
+        let mut rest = vec![];
+        resumed.read_to_end(&mut rest).unwrap();
+        assert_eq!(
+            &content[checkpoints.last().unwrap().decompressed as usize..],
+            &rest[..]
+        );
+    }
+
+    #[test]
+    fn test_checkpoints_at_xz_blocks() {
+        let tmp_dir = TempDir::new("compression").expect("could not create temp dir");
+        let path = tmp_dir.path().join("blocks.xz");
+        let content = large_content(50_000);
+        let stream = xz2::stream::MtStreamBuilder::new()
+            .threads(2)
+            .block_size(64 * 1024)
+            .preset(1)
+            .encoder()
+            .unwrap();
+        let mut encoder = xz2::write::XzEncoder::new_stream(vec![], stream);
+        encoder.write_all(&content).unwrap();
+        // a second stream follows the one with blocks
+        let mut compressed = encoder.finish().unwrap();
+        compressed.extend(compress(Compression::Xz, b"last line\n"));
+        fs::write(&path, compressed).unwrap();
+
+        let mut decoder = Decoder::new(
+            Compression::Xz,
+            BufReader::new(fs::File::open(&path).unwrap()),
+        )
+        .checkpoint_interval(0);
+        let mut decoded = vec![];
+        decoder.read_to_end(&mut decoded).unwrap();
+        let mut expected = content.clone();
+        expected.extend_from_slice(b"last line\n");
+        assert_eq!(expected, decoded);
+        let checkpoints = decoder.checkpoints().to_vec();
+        let blocks = checkpoints.iter().filter(|c| c.state.is_some()).count();
+        assert_eq!(content.len() / (64 * 1024), blocks);
+
+        let offset = content.len() as u64 - 100;
+        let (bytes, consumed) = resume_before(&path, Compression::Xz, &checkpoints, offset);
+        assert_eq!(&expected[offset as usize..offset as usize + 20], &bytes[..]);
+        assert!(consumed < fs::metadata(&path).unwrap().len() / 5);
+        // decoding from a block continues with the next stream
+        let mut resumed = Decoder::resume(&path, Compression::Xz, &checkpoints[blocks]).unwrap();
+        let mut rest = vec![];
+        resumed.read_to_end(&mut rest).unwrap();
+        assert_eq!(
+            &expected[checkpoints[blocks].decompressed as usize..],
+            &rest[..]
+        );
+    }
+}
@@ -0,0 +1,153 @@
+#[cfg(test)]
+mod tests {
+    use crate::{
+        config::{IndexSection, SectionConfig},
+        container::*,
+        export::export_file_line_based,
+        utils::{self, ENTRY_NEWLINE_SENTINAL_STR, PLUGIN_ID_SENTINAL, ROW_NUMBER_SENTINAL},
+    };
+    use pretty_assertions::assert_eq;
+    use std::{fs, io::BufWriter, path::Path};
+    use tempdir::TempDir;
+
+    fn write_rows(path: &Path, append: bool, rows: &[(&str, usize, Option<i64>)]) -> usize {
+        let (out_file, current_out_file_size) = utils::get_out_file_and_size(append, path).unwrap();
+        let mut writer =
+            RowWriter::new(BufWriter::new(out_file), path, current_out_file_size).unwrap();
+        let mut written = 0;
+        for (content, row, timestamp) in rows {
+            written += writer
+                .write_row("tag", content, *row, true, *timestamp)
+                .unwrap();
+        }
+        writer.flush().unwrap();
+        written
+    }
+
+    #[test]
+    fn test_output_format_for_path() {
+        assert_eq!(
+            OutputFormat::Container,
+            OutputFormat::for_path(Path::new("/tmp/out.rows"))
+        );
+        assert_eq!(
+            OutputFormat::Tagged,
+            OutputFormat::for_path(Path::new("/tmp/out.out"))
+        );
+        assert_eq!(
+            Path::new("/tmp/out.rows.idx"),
+            index_path(Path::new("/tmp/out.rows"))
+        );
+    }
+
+    #[test]
+    fn test_container_keeps_sentinels_in_content() {
+        let tmp_dir = TempDir::new("container").expect("could not create temp dir");
+        let out_path = tmp_dir.path().join("out.rows");
+        let tricky = format!("a{}b{}c\u{0004}", PLUGIN_ID_SENTINAL, ROW_NUMBER_SENTINAL);
+        let multiline = format!("first{}second", ENTRY_NEWLINE_SENTINAL_STR);
+        let written = write_rows(
+            &out_path,
+            false,
+            &[(&tricky, 0, None), (&multiline, 1, Some(1_500_000_000_000))],
+        );
+        assert_eq!(fs::metadata(&out_path).unwrap().len() as usize, written);
+
+        let records: Vec<Record> = ContainerReader::open(&out_path)
+            .unwrap()
+            .map(|r| r.unwrap())
+            .collect();
+        assert_eq!(
+            vec![
+                Record {
+                    tag: "tag".to_string(),
+                    source_row: 0,
+                    timestamp: None,
+                    had_newline: true,
+                    content: tricky.into_bytes(),
+                },
+                Record {
+                    tag: "tag".to_string(),
+                    source_row: 1,
+                    timestamp: Some(1_500_000_000_000),
+                    had_newline: true,
+                    content: b"first\nsecond".to_vec(),
+                },
+            ],
+            records
+        );
+    }
+
+    #[test]
+    fn test_append_and_seek_rows() {
+        let tmp_dir = TempDir::new("container").expect("could not create temp dir");
+        let out_path = tmp_dir.path().join("out.rows");
+        write_rows(&out_path, false, &[("zero", 0, None), ("one", 1, None)]);
+        assert_eq!(2, utils::next_line_nr(&out_path).unwrap());
+        write_rows(&out_path, true, &[("two", 2, None), ("three", 3, None)]);
+        assert_eq!(4, utils::next_line_nr(&out_path).unwrap());
+
+        let mut reader = ContainerReader::open(&out_path).unwrap();
+        assert_eq!(4, reader.rows());
+        let third = reader.record_at(2).unwrap().unwrap();
+        assert_eq!(
+            ("two".as_bytes(), 2),
+            (&third.content[..], third.source_row)
+        );
+        assert_eq!(b"three".to_vec(), reader.next().unwrap().unwrap().content);
+        assert!(reader.next().is_none());
+        assert_eq!(
+            b"one".to_vec(),
+            reader.record_at(1).unwrap().unwrap().content
+        );
+        assert_eq!(None, reader.record_at(4).unwrap());
+    }
+
+    #[test]
+    fn test_tagged_rows_are_unchanged() {
+        let tmp_dir = TempDir::new("container").expect("could not create temp dir");
+        let out_path = tmp_dir.path().join("out.out");
+        write_rows(&out_path, false, &[("line", 7, Some(42))]);
+        let mut expected = vec![];
+        utils::write_tagged_line("tag", &mut expected, "line", 7, true, Some(42)).unwrap();
+        assert_eq!(expected, fs::read(&out_path).unwrap());
+        assert!(!index_path(&out_path).exists());
+    }
+
+    #[test]
+    fn test_export_sections_of_container() {
+        let tmp_dir = TempDir::new("container").expect("could not create temp dir");
+        let out_path = tmp_dir.path().join("out.rows");
+        let lines = ["l0", "l1", "l2", "l3", "l4", "l5"];
+        let rows: Vec<(&str, usize, Option<i64>)> = lines
+            .iter()
+            .enumerate()
+            .map(|(i, l)| (*l, i, None))
+            .collect();
+        write_rows(&out_path, false, &rows);
+
+        let destination = tmp_dir.path().join("export.txt");
+        let (tx, _rx) = crossbeam_channel::unbounded();
+        export_file_line_based(
+            out_path,
+            destination.clone(),
+            SectionConfig {
+                sections: vec![
+                    IndexSection {
+                        first_line: 1,
+                        last_line: 2,
+                    },
+                    IndexSection {
+                        first_line: 4,
+                        last_line: 4,
+                    },
+                ],
+            },
+            true,
+            None,
+            tx,
+        )
+        .unwrap();
+        assert_eq!("l1\nl2\nl4\n", fs::read_to_string(&destination).unwrap());
+    }
+}
@@ -1,2 +1,6 @@
 #[macro_use]
 mod utils_tests;
+mod chunk_index_tests;
+mod compression_tests;
+mod container_tests;
+mod redaction_tests;
@@ -0,0 +1,130 @@
+#[cfg(test)]
+mod tests {
+    use crate::{config::SectionConfig, export::export_file_line_based, redaction::*};
+    use pretty_assertions::assert_eq;
+    use std::fs;
+    use tempdir::TempDir;
+
+    fn redactor(config: &str) -> Redactor {
+        Redactor::new(serde_json::from_str(config).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_structured_rules() {
+        let mut redactor = redactor(
+            r#"{"rules": [
+                {"name": "vin", "type": "vin", "action": "mask"},
+                {"name": "mac", "type": "mac", "action": "mask"},
+                {"name": "ip", "type": "ip", "action": "mask"},
+                {"name": "gps", "type": "gps", "action": "mask"},
+                {"name": "users", "type": "names", "names": ["jdoe"], "action": "mask"}
+            ]}"#,
+        );
+        assert_eq!(
+            Redaction::Changed(
+                "car ***************** at ****************, \
+                 mac *****************, ip ***********, user ****"
+                    .to_string()
+            ),
+            redactor.redact_line(
+                "car WVWZZZ1JZXW000001 at 48.1374, 11.5755, \
+                 mac 00:1A:2b:3C:4d:5E, ip 192.168.0.1, user JDoe"
+            )
+        );
+        assert_eq!(
+            Redaction::Unchanged,
+            redactor.redact_line("nothing 1.2 here")
+        );
+        let report = redactor.report();
+        assert_eq!(
+            (2, 1, 0),
+            (
+                report.entries,
+                report.changed_entries,
+                report.dropped_entries
+            )
+        );
+        assert!(report.rules.values().all(|r| *r
+            == RuleReport {
+                matches: 1,
+                entries: 1
+            }));
+    }
+
+    #[test]
+    fn test_hash_is_consistent() {
+        let config = r#"{"salt": "s", "rules": [{"name": "ip", "type": "ip", "action": "hash"}]}"#;
+        let hashed = |line: &str| match redactor(config).redact_line(line) {
+            Redaction::Changed(line) => line,
+            other => panic!("unexpected {:?}", other),
+        };
+        let first = hashed("from 10.0.0.1 to 10.0.0.2");
+        assert_eq!(first, hashed("from 10.0.0.1 to 10.0.0.2"));
+        let parts: Vec<&str> = first.split(' ').collect();
+        assert!(parts[1].starts_with('#'));
+        assert_ne!(parts[1], parts[3]);
+        let unsalted = r#"{"rules": [{"name": "ip", "type": "ip", "action": "hash"}]}"#;
+        assert_ne!(
+            Redaction::Changed(first),
+            redactor(unsalted).redact_line("from 10.0.0.1 to 10.0.0.2")
+        );
+    }
+
+    #[test]
+    fn test_invalid_rules() {
+        let invalid = |config: &str| Redactor::new(serde_json::from_str(config).unwrap()).is_err();
+        assert!(invalid(
+            r#"{"rules": [{"name": "r", "type": "regex", "pattern": "(", "action": "mask"}]}"#
+        ));
+        assert!(invalid(
+            r#"{"rules": [{"name": "n", "type": "names", "names": [], "action": "drop"}]}"#
+        ));
+    }
+
+    #[test]
+    fn test_export_redacted_lines() {
+        let tmp_dir = TempDir::new("redaction").expect("could not create temp dir");
+        let in_path = tmp_dir.path().join("in.txt");
+        fs::write(&in_path, "ok\nsecret: 1234\nlogin by jdoe\nok again\n").unwrap();
+        let config_path = tmp_dir.path().join("rules.json");
+        fs::write(
+            &config_path,
+            r#"{"rules": [
+                {"name": "secret", "type": "regex", "pattern": "secret", "action": "drop"},
+                {"name": "users", "type": "names", "names": ["jdoe"], "action": "mask"}
+            ]}"#,
+        )
+        .unwrap();
+        let mut redactor = Redactor::from_file(&config_path).unwrap();
+        let out_path = tmp_dir.path().join("out.txt");
+        let (tx, _rx) = crossbeam_channel::unbounded();
+        export_file_line_based(
+            in_path,
+            out_path.clone(),
+            SectionConfig { sections: vec![] },
+            false,
+            Some(&mut redactor),
+            tx,
+        )
+        .unwrap();
+        assert_eq!(
+            "ok\nlogin by ****\nok again\n",
+            fs::read_to_string(&out_path).unwrap()
+        );
+
+        let report_path = report_path(&out_path);
+        assert_eq!(tmp_dir.path().join("out.txt.redaction.json"), report_path);
+        redactor.report().write_to(&report_path).unwrap();
+        let written: RedactionReport =
+            serde_json::from_str(&fs::read_to_string(&report_path).unwrap()).unwrap();
+        assert_eq!(redactor.report(), &written);
+        assert_eq!(
+            (4, 1, 1),
+            (
+                written.entries,
+                written.changed_entries,
+                written.dropped_entries
+            )
+        );
+    }
+}
@@ -11,6 +11,7 @@
 // from E.S.R.Labs.
 use crossbeam_channel as cc;
 use std::{
+    borrow::Cow,
     char,
     fmt::Display,
     fs,
@@ -31,6 +32,9 @@ pub enum Error {
 
 pub const ROW_NUMBER_SENTINAL: char = '\u{0002}';
 pub const PLUGIN_ID_SENTINAL: char = '\u{0003}';
+/// replaces the newlines inside a multi-line log entry so the entry fits into one row
+pub const ENTRY_NEWLINE_SENTINAL: char = '\u{0006}';
+pub const ENTRY_NEWLINE_SENTINAL_STR: &str = "\u{0006}";
 pub const SENTINAL_LENGTH: usize = 1;
 // 1449941111000
 pub const POSIX_TIMESTAMP_LENGTH: usize = 13;
@@ -42,11 +46,15 @@ pub fn is_newline(c: char) -> bool {
 }
 
 #[inline]
-pub fn restore_line(line: &str) -> &str {
-    if let Some(cleaned) = line.split(PLUGIN_ID_SENTINAL).next() {
-        cleaned
+pub fn restore_line(line: &str) -> Cow<'_, str> {
+    let cleaned = match line.split(PLUGIN_ID_SENTINAL).next() {
+        Some(cleaned) => cleaned,
+        None => line,
+    };
+    if cleaned.contains(ENTRY_NEWLINE_SENTINAL) {
+        Cow::Owned(cleaned.replace(ENTRY_NEWLINE_SENTINAL, "\n"))
     } else {
-        line
+        Cow::Borrowed(cleaned)
     }
 }
 
@@ -186,6 +194,9 @@ pub fn next_line_nr(path: &std::path::Path) -> Result<usize, Error> {
     if !path.exists() {
         return Ok(0);
     }
+    if crate::container::is_container(path) {
+        return Ok(crate::container::next_source_row(path)?);
+    }
     let file = fs::File::open(path)?;
     let file_size = file.metadata()?.len();
     if file_size == 0 {
@@ -202,7 +213,7 @@ pub fn next_line_nr(path: &std::path::Path) -> Result<usize, Error> {
             )));
         }
     };
-    let size_of_slice = seek_offset.abs() as usize;
+    let size_of_slice = seek_offset.unsigned_abs() as usize;
     let mut buf: Vec<u8> = vec![0; size_of_slice];
     reader.read_exact(&mut buf)?;
     // |tag|#row#\n
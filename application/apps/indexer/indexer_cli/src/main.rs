@@ -23,8 +23,16 @@ use anyhow::{anyhow, Result};
 use crossbeam_channel as cc;
 use crossbeam_channel::unbounded;
 use dlt::{
-    dlt_file::{count_dlt_messages, export_as_dlt_file, get_dlt_file_info, StatisticsResults},
+    dlt_file::{
+        count_dlt_messages, export_as_dlt_file_with_slots, get_dlt_file_info_with_timeline,
+        StatisticsResults,
+    },
+    dlt_health::{analyze_dlt_health, HealthResults, DEFAULT_CLOCK_TOLERANCE_MS},
+    dlt_non_verbose::{get_non_verbose_report, NonVerboseResults},
     dlt_pcap::pcap_to_dlt,
+    dlt_split::{split_dlt_file, SplitBy, SplitResults},
+    dlt_timeline::{combine_buckets, write_timeline, TimelineFormat, TimelineResults},
+    fibex_cache::{gather_fibex_data_cached, init_fibex_cache},
 };
 use dlt_core::{
     fibex::FibexConfig,
@@ -32,18 +40,26 @@ use dlt_core::{
 };
 use env_logger::Env;
 use indexer_base::{
-    chunks::{serialize_chunks, Chunk, ChunkResults, VoidResults},
+    chunks::{serialize_chunks, Chunk, ChunkIndex, ChunkResults, VoidResults},
     config::*,
     error_reporter::*,
     export::export_file_line_based,
     progress::IndexingResults,
+    redaction::{self, Redactor},
 };
 use indicatif::{ProgressBar, ProgressStyle};
-use merging::merger::merge_files_use_config_file;
+use merging::{
+    alignment::align_inputs,
+    dlt_merger::{merge_dlt_files, read_dlt_merge_inputs},
+    merger::{merge_files_use_config_file, read_merge_options},
+};
 use processor::{
     dlt_source::DltSource,
+    export::{export_rows, read_search_result, ExportFormat},
     grabber::{GrabError, GrabbedContent},
+    structured::StructuredFormat,
     text_source::TextFileSource,
+    text_to_dlt::{convert_text_to_dlt, TextToDltConfig},
 };
 use std::path::Path;
 
@@ -92,6 +108,18 @@ pub async fn main() -> Result<()> {
                 .multiple(true)
                 .help("Sets the level of verbosity"),
         )
+        .arg(
+            Arg::with_name("fibex-cache")
+                .long("fibex-cache")
+                .value_name("DIR")
+                .help("directory for the condensed fibex files (default: ~/.chipmunk/fibex_cache)"),
+        )
+        .arg(
+            Arg::with_name("no-fibex-cache")
+                .long("no-fibex-cache")
+                .conflicts_with("fibex-cache")
+                .help("keep fibex data only in memory"),
+        )
         .subcommand(
             SubCommand::with_name("grab")
                 .about("command for grabbing part of a file")
@@ -172,6 +200,33 @@ pub async fn main() -> Result<()> {
                         .short("w")
                         .help("add timestamp info if available"),
                 )
+                .arg(
+                    Arg::with_name("multiline")
+                        .short("l")
+                        .long("multiline")
+                        .help("attach lines without leading timestamp to the previous entry"),
+                )
+                .arg(
+                    Arg::with_name("sort")
+                        .long("sort")
+                        .conflicts_with("parser")
+                        .help("sort the entries by their timestamps"),
+                )
+                .arg(
+                    Arg::with_name("parser")
+                        .short("p")
+                        .long("parser")
+                        .value_name("PARSER")
+                        .possible_values(&["json", "logfmt", "syslog", "journal", "logcat"])
+                        .help("parse a structured log format into records"),
+                )
+                .arg(
+                    Arg::with_name("year")
+                        .short("y")
+                        .long("year")
+                        .value_name("YEAR")
+                        .help("year to use for formats without year (logcat threadtime)"),
+                )
                 .arg(
                     Arg::with_name("append")
                         .short("a")
@@ -233,6 +288,40 @@ pub async fn main() -> Result<()> {
                         .help("append to file if exists"),
                 ),
         )
+        .subcommand(
+            SubCommand::with_name("align")
+                .about("compute offsets that align the files of a merge config")
+                .arg(
+                    Arg::with_name("merge_config")
+                        .short("m")
+                        .long("merge")
+                        .help("json file that defines all files to be merged")
+                        .value_name("MERGE_CONFIG")
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("anchor")
+                        .short("a")
+                        .long("anchor")
+                        .help("regex for an event logged in several files, markers are detected if not present")
+                        .value_name("ANCHOR")
+                        .multiple(true)
+                        .number_of_values(1),
+                )
+                .arg(
+                    Arg::with_name("drift")
+                        .short("d")
+                        .long("drift")
+                        .help("also estimate a linear clock drift"),
+                )
+                .arg(
+                    Arg::with_name("output")
+                        .short("o")
+                        .long("out")
+                        .value_name("OUT")
+                        .help("write the aligned merge config here"),
+                ),
+        )
         .subcommand(
             SubCommand::with_name("format")
                 .about("test format string")
@@ -295,6 +384,100 @@ pub async fn main() -> Result<()> {
                         .long("out")
                         .value_name("OUT")
                         .help("Output file, \"<file_to_export>.out\" if not present"),
+                )
+                .arg(
+                    Arg::with_name("metadata")
+                        .short("m")
+                        .long("meta")
+                        .value_name("META")
+                        .help("slot metadata of the dlt file (from grab --export) to avoid a full scan"),
+                )
+                .arg(
+                    Arg::with_name("redact")
+                        .long("redact")
+                        .value_name("REDACTION_CONFIG")
+                        .help("json file with redaction rules, a report is written next to the output"),
+                )
+                .arg(
+                    Arg::with_name("search_result")
+                        .short("r")
+                        .long("search-result")
+                        .value_name("SEARCH_RESULT")
+                        .conflicts_with("sections")
+                        .help("export exactly the rows of this search result file"),
+                )
+                .arg(
+                    Arg::with_name("context")
+                        .short("c")
+                        .long("context")
+                        .value_name("CONTEXT")
+                        .help("rows to export before and after each row of the search result")
+                        .default_value("0"),
+                )
+                .arg(
+                    Arg::with_name("format")
+                        .long("format")
+                        .value_name("FORMAT")
+                        .possible_values(&["dlt", "text", "csv", "json"])
+                        .help("format for exporting a search result (dlt for dlt files, otherwise text)"),
+                )
+                .arg(
+                    Arg::with_name("fibex")
+                        .long("fibex")
+                        .value_name("FIBEX")
+                        .help("fibex file to resolve non-verbose messages for text, csv and json"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("chunks")
+                .about("query the chunk index that was written next to an indexed file")
+                .arg(
+                    Arg::with_name("file")
+                        .short("f")
+                        .long("file")
+                        .help("the indexed file")
+                        .required(true)
+                        .index(1),
+                )
+                .arg(
+                    Arg::with_name("rows")
+                        .short("r")
+                        .long("rows")
+                        .value_name("ROWS")
+                        .help("print the byte range of these rows, e.g. \"10,20\""),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("text-to-dlt")
+                .about("convert a text log with timestamps into a dlt file")
+                .arg(
+                    Arg::with_name("input")
+                        .short("i")
+                        .long("input")
+                        .help("the text log to convert")
+                        .required(true)
+                        .index(1),
+                )
+                .arg(
+                    Arg::with_name("output")
+                        .short("o")
+                        .long("out")
+                        .value_name("OUT")
+                        .help("Output file, \"<input>.dlt\" if not present"),
+                )
+                .arg(
+                    Arg::with_name("config")
+                        .short("c")
+                        .long("config")
+                        .value_name("CONFIG")
+                        .help("json file with the conversion config (format, ids, log levels)"),
+                )
+                .arg(
+                    Arg::with_name("format")
+                        .short("f")
+                        .long("format")
+                        .value_name("FORMAT")
+                        .help("format of the timestamps, overrides the config, detected if missing"),
                 ),
         )
         .subcommand(
@@ -429,6 +612,13 @@ pub async fn main() -> Result<()> {
                         .short("n")
                         .long("convert")
                         .help("convert file to dlt format"),
+                )
+                .arg(
+                    Arg::with_name("redact")
+                        .long("redact")
+                        .value_name("REDACTION_CONFIG")
+                        .requires("convert")
+                        .help("json file with redaction rules for converting, a report is written next to the output"),
                 ),
         )
         .subcommand(
@@ -502,6 +692,132 @@ pub async fn main() -> Result<()> {
                         .short("s")
                         .long("stdout")
                         .help("put out chunk information on stdout"),
+                )
+                .arg(
+                    Arg::with_name("timeline")
+                        .short("t")
+                        .long("timeline")
+                        .value_name("MS")
+                        .help("also count messages and bytes in time buckets of this length"),
+                )
+                .arg(
+                    Arg::with_name("timeline-out")
+                        .long("timeline-out")
+                        .value_name("FILE")
+                        .requires("timeline")
+                        .help("file for the timeline, stdout if not present"),
+                )
+                .arg(
+                    Arg::with_name("timeline-format")
+                        .long("timeline-format")
+                        .value_name("FORMAT")
+                        .possible_values(&["csv", "json"])
+                        .default_value("csv"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("dlt-split")
+                .about("split a dlt file into several dlt files")
+                .arg(
+                    Arg::with_name("input")
+                        .short("i")
+                        .long("input")
+                        .help("the DLT file to split")
+                        .required(true)
+                        .index(1),
+                )
+                .arg(
+                    Arg::with_name("out-dir")
+                        .short("o")
+                        .long("out-dir")
+                        .value_name("DIR")
+                        .help("directory for the split files, the directory of the input if not present"),
+                )
+                .arg(
+                    Arg::with_name("by")
+                        .short("b")
+                        .long("by")
+                        .value_name("BY")
+                        .help("one file per ecu, per app id, per time window or per size")
+                        .possible_values(&["ecu", "apid", "time", "size"])
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("window")
+                        .short("w")
+                        .long("window")
+                        .value_name("SECONDS")
+                        .help("length of the time windows")
+                        .default_value("3600"),
+                )
+                .arg(
+                    Arg::with_name("size")
+                        .short("s")
+                        .long("size")
+                        .value_name("MB")
+                        .help("maximum size of the split files")
+                        .default_value("100"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("dlt-health")
+                .about("check a dlt file for corruption, counter gaps and time jumps")
+                .arg(
+                    Arg::with_name("input")
+                        .short("i")
+                        .long("input")
+                        .help("the DLT file to check")
+                        .required(true)
+                        .index(1),
+                )
+                .arg(
+                    Arg::with_name("clock-tolerance")
+                        .short("t")
+                        .long("clock-tolerance")
+                        .value_name("MS")
+                        .help("accepted drift between storage header time and ECU time"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("dlt-non-verbose")
+                .about("list the non-verbose message ids of a dlt file and if fibex resolves them")
+                .arg(
+                    Arg::with_name("input")
+                        .short("i")
+                        .long("input")
+                        .help("the DLT file to check")
+                        .required(true)
+                        .index(1),
+                )
+                .arg(
+                    Arg::with_name("fibex")
+                        .short("f")
+                        .long("fibex")
+                        .value_name("FIBEX")
+                        .multiple(true)
+                        .number_of_values(1)
+                        .help("fibex file to resolve non-verbose messages, can be given more than once"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("dlt-merge")
+                .about("merge dlt files into one dlt file, ordered by storage header time")
+                .arg(
+                    Arg::with_name("config")
+                        .short("c")
+                        .long("config")
+                        .value_name("CONFIG")
+                        .help("json file with the dlt files to merge (path, offset, ecu_id)")
+                        .required(true)
+                        .index(1),
+                )
+                .arg(
+                    Arg::with_name("output")
+                        .short("o")
+                        .long("out")
+                        .value_name("OUT")
+                        .required(true)
+                        .help("Output file"),
                 ),
         )
         .get_matches();
@@ -509,9 +825,16 @@ pub async fn main() -> Result<()> {
     // Vary the output based on how many times the user used the "verbose" flag
     // (i.e. 'myprog -v -v -v' or 'myprog -vvv' vs 'myprog -v'
     let use_stderr_for_status_updates = matches.occurrences_of("v") >= 1;
+    if matches.is_present("no-fibex-cache") {
+        init_fibex_cache(None);
+    } else if let Some(dir) = matches.value_of("fibex-cache") {
+        init_fibex_cache(Some(path::PathBuf::from(dir)));
+    }
 
     if let Some(matches) = matches.subcommand_matches("merge") {
         handle_merge_subcommand(matches, start).await
+    } else if let Some(matches) = matches.subcommand_matches("align") {
+        handle_align_subcommand(matches).await
     } else if let Some(matches) = matches.subcommand_matches("grab") {
         handle_grab_subcommand(matches, start, use_stderr_for_status_updates)
             .await
@@ -530,6 +853,18 @@ pub async fn main() -> Result<()> {
         handle_dlt_udp_subcommand(matches).await
     } else if let Some(matches) = matches.subcommand_matches("dlt-stats") {
         handle_dlt_stats_subcommand(matches, start, use_stderr_for_status_updates).await
+    } else if let Some(matches) = matches.subcommand_matches("dlt-split") {
+        handle_dlt_split_subcommand(matches, start, use_stderr_for_status_updates)
+    } else if let Some(matches) = matches.subcommand_matches("dlt-health") {
+        handle_dlt_health_subcommand(matches)
+    } else if let Some(matches) = matches.subcommand_matches("dlt-non-verbose") {
+        handle_dlt_non_verbose_subcommand(matches)
+    } else if let Some(matches) = matches.subcommand_matches("dlt-merge") {
+        handle_dlt_merge_subcommand(matches, start, use_stderr_for_status_updates)
+    } else if let Some(matches) = matches.subcommand_matches("chunks") {
+        handle_chunks_subcommand(matches)
+    } else if let Some(matches) = matches.subcommand_matches("text-to-dlt") {
+        handle_text_to_dlt_subcommand(matches)
     } else if let Some(matches) = matches.subcommand_matches("discover") {
         handle_discover_subcommand(matches).await
     }
@@ -627,15 +962,15 @@ pub async fn main() -> Result<()> {
                 match res {
                     Ok((v, start_op)) => {
                         duration_report(start_op, format!("grabbing {} lines", length));
-                        let mut i = start_index;
                         let cap_after = 150;
-                        for (cnt, s) in v.grabbed_elements.iter().enumerate() {
+                        for (i, (cnt, s)) in
+                            (start_index..).zip(v.grabbed_elements.iter().enumerate())
+                        {
                             if s.content.len() > cap_after {
                                 println!("[{}]--> {}", i + 1, &s.content[..cap_after]);
                             } else {
                                 println!("[{}]--> {}", i + 1, &s.content);
                             }
-                            i += 1;
                             if cnt > 15 {
                                 println!("...{} more lines", v.grabbed_elements.len() - 15);
                                 break;
@@ -691,11 +1026,8 @@ pub async fn main() -> Result<()> {
             let progress_bar = initialize_progress_bar(total);
             let tag_string = tag.to_string();
             let fallback_out = file.to_string() + ".out";
-            let out_path = path::PathBuf::from(
-                matches
-                    .value_of("output")
-                    .unwrap_or_else(|| fallback_out.as_str()),
-            );
+            let out_path =
+                path::PathBuf::from(matches.value_of("output").unwrap_or(fallback_out.as_str()));
             let mapping_out_path: path::PathBuf =
                 path::PathBuf::from(file.to_string() + ".map.json");
             let chunk_size = value_t_or_exit!(matches.value_of("chunk_size"), usize);
@@ -710,33 +1042,82 @@ pub async fn main() -> Result<()> {
             let append: bool = matches.is_present("append");
             let watch: bool = matches.is_present("watch");
             let timestamps: bool = matches.is_present("timestamp");
+            let multiline: bool = matches.is_present("multiline");
+            let sort: bool = matches.is_present("sort");
+            let parser = matches.value_of("parser").map(|p| p.to_string());
+            let year = matches
+                .value_of("year")
+                .map(|y| y.parse::<i32>().expect("year must be a number"));
             let (tx, rx): (
                 cc::Sender<IndexingResults<Chunk>>,
                 cc::Receiver<ChunkResults>,
             ) = unbounded();
 
             let _h = tokio::spawn(async move {
-                match processor::processor::create_index_and_mapping(
-                    IndexingConfig {
-                        tag: tag_string,
-                        chunk_size,
-                        in_file: file_path,
-                        out_path,
-                        append,
-                        watch,
-                    },
-                    source_file_size,
-                    timestamps,
-                    tx,
-                    None,
-                )
-                .await
-                {
-                    Err(why) => {
-                        report_error(format!("couldn't process: {}", why));
-                        std::process::exit(2)
+                let config = IndexingConfig {
+                    tag: tag_string,
+                    chunk_size,
+                    in_file: file_path,
+                    out_path,
+                    append,
+                    watch,
+                };
+                let res = match parser.as_deref() {
+                    Some("logcat") => {
+                        processor::logcat::create_index_and_mapping_logcat(
+                            config,
+                            source_file_size,
+                            year,
+                            tx,
+                            None,
+                        )
+                        .await
                     }
-                    Ok(()) => (),
+                    Some("journal") => {
+                        processor::journal::create_index_and_mapping_journal(
+                            config,
+                            source_file_size,
+                            tx,
+                            None,
+                        )
+                        .await
+                    }
+                    Some(p) => {
+                        processor::structured::create_index_and_mapping_structured(
+                            config,
+                            source_file_size,
+                            p.parse::<StructuredFormat>().expect("invalid parser"),
+                            tx,
+                            None,
+                        )
+                        .await
+                    }
+                    None if sort => {
+                        processor::sort::create_sorted_index_and_mapping(
+                            config,
+                            source_file_size,
+                            multiline,
+                            processor::sort::DEFAULT_RUN_SIZE,
+                            tx,
+                            None,
+                        )
+                        .await
+                    }
+                    None => {
+                        processor::processor::create_index_and_mapping(
+                            config,
+                            source_file_size,
+                            timestamps,
+                            multiline,
+                            tx,
+                            None,
+                        )
+                        .await
+                    }
+                };
+                if let Err(why) = res {
+                    report_error(format!("couldn't process: {}", why));
+                    std::process::exit(2)
                 }
             });
             loop {
@@ -822,7 +1203,7 @@ pub async fn main() -> Result<()> {
                         report_error(format!("couldn't process: {}", why));
                         std::process::exit(2)
                     }
-                    Ok(Ok(IndexingProgress::Finished { .. })) => {
+                    Ok(Ok(IndexingProgress::Finished)) => {
                         println!("received finish event");
                         progress_bar.finish_and_clear();
                         break;
@@ -861,6 +1242,50 @@ pub async fn main() -> Result<()> {
         std::process::exit(0)
     }
 
+    async fn handle_align_subcommand(matches: &clap::ArgMatches<'_>) {
+        let merge_conf_path =
+            path::PathBuf::from(value_t_or_exit!(matches.value_of("merge_config"), String));
+        let anchors: Vec<String> = matches
+            .values_of("anchor")
+            .map(|values| values.map(String::from).collect())
+            .unwrap_or_default();
+        let options = fs::File::open(&merge_conf_path)
+            .map_err(anyhow::Error::from)
+            .and_then(|mut f| read_merge_options(&mut f, merge_conf_path.parent()));
+        let alignment = match options
+            .and_then(|options| align_inputs(options, &anchors, matches.is_present("drift")))
+        {
+            Ok(alignment) => alignment,
+            Err(e) => {
+                report_error(format!("couldn't align inputs: {}", e));
+                std::process::exit(2)
+            }
+        };
+        println!("anchors: {:?}", alignment.anchors);
+        for input in &alignment.report {
+            println!(
+                "{}: offset {} ms, drift {:?}, {} matches, residual rms {:.1} ms (max {} ms)",
+                input.tag,
+                input.offset,
+                input.clock_drift.map(|d| d.ppm),
+                input.matches,
+                input.residual_rms,
+                input.residual_max
+            );
+        }
+        let aligned_config =
+            serde_json::to_string_pretty(&alignment.options).expect("could not serialize config");
+        match matches.value_of("output") {
+            Some(out) => {
+                if let Err(e) = fs::write(out, aligned_config) {
+                    report_error(format!("couldn't write {}: {}", out, e));
+                    std::process::exit(2)
+                }
+            }
+            None => println!("{}", aligned_config),
+        }
+    }
+
     async fn handle_format_subcommand(
         matches: &clap::ArgMatches<'_>,
         start: std::time::Instant,
@@ -949,41 +1374,204 @@ pub async fn main() -> Result<()> {
 
         if let Some(file_name) = matches.value_of("file") {
             let fallback_out = file_name.to_string() + ".out";
-            let out_path = path::PathBuf::from(
-                matches
-                    .value_of("output")
-                    .unwrap_or_else(|| fallback_out.as_str()),
-            );
+            let out_path =
+                path::PathBuf::from(matches.value_of("target").unwrap_or(fallback_out.as_str()));
             let file_path = path::PathBuf::from(file_name);
             let was_session_file: bool = matches.is_present("is_session_file");
+            if let Some(search_result) = matches.value_of("search_result") {
+                export_search_result(matches, &file_path, &out_path, Path::new(search_result));
+                std::process::exit(0)
+            }
             let sections_string = value_t_or_exit!(matches.value_of("sections"), String);
             let sections: Vec<IndexSection> = sections_string
                 .split('|')
                 .map(|s| to_pair(s).expect("could not parse section pair"))
                 .collect();
 
+            let mut redactor = matches.value_of("redact").map(load_redactor);
             let (tx, _rx): (cc::Sender<ChunkResults>, cc::Receiver<ChunkResults>) = unbounded();
             let ending = &file_path.extension().expect("could not get extension");
             if ending.to_str() == Some("dlt") {
                 trace!("was dlt file");
-                export_as_dlt_file(file_path, out_path, SectionConfig { sections }, tx)
-                    .expect("export did not work");
+                let slots = match matches.value_of("metadata") {
+                    Some(metadata_path) => {
+                        let metadata = processor::grabber::read_metadata(metadata_path)
+                            .expect("could not read metadata");
+                        match processor::dlt_source::message_slots(&metadata) {
+                            Ok(slots) => slots,
+                            Err(e) => {
+                                report_error(format!("metadata can't be used: {}", e));
+                                std::process::exit(2);
+                            }
+                        }
+                    }
+                    None => vec![],
+                };
+                export_as_dlt_file_with_slots(
+                    file_path,
+                    out_path.clone(),
+                    SectionConfig { sections },
+                    slots,
+                    redactor.as_mut(),
+                    tx,
+                )
+                .expect("export did not work");
             } else {
                 trace!("was regular file");
                 export_file_line_based(
                     file_path,
-                    out_path,
+                    out_path.clone(),
                     SectionConfig { sections },
                     was_session_file,
+                    redactor.as_mut(),
                     tx,
                 )
                 .expect("export did not work");
             };
+            if let Some(redactor) = redactor {
+                write_redaction_report(&redactor, &out_path);
+            }
 
             println!("done with handle_export_subcommand");
             std::process::exit(0)
         }
     }
+    fn load_redactor(config_path: &str) -> Redactor {
+        match Redactor::from_file(Path::new(config_path)) {
+            Ok(redactor) => redactor,
+            Err(e) => {
+                report_error(format!("could not load redaction rules: {}", e));
+                std::process::exit(2)
+            }
+        }
+    }
+    fn write_redaction_report(redactor: &Redactor, out_path: &Path) {
+        let report = redactor.report();
+        let report_path = redaction::report_path(out_path);
+        if let Err(e) = report.write_to(&report_path) {
+            report_error(format!("could not write redaction report: {}", e));
+            std::process::exit(2)
+        }
+        println!(
+            "redacted {} of {} entries, dropped {} (report: {:?})",
+            report.changed_entries, report.entries, report.dropped_entries, report_path
+        );
+    }
+    fn export_search_result(
+        matches: &clap::ArgMatches<'_>,
+        file_path: &Path,
+        out_path: &Path,
+        search_result: &Path,
+    ) {
+        let context = value_t_or_exit!(matches.value_of("context"), usize);
+        let is_dlt = file_path.extension().and_then(|e| e.to_str()) == Some("dlt");
+        let format = match matches.value_of("format") {
+            Some(format) => format.parse().expect("unknown export format"),
+            None if is_dlt => ExportFormat::Dlt,
+            None => ExportFormat::Text,
+        };
+        let rows = match read_search_result(search_result) {
+            Ok(rows) => rows,
+            Err(e) => {
+                report_error(format!("could not read search result: {}", e));
+                std::process::exit(2);
+            }
+        };
+        let (tx, _rx): (cc::Sender<ChunkResults>, cc::Receiver<ChunkResults>) = unbounded();
+        let fibex_metadata = matches.value_of("fibex").and_then(|fibex| {
+            gather_fibex_data_cached(
+                FibexConfig {
+                    fibex_file_paths: vec![fibex.to_string()],
+                },
+                &tx,
+            )
+        });
+        match export_rows(
+            file_path,
+            out_path,
+            &rows,
+            context,
+            format,
+            fibex_metadata.as_ref(),
+            tx,
+        ) {
+            Ok(exported) => println!("exported {} rows to {:?}", exported, out_path),
+            Err(e) => {
+                report_error(format!("export did not work: {}", e));
+                std::process::exit(2);
+            }
+        }
+    }
+    fn handle_chunks_subcommand(matches: &clap::ArgMatches<'_>) {
+        debug!("handle_chunks_subcommand");
+        let file_path = path::PathBuf::from(value_t_or_exit!(matches.value_of("file"), String));
+        let index = match ChunkIndex::load(&file_path) {
+            Ok(index) => index,
+            Err(e) => {
+                report_error(format!("could not load chunk index: {}", e));
+                std::process::exit(2);
+            }
+        };
+        match matches.value_of("rows") {
+            Some(rows) => {
+                let rows = to_pair(rows).expect("could not parse rows");
+                match index.byte_range(rows.first_line, rows.last_line) {
+                    Some((start, end)) => println!("{},{}", start, end),
+                    None => {
+                        report_error(format!(
+                            "rows {}..{} are not in the chunk index ({} rows)",
+                            rows.first_line,
+                            rows.last_line,
+                            index.rows()
+                        ));
+                        std::process::exit(2);
+                    }
+                }
+            }
+            None => match serde_json::to_string(index.chunks()) {
+                Ok(chunks) => println!("{}", chunks),
+                Err(e) => {
+                    report_error(format!("could not serialize chunks: {}", e));
+                    std::process::exit(2);
+                }
+            },
+        }
+    }
+    fn handle_text_to_dlt_subcommand(matches: &clap::ArgMatches<'_>) {
+        debug!("handle_text_to_dlt_subcommand");
+        let file_name = value_t_or_exit!(matches.value_of("input"), String);
+        let fallback_out = file_name.clone() + ".dlt";
+        let out_path =
+            path::PathBuf::from(matches.value_of("output").unwrap_or(fallback_out.as_str()));
+        let mut config: TextToDltConfig = match matches.value_of("config") {
+            Some(config_path) => {
+                let parsed = fs::read_to_string(config_path)
+                    .map_err(|e| anyhow!("{}", e))
+                    .and_then(|content| {
+                        serde_json::from_str(&content).map_err(|e| anyhow!("{}", e))
+                    });
+                match parsed {
+                    Ok(config) => config,
+                    Err(e) => {
+                        report_error(format!("could not read config {}: {}", config_path, e));
+                        std::process::exit(2)
+                    }
+                }
+            }
+            None => TextToDltConfig::default(),
+        };
+        if let Some(format) = matches.value_of("format") {
+            config.format = Some(format.to_string());
+        }
+        let (tx, _rx): (cc::Sender<VoidResults>, cc::Receiver<VoidResults>) = unbounded();
+        match convert_text_to_dlt(Path::new(&file_name), &out_path, &config, tx) {
+            Ok(written) => println!("wrote {} messages to {:?}", written, out_path),
+            Err(e) => {
+                report_error(format!("could not convert to dlt: {}", e));
+                std::process::exit(2)
+            }
+        }
+    }
     async fn handle_dlt_subcommand(matches: &clap::ArgMatches<'_>, start: std::time::Instant) {
         debug!("handle_dlt_subcommand");
         if let (Some(file_name), Some(tag)) = (matches.value_of("input"), matches.value_of("tag")) {
@@ -1010,11 +1598,8 @@ pub async fn main() -> Result<()> {
                 }
             };
             let fallback_out = file_name.to_string() + ".out";
-            let out_path = path::PathBuf::from(
-                matches
-                    .value_of("output")
-                    .unwrap_or_else(|| fallback_out.as_str()),
-            );
+            let out_path =
+                path::PathBuf::from(matches.value_of("output").unwrap_or(fallback_out.as_str()));
             let file_path = path::PathBuf::from(file_name);
             let mapping_out_path: path::PathBuf =
                 path::PathBuf::from(file_name.to_string() + ".map.json");
@@ -1061,7 +1646,7 @@ pub async fn main() -> Result<()> {
                         report_error(format!("couldn't process: {}", why));
                         std::process::exit(2)
                     }
-                    Ok(Ok(IndexingProgress::Finished { .. })) => {
+                    Ok(Ok(IndexingProgress::Finished)) => {
                         let _ = serialize_chunks(&chunks, &mapping_out_path);
                         let file_size_in_mb = source_file_size as f64 / 1024.0 / 1024.0;
                         duration_report_throughput(
@@ -1121,11 +1706,8 @@ pub async fn main() -> Result<()> {
             };
             let append: bool = matches.is_present("append");
             let fallback_out = file_name.to_string() + ".out";
-            let out_path = path::PathBuf::from(
-                matches
-                    .value_of("output")
-                    .unwrap_or_else(|| fallback_out.as_str()),
-            );
+            let out_path =
+                path::PathBuf::from(matches.value_of("output").unwrap_or(fallback_out.as_str()));
             let file_path = path::PathBuf::from(file_name);
             let mapping_out_path: path::PathBuf =
                 path::PathBuf::from(file_name.to_string() + ".map.json");
@@ -1137,8 +1719,10 @@ pub async fn main() -> Result<()> {
             let in_one_go: bool = matches.is_present("convert");
             let shutdown_channel = sync::mpsc::channel(1);
             if in_one_go {
+                let mut redactor = matches.value_of("redact").map(load_redactor);
                 let (tx, rx): (cc::Sender<VoidResults>, cc::Receiver<VoidResults>) = unbounded();
-                tokio::spawn(async move {
+                let report_out_path = out_path.clone();
+                let conversion = tokio::spawn(async move {
                     let res = pcap_to_dlt(
                         &file_path,
                         &out_path,
@@ -1146,12 +1730,14 @@ pub async fn main() -> Result<()> {
                         tx,
                         shutdown_channel.1,
                         Some(load_test_fibex()),
+                        redactor.as_mut(),
                     )
                     .await;
                     if let Err(reason) = res {
                         report_error(format!("couldn't convert: {}", reason));
                         std::process::exit(2)
                     }
+                    redactor
                 });
                 loop {
                     match rx.recv() {
@@ -1159,7 +1745,7 @@ pub async fn main() -> Result<()> {
                             report_error(format!("couldn't process: {}", why));
                             std::process::exit(2)
                         }
-                        Ok(Ok(IndexingProgress::Finished { .. })) => {
+                        Ok(Ok(IndexingProgress::Finished)) => {
                             progress_bar.finish_and_clear();
                             break;
                         }
@@ -1185,6 +1771,9 @@ pub async fn main() -> Result<()> {
                         Ok(_) => report_warning("process finished without result"),
                     }
                 }
+                if let Ok(Some(redactor)) = conversion.await {
+                    write_redaction_report(&redactor, &report_out_path);
+                }
             } else {
                 let (tx, rx): (cc::Sender<ChunkResults>, cc::Receiver<ChunkResults>) = unbounded();
                 tokio::spawn(async move {
@@ -1216,7 +1805,7 @@ pub async fn main() -> Result<()> {
                             report_error(format!("couldn't process: {}", why));
                             std::process::exit(2)
                         }
-                        Ok(Ok(IndexingProgress::Finished { .. })) => {
+                        Ok(Ok(IndexingProgress::Finished)) => {
                             let _ = serialize_chunks(&chunks, &mapping_out_path);
                             progress_bar.finish_and_clear();
                             break;
@@ -1319,7 +1908,7 @@ pub async fn main() -> Result<()> {
                         report_error(format!("couldn't process: {}", why));
                         std::process::exit(2)
                     }
-                    Ok(Ok(IndexingProgress::Finished { .. })) => {
+                    Ok(Ok(IndexingProgress::Finished)) => {
                         let _ = serialize_chunks(&chunks, &mapping_out_path);
                         break;
                     }
@@ -1538,8 +2127,18 @@ pub async fn main() -> Result<()> {
                 cc::Receiver<StatisticsResults>,
             ) = unbounded();
 
+            let timeline_ms = if matches.is_present("timeline") {
+                Some(value_t_or_exit!(matches.value_of("timeline"), u64))
+            } else {
+                None
+            };
+            let (timeline_tx, timeline_rx): (
+                cc::Sender<TimelineResults>,
+                cc::Receiver<TimelineResults>,
+            ) = unbounded();
             thread::spawn(move || {
-                if let Err(why) = get_dlt_file_info(&file_path, &tx, None) {
+                let timeline = timeline_ms.map(|ms| (ms, timeline_tx));
+                if let Err(why) = get_dlt_file_info_with_timeline(&file_path, &tx, timeline, None) {
                     report_error(format!("couldn't collect statistics: {}", why));
                     std::process::exit(2)
                 }
@@ -1601,6 +2200,257 @@ pub async fn main() -> Result<()> {
                     }
                 }
             }
+            if timeline_ms.is_some() {
+                let buckets = combine_buckets(
+                    timeline_rx
+                        .try_iter()
+                        .filter_map(|item| match item {
+                            Ok(IndexingProgress::GotItem { item }) => Some(item),
+                            _ => None,
+                        })
+                        .collect(),
+                );
+                let format = match matches.value_of("timeline-format") {
+                    Some("json") => TimelineFormat::Json,
+                    _ => TimelineFormat::Csv,
+                };
+                let written = match matches.value_of("timeline-out") {
+                    Some(out) => fs::File::create(out)
+                        .and_then(|f| write_timeline(&buckets, format, std::io::BufWriter::new(f))),
+                    None => write_timeline(&buckets, format, std::io::stdout()),
+                };
+                if let Err(e) = written {
+                    report_error(format!("could not write timeline: {}", e));
+                    std::process::exit(2)
+                }
+            }
+        }
+    }
+
+    fn handle_dlt_split_subcommand(
+        matches: &clap::ArgMatches<'_>,
+        start: std::time::Instant,
+        status_updates: bool,
+    ) {
+        let file_path = path::PathBuf::from(value_t_or_exit!(matches.value_of("input"), String));
+        let out_dir = match matches.value_of("out-dir") {
+            Some(dir) => path::PathBuf::from(dir),
+            None => file_path
+                .parent()
+                .map(Path::to_path_buf)
+                .unwrap_or_default(),
+        };
+        let split_by = match matches.value_of("by") {
+            Some("ecu") => SplitBy::Ecu,
+            Some("apid") => SplitBy::AppId,
+            Some("time") => {
+                SplitBy::TimeWindow(value_t_or_exit!(matches.value_of("window"), u64) * 1000)
+            }
+            _ => SplitBy::Size(value_t_or_exit!(matches.value_of("size"), u64) * 1024 * 1024),
+        };
+        let source_file_size = match fs::metadata(&file_path) {
+            Ok(file_meta) => file_meta.len(),
+            Err(_) => {
+                report_error(format!("could not find out size of {:?}", file_path));
+                std::process::exit(2);
+            }
+        };
+        let progress_bar = initialize_progress_bar(source_file_size);
+        let (tx, rx): (cc::Sender<SplitResults>, cc::Receiver<SplitResults>) = unbounded();
+        let handle =
+            thread::spawn(move || split_dlt_file(&file_path, &out_dir, &split_by, &tx, None));
+        loop {
+            match rx.recv() {
+                Ok(Ok(IndexingProgress::GotItem { item: files })) => {
+                    for file in files {
+                        println!("wrote {} messages to {:?}", file.messages, file.path);
+                    }
+                }
+                Ok(Ok(IndexingProgress::Progress { ticks: t })) => {
+                    progress_bar.set_position(t.0);
+                }
+                Ok(Ok(IndexingProgress::Finished)) | Ok(Ok(IndexingProgress::Stopped)) => {
+                    progress_bar.finish_and_clear();
+                    break;
+                }
+                Ok(Err(Notification {
+                    severity,
+                    content,
+                    line,
+                })) => {
+                    if severity == Severity::WARNING {
+                        report_warning_ln(content, line);
+                    } else {
+                        report_error_ln(content, line);
+                    }
+                }
+                Err(_) => break,
+            }
+        }
+        match handle.join() {
+            Ok(Ok(_)) => {
+                if status_updates {
+                    duration_report(start, "splitting dlt file".to_string());
+                }
+            }
+            Ok(Err(e)) => {
+                report_error(format!("could not split dlt file: {}", e));
+                std::process::exit(2)
+            }
+            Err(_) => {
+                report_error("splitting thread panicked");
+                std::process::exit(2)
+            }
+        }
+    }
+
+    fn handle_dlt_health_subcommand(matches: &clap::ArgMatches<'_>) {
+        let file_path = path::PathBuf::from(value_t_or_exit!(matches.value_of("input"), String));
+        let clock_tolerance = if matches.is_present("clock-tolerance") {
+            value_t_or_exit!(matches.value_of("clock-tolerance"), u64)
+        } else {
+            DEFAULT_CLOCK_TOLERANCE_MS
+        };
+        let source_file_size = match fs::metadata(&file_path) {
+            Ok(file_meta) => file_meta.len(),
+            Err(_) => {
+                report_error(format!("could not find out size of {:?}", file_path));
+                std::process::exit(2);
+            }
+        };
+        let progress_bar = initialize_progress_bar(source_file_size);
+        let (tx, rx): (cc::Sender<HealthResults>, cc::Receiver<HealthResults>) = unbounded();
+        let handle =
+            thread::spawn(move || analyze_dlt_health(&file_path, clock_tolerance, &tx, None));
+        loop {
+            match rx.recv() {
+                Ok(Ok(IndexingProgress::Progress { ticks: t })) => {
+                    progress_bar.set_position(t.0);
+                }
+                Ok(Ok(IndexingProgress::Finished)) | Ok(Ok(IndexingProgress::Stopped)) => {
+                    progress_bar.finish_and_clear();
+                    break;
+                }
+                Ok(Err(Notification {
+                    severity,
+                    content,
+                    line,
+                })) => {
+                    if severity == Severity::WARNING {
+                        report_warning_ln(content, line);
+                    } else {
+                        report_error_ln(content, line);
+                    }
+                }
+                Ok(_) => (),
+                Err(_) => break,
+            }
+        }
+        match handle.join() {
+            Ok(Ok(report)) => match serde_json::to_string_pretty(&report) {
+                Ok(json) => println!("{}", json),
+                Err(e) => {
+                    report_error(format!("serializing health report failed: {}", e));
+                    std::process::exit(2)
+                }
+            },
+            Ok(Err(e)) => {
+                report_error(format!("could not check dlt file: {}", e));
+                std::process::exit(2)
+            }
+            Err(_) => {
+                report_error("health check thread panicked");
+                std::process::exit(2)
+            }
+        }
+    }
+
+    fn handle_dlt_non_verbose_subcommand(matches: &clap::ArgMatches<'_>) {
+        let file_path = path::PathBuf::from(value_t_or_exit!(matches.value_of("input"), String));
+        let fibex = matches.values_of("fibex").map(|paths| FibexConfig {
+            fibex_file_paths: paths.map(String::from).collect(),
+        });
+        let (tx, rx): (
+            cc::Sender<NonVerboseResults>,
+            cc::Receiver<NonVerboseResults>,
+        ) = unbounded();
+        match get_non_verbose_report(&file_path, fibex, &tx, None) {
+            Ok(report) => {
+                for notification in rx.try_iter().filter_map(Result::err) {
+                    report_error_ln(notification.content, notification.line);
+                }
+                match serde_json::to_string_pretty(&report) {
+                    Ok(json) => println!("{}", json),
+                    Err(e) => {
+                        report_error(format!("serializing non-verbose report failed: {}", e));
+                        std::process::exit(2)
+                    }
+                }
+            }
+            Err(e) => {
+                report_error(format!("could not check non-verbose messages: {}", e));
+                std::process::exit(2)
+            }
+        }
+    }
+
+    fn handle_dlt_merge_subcommand(
+        matches: &clap::ArgMatches<'_>,
+        start: std::time::Instant,
+        status_updates: bool,
+    ) {
+        let config_path = path::PathBuf::from(value_t_or_exit!(matches.value_of("config"), String));
+        let out_path = path::PathBuf::from(value_t_or_exit!(matches.value_of("output"), String));
+        let inputs = match fs::File::open(&config_path)
+            .map_err(|e| anyhow!("{}", e))
+            .and_then(|mut f| read_dlt_merge_inputs(&mut f, config_path.parent()))
+        {
+            Ok(inputs) => inputs,
+            Err(e) => {
+                report_error(format!("could not read config {:?}: {}", config_path, e));
+                std::process::exit(2)
+            }
+        };
+        let total: u64 = inputs
+            .iter()
+            .filter_map(|input| fs::metadata(&input.path).ok())
+            .map(|meta| meta.len())
+            .sum();
+        let progress_bar = initialize_progress_bar(total);
+        let (tx, rx): (cc::Sender<VoidResults>, cc::Receiver<VoidResults>) = unbounded();
+        let handle = thread::spawn(move || merge_dlt_files(inputs, &out_path, tx, None));
+        loop {
+            match rx.recv() {
+                Ok(Ok(IndexingProgress::Progress { ticks: t })) => {
+                    progress_bar.set_position(t.0);
+                }
+                Ok(Ok(IndexingProgress::Finished)) | Ok(Ok(IndexingProgress::Stopped)) => {
+                    progress_bar.finish_and_clear();
+                    break;
+                }
+                Ok(_) => (),
+                Err(_) => break,
+            }
+        }
+        match handle.join() {
+            Ok(Ok(written)) => {
+                println!(
+                    "merged {} messages ({:?} per input)",
+                    written.iter().sum::<usize>(),
+                    written
+                );
+                if status_updates {
+                    duration_report(start, "merging dlt files".to_string());
+                }
+            }
+            Ok(Err(e)) => {
+                report_error(format!("could not merge dlt files: {}", e));
+                std::process::exit(2)
+            }
+            Err(_) => {
+                report_error("merging thread panicked");
+                std::process::exit(2)
+            }
         }
     }
     Ok(())
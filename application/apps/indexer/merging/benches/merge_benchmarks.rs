@@ -0,0 +1,90 @@
+extern crate criterion;
+extern crate merging;
+
+use criterion::{Criterion, *};
+use crossbeam_channel as cc;
+use indexer_base::chunks::ChunkResults;
+use merging::merger::{merge_files_use_config, FileMergeOptions};
+use std::{
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+};
+use tempdir::TempDir;
+
+const TOTAL_LINES: usize = 20_000;
+
+/// write `TOTAL_LINES` lines spread over `input_count` files, the timestamps
+/// of the files interleave
+fn create_inputs(dir: &Path, input_count: usize) -> Vec<PathBuf> {
+    (0..input_count)
+        .map(|input| {
+            let path = dir.join(format!("input_{}.log", input));
+            let mut file = fs::File::create(&path).unwrap();
+            for i in (input..TOTAL_LINES).step_by(input_count) {
+                writeln!(
+                    file,
+                    "05-22-2019 {:02}:{:02}:{:02}.{:03} line {} of input {}",
+                    i / 3_600_000 % 24,
+                    i / 60_000 % 60,
+                    i / 1000 % 60,
+                    i % 1000,
+                    i,
+                    input
+                )
+                .unwrap();
+            }
+            path
+        })
+        .collect()
+}
+
+fn merge_options(paths: &[PathBuf]) -> Vec<FileMergeOptions> {
+    paths
+        .iter()
+        .enumerate()
+        .map(|(input, path)| FileMergeOptions {
+            path: path.to_string_lossy().into(),
+            offset: None,
+            year: None,
+            tag: format!("TAG{}", input),
+            format: "MM-DD-YYYY hh:mm:ss.s".to_string(),
+            multiline: false,
+            input_type: Default::default(),
+            ecu_time_reference: None,
+            reorder_window: None,
+            clock_drift: None,
+        })
+        .collect()
+}
+
+fn merge_inputs_benchmark(c: &mut Criterion) {
+    let mut group = c.benchmark_group("merge");
+    group.sample_size(10);
+    group.throughput(Throughput::Elements(TOTAL_LINES as u64));
+    for input_count in [2usize, 50].iter() {
+        let tmp_dir = TempDir::new("merge_bench").expect("could not create temp dir");
+        let out_path = tmp_dir.path().join("merged.out");
+        let paths = create_inputs(tmp_dir.path(), *input_count);
+        group.bench_with_input(
+            BenchmarkId::from_parameter(input_count),
+            &paths,
+            |b, paths| {
+                b.iter_batched(
+                    || merge_options(paths),
+                    |options| {
+                        let (tx, _rx): (cc::Sender<ChunkResults>, cc::Receiver<ChunkResults>) =
+                            cc::unbounded();
+                        merge_files_use_config(options, &out_path, false, 500, tx, None)
+                            .expect("merge failed")
+                    },
+                    BatchSize::SmallInput,
+                )
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, merge_inputs_benchmark);
+criterion_main!(benches);
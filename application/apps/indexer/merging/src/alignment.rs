@@ -0,0 +1,257 @@
+// Copyright (c) 2019 E.S.R.Labs. All rights reserved.
+//
+// NOTICE:  All information contained herein is, and remains
+// the property of E.S.R.Labs and its suppliers, if any.
+// The intellectual and technical concepts contained herein are
+// proprietary to E.S.R.Labs and its suppliers and may be covered
+// by German and Foreign Patents, patents in process, and are protected
+// by trade secret or copyright law.
+// Dissemination of this information or reproduction of this material
+// is strictly forbidden unless prior written permission is obtained
+// from E.S.R.Labs.
+use crate::{
+    merger::{open_timed_lines, FileMergeOptions, InputType},
+    report::InputCounts,
+};
+use anyhow::{anyhow, Result};
+use processor::parse::lookup_regex_for_format_str;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Linear correction for a clock that runs too fast or too slow
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct ClockDrift {
+    /// time (after the offset was applied) at which the clock is right
+    pub since: i64,
+    /// how many ms the clock has to be corrected per 1000 s
+    pub ppm: f64,
+}
+
+impl ClockDrift {
+    pub fn correct(&self, timestamp: i64) -> i64 {
+        timestamp + ((timestamp - self.since) as f64 * self.ppm / 1e6).round() as i64
+    }
+}
+
+/// How well the occurrences of the anchors line up with the reference after alignment
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct InputAlignment {
+    pub tag: String,
+    pub offset: i64,
+    pub clock_drift: Option<ClockDrift>,
+    /// number of anchor occurrences that were matched with the reference
+    pub matches: usize,
+    /// root mean square of the remaining differences in ms
+    pub residual_rms: f64,
+    /// biggest remaining difference in ms
+    pub residual_max: i64,
+}
+
+#[derive(Serialize, Debug)]
+pub struct Alignment {
+    /// the inputs with offsets (and drifts) that align them to the first input
+    pub options: Vec<FileMergeOptions>,
+    /// the anchors that were used, detected markers if no anchors were given
+    pub anchors: Vec<String>,
+    /// one entry for each input but the first
+    pub report: Vec<InputAlignment>,
+}
+
+/// timestamps of each anchor in an input
+type Occurrences = HashMap<String, Vec<i64>>;
+
+/// Compute offsets for merge inputs so that events which are logged in several inputs
+/// line up with the first input (the reference).
+///
+/// `anchors` are regular expressions for such events. Without anchors, lines that
+/// occur exactly once in the reference and in another input are used as markers;
+/// lines are compared by their words only, digits and the timestamp are ignored.
+/// With `with_drift` a linear clock drift is estimated as well, this needs at least
+/// two matching events.
+pub fn align_inputs(
+    inputs: Vec<FileMergeOptions>,
+    anchors: &[String],
+    with_drift: bool,
+) -> Result<Alignment> {
+    if inputs.is_empty() {
+        return Err(anyhow!("no inputs to align"));
+    }
+    let anchor_regexes = anchors
+        .iter()
+        .map(|a| Regex::new(a).map_err(|e| anyhow!("invalid anchor {}: {}", a, e)))
+        .collect::<Result<Vec<Regex>>>()?;
+    let mut occurrences = vec![];
+    for (i, input) in inputs.iter().enumerate() {
+        // the reference is taken as it would be merged, the others without correction
+        let raw = FileMergeOptions {
+            offset: if i == 0 { input.offset } else { Some(0) },
+            clock_drift: if i == 0 { input.clock_drift } else { None },
+            ..input.clone()
+        };
+        occurrences.push(if anchor_regexes.is_empty() {
+            marker_occurrences(raw)?
+        } else {
+            anchor_occurrences(raw, &anchor_regexes)?
+        });
+    }
+    let used_anchors: Vec<String> = if anchor_regexes.is_empty() {
+        let mut markers: Vec<String> = occurrences[0]
+            .iter()
+            .filter(|(marker, times)| {
+                times.len() == 1
+                    && occurrences[1..]
+                        .iter()
+                        .any(|other| other.get(*marker).is_some_and(|t| t.len() == 1))
+            })
+            .map(|(marker, _)| marker.clone())
+            .collect();
+        markers.sort();
+        markers
+    } else {
+        anchors.to_vec()
+    };
+
+    let mut inputs = inputs.into_iter();
+    let mut options = vec![];
+    options.extend(inputs.next());
+    let mut report = vec![];
+    for (input, input_occurrences) in inputs.zip(occurrences.iter().skip(1)) {
+        let pairs = match_occurrences(&used_anchors, &occurrences[0], input_occurrences);
+        let alignment = if pairs.is_empty() {
+            warn!("no anchor of {} matches the reference", input.tag);
+            InputAlignment {
+                tag: input.tag.clone(),
+                offset: input.offset.unwrap_or(0),
+                clock_drift: input.clock_drift,
+                matches: 0,
+                residual_rms: 0.0,
+                residual_max: 0,
+            }
+        } else {
+            fit(&input.tag, &pairs, with_drift)
+        };
+        options.push(FileMergeOptions {
+            offset: Some(alignment.offset),
+            clock_drift: alignment.clock_drift,
+            ..input
+        });
+        report.push(alignment);
+    }
+    Ok(Alignment {
+        options,
+        anchors: used_anchors,
+        report,
+    })
+}
+
+fn anchor_occurrences(input: FileMergeOptions, anchors: &[Regex]) -> Result<Occurrences> {
+    let mut occurrences = Occurrences::new();
+    for line in open_timed_lines(input, None, &InputCounts::default())? {
+        for anchor in anchors.iter().filter(|a| a.is_match(&line.content)) {
+            occurrences
+                .entry(anchor.as_str().to_string())
+                .or_default()
+                .push(line.timestamp);
+        }
+    }
+    Ok(occurrences)
+}
+
+fn marker_occurrences(input: FileMergeOptions) -> Result<Occurrences> {
+    let timestamp_regex = match input.input_type {
+        InputType::Text => Some(lookup_regex_for_format_str(&input.format)?),
+        _ => None,
+    };
+    let mut occurrences = Occurrences::new();
+    for line in open_timed_lines(input, None, &InputCounts::default())? {
+        let content = match &timestamp_regex {
+            Some(regex) => regex.replace(&line.content, ""),
+            None => line.content.as_str().into(),
+        };
+        let marker = content
+            .split(|c: char| !c.is_alphabetic())
+            .filter(|word| word.len() > 1)
+            .collect::<Vec<&str>>()
+            .join(" ");
+        if !marker.is_empty() {
+            occurrences.entry(marker).or_default().push(line.timestamp);
+        }
+    }
+    Ok(occurrences)
+}
+
+fn median(mut values: Vec<i64>) -> i64 {
+    values.sort_unstable();
+    values[values.len() / 2]
+}
+
+/// pairs of (input time, reference time) for the same event
+///
+/// The first occurrences of each anchor give a rough offset, every occurrence
+/// is then paired with the reference occurrence that is closest to it.
+fn match_occurrences(
+    anchors: &[String],
+    reference: &Occurrences,
+    input: &Occurrences,
+) -> Vec<(i64, i64)> {
+    let both: Vec<(&Vec<i64>, &Vec<i64>)> = anchors
+        .iter()
+        .filter_map(|a| Some((input.get(a)?, reference.get(a)?)))
+        .collect();
+    if both.is_empty() {
+        return vec![];
+    }
+    let rough_offset = median(both.iter().map(|(own, refs)| refs[0] - own[0]).collect());
+    let mut pairs = vec![];
+    for (own, refs) in both {
+        for &t in own {
+            let expected = t + rough_offset;
+            if let Some(&closest) = refs.iter().min_by_key(|&&r| (r - expected).abs()) {
+                pairs.push((t, closest));
+            }
+        }
+    }
+    pairs
+}
+
+fn fit(tag: &str, pairs: &[(i64, i64)], with_drift: bool) -> InputAlignment {
+    let n = pairs.len() as f64;
+    let mean_t = pairs.iter().map(|(t, _)| *t as f64).sum::<f64>() / n;
+    let variance: f64 = pairs
+        .iter()
+        .map(|(t, _)| (*t as f64 - mean_t).powi(2))
+        .sum();
+    // how much the input has to be moved, the merge offset is subtracted so it is -shift
+    let (shift, clock_drift) = if with_drift && variance > 0.0 {
+        // least squares for d = shift + k * (t - mean_t) with d = r - t
+        let mean_d = pairs.iter().map(|(t, r)| (r - t) as f64).sum::<f64>() / n;
+        let covariance: f64 = pairs
+            .iter()
+            .map(|(t, r)| (*t as f64 - mean_t) * ((r - t) as f64 - mean_d))
+            .sum();
+        let shift = mean_d.round() as i64;
+        let drift = ClockDrift {
+            since: mean_t.round() as i64 + shift,
+            ppm: covariance / variance * 1e6,
+        };
+        (shift, Some(drift))
+    } else {
+        (median(pairs.iter().map(|(t, r)| r - t).collect()), None)
+    };
+    let residuals: Vec<i64> = pairs
+        .iter()
+        .map(|(t, r)| {
+            let aligned = t + shift;
+            clock_drift.map_or(aligned, |d| d.correct(aligned)) - r
+        })
+        .collect();
+    InputAlignment {
+        tag: tag.to_string(),
+        offset: -shift,
+        clock_drift,
+        matches: pairs.len(),
+        residual_rms: (residuals.iter().map(|r| (r * r) as f64).sum::<f64>() / n).sqrt(),
+        residual_max: residuals.iter().map(|r| r.abs()).max().unwrap_or(0),
+    }
+}
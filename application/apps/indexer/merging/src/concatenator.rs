@@ -12,8 +12,12 @@
 use crate::merger::combined_file_size;
 use anyhow::{anyhow, Result};
 use crossbeam_channel as cc;
+use dlt::dlt_file::{detect_dlt_file, dlt_messages, dlt_messages_with_bytes};
+use dlt_core::{dlt::DltTimeStamp, fmt::FormattableMessage};
 use indexer_base::{
     chunks::{ChunkFactory, ChunkResults},
+    compression::{shared_position_if_compressed, CompressedPosition, InputFile},
+    container::RowWriter,
     progress::{IndexingProgress, ProgressReporter},
     utils,
 };
@@ -23,6 +27,7 @@ use std::{
     io::{BufRead, BufReader, BufWriter, Read, Write},
     iter::Iterator,
     path::{Path, PathBuf},
+    time::UNIX_EPOCH,
 };
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -49,15 +54,35 @@ pub struct ConcatenatorResult {
     pub file_cnt: usize,
     pub line_cnt: usize,
     pub byte_cnt: usize,
+    /// lines (text inputs) or messages (dlt inputs) of each input
+    pub entry_cnt_per_file: Vec<usize>,
+}
+
+/// What the concatenation writes
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ConcatOutput {
+    /// tagged lines, messages of dlt inputs are formatted
+    Text,
+    /// a dlt file with the messages of all inputs, only possible if all inputs are dlt
+    Dlt,
+}
+
+/// the kind of content of an input, detected when concatenating
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum InputKind {
+    Text,
+    Dlt { with_storage_header: bool },
 }
 pub fn concat_files_use_config_file(
     config_path: &Path,
     out_path: &Path,
     append: bool,
     chunk_size: usize, // used for mapping line numbers to byte positions
+    output: ConcatOutput,
     update_channel: cc::Sender<ChunkResults>,
     shutdown_rx: Option<cc::Receiver<()>>,
-) -> Result<()> {
+) -> Result<ConcatenatorResult> {
     let mut concat_option_file = fs::File::open(config_path)?;
     let dir_name = config_path
         .parent()
@@ -78,85 +103,242 @@ pub fn concat_files_use_config_file(
         out_path,
         append,
         chunk_size,
+        output,
         update_channel,
         shutdown_rx,
     )
 }
+/// Concatenate the inputs into `out_path`.
+///
+/// Inputs are checked for dlt content (see `detect_dlt_file`). With `ConcatOutput::Text`
+/// each line of a text input and each message of a dlt input becomes a tagged line.
+/// With `ConcatOutput::Dlt` the messages are written as they are, messages without a
+/// storage header get one with the modification time of their input.
 pub fn concat_files(
     concat_inputs: Vec<ConcatenatorInput>,
     out_path: &Path,
     append: bool,
     chunk_size: usize, // used for mapping line numbers to byte positions
+    output: ConcatOutput,
     update_channel: cc::Sender<ChunkResults>,
     shutdown_rx: Option<cc::Receiver<()>>,
-) -> Result<()> {
+) -> Result<ConcatenatorResult> {
     let file_cnt = concat_inputs.len();
     trace!("concat_files called with {} files", file_cnt);
-    let mut line_nr = 0;
+    let kinds = concat_inputs
+        .iter()
+        .map(|input| {
+            Ok(match detect_dlt_file(Path::new(&input.path))? {
+                Some(with_storage_header) => InputKind::Dlt {
+                    with_storage_header,
+                },
+                None => InputKind::Text,
+            })
+        })
+        .collect::<Result<Vec<InputKind>>>()?;
+    if output == ConcatOutput::Dlt {
+        if let Some((input, _)) = concat_inputs
+            .iter()
+            .zip(kinds.iter())
+            .find(|(_, kind)| **kind == InputKind::Text)
+        {
+            return Err(anyhow!("{} is not a dlt file", input.path));
+        }
+    }
     let out_file: std::fs::File = if append {
         std::fs::OpenOptions::new()
             .append(true)
             .create(true)
             .open(out_path)?
     } else {
-        std::fs::File::create(&out_path)?
+        std::fs::File::create(out_path)?
     };
     let original_file_size = out_file.metadata()?.len() as usize;
-    let mut buf_writer = BufWriter::with_capacity(10 * 1024 * 1024, out_file);
-    let mut chunk_factory = ChunkFactory::new(chunk_size, original_file_size);
-    let mut chunk_count = 0usize;
     let paths: Vec<PathBuf> = concat_inputs
         .iter()
         .map(|x| PathBuf::from(x.path.clone()))
         .collect();
-    let mut progress_reporter =
-        ProgressReporter::new(combined_file_size(&paths)?, update_channel.clone());
+    let compressed_position = shared_position_if_compressed(&paths);
+    let chunk_factory = ChunkFactory::new(chunk_size, original_file_size);
+    // dlt output is not chunked
+    let chunk_factory = match output {
+        ConcatOutput::Text => chunk_factory.with_index(out_path)?,
+        ConcatOutput::Dlt => chunk_factory,
+    };
+    let mut writer = ConcatWriter {
+        line_nr: if append && output == ConcatOutput::Text {
+            utils::next_line_nr(out_path)?
+        } else {
+            0
+        },
+        byte_cnt: 0,
+        row_writer: RowWriter::new(
+            BufWriter::with_capacity(10 * 1024 * 1024, out_file),
+            out_path,
+            original_file_size,
+        )?,
+        chunk_factory,
+        chunk_count: 0,
+        update_channel: update_channel.clone(),
+        progress_reporter: ProgressReporter::new(
+            combined_file_size(&paths)?,
+            update_channel.clone(),
+        )
+        .with_compressed_position(compressed_position.clone()),
+    };
+    let first_line = writer.line_nr;
+    let mut entry_cnt_per_file = vec![];
+    let mut stopped = false;
 
-    for input in concat_inputs {
+    for (input, kind) in concat_inputs.iter().zip(kinds) {
         if utils::check_if_stop_was_requested(shutdown_rx.as_ref(), "concatenator") {
             update_channel.send(Ok(IndexingProgress::Stopped))?;
-            return Ok(());
+            stopped = true;
+            break;
+        }
+        let entries = match (kind, output) {
+            (InputKind::Text, _) => writer.concat_text(input, compressed_position.clone())?,
+            (
+                InputKind::Dlt {
+                    with_storage_header,
+                },
+                ConcatOutput::Text,
+            ) => writer.concat_dlt_as_text(
+                input,
+                with_storage_header,
+                compressed_position.clone(),
+            )?,
+            (
+                InputKind::Dlt {
+                    with_storage_header,
+                },
+                ConcatOutput::Dlt,
+            ) => writer.concat_dlt(input, with_storage_header, compressed_position.clone())?,
+        };
+        entry_cnt_per_file.push(entries);
+    }
+    writer.row_writer.flush()?;
+    if !stopped {
+        if output == ConcatOutput::Text {
+            if let Some(chunk) = writer
+                .chunk_factory
+                .create_last_chunk(writer.line_nr, writer.chunk_count > 0)
+            {
+                update_channel.send(Ok(IndexingProgress::GotItem { item: chunk }))?;
+            }
         }
-        let f: fs::File = fs::File::open(input.path)?;
-        let mut reader: BufReader<&std::fs::File> = BufReader::new(&f);
+        let _ = update_channel.send(Ok(IndexingProgress::Finished));
+    }
+    Ok(ConcatenatorResult {
+        file_cnt,
+        line_cnt: writer.line_nr - first_line,
+        byte_cnt: writer.byte_cnt,
+        entry_cnt_per_file,
+    })
+}
+
+struct ConcatWriter {
+    line_nr: usize,
+    /// bytes written to the output
+    byte_cnt: usize,
+    row_writer: RowWriter<BufWriter<fs::File>>,
+    chunk_factory: ChunkFactory,
+    chunk_count: usize,
+    update_channel: cc::Sender<ChunkResults>,
+    progress_reporter: ProgressReporter<indexer_base::chunks::Chunk>,
+}
+
+impl ConcatWriter {
+    fn add_tagged_line(&mut self, tag: &str, line: &str, original_length: usize) -> Result<()> {
+        let additional_bytes = self
+            .row_writer
+            .write_row(tag, line, self.line_nr, true, None)?;
+        self.line_nr += 1;
+        self.byte_cnt += additional_bytes;
+        if let Some(chunk) = self.chunk_factory.add_bytes(
+            self.line_nr, // TODO avoid passing in this line...error prone
+            additional_bytes,
+        ) {
+            self.chunk_count += 1;
+            self.row_writer.flush()?;
+            self.update_channel
+                .send(Ok(IndexingProgress::GotItem { item: chunk }))?;
+        }
+        self.progress_reporter.make_progress(original_length);
+        Ok(())
+    }
+
+    fn concat_text(
+        &mut self,
+        input: &ConcatenatorInput,
+        compressed_position: Option<CompressedPosition>,
+    ) -> Result<usize> {
+        let f = InputFile::open_with_position(Path::new(&input.path), compressed_position)?;
+        let mut reader = BufReader::new(f);
         let mut buf = vec![];
+        let mut lines = 0;
         while let Ok(len) = reader.read_until(b'\n', &mut buf) {
             if len == 0 {
                 // no more content
                 break;
             };
-            let original_line_length = len;
             let s = unsafe { std::str::from_utf8_unchecked(&buf) };
             let trimmed_line = s.trim_matches(utils::is_newline);
-
-            let additional_bytes = utils::write_tagged_line(
-                &input.tag[..],
-                &mut buf_writer,
-                trimmed_line,
-                line_nr,
-                true,
-                None,
-            )?;
-            line_nr += 1;
-            if let Some(chunk) = chunk_factory.add_bytes(
-                line_nr, // TODO avoid passing in this line...error prone
-                additional_bytes,
-            ) {
-                // check if stop was requested
-                chunk_count += 1;
-                buf_writer.flush()?;
-                update_channel.send(Ok(IndexingProgress::GotItem { item: chunk }))?;
-            }
-
-            progress_reporter.make_progress(original_line_length);
+            self.add_tagged_line(&input.tag, trimmed_line, len)?;
+            lines += 1;
             buf = vec![];
         }
+        Ok(lines)
     }
-    buf_writer.flush()?;
-    if let Some(chunk) = chunk_factory.create_last_chunk(line_nr, chunk_count > 0) {
-        update_channel.send(Ok(IndexingProgress::GotItem { item: chunk }))?;
+
+    fn concat_dlt_as_text(
+        &mut self,
+        input: &ConcatenatorInput,
+        with_storage_header: bool,
+        compressed_position: Option<CompressedPosition>,
+    ) -> Result<usize> {
+        let f = InputFile::open_with_position(Path::new(&input.path), compressed_position)?;
+        let mut messages = 0;
+        for (consumed, msg) in dlt_messages(f, with_storage_header) {
+            let formattable_msg = FormattableMessage {
+                message: msg,
+                fibex_metadata: None,
+                options: None,
+            };
+            self.add_tagged_line(&input.tag, &formattable_msg.to_string(), consumed)?;
+            messages += 1;
+        }
+        Ok(messages)
     }
 
-    let _ = update_channel.send(Ok(IndexingProgress::Finished));
-    Ok(())
+    fn concat_dlt(
+        &mut self,
+        input: &ConcatenatorInput,
+        with_storage_header: bool,
+        compressed_position: Option<CompressedPosition>,
+    ) -> Result<usize> {
+        let path = Path::new(&input.path);
+        let recorded_at = fs::metadata(path)?
+            .modified()?
+            .duration_since(UNIX_EPOCH)
+            .map(|d| DltTimeStamp::from_ms(d.as_millis() as u64))
+            .ok();
+        let f = InputFile::open_with_position(path, compressed_position)?;
+        let mut messages = 0;
+        for (consumed, msg, bytes) in dlt_messages_with_bytes(f, with_storage_header) {
+            // messages are copied unchanged, only a missing storage header is added
+            if msg.storage_header.is_none() {
+                if let Some(header) = msg.add_storage_header(recorded_at.clone()).storage_header {
+                    let header = header.as_bytes();
+                    self.row_writer.get_mut().write_all(&header)?;
+                    self.byte_cnt += header.len();
+                }
+            }
+            self.row_writer.get_mut().write_all(&bytes)?;
+            self.byte_cnt += bytes.len();
+            self.progress_reporter.make_progress(consumed);
+            messages += 1;
+        }
+        Ok(messages)
+    }
 }
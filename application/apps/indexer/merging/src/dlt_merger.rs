@@ -0,0 +1,211 @@
+// Copyright (c) 2019 E.S.R.Labs. All rights reserved.
+//
+// NOTICE:  All information contained herein is, and remains
+// the property of E.S.R.Labs and its suppliers, if any.
+// The intellectual and technical concepts contained herein are
+// proprietary to E.S.R.Labs and its suppliers and may be covered
+// by German and Foreign Patents, patents in process, and are protected
+// by trade secret or copyright law.
+// Dissemination of this information or reproduction of this material
+// is strictly forbidden unless prior written permission is obtained
+// from E.S.R.Labs.
+use crate::merger::{Len, MergeHeap, Timestamped};
+use anyhow::{anyhow, Result};
+use crossbeam_channel as cc;
+use dlt::dlt_file::{dlt_messages_with_bytes, message_timestamp};
+use dlt_core::dlt::{DltTimeStamp, Message};
+use indexer_base::{
+    chunks::VoidResults,
+    compression::{shared_position_if_compressed, CompressedPosition, InputFile},
+    progress::{IndexingProgress, ProgressReporter},
+    utils,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::File,
+    io::{BufWriter, Read, Write},
+    path::{Path, PathBuf},
+};
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct DltMergeInput {
+    /// dlt file with storage headers
+    pub path: PathBuf,
+    /// Offset in ms, subtracted from the storage header time of each message
+    pub offset: Option<i64>,
+    /// ECU id for the messages of this file that have none
+    pub ecu_id: Option<String>,
+}
+
+/// the bytes of a message together with its time in the merged file
+struct DltEntry {
+    timestamp: i64,
+    consumed: usize,
+    bytes: Vec<u8>,
+}
+
+impl Timestamped for DltEntry {
+    fn timestamp(&self) -> i64 {
+        self.timestamp
+    }
+}
+
+type DltEntries = Box<dyn Iterator<Item = DltEntry> + Send>;
+
+pub fn read_dlt_merge_inputs(
+    f: &mut File,
+    relative_path: Option<impl AsRef<Path>>,
+) -> Result<Vec<DltMergeInput>> {
+    let mut contents = String::new();
+    f.read_to_string(&mut contents)?;
+    let inputs: Vec<DltMergeInput> = serde_json::from_str(&contents)?;
+    Ok(match relative_path {
+        Some(relative) => inputs
+            .into_iter()
+            .map(|input| DltMergeInput {
+                path: relative.as_ref().join(input.path),
+                ..input
+            })
+            .collect(),
+        None => inputs,
+    })
+}
+
+/// shift the storage header time by `offset` ms, times before 1970 become 0
+fn shift_storage_time(message: &mut Message, offset: i64) {
+    if let Some(storage_header) = &mut message.storage_header {
+        let timestamp = &storage_header.timestamp;
+        let microseconds = (timestamp.seconds as i64 * 1_000_000 + timestamp.microseconds as i64
+            - offset * 1000)
+            .max(0);
+        storage_header.timestamp = DltTimeStamp {
+            seconds: (microseconds / 1_000_000) as u32,
+            microseconds: (microseconds % 1_000_000) as u32,
+        };
+    }
+}
+
+/// write the storage header time of `message` into its raw `bytes`
+fn patch_storage_time(bytes: &mut [u8], message: &Message) {
+    if let Some(storage_header) = &message.storage_header {
+        let timestamp = &storage_header.timestamp;
+        bytes[4..8].copy_from_slice(&timestamp.seconds.to_le_bytes());
+        bytes[8..12].copy_from_slice(&timestamp.microseconds.to_le_bytes());
+    }
+}
+
+/// Set `ecu_id` where the message has none. The standard header only gets the id
+/// if the message is still short enough with it.
+/// Returns true if the message was changed.
+fn fill_missing_ecu_id(message: &mut Message, ecu_id: &str) -> bool {
+    let mut changed = false;
+    if let Some(storage_header) = &mut message.storage_header {
+        if storage_header.ecu_id.is_empty() {
+            storage_header.ecu_id = ecu_id.to_string();
+            changed = true;
+        }
+    }
+    if message.header.ecu_id.is_none() && message.header.overall_length() <= u16::MAX - 4 {
+        message.header.ecu_id = Some(ecu_id.to_string());
+        changed = true;
+    }
+    changed
+}
+
+fn open_dlt_entries(
+    input: &DltMergeInput,
+    compressed_position: Option<CompressedPosition>,
+) -> Result<DltEntries> {
+    let file = InputFile::open_with_position(&input.path, compressed_position)?;
+    let offset = input.offset.unwrap_or(0);
+    let ecu_id = input.ecu_id.as_ref().map(|id| {
+        id.chars()
+            .filter(|c| c.is_ascii())
+            .take(4)
+            .collect::<String>()
+    });
+    Ok(Box::new(dlt_messages_with_bytes(file, true).filter_map(
+        move |(consumed, mut message, mut bytes)| {
+            let timestamp = message_timestamp(&message, None)? - offset;
+            if offset != 0 {
+                shift_storage_time(&mut message, offset);
+            }
+            let ecu_id_filled = match &ecu_id {
+                Some(id) => fill_missing_ecu_id(&mut message, id),
+                None => false,
+            };
+            if ecu_id_filled {
+                bytes = message.as_bytes();
+            } else if offset != 0 {
+                patch_storage_time(&mut bytes, &message);
+            }
+            Some(DltEntry {
+                timestamp,
+                consumed,
+                bytes,
+            })
+        },
+    )))
+}
+
+/// Merge dlt files into one dlt file, ordered by the storage header time.
+///
+/// Every input has to be sorted by time already. Messages with the same time are
+/// taken in the order of the inputs. The messages are written as they were read,
+/// only the storage header time (with an offset) is patched. Messages that get
+/// a missing ECU id are written as they were parsed.
+/// Returns the number of messages that were written for each input.
+pub fn merge_dlt_files(
+    inputs: Vec<DltMergeInput>,
+    out_path: &Path,
+    update_channel: cc::Sender<VoidResults>,
+    shutdown_rx: Option<&cc::Receiver<()>>,
+) -> Result<Vec<usize>> {
+    trace!("merge {} dlt files", inputs.len());
+    let paths: Vec<&PathBuf> = inputs.iter().map(|input| &input.path).collect();
+    let total = paths
+        .iter()
+        .map(|path| path.len())
+        .sum::<Result<u64>>()
+        .map_err(|e| anyhow!("could not get size of dlt merge inputs: {}", e))?;
+    let compressed_position = shared_position_if_compressed(&paths);
+    let mut readers = inputs
+        .iter()
+        .map(|input| open_dlt_entries(input, compressed_position.clone()))
+        .collect::<Result<Vec<DltEntries>>>()?;
+    let mut progress_reporter = ProgressReporter::new(total, update_channel.clone())
+        .with_compressed_position(compressed_position);
+
+    let (out_file, _) = utils::get_out_file_and_size(false, out_path)?;
+    let mut out = BufWriter::new(out_file);
+    let mut written = vec![0usize; readers.len()];
+    let mut heap: MergeHeap<DltEntry> = MergeHeap::new(readers.len());
+    let mut to_refill: Vec<usize> = (0..readers.len()).collect();
+    let mut stopped = false;
+    loop {
+        for index in to_refill.drain(..) {
+            if let Some(entry) = readers[index].next() {
+                heap.push(index, entry);
+            }
+        }
+        let (index, entry) = match heap.pop() {
+            Some(next) => next,
+            None => break,
+        };
+        to_refill.push(index);
+        out.write_all(&entry.bytes)?;
+        written[index] += 1;
+        progress_reporter.make_progress(entry.consumed);
+        stopped = utils::check_if_stop_was_requested(shutdown_rx, "dlt merger");
+        if stopped {
+            info!("we where stopped while merging dlt files");
+            break;
+        }
+    }
+    out.flush()?;
+    if stopped {
+        update_channel.send(Ok(IndexingProgress::Stopped))?;
+    }
+    update_channel.send(Ok(IndexingProgress::Finished))?;
+    Ok(written)
+}
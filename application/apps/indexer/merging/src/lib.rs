@@ -14,10 +14,14 @@ extern crate dlt;
 #[macro_use]
 extern crate log;
 
+pub mod alignment;
 pub mod concatenator;
+pub mod dlt_merger;
 pub mod merger;
-// WIP for new merger api
-// pub mod merger_async;
+pub mod merger_async;
+pub mod provenance;
+pub mod reorder;
+pub mod report;
 
 #[cfg(test)]
 mod tests;
@@ -9,39 +9,92 @@
 // Dissemination of this information or reproduction of this material
 // is strictly forbidden unless prior written permission is obtained
 // from E.S.R.Labs.
+use crate::{
+    alignment::ClockDrift,
+    provenance::ProvenanceMap,
+    reorder::{ReorderWindow, Reordered},
+    report::{validate_inputs, EntryCount, InputCounts, InputReport, ValidatedInput},
+};
 use anyhow::{anyhow, Result};
 use crossbeam_channel as cc;
+use dlt::{dlt_file::dlt_timed_lines, dlt_pcap::pcap_timed_lines};
 use indexer_base::{
     chunks::{Chunk, ChunkFactory, ChunkResults},
+    compression::{shared_position_if_compressed, CompressedPosition, InputFile},
+    container::RowWriter,
     error_reporter::*,
-    progress::{IndexingProgress, ProgressReporter},
+    progress::{IndexingProgress, Notification, ProgressReporter, Severity},
     timedline::*,
     utils,
 };
-use processor::parse::{line_to_timed_line, lookup_regex_for_format_str};
+use processor::{
+    entries::EntryReader,
+    journal::journal_timed_lines,
+    logcat::logcat_timed_lines,
+    parse::{line_to_timed_line, lookup_regex_for_format_str},
+};
 use regex::Regex;
 use serde::{Deserialize, Serialize};
 use std::{
+    cmp::Reverse,
+    collections::BinaryHeap,
     fmt::Debug,
     fs::{self, File},
-    io::{BufRead, BufReader, BufWriter, Read, Write},
-    iter::{Iterator, Peekable},
+    io::{BufReader, BufWriter, Read},
+    iter::Iterator,
     path::{Path, PathBuf},
+    thread,
 };
-use tokio_stream::Stream;
+use tokio::sync::mpsc;
+use tokio_stream::{wrappers::ReceiverStream, Stream};
+
+/// entries a `FileLogEntryProducer` reads ahead of the merge
+const PRODUCER_CHANNEL_SIZE: usize = 1024;
+
+/// The kind of log file a merge input is
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum InputType {
+    /// text file with a timestamp in `format` for each line
+    #[default]
+    Text,
+    /// systemd journal export (`journalctl -o export`)
+    Journal,
+    /// android logcat, binary (`logcat -B`) or threadtime, `year` is used for threadtime
+    Logcat,
+    /// dlt file with storage headers
+    Dlt,
+    /// PCAPNG file with dlt messages
+    Pcap,
+}
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct FileMergeOptions {
     /// Path to the file to merge
     pub path: String,
-    /// Offset in ms, this will be added to each posix timestamp
+    /// Offset in ms, this will be subtracted from each posix timestamp
+    /// (for text inputs it is used instead of the timezone)
     pub offset: Option<i64>,
     /// If a year is provided and if no year was detected, this is what we use
     pub year: Option<i32>,
     /// An identifier string for the file
     pub tag: String,
     /// how we interpret the date string in each line
+    #[serde(default)]
     pub format: String,
+    /// lines that do not start with `format` belong to the previous entry
+    #[serde(default)]
+    pub multiline: bool,
+    /// text is the default, other input types bring their own timestamps
+    #[serde(default)]
+    pub input_type: InputType,
+    /// for dlt/pcap: posix time in ms the ECU was started at, when set, the ECU timestamp
+    /// of a message is used instead of the storage header time
+    pub ecu_time_reference: Option<i64>,
+    /// sort entries that are out of order within this window before merging
+    pub reorder_window: Option<ReorderWindow>,
+    /// correction for a clock that runs too fast or too slow, see `alignment::align_inputs`
+    pub clock_drift: Option<ClockDrift>,
 }
 
 pub fn read_merge_options(
@@ -64,43 +117,52 @@ pub fn read_merge_options(
     }
 }
 
-#[allow(dead_code)]
+/// Stream of the entries of a merge input, ends when the input is exhausted
+///
+/// The input is read on it's own thread and the entries are passed on through a
+/// channel, so polling the stream does not block. It is pending until the reader
+/// delivered the next entry.
 pub struct FileLogEntryProducer {
-    timed_line_iterator: TimedLineIter,
+    entries: ReceiverStream<TimedLine>,
 }
-#[allow(dead_code)]
 impl FileLogEntryProducer {
-    pub(crate) fn new(
-        read_from: File,
-        tag: String,
-        regex: Regex,
-        year: Option<i32>,
-        time_offset: Option<i64>,
-        current_line_nr: usize,
-    ) -> FileLogEntryProducer {
-        let iter = TimedLineIter::new(read_from, tag, regex, year, time_offset, current_line_nr);
+    /// producer for any kind of merge input, see `InputType`
+    pub fn new(input: FileMergeOptions, writer: &IndexOutput) -> Result<FileLogEntryProducer> {
+        Ok(FileLogEntryProducer::with_timed_lines(open_timed_lines(
+            input,
+            writer.compressed_position(),
+            &InputCounts::default(),
+        )?))
+    }
+
+    pub(crate) fn with_timed_lines(timed_lines: TimedLines) -> FileLogEntryProducer {
+        let (tx, rx) = mpsc::channel(PRODUCER_CHANNEL_SIZE);
+        thread::spawn(move || {
+            for line in timed_lines {
+                if tx.blocking_send(line).is_err() {
+                    trace!("merge was stopped, input is not read any further");
+                    break;
+                }
+            }
+        });
         FileLogEntryProducer {
-            timed_line_iterator: iter,
+            entries: ReceiverStream::new(rx),
         }
     }
 }
 
 impl Stream for FileLogEntryProducer {
-    type Item = Result<Option<TimedLine>>;
+    type Item = TimedLine;
     fn poll_next(
         mut self: std::pin::Pin<&mut Self>,
-        _cx: &mut std::task::Context,
+        cx: &mut std::task::Context,
     ) -> core::task::Poll<Option<Self::Item>> {
-        let next = self.timed_line_iterator.next();
-        match next {
-            Some(msg) => core::task::Poll::Ready(Some(Ok(Some(msg)))),
-            None => core::task::Poll::Ready(Some(Err(anyhow!("no more message")))),
-        }
+        std::pin::Pin::new(&mut self.entries).poll_next(cx)
     }
 }
 
 pub struct TimedLineIter {
-    reader: BufReader<File>,
+    entries: EntryReader<BufReader<InputFile>>,
     tag: String,
     regex: Regex,
     year: Option<i32>,
@@ -108,19 +170,24 @@ pub struct TimedLineIter {
     last_timestamp: i64,
     pub current_line_nr: usize,
     reporter: Reporter,
+    without_timestamp: EntryCount,
 }
 impl TimedLineIter {
+    /// With `multiline` set, lines that do not start with a timestamp are attached to
+    /// the previous line and the whole entry is returned as one `TimedLine`
     pub fn new(
-        read_from: File,
+        read_from: InputFile,
         tag: String,
         regex: Regex,
         year: Option<i32>,
         time_offset: Option<i64>,
         current_line_nr: usize,
+        multiline: bool,
     ) -> TimedLineIter {
-        trace!("create TimedLineIter for {:?}", read_from);
+        trace!("create TimedLineIter for {}", tag);
+        let entry_start = if multiline { Some(regex.clone()) } else { None };
         TimedLineIter {
-            reader: BufReader::new(read_from),
+            entries: EntryReader::new(BufReader::new(read_from), entry_start),
             tag,
             regex,
             year,
@@ -128,49 +195,59 @@ impl TimedLineIter {
             last_timestamp: 0,
             current_line_nr,
             reporter: Default::default(),
+            without_timestamp: EntryCount::default(),
+        }
+    }
+
+    /// count the entries that have no readable timestamp in `count`
+    pub fn counting_missing_timestamps(self, count: EntryCount) -> Self {
+        TimedLineIter {
+            without_timestamp: count,
+            ..self
         }
     }
 }
 impl Iterator for TimedLineIter {
     type Item = TimedLine;
     fn next(&mut self) -> Option<TimedLine> {
-        let mut buf = vec![];
-        match self.reader.read_until(b'\n', &mut buf) {
-            Ok(len) => {
-                if len == 0 {
-                    self.reporter.flush();
-                    return None;
-                }
-                let original_line_length = len;
-                let s = unsafe { std::str::from_utf8_unchecked(&buf) };
-                let trimmed_line = s.trim_matches(utils::is_newline);
-                let timed_line = line_to_timed_line(
-                    trimmed_line,
-                    original_line_length,
-                    &self.tag,
-                    &self.regex,
-                    self.year,
-                    self.time_offset,
-                    self.current_line_nr,
-                    &mut self.reporter,
-                )
-                .unwrap_or_else(|_| TimedLine {
-                    content: trimmed_line.to_string(),
+        let entry = match self.entries.next() {
+            Some(entry) => entry,
+            None => {
+                self.reporter.flush();
+                return None;
+            }
+        };
+        let original_line_length = entry.consumed;
+        let content = entry.joined();
+        let timed_line = match line_to_timed_line(
+            &entry.lines[0],
+            original_line_length,
+            &self.tag,
+            &self.regex,
+            self.year,
+            self.time_offset,
+            self.current_line_nr,
+            &mut self.reporter,
+        ) {
+            Ok(timed_line) => TimedLine {
+                content,
+                ..timed_line
+            },
+            Err(_) => {
+                self.without_timestamp.increment();
+                TimedLine {
+                    content,
                     tag: self.tag.to_string(),
                     timestamp: self.last_timestamp,
                     original_length: original_line_length,
                     year_was_missing: false,
                     line_nr: self.current_line_nr,
-                });
-                self.last_timestamp = timed_line.timestamp;
-                self.current_line_nr += 1;
-                Some(timed_line)
-            }
-            Err(e) => {
-                warn!("error iterating: {}", e);
-                None
+                }
             }
-        }
+        };
+        self.last_timestamp = timed_line.timestamp;
+        self.current_line_nr += 1;
+        Some(timed_line)
     }
 }
 
@@ -184,6 +261,9 @@ impl Iterator for TimedLineIter {
 /// As a result, updates of the progress are reported through the `update_channel`
 /// when finished, the resulting output file is stored in `out_path`
 ///
+/// All inputs are checked before merging, text inputs without a format get the format
+/// that is detected in the file. Inputs that can not be merged are left out and reported
+/// with an error `Notification`. A report for every input is returned.
 pub fn merge_files_use_config(
     options: Vec<FileMergeOptions>,
     out_path: &Path,
@@ -191,7 +271,7 @@ pub fn merge_files_use_config(
     chunk_size: usize, // used for mapping line numbers to byte positions
     update_channel: cc::Sender<ChunkResults>,
     shutdown_rx: Option<cc::Receiver<()>>,
-) -> Result<()> {
+) -> Result<Vec<InputReport>> {
     trace!("merge {} files", options.len());
     do_the_merge(
         append,
@@ -216,7 +296,7 @@ pub fn merge_files_use_config_file(
     chunk_size: usize, // used for mapping line numbers to byte positions
     update_channel: cc::Sender<ChunkResults>,
     shutdown_rx: Option<cc::Receiver<()>>,
-) -> Result<()> {
+) -> Result<Vec<InputReport>> {
     trace!("merge files using config from {}", config_path.display());
     let mut merge_option_file = File::open(config_path)?;
     let options: Vec<FileMergeOptions> =
@@ -239,16 +319,18 @@ fn do_the_merge(
     merger_inputs: Vec<FileMergeOptions>,
     update_channel: cc::Sender<ChunkResults>,
     shutdown_rx: Option<&cc::Receiver<()>>,
-) -> Result<()> {
-    let mut writer = IndexOutput::new(
+) -> Result<Vec<InputReport>> {
+    let validated = validate_inputs(merger_inputs);
+    let mut writer = IndexOutput::for_inputs(
         append,
         out_path,
         chunk_size,
-        combined_file_size(&merger_inputs)?,
+        &validated,
         update_channel.clone(),
     )?;
     trace!("calling merge_inputs_with_writer");
-    merge_inputs_with_writer(&mut writer, merger_inputs, update_channel, shutdown_rx)
+    merge_inputs_with_writer(&mut writer, validated, update_channel, shutdown_rx)?;
+    Ok(writer.input_reports())
 }
 
 pub trait Len {
@@ -297,80 +379,174 @@ pub trait TimedLogEntry: PartialOrd {
 //     shutdown_rx: Option<&cc::Receiver<()>>,
 // ) -> Result<()> {
 
+/// apply the configured offset to the timestamps of inputs that bring their own timestamps,
+/// like for text inputs (where it is the timezone offset) it is subtracted
+fn with_offset(
+    lines: impl Iterator<Item = TimedLine>,
+    offset: Option<i64>,
+) -> impl Iterator<Item = TimedLine> {
+    let offset = offset.unwrap_or(0);
+    lines.map(move |line| TimedLine {
+        timestamp: line.timestamp - offset,
+        ..line
+    })
+}
+
+/// all entries of a merge input, in the order they appear in the file
+pub(crate) type TimedLines = Box<dyn Iterator<Item = TimedLine> + Send>;
+
+/// open a merge input and create the iterator that fits its `input_type`
+///
+/// The `line_nr` of the entries is their number in the input.
+pub(crate) fn open_timed_lines(
+    input: FileMergeOptions,
+    compressed_position: Option<CompressedPosition>,
+    counts: &InputCounts,
+) -> Result<TimedLines> {
+    let line_nr = 0;
+    let absolute_path = PathBuf::from(input.path);
+    trace!("absolute_path was: {:?}", absolute_path);
+    let file = InputFile::open_with_position(&absolute_path, compressed_position)?;
+    let iter: TimedLines = match input.input_type {
+        InputType::Text => Box::new(
+            TimedLineIter::new(
+                file,
+                input.tag,
+                lookup_regex_for_format_str(&input.format)?,
+                input.year,
+                input.offset,
+                line_nr,
+                input.multiline,
+            )
+            .counting_missing_timestamps(counts.without_timestamp.clone()),
+        ),
+        InputType::Journal => Box::new(with_offset(
+            journal_timed_lines(BufReader::new(file), input.tag, line_nr),
+            input.offset,
+        )),
+        InputType::Logcat => Box::new(with_offset(
+            logcat_timed_lines(&absolute_path, file, input.tag, input.year, line_nr)?,
+            input.offset,
+        )),
+        InputType::Dlt => Box::new(with_offset(
+            dlt_timed_lines(file, input.tag, input.ecu_time_reference, line_nr),
+            input.offset,
+        )),
+        InputType::Pcap => Box::new(with_offset(
+            pcap_timed_lines(file, input.tag, input.ecu_time_reference, line_nr)?,
+            input.offset,
+        )),
+    };
+    let iter: TimedLines = match input.clock_drift {
+        Some(drift) => Box::new(iter.map(move |line| TimedLine {
+            timestamp: drift.correct(line.timestamp),
+            ..line
+        })),
+        None => iter,
+    };
+    let out_of_order = counts.out_of_order.clone();
+    let mut newest: Option<i64> = None;
+    let iter: TimedLines = Box::new(iter.inspect(move |line| match newest {
+        Some(newest) if line.timestamp < newest => out_of_order.increment(),
+        _ => newest = Some(line.timestamp),
+    }));
+    Ok(match input.reorder_window {
+        Some(window) => Box::new(Reordered::new(iter, window, counts.reordered.clone())),
+        None => iter,
+    })
+}
+
+/// An entry of a merge input, inputs are merged by this time
+pub(crate) trait Timestamped {
+    fn timestamp(&self) -> i64;
+}
+
+impl Timestamped for TimedLine {
+    fn timestamp(&self) -> i64 {
+        self.timestamp
+    }
+}
+
+/// Keeps the next entry of each merge input, ordered by time. Entries with the same
+/// timestamp are taken in the order of the inputs.
+pub(crate) struct MergeHeap<T: Timestamped = TimedLine> {
+    heads: Vec<Option<T>>,
+    heap: BinaryHeap<Reverse<(i64, usize)>>,
+}
+
+impl<T: Timestamped> MergeHeap<T> {
+    pub(crate) fn new(input_count: usize) -> Self {
+        MergeHeap {
+            heads: (0..input_count).map(|_| None).collect(),
+            heap: BinaryHeap::with_capacity(input_count),
+        }
+    }
+
+    /// set the next entry of input `index`, the previous one has to be taken already
+    pub(crate) fn push(&mut self, index: usize, entry: T) {
+        self.heap.push(Reverse((entry.timestamp(), index)));
+        self.heads[index] = Some(entry);
+    }
+
+    /// take the oldest entry together with the index of its input
+    pub(crate) fn pop(&mut self) -> Option<(usize, T)> {
+        let Reverse((_, index)) = self.heap.pop()?;
+        self.heads[index].take().map(|entry| (index, entry))
+    }
+}
+
+/// k-way merge of inputs that are each sorted by time
+pub(crate) struct MergedLines {
+    readers: Vec<TimedLines>,
+    heap: MergeHeap,
+    to_refill: Vec<usize>,
+}
+
+impl MergedLines {
+    pub(crate) fn new(readers: Vec<TimedLines>) -> Self {
+        MergedLines {
+            heap: MergeHeap::new(readers.len()),
+            to_refill: (0..readers.len()).collect(),
+            readers,
+        }
+    }
+}
+
+impl Iterator for MergedLines {
+    /// index of the input and its entry
+    type Item = (usize, TimedLine);
+    fn next(&mut self) -> Option<(usize, TimedLine)> {
+        for index in self.to_refill.drain(..) {
+            if let Some(line) = self.readers[index].next() {
+                self.heap.push(index, line);
+            }
+        }
+        let (index, line) = self.heap.pop()?;
+        self.to_refill.push(index);
+        Some((index, line))
+    }
+}
+
 pub(crate) fn merge_inputs_with_writer(
     writer: &mut IndexOutput,
-    merger_inputs: Vec<FileMergeOptions>,
+    merger_inputs: Vec<ValidatedInput>,
     update_channel: cc::Sender<ChunkResults>,
     shutdown_rx: Option<&cc::Receiver<()>>,
 ) -> Result<()> {
     trace!("merge_inputs_with_writer ({} files)", merger_inputs.len());
     let mut lines_with_year_missing = 0usize;
-    // create a peekable iterator for all file inputs
-    let mut readers: Vec<Peekable<TimedLineIter>> = merger_inputs
-        .into_iter()
-        .map(
-            |input: FileMergeOptions| -> Result<Peekable<TimedLineIter>> {
-                let file_path = PathBuf::from(input.path);
-                let absolute_path = file_path;
-                //     Some(dir) if !file_path.is_absolute() => PathBuf::from(&dir).join(file_path),
-                //     _ => file_path,
-                // };
-                trace!("absolute_path was: {:?}", absolute_path);
-                Ok(TimedLineIter::new(
-                    File::open(absolute_path)?,
-                    input.tag,
-                    lookup_regex_for_format_str(&input.format)?,
-                    input.year,
-                    input.offset,
-                    writer.line_nr,
-                )
-                .peekable())
-            },
-        )
-        .filter_map(Result::ok) // TODO better error handling
-        .collect();
+    let readers = writer.open_inputs(merger_inputs)?;
     let mut stopped = false;
-    loop {
+    for (index, line) in MergedLines::new(readers) {
+        if line.year_was_missing {
+            lines_with_year_missing += 1
+        }
+        writer.add_entry(index, &line)?;
+        stopped = utils::check_if_stop_was_requested(shutdown_rx, "merger");
         if stopped {
             info!("we where stopped while merging");
             break;
         }
-        // keep track of the min timestamp together with the index of the file it belongs to
-        // to do this, we peek an entry of each reader and of all those entries we
-        // find the minimum timestamp entry
-        let mut minimum: Option<(i64, usize)> = None;
-        for (i, iter) in readers.iter_mut().enumerate() {
-            if let Some(entry) = iter.peek() {
-                match minimum {
-                    Some((t_min, _)) => {
-                        if entry.timestamp < t_min {
-                            minimum = Some((entry.timestamp, i));
-                        }
-                    }
-                    None => {
-                        minimum = Some((entry.timestamp, i));
-                    }
-                }
-                if entry.year_was_missing {
-                    lines_with_year_missing += 1
-                }
-            }
-        }
-        if let Some((_, min_index)) = minimum {
-            // we found an entry with a minimal timestamp
-            if let Some(line) = readers[min_index].next() {
-                // important: keep track of how many bytes we processed
-                let trimmed_len = line.content.len();
-                if trimmed_len > 0 {
-                    writer.add_to_chunk(&line.content, &line.tag, line.original_length)?;
-                    stopped = utils::check_if_stop_was_requested(shutdown_rx, "merger");
-                }
-            } else {
-                break;
-            }
-        } else {
-            break;
-        }
     }
     if stopped {
         debug!("sending IndexingProgress::Stopped");
@@ -382,19 +558,36 @@ pub(crate) fn merge_inputs_with_writer(
                 lines_with_year_missing
             ));
         }
+        writer.report_reordered()?;
         writer.write_rest()?;
     }
     update_channel.send(Ok(IndexingProgress::Finished))?;
     Ok(())
 }
 
+/// an input that is merged by an `IndexOutput`
+struct MergeInput {
+    /// index in `IndexOutput::reports`
+    report: usize,
+    /// index in the provenance map
+    provenance: usize,
+    counts: InputCounts,
+}
+
 pub struct IndexOutput {
     pub(crate) line_nr: usize,
     update_channel: cc::Sender<ChunkResults>,
     chunk_count: usize,
     chunk_factory: ChunkFactory,
-    buf_writer: BufWriter<File>,
+    row_writer: RowWriter<BufWriter<File>>,
     progress_reporter: ProgressReporter<Chunk>,
+    /// set if some inputs are compressed, all inputs count the bytes they read here
+    compressed_position: Option<CompressedPosition>,
+    inputs: Vec<MergeInput>,
+    /// one report for each input, including those that could not be merged
+    reports: Vec<InputReport>,
+    provenance: ProvenanceMap,
+    provenance_path: PathBuf,
 }
 
 pub(crate) fn combined_file_size<T>(paths: &[T]) -> Result<u64>
@@ -402,17 +595,20 @@ where
     T: Len + Debug,
 {
     paths.iter().try_fold(0, |acc, x| match x.len() {
-        Ok(len) => Ok(acc + len as u64),
+        Ok(len) => Ok(acc + len),
         Err(e) => Err(anyhow!("error getting combined file size ({})", e)),
     })
 }
 
 impl IndexOutput {
-    fn new(
+    /// Output of a merge to `out_path`. `combined_size` is the size of all inputs
+    /// together, the progress is reported relative to it.
+    pub fn new(
         append: bool,
         out_path: &Path,
         chunk_size: usize,
         combined_size: u64,
+        compressed_position: Option<CompressedPosition>,
         update_channel: cc::Sender<ChunkResults>,
     ) -> Result<Self> {
         let out_file: File = if append {
@@ -421,7 +617,7 @@ impl IndexOutput {
                 .create(true)
                 .open(out_path)?
         } else {
-            File::create(&out_path)?
+            File::create(out_path)?
         };
         let line_nr = if append {
             utils::next_line_nr(out_path)?
@@ -429,27 +625,154 @@ impl IndexOutput {
             0
         };
         let original_file_size = out_file.metadata()?.len() as usize;
-        let buf_writer = BufWriter::with_capacity(100 * 1024 * 1024, out_file);
+        let row_writer = RowWriter::new(
+            BufWriter::with_capacity(100 * 1024 * 1024, out_file),
+            out_path,
+            original_file_size,
+        )?;
+        let provenance_path = ProvenanceMap::path_for(out_path);
+        let existing_provenance = if append && provenance_path.exists() {
+            ProvenanceMap::load(&provenance_path).ok()
+        } else {
+            None
+        };
 
-        let progress_reporter = ProgressReporter::new(combined_size, update_channel.clone());
+        let progress_reporter = ProgressReporter::new(combined_size, update_channel.clone())
+            .with_compressed_position(compressed_position.clone());
         Ok(IndexOutput {
             line_nr,
             update_channel,
-            chunk_factory: ChunkFactory::new(chunk_size, original_file_size),
+            chunk_factory: ChunkFactory::new(chunk_size, original_file_size)
+                .with_index(out_path)?,
             chunk_count: 0usize,
-            buf_writer,
+            row_writer,
             progress_reporter,
+            compressed_position,
+            inputs: vec![],
+            reports: vec![],
+            provenance: ProvenanceMap::continue_at(existing_provenance, line_nr),
+            provenance_path,
         })
     }
 
-    pub(crate) fn add_to_chunk(
-        &mut self,
-        content: &str,
-        tag: &str,
-        original_len: usize,
-    ) -> Result<()> {
-        let additional_bytes =
-            utils::write_tagged_line(tag, &mut self.buf_writer, content, self.line_nr, true, None)?;
+    /// writer for the inputs that passed validation
+    pub(crate) fn for_inputs(
+        append: bool,
+        out_path: &Path,
+        chunk_size: usize,
+        inputs: &[ValidatedInput],
+        update_channel: cc::Sender<ChunkResults>,
+    ) -> Result<Self> {
+        let valid: Vec<FileMergeOptions> = inputs
+            .iter()
+            .filter_map(|input| input.as_ref().ok().cloned())
+            .collect();
+        let paths: Vec<&str> = valid.iter().map(|i| i.path.as_str()).collect();
+        IndexOutput::new(
+            append,
+            out_path,
+            chunk_size,
+            combined_file_size(&valid)?,
+            shared_position_if_compressed(&paths),
+            update_channel,
+        )
+    }
+
+    pub(crate) fn compressed_position(&self) -> Option<CompressedPosition> {
+        self.compressed_position.clone()
+    }
+
+    /// Open all inputs that passed validation, the readers are returned in the order
+    /// of the inputs that can be merged. Each input that can not be merged is reported
+    /// through an error `Notification`.
+    pub(crate) fn open_inputs(&mut self, inputs: Vec<ValidatedInput>) -> Result<Vec<TimedLines>> {
+        let mut readers = vec![];
+        for input in inputs {
+            let opened = input.and_then(|options| {
+                let report = InputReport::new(&options);
+                let counts = InputCounts::default();
+                match open_timed_lines(options.clone(), self.compressed_position(), &counts) {
+                    Ok(reader) => Ok((report, counts, reader)),
+                    Err(e) => Err(Box::new(InputReport::failed(&options, &e))),
+                }
+            });
+            match opened {
+                Ok((report, counts, reader)) => {
+                    self.add_input(report, counts);
+                    readers.push(reader);
+                }
+                Err(report) => {
+                    self.update_channel.send(Err(Notification {
+                        severity: Severity::ERROR,
+                        content: format!(
+                            "{} can not be merged: {}",
+                            report.path,
+                            report.error.as_deref().unwrap_or_default()
+                        ),
+                        line: None,
+                    }))?;
+                    self.reports.push(*report);
+                }
+            }
+        }
+        Ok(readers)
+    }
+
+    /// Add an input that is not read from a file (e.g. a live source), its entries
+    /// are passed to `merger_async::merge_streams_with_writer`. `path` and `tag` of
+    /// `input` are used for its report and provenance.
+    /// Returns the index of the input.
+    pub fn add_stream_input(&mut self, input: &FileMergeOptions) -> usize {
+        self.add_input(InputReport::new(input), InputCounts::default())
+    }
+
+    fn add_input(&mut self, report: InputReport, counts: InputCounts) -> usize {
+        self.inputs.push(MergeInput {
+            report: self.reports.len(),
+            provenance: self.provenance.add_input(&report.path),
+            counts,
+        });
+        self.reports.push(report);
+        self.inputs.len() - 1
+    }
+
+    /// the reports of all inputs in the order they were given
+    pub fn input_reports(&self) -> Vec<InputReport> {
+        let mut reports = self.reports.clone();
+        for input in &self.inputs {
+            let report = &mut reports[input.report];
+            report.out_of_order = input.counts.out_of_order.get();
+            report.reordered = input.counts.reordered.get();
+            report.without_timestamp = input.counts.without_timestamp.get();
+        }
+        reports
+    }
+
+    /// write an entry of the input with index `input` (in the order of the readers
+    /// from `open_inputs`), entries without content are skipped
+    pub(crate) fn add_entry(&mut self, input: usize, line: &TimedLine) -> Result<()> {
+        let input = &self.inputs[input];
+        self.reports[input.report].add(line);
+        // important: keep track of how many bytes we processed
+        let written = !line.content.is_empty();
+        self.provenance.add_entry(
+            input.provenance,
+            line.line_nr,
+            line.original_length,
+            written,
+        );
+        if written {
+            self.add_to_chunk(&line.content, &line.tag, line.original_length)?;
+        } else {
+            self.progress_reporter.make_progress(line.original_length);
+        }
+        Ok(())
+    }
+
+    fn add_to_chunk(&mut self, content: &str, tag: &str, original_len: usize) -> Result<()> {
+        let additional_bytes = self
+            .row_writer
+            .write_row(tag, content, self.line_nr, true, None)?;
         self.line_nr += 1;
         self.progress_reporter.make_progress(original_len);
         if let Some(chunk) = self.chunk_factory.add_bytes(
@@ -457,14 +780,28 @@ impl IndexOutput {
             additional_bytes,
         ) {
             self.chunk_count += 1;
-            self.buf_writer.flush()?;
+            self.row_writer.flush()?;
             self.update_channel
                 .send(Ok(IndexingProgress::GotItem { item: chunk }))?;
         }
         Ok(())
     }
+    pub(crate) fn report_reordered(&self) -> Result<()> {
+        let reordered: usize = self.inputs.iter().map(|i| i.counts.reordered.get()).sum();
+        if reordered > 0 {
+            self.update_channel.send(Err(Notification {
+                severity: Severity::WARNING,
+                content: format!("{} entries were out of order and got reordered", reordered),
+                line: None,
+            }))?;
+        }
+        Ok(())
+    }
+
     pub(crate) fn write_rest(&mut self) -> Result<()> {
-        self.buf_writer.flush()?;
+        self.row_writer.flush()?;
+        self.provenance.finish();
+        self.provenance.save(&self.provenance_path)?;
         if let Some(chunk) = self
             .chunk_factory
             .create_last_chunk(self.line_nr, self.chunk_count > 0)
@@ -1,99 +1,163 @@
-use crate::merger::{FileLogEntryProducer, FileMergeOptions, IndexOutput};
+// Copyright (c) 2019 E.S.R.Labs. All rights reserved.
+//
+// NOTICE:  All information contained herein is, and remains
+// the property of E.S.R.Labs and its suppliers, if any.
+// The intellectual and technical concepts contained herein are
+// proprietary to E.S.R.Labs and its suppliers and may be covered
+// by German and Foreign Patents, patents in process, and are protected
+// by trade secret or copyright law.
+// Dissemination of this information or reproduction of this material
+// is strictly forbidden unless prior written permission is obtained
+// from E.S.R.Labs.
+use crate::{
+    merger::{FileLogEntryProducer, FileMergeOptions, IndexOutput, MergeHeap},
+    report::{validate_inputs, InputReport},
+};
 use anyhow::Result;
 use crossbeam_channel as cc;
-use futures::{
-    pin_mut,
-    stream::{Peekable, StreamExt},
-};
 use indexer_base::{
-    chunks::ChunkResults, error_reporter::*, progress::IndexingProgress, timedline::*, utils,
+    chunks::ChunkResults, error_reporter::*, progress::IndexingProgress, timedline::*,
 };
-use processor::parse::lookup_regex_for_format_str;
 use std::{
-    fs::File,
-    iter::Iterator,
-    path::{Path, PathBuf},
+    path::Path,
+    pin::Pin,
+    task::{Context, Poll},
 };
+use tokio::sync;
+use tokio_stream::{Stream, StreamExt};
 
-pub(crate) async fn _merge_inputs_with_writer_async(
-    parent_dir: Option<&Path>,
-    writer: &mut IndexOutput,
-    merger_inputs: Vec<FileMergeOptions>,
+/// k-way merge of streams of `TimedLine`s that are each sorted by time
+///
+/// The next entry is only produced once every input that is not finished yet
+/// delivered its next entry, so a live input that has nothing to say yet holds
+/// back the merge. Inputs are only polled when the merged stream is polled, a slow
+/// consumer slows down reading the inputs.
+/// Entries with the same timestamp are produced in the order of the inputs.
+/// Each entry comes with the index of its input.
+pub struct MergedStream<S> {
+    inputs: Vec<S>,
+    heap: MergeHeap,
+    /// inputs that need to deliver their next entry
+    to_refill: Vec<usize>,
+}
+
+pub fn merge_streams<S>(inputs: Vec<S>) -> MergedStream<S>
+where
+    S: Stream<Item = TimedLine> + Unpin,
+{
+    MergedStream {
+        heap: MergeHeap::new(inputs.len()),
+        to_refill: (0..inputs.len()).collect(),
+        inputs,
+    }
+}
+
+impl<S> Stream for MergedStream<S>
+where
+    S: Stream<Item = TimedLine> + Unpin,
+{
+    type Item = (usize, TimedLine);
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+        let mut waiting = vec![];
+        for index in this.to_refill.drain(..) {
+            match Pin::new(&mut this.inputs[index]).poll_next(cx) {
+                Poll::Ready(Some(line)) => this.heap.push(index, line),
+                Poll::Ready(None) => trace!("merge input {} is finished", index),
+                Poll::Pending => waiting.push(index),
+            }
+        }
+        if !waiting.is_empty() {
+            this.to_refill = waiting;
+            return Poll::Pending;
+        }
+        match this.heap.pop() {
+            Some((index, line)) => {
+                this.to_refill.push(index);
+                Poll::Ready(Some((index, line)))
+            }
+            None => Poll::Ready(None),
+        }
+    }
+}
+
+/// Merge files like `merger::merge_files_use_config` does but read them as streams
+///
+/// The merge can be cancelled by sending on `shutdown_rx`.
+pub async fn merge_files_use_config_async(
+    options: Vec<FileMergeOptions>,
+    out_path: &Path,
+    append: bool,
+    chunk_size: usize, // used for mapping line numbers to byte positions
     update_channel: cc::Sender<ChunkResults>,
-    shutdown_rx: Option<&cc::Receiver<()>>,
-) -> Result<()> {
-    trace!("merge_inputs_with_writer ({} files)", merger_inputs.len());
-    let mut lines_with_year_missing = 0usize;
-    // create a peekable iterator for all file inputs
-    let mut log_entry_streams: Vec<Peekable<FileLogEntryProducer>> = merger_inputs
+    shutdown_rx: Option<sync::mpsc::Receiver<()>>,
+) -> Result<Vec<InputReport>> {
+    trace!("merge {} files (async)", options.len());
+    let validated = validate_inputs(options);
+    let mut writer = IndexOutput::for_inputs(
+        append,
+        out_path,
+        chunk_size,
+        &validated,
+        update_channel.clone(),
+    )?;
+    let inputs = writer
+        .open_inputs(validated)?
         .into_iter()
-        .map(
-            |input: FileMergeOptions| -> Result<Peekable<FileLogEntryProducer>> {
-                let file_path = PathBuf::from(input.path);
-                let absolute_path = match parent_dir {
-                    Some(dir) if !file_path.is_absolute() => PathBuf::from(&dir).join(file_path),
-                    _ => file_path,
-                };
-                let x = FileLogEntryProducer::new(
-                    File::open(absolute_path)?,
-                    input.tag,
-                    lookup_regex_for_format_str(&input.format)?,
-                    input.year,
-                    input.offset,
-                    writer.line_nr,
-                )?;
-                Ok(x.peekable())
-            },
-        )
-        .filter_map(Result::ok) // TODO better error handling
+        .map(FileLogEntryProducer::with_timed_lines)
         .collect();
+    merge_streams_with_writer(&mut writer, inputs, update_channel, shutdown_rx).await?;
+    Ok(writer.input_reports())
+}
+
+/// Merge the entries of any streams into the output of `writer`
+///
+/// `inputs` have to be in the order of the inputs that were added to `writer`
+/// (see `IndexOutput::add_stream_input`). A stream that is pending holds back the
+/// merge until it delivers its next entry or ends.
+pub async fn merge_streams_with_writer<S>(
+    writer: &mut IndexOutput,
+    inputs: Vec<S>,
+    update_channel: cc::Sender<ChunkResults>,
+    mut shutdown_rx: Option<sync::mpsc::Receiver<()>>,
+) -> Result<()>
+where
+    S: Stream<Item = TimedLine> + Unpin,
+{
+    trace!("merge_streams_with_writer ({} inputs)", inputs.len());
+    let mut lines_with_year_missing = 0usize;
+    let mut merged = merge_streams(inputs);
     let mut stopped = false;
     loop {
-        if stopped {
-            info!("we where stopped while merging");
-            break;
-        }
-        // keep track of the min timestamp together with the index of the file it belongs to
-        // to do this, we peek an entry of each reader and of all those entries we
-        // find the minimum timestamp entry
-        let mut minimum: Option<(i64, usize)> = None;
-        for (i, iter) in log_entry_streams.iter().enumerate() {
-            let p: &Peekable<FileLogEntryProducer> = iter;
-            pin_mut!(p);
-            // if let Some(Ok(Some(entry))) = p.as_mut().peek().await {
-            //     match minimum {
-            //         Some((t_min, _)) => {
-            //             if entry.timestamp < t_min {
-            //                 minimum = Some((entry.timestamp, i));
-            //             }
-            //         }
-            //         None => {
-            //             minimum = Some((entry.timestamp, i));
-            //         }
-            //     }
-            //     if entry.year_was_missing {
-            //         lines_with_year_missing += 1
-            //     }
-            // }
-        }
-        if let Some((_, min_index)) = minimum {
-            // we found an entry with a minimal timestamp
-            if let Some(Ok(Some(line))) = log_entry_streams[min_index].next().await {
-                // important: keep track of how many bytes we processed
-                let trimmed_len = line.content.len();
-                if trimmed_len > 0 {
-                    writer.add_to_chunk(&line.content, &line.tag, line.original_length)?;
-                    stopped = utils::check_if_stop_was_requested(shutdown_rx, "merger");
+        tokio::select! {
+            // a requested shutdown wins over entries that are ready
+            biased;
+            Some(()) = async {
+                match shutdown_rx.as_mut() {
+                    Some(rx) => rx.recv().await,
+                    None => None,
                 }
-            } else {
+            } => {
+                debug!("received shutdown through future channel");
+                stopped = true;
                 break;
             }
-        } else {
-            break;
+            next = merged.next() => {
+                match next {
+                    Some((index, line)) => {
+                        if line.year_was_missing {
+                            lines_with_year_missing += 1;
+                        }
+                        writer.add_entry(index, &line)?;
+                    }
+                    None => break,
+                }
+            }
         }
     }
     if stopped {
-        debug!("sending IndexingProgress::Stopped");
+        info!("we where stopped while merging");
         update_channel.send(Ok(IndexingProgress::Stopped))?;
     } else {
         if lines_with_year_missing > 0 {
@@ -102,86 +166,9 @@ pub(crate) async fn _merge_inputs_with_writer_async(
                 lines_with_year_missing
             ));
         }
+        writer.report_reordered()?;
         writer.write_rest()?;
     }
     update_channel.send(Ok(IndexingProgress::Finished))?;
     Ok(())
 }
-mod test {
-
-    use anyhow::{anyhow, Result};
-    use futures::{
-        executor::block_on,
-        pin_mut,
-        stream::{self, Peekable, StreamExt},
-    };
-
-    #[derive(PartialEq, Debug)]
-    pub struct Item {
-        num: usize,
-    }
-    struct ItemIter {
-        content: Vec<Item>,
-    }
-    impl ItemIter {
-        fn new(elems: &[usize]) -> Self {
-            ItemIter {
-                content: elems.iter().map(|&num| Item { num }).collect(),
-            }
-        }
-    }
-
-    impl Iterator for ItemIter {
-        type Item = Item;
-        fn next(&mut self) -> Option<Item> {
-            self.content.pop()
-        }
-    }
-    pub struct ItemProducer {
-        item_iterator: ItemIter,
-    }
-
-    impl futures::Stream for ItemProducer {
-        type Item = Result<Option<Item>>;
-        fn poll_next(
-            mut self: std::pin::Pin<&mut Self>,
-            _cx: &mut std::task::Context,
-        ) -> futures::task::Poll<Option<Self::Item>> {
-            let next = self.item_iterator.next();
-            match next {
-                Some(msg) => futures::task::Poll::Ready(Some(Ok(Some(msg)))),
-                None => futures::task::Poll::Ready(Some(Err(anyhow!("no more elements")))),
-            }
-        }
-    }
-    #[async_std::test]
-    async fn peekable() {
-        let iter_1 = ItemIter::new(&[1, 3, 5]);
-        let iter_2 = ItemIter::new(&[2, 4, 6]);
-        let inputs = vec![iter_1, iter_2];
-        let mut item_streams: Vec<Peekable<ItemProducer>> = inputs
-            .into_iter()
-            .map(|input: ItemIter| -> Result<Peekable<ItemProducer>> {
-                let x = ItemProducer {
-                    item_iterator: input,
-                };
-                Ok(x.peekable())
-            })
-            .filter_map(Result::ok)
-            .collect();
-        for (i, stream) in item_streams.iter().enumerate() {
-            let p: &Peekable<ItemProducer> = stream;
-            pin_mut!(p);
-        }
-
-        // let (a1, a2, a3) = (Item { num: 1 }, Item { num: 2 }, Item { num: 3 });
-        // let (a4, a5, a6) = (Item { num: 4 }, Item { num: 5 }, Item { num: 6 });
-        // let peekable: Peekable<_> = stream::iter(vec![a1, a2, a3]).peekable();
-        // pin_mut!(peekable);
-        // assert_eq!(peekable.as_mut().peek().await.unwrap().num, 1);
-        // assert_eq!(
-        //     peekable.collect::<Vec<Item>>().await,
-        //     vec![Item { num: 1 }, Item { num: 2 }, Item { num: 3 }]
-        // );
-    }
-}
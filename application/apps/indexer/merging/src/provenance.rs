@@ -0,0 +1,187 @@
+// Copyright (c) 2019 E.S.R.Labs. All rights reserved.
+//
+// NOTICE:  All information contained herein is, and remains
+// the property of E.S.R.Labs and its suppliers, if any.
+// The intellectual and technical concepts contained herein are
+// proprietary to E.S.R.Labs and its suppliers and may be covered
+// by German and Foreign Patents, patents in process, and are protected
+// by trade secret or copyright law.
+// Dissemination of this information or reproduction of this material
+// is strictly forbidden unless prior written permission is obtained
+// from E.S.R.Labs.
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Where a line of a merged file comes from
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+pub struct Origin {
+    /// index of the input in `ProvenanceMap::inputs`
+    pub input: usize,
+    /// entry number in the input (0 based)
+    pub line: usize,
+    /// byte offset of the entry in the (decompressed) input
+    pub offset: u64,
+}
+
+/// Maps every line of a merged file to the input entry it was created from.
+///
+/// The map is stored next to the merged file (see `ProvenanceMap::path_for`).
+/// When appending to a merged file the map is extended.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct ProvenanceMap {
+    inputs: Vec<String>,
+    /// output line of the first entry, lines before were not merged
+    first_line: usize,
+    /// index of the input for each output line
+    line_inputs: Vec<u32>,
+    /// entry number in the input for each output line
+    source_lines: Vec<u64>,
+    /// byte offsets of the entries of each input
+    offsets: Vec<Vec<u64>>,
+    /// inputs from here on still have entry lengths in `offsets`
+    #[serde(skip)]
+    unfinished_input: usize,
+    /// for each input the entry numbers with their output lines, sorted by entry number
+    #[serde(skip)]
+    input_entries: Vec<Vec<(u64, usize)>>,
+}
+
+impl ProvenanceMap {
+    pub fn path_for(merged_file: &Path) -> PathBuf {
+        let mut name = merged_file.as_os_str().to_owned();
+        name.push(".provenance");
+        PathBuf::from(name)
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let map: ProvenanceMap = bincode::deserialize(&fs::read(path)?)
+            .map_err(|e| anyhow!("could not deserialize provenance map: {}", e))?;
+        let mut map = ProvenanceMap {
+            unfinished_input: map.inputs.len(),
+            ..map
+        };
+        map.index_entries();
+        Ok(map)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let encoded = bincode::serialize(self)
+            .map_err(|e| anyhow!("could not serialize provenance map: {}", e))?;
+        fs::write(path, encoded)?;
+        Ok(())
+    }
+
+    /// paths of all inputs that were merged
+    pub fn inputs(&self) -> &[String] {
+        &self.inputs
+    }
+
+    /// number of output lines that are covered
+    pub fn len(&self) -> usize {
+        self.line_inputs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.line_inputs.is_empty()
+    }
+
+    /// the input entry an output line was created from
+    pub fn origin(&self, output_line: usize) -> Option<Origin> {
+        let index = output_line.checked_sub(self.first_line)?;
+        let input = *self.line_inputs.get(index)? as usize;
+        let line = self.source_lines[index] as usize;
+        Some(Origin {
+            input,
+            line,
+            offset: self.offsets[input].get(line).copied().unwrap_or(0),
+        })
+    }
+
+    /// the output line of an entry of an input
+    pub fn output_line(&self, input: usize, line: usize) -> Option<usize> {
+        let entries = self.input_entries.get(input)?;
+        entries
+            .binary_search_by_key(&(line as u64), |&(entry, _)| entry)
+            .ok()
+            .map(|index| entries[index].1)
+    }
+
+    /// output lines of all entries of an input, in the order they have in the input
+    pub fn output_lines_in_input_order(&self, input: usize) -> Vec<usize> {
+        self.input_entries
+            .get(input)
+            .map(|entries| {
+                entries
+                    .iter()
+                    .map(|&(_, output_line)| output_line)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    fn index_entries(&mut self) {
+        let mut input_entries = vec![vec![]; self.inputs.len()];
+        for (index, (&input, &line)) in self
+            .line_inputs
+            .iter()
+            .zip(self.source_lines.iter())
+            .enumerate()
+        {
+            if let Some(entries) = input_entries.get_mut(input as usize) {
+                entries.push((line, index + self.first_line));
+            }
+        }
+        for entries in input_entries.iter_mut() {
+            entries.sort_unstable();
+        }
+        self.input_entries = input_entries;
+    }
+
+    /// continue the map of an existing merged file that ends at `next_line`
+    pub(crate) fn continue_at(existing: Option<ProvenanceMap>, next_line: usize) -> Self {
+        match existing {
+            Some(map) if map.first_line + map.len() == next_line => map,
+            _ => ProvenanceMap {
+                first_line: next_line,
+                ..Default::default()
+            },
+        }
+    }
+
+    pub(crate) fn add_input(&mut self, path: &str) -> usize {
+        self.inputs.push(path.to_string());
+        self.offsets.push(vec![]);
+        self.inputs.len() - 1
+    }
+
+    /// record an entry of an input, `written` if it became the next output line
+    pub(crate) fn add_entry(&mut self, input: usize, line: usize, length: usize, written: bool) {
+        let lengths = &mut self.offsets[input];
+        if lengths.len() <= line {
+            lengths.resize(line + 1, 0);
+        }
+        lengths[line] = length as u64;
+        if written {
+            self.line_inputs.push(input as u32);
+            self.source_lines.push(line as u64);
+        }
+    }
+
+    /// turn the recorded entry lengths of the new inputs into offsets
+    pub(crate) fn finish(&mut self) {
+        for lengths in self.offsets.iter_mut().skip(self.unfinished_input) {
+            let mut offset = 0u64;
+            for entry in lengths.iter_mut() {
+                let length = *entry;
+                *entry = offset;
+                offset += length;
+            }
+        }
+        self.unfinished_input = self.inputs.len();
+        self.index_entries();
+    }
+}
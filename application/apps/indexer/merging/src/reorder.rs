@@ -0,0 +1,112 @@
+// Copyright (c) 2019 E.S.R.Labs. All rights reserved.
+//
+// NOTICE:  All information contained herein is, and remains
+// the property of E.S.R.Labs and its suppliers, if any.
+// The intellectual and technical concepts contained herein are
+// proprietary to E.S.R.Labs and its suppliers and may be covered
+// by German and Foreign Patents, patents in process, and are protected
+// by trade secret or copyright law.
+// Dissemination of this information or reproduction of this material
+// is strictly forbidden unless prior written permission is obtained
+// from E.S.R.Labs.
+use crate::report::EntryCount;
+use indexer_base::timedline::TimedLine;
+use serde::{Deserialize, Serialize};
+use std::{
+    cmp::{Ordering, Reverse},
+    collections::BinaryHeap,
+};
+
+/// How far an entry may appear after entries that are newer than itself
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ReorderWindow {
+    /// at most this many entries
+    Entries(usize),
+    /// at most this many ms
+    Millis(i64),
+}
+
+struct Pending {
+    /// position in the input, keeps the input order for equal timestamps
+    seq: u64,
+    line: TimedLine,
+}
+
+impl PartialEq for Pending {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+impl Eq for Pending {}
+impl PartialOrd for Pending {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl Ord for Pending {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.line.timestamp, self.seq).cmp(&(other.line.timestamp, other.seq))
+    }
+}
+
+/// Sorts the entries of an input that is sorted except for local reordering
+/// (e.g. multithreaded writers). Entries are held back until no entry within the
+/// window can come before them anymore.
+pub struct Reordered<I> {
+    input: I,
+    window: ReorderWindow,
+    buffer: BinaryHeap<Reverse<Pending>>,
+    seq: u64,
+    newest: Option<i64>,
+    exhausted: bool,
+    reordered: EntryCount,
+}
+
+impl<I: Iterator<Item = TimedLine>> Reordered<I> {
+    pub fn new(input: I, window: ReorderWindow, reordered: EntryCount) -> Self {
+        Self {
+            input,
+            window,
+            buffer: BinaryHeap::new(),
+            seq: 0,
+            newest: None,
+            exhausted: false,
+            reordered,
+        }
+    }
+
+    fn oldest_is_final(&self) -> bool {
+        match (self.buffer.peek(), self.window) {
+            (None, _) => false,
+            (Some(_), ReorderWindow::Entries(n)) => self.buffer.len() > n,
+            (Some(Reverse(oldest)), ReorderWindow::Millis(ms)) => self
+                .newest
+                .is_some_and(|newest| oldest.line.timestamp <= newest - ms),
+        }
+    }
+}
+
+impl<I: Iterator<Item = TimedLine>> Iterator for Reordered<I> {
+    type Item = TimedLine;
+
+    fn next(&mut self) -> Option<TimedLine> {
+        while !self.exhausted && !self.oldest_is_final() {
+            match self.input.next() {
+                Some(line) => {
+                    match self.newest {
+                        Some(newest) if line.timestamp < newest => self.reordered.increment(),
+                        _ => self.newest = Some(line.timestamp),
+                    }
+                    self.buffer.push(Reverse(Pending {
+                        seq: self.seq,
+                        line,
+                    }));
+                    self.seq += 1;
+                }
+                None => self.exhausted = true,
+            }
+        }
+        self.buffer.pop().map(|Reverse(pending)| pending.line)
+    }
+}
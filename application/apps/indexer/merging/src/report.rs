@@ -0,0 +1,148 @@
+// Copyright (c) 2019 E.S.R.Labs. All rights reserved.
+//
+// NOTICE:  All information contained herein is, and remains
+// the property of E.S.R.Labs and its suppliers, if any.
+// The intellectual and technical concepts contained herein are
+// proprietary to E.S.R.Labs and its suppliers and may be covered
+// by German and Foreign Patents, patents in process, and are protected
+// by trade secret or copyright law.
+// Dissemination of this information or reproduction of this material
+// is strictly forbidden unless prior written permission is obtained
+// from E.S.R.Labs.
+use crate::merger::{FileMergeOptions, InputType};
+use anyhow::{anyhow, Result};
+use indexer_base::timedline::TimedLine;
+use processor::parse::{detect_timestamp_format_in_file, lookup_regex_for_format_str};
+use serde::Serialize;
+use std::{
+    fs,
+    path::Path,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+/// Number of entries with some property, can be shared with the reader of an input
+#[derive(Clone, Debug, Default)]
+pub struct EntryCount(Arc<AtomicUsize>);
+
+impl EntryCount {
+    pub fn get(&self) -> usize {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn increment(&self) {
+        self.0.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// What the merger saw in one of its inputs
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+pub struct InputReport {
+    pub path: String,
+    pub tag: String,
+    /// timestamp format of a text input, detected if none was configured
+    pub format: Option<String>,
+    /// why the input could not be merged, nothing of it is in the output then
+    pub error: Option<String>,
+    /// entries that were merged
+    pub entries: usize,
+    /// text entries without a readable timestamp, they got the time of the entry before
+    pub without_timestamp: usize,
+    pub year_missing: usize,
+    pub min_time: Option<i64>,
+    pub max_time: Option<i64>,
+    /// entries with a time before an earlier entry of the input, with or without
+    /// a reorder window
+    pub out_of_order: usize,
+    /// entries that were out of order and got sorted by the reorder window
+    pub reordered: usize,
+}
+
+impl InputReport {
+    pub(crate) fn new(input: &FileMergeOptions) -> Self {
+        InputReport {
+            path: input.path.clone(),
+            tag: input.tag.clone(),
+            format: match input.input_type {
+                InputType::Text => Some(input.format.clone()),
+                _ => None,
+            },
+            ..Default::default()
+        }
+    }
+
+    pub(crate) fn failed(input: &FileMergeOptions, error: &anyhow::Error) -> Self {
+        InputReport {
+            error: Some(error.to_string()),
+            ..InputReport::new(input)
+        }
+    }
+
+    pub(crate) fn add(&mut self, line: &TimedLine) {
+        self.entries += 1;
+        if line.year_was_missing {
+            self.year_missing += 1;
+        }
+        self.min_time = Some(
+            self.min_time
+                .map_or(line.timestamp, |t| t.min(line.timestamp)),
+        );
+        self.max_time = Some(
+            self.max_time
+                .map_or(line.timestamp, |t| t.max(line.timestamp)),
+        );
+    }
+}
+
+/// counts that are kept by the reader of an input
+#[derive(Clone, Debug, Default)]
+pub(crate) struct InputCounts {
+    pub(crate) out_of_order: EntryCount,
+    pub(crate) reordered: EntryCount,
+    pub(crate) without_timestamp: EntryCount,
+}
+
+/// an input that can be merged or the report why it can not
+pub(crate) type ValidatedInput = std::result::Result<FileMergeOptions, Box<InputReport>>;
+
+/// Check all inputs before anything is merged.
+///
+/// Text inputs without a `format` get the format that is detected in the file.
+pub(crate) fn validate_inputs(inputs: Vec<FileMergeOptions>) -> Vec<ValidatedInput> {
+    inputs
+        .into_iter()
+        .map(|input| {
+            validate_input(&input).map_err(|e| {
+                warn!("input {} can not be merged: {}", input.path, e);
+                Box::new(InputReport::failed(&input, &e))
+            })
+        })
+        .collect()
+}
+
+fn validate_input(input: &FileMergeOptions) -> Result<FileMergeOptions> {
+    let path = Path::new(&input.path);
+    let metadata =
+        fs::metadata(path).map_err(|e| anyhow!("could not access {}: {}", input.path, e))?;
+    if !metadata.is_file() {
+        return Err(anyhow!("{} is not a file", input.path));
+    }
+    if input.input_type != InputType::Text {
+        return Ok(input.clone());
+    }
+    let format = if input.format.is_empty() {
+        let detected = detect_timestamp_format_in_file(path)?;
+        debug!("detected format {} for {}", detected, input.path);
+        detected
+    } else {
+        input.format.clone()
+    };
+    lookup_regex_for_format_str(&format)
+        .map_err(|e| anyhow!("invalid format \"{}\": {}", format, e))?;
+    Ok(FileMergeOptions {
+        format,
+        ..input.clone()
+    })
+}
@@ -0,0 +1,133 @@
+#[cfg(test)]
+mod tests {
+    use crate::{alignment::*, merger::FileMergeOptions};
+    use pretty_assertions::assert_eq;
+    use std::{fs, path::Path};
+    use tempdir::TempDir;
+
+    /// 05-22-2019 12:00:00.000 + `ms`
+    fn log_line(ms: i64, text: &str) -> String {
+        format!(
+            "05-22-2019 12:{:02}:{:02}.{:03} {}\n",
+            ms / 60_000,
+            ms / 1000 % 60,
+            ms % 1000,
+            text
+        )
+    }
+
+    fn write_input(dir: &Path, name: &str, lines: &[(i64, &str)]) -> FileMergeOptions {
+        let path = dir.join(name);
+        let content: String = lines.iter().map(|(ms, text)| log_line(*ms, text)).collect();
+        fs::write(&path, content).unwrap();
+        serde_json::from_value(serde_json::json!({
+            "path": path.to_string_lossy(),
+            "offset": 0,
+            "tag": name,
+            "format": "MM-DD-YYYY hh:mm:ss.s",
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_align_with_anchors() {
+        let tmp_dir = TempDir::new("alignment").expect("could not create temp dir");
+        let reference = write_input(
+            tmp_dir.path(),
+            "head_unit",
+            &[
+                (1000, "boot"),
+                (5000, "ignition on"),
+                (9000, "some noise"),
+                (20_000, "door open"),
+            ],
+        );
+        // this clock is 2.5 s late
+        let late = write_input(
+            tmp_dir.path(),
+            "gateway",
+            &[
+                (2000, "startup"),
+                (7500, "ignition on"),
+                (22_510, "door open"),
+                (30_000, "shutdown"),
+            ],
+        );
+        let alignment = align_inputs(
+            vec![reference, late],
+            &["ignition on".to_string(), "door open".to_string()],
+            false,
+        )
+        .unwrap();
+        assert_eq!(Some(0), alignment.options[0].offset);
+        assert_eq!(Some(2500), alignment.options[1].offset);
+        let report = &alignment.report[0];
+        assert_eq!("gateway", report.tag);
+        assert_eq!(2, report.matches);
+        assert_eq!(10, report.residual_max);
+        assert!(report.clock_drift.is_none());
+    }
+
+    #[test]
+    fn test_align_with_drift() {
+        let tmp_dir = TempDir::new("alignment").expect("could not create temp dir");
+        let events = [0i64, 100_000, 200_000, 300_000];
+        let reference: Vec<(i64, &str)> = events.iter().map(|&t| (t, "sync event")).collect();
+        // 1 s late at the start and 1 ms per s (1000 ppm) too fast
+        let drifting: Vec<(i64, &str)> = events
+            .iter()
+            .map(|&t| (t + 1000 + t / 1000, "sync event"))
+            .collect();
+        let inputs = vec![
+            write_input(tmp_dir.path(), "reference", &reference),
+            write_input(tmp_dir.path(), "drifting", &drifting),
+        ];
+        let alignment = align_inputs(inputs, &["sync".to_string()], true).unwrap();
+        let report = &alignment.report[0];
+        assert_eq!(4, report.matches);
+        assert!(report.residual_max <= 1, "{:?}", report);
+        let drift = report.clock_drift.expect("no drift");
+        assert!((drift.ppm + 999.0).abs() < 1.0, "{:?}", drift);
+        let offset = alignment.options[1].offset.unwrap();
+        // 05-22-2019 12:00:00.000
+        let base = 1_558_526_400_000i64;
+        for (own, expected) in drifting.iter().zip(events.iter()) {
+            assert!((drift.correct(base + own.0 - offset) - (base + expected)).abs() <= 1);
+        }
+    }
+
+    #[test]
+    fn test_align_with_detected_markers() {
+        let tmp_dir = TempDir::new("alignment").expect("could not create temp dir");
+        let reference = write_input(
+            tmp_dir.path(),
+            "a",
+            &[
+                (1000, "tick"),
+                (2000, "tick"),
+                (3000, "network up on eth0"),
+                (4000, "alpha"),
+            ],
+        );
+        let other = write_input(
+            tmp_dir.path(),
+            "b",
+            &[(500, "tick"), (1400, "network up on eth1"), (2000, "beta")],
+        );
+        let alignment = align_inputs(vec![reference, other], &[], false).unwrap();
+        assert_eq!(vec!["network up on eth".to_string()], alignment.anchors);
+        assert_eq!(Some(-1600), alignment.options[1].offset);
+    }
+
+    #[test]
+    fn test_align_without_matches_keeps_offset() {
+        let tmp_dir = TempDir::new("alignment").expect("could not create temp dir");
+        let reference = write_input(tmp_dir.path(), "a", &[(1000, "foo")]);
+        let mut other = write_input(tmp_dir.path(), "b", &[(1000, "bar")]);
+        other.offset = Some(42);
+        let alignment = align_inputs(vec![reference, other], &["baz".to_string()], false).unwrap();
+        assert_eq!(Some(42), alignment.options[1].offset);
+        assert_eq!(0, alignment.report[0].matches);
+        assert!(align_inputs(vec![], &[], false).is_err());
+    }
+}
@@ -0,0 +1,115 @@
+#[cfg(test)]
+mod tests {
+    use crate::concatenator::*;
+    use crossbeam_channel as cc;
+    use dlt::dlt_file::{dlt_messages, dlt_messages_with_bytes, STORAGE_HEADER_LENGTH};
+    use indexer_base::chunks::ChunkResults;
+    use pretty_assertions::assert_eq;
+    use std::{fs, path::Path};
+    use tempdir::TempDir;
+
+    const DLT_SAMPLE: &str = "../dlt/test_samples/timed_messages.dlt";
+
+    fn input(path: &Path, tag: &str) -> ConcatenatorInput {
+        serde_json::from_value(serde_json::json!({
+            "path": path.to_string_lossy(),
+            "tag": tag,
+        }))
+        .unwrap()
+    }
+
+    fn concat(
+        inputs: Vec<ConcatenatorInput>,
+        out_path: &Path,
+        output: ConcatOutput,
+    ) -> anyhow::Result<ConcatenatorResult> {
+        let (tx, _rx): (cc::Sender<ChunkResults>, cc::Receiver<ChunkResults>) = cc::unbounded();
+        concat_files(inputs, out_path, false, 5, output, tx, None)
+    }
+
+    /// the messages of the dlt sample written without storage headers
+    fn write_without_storage_headers(path: &Path) -> usize {
+        let mut content = vec![];
+        let mut count = 0;
+        for (_, mut msg) in dlt_messages(fs::File::open(DLT_SAMPLE).unwrap(), true) {
+            msg.storage_header = None;
+            content.extend(msg.as_bytes());
+            count += 1;
+        }
+        fs::write(path, content).unwrap();
+        count
+    }
+
+    #[test]
+    fn test_concat_dlt_files_as_dlt() {
+        let tmp_dir = TempDir::new("concat").expect("could not create temp dir");
+        let raw_path = tmp_dir.path().join("raw.dlt");
+        let message_count = write_without_storage_headers(&raw_path);
+        let out_path = tmp_dir.path().join("concatenated.dlt");
+        let result = concat(
+            vec![input(Path::new(DLT_SAMPLE), "A"), input(&raw_path, "B")],
+            &out_path,
+            ConcatOutput::Dlt,
+        )
+        .expect("concatenation should succeed");
+        assert_eq!(
+            vec![message_count, message_count],
+            result.entry_cnt_per_file
+        );
+        let written = fs::read(&out_path).unwrap();
+        assert_eq!(written.len(), result.byte_cnt);
+        assert!(written.starts_with(&fs::read(DLT_SAMPLE).unwrap()));
+        let messages: Vec<_> = dlt_messages(fs::File::open(&out_path).unwrap(), true)
+            .map(|(_, msg)| msg)
+            .collect();
+        assert_eq!(2 * message_count, messages.len());
+        assert!(messages.iter().all(|msg| msg.storage_header.is_some()));
+        // the messages without storage header are copied unchanged behind the added header
+        let copied: Vec<u8> = dlt_messages_with_bytes(fs::File::open(&out_path).unwrap(), true)
+            .skip(message_count)
+            .flat_map(|(_, _, bytes)| bytes[STORAGE_HEADER_LENGTH..].to_vec())
+            .collect();
+        assert_eq!(fs::read(&raw_path).unwrap(), copied);
+    }
+
+    #[test]
+    fn test_concat_dlt_and_text_as_text() {
+        let tmp_dir = TempDir::new("concat").expect("could not create temp dir");
+        let text_path = tmp_dir.path().join("a.log");
+        fs::write(&text_path, "first line\nsecond line\n").unwrap();
+        let out_path = tmp_dir.path().join("concatenated.out");
+        let result = concat(
+            vec![
+                input(&text_path, "TEXT"),
+                input(Path::new(DLT_SAMPLE), "DLT"),
+            ],
+            &out_path,
+            ConcatOutput::Text,
+        )
+        .expect("concatenation should succeed");
+        let message_count = dlt_messages(fs::File::open(DLT_SAMPLE).unwrap(), true).count();
+        assert_eq!(vec![2, message_count], result.entry_cnt_per_file);
+        assert_eq!(2 + message_count, result.line_cnt);
+        let content = fs::read_to_string(&out_path).unwrap();
+        let tags: Vec<&str> = content
+            .lines()
+            .map(|line| line.split('\u{0003}').nth(1).unwrap())
+            .collect();
+        assert_eq!(result.line_cnt, tags.len());
+        assert_eq!(vec!["TEXT", "TEXT", "DLT"], tags[..3].to_vec());
+        assert!(content.starts_with("first line\u{0003}TEXT"));
+    }
+
+    #[test]
+    fn test_concat_as_dlt_needs_dlt_inputs() {
+        let tmp_dir = TempDir::new("concat").expect("could not create temp dir");
+        let text_path = tmp_dir.path().join("a.log");
+        fs::write(&text_path, "no dlt\n").unwrap();
+        let result = concat(
+            vec![input(Path::new(DLT_SAMPLE), "A"), input(&text_path, "B")],
+            &tmp_dir.path().join("concatenated.dlt"),
+            ConcatOutput::Dlt,
+        );
+        assert!(result.is_err());
+    }
+}
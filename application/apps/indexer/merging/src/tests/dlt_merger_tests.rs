@@ -0,0 +1,146 @@
+#[cfg(test)]
+mod tests {
+    use crate::dlt_merger::*;
+    use crossbeam_channel as cc;
+    use dlt::dlt_file::{dlt_messages, message_timestamp};
+    use dlt_core::dlt::{
+        Argument, DltTimeStamp, Endianness, ExtendedHeaderConfig, LogLevel, Message, MessageConfig,
+        MessageType, PayloadContent, StorageHeader, StringCoding, TypeInfo, TypeInfoKind, Value,
+    };
+    use indexer_base::chunks::VoidResults;
+    use pretty_assertions::assert_eq;
+    use std::{
+        fs,
+        path::{Path, PathBuf},
+    };
+    use tempdir::TempDir;
+
+    const DLT_SAMPLE: &str = "../dlt/test_samples/timed_messages.dlt";
+
+    fn input(path: &str, offset: Option<i64>, ecu_id: Option<&str>) -> DltMergeInput {
+        DltMergeInput {
+            path: PathBuf::from(path),
+            offset,
+            ecu_id: ecu_id.map(String::from),
+        }
+    }
+
+    fn merge(inputs: Vec<DltMergeInput>, out_path: &Path) -> Vec<usize> {
+        let (tx, _rx): (cc::Sender<VoidResults>, cc::Receiver<VoidResults>) = cc::unbounded();
+        merge_dlt_files(inputs, out_path, tx, None).expect("dlt merge should succeed")
+    }
+
+    fn read_messages(path: &Path) -> Vec<Message> {
+        dlt_messages(fs::File::open(path).unwrap(), true)
+            .map(|(_, message)| message)
+            .collect()
+    }
+
+    #[test]
+    fn test_merge_single_dlt_file_keeps_messages() {
+        let tmp_dir = TempDir::new("dlt_merge").unwrap();
+        let out_path = tmp_dir.path().join("merged.dlt");
+        assert_eq!(
+            vec![3],
+            merge(vec![input(DLT_SAMPLE, None, None)], &out_path)
+        );
+        assert_eq!(fs::read(DLT_SAMPLE).unwrap(), fs::read(&out_path).unwrap());
+    }
+
+    #[test]
+    fn test_merge_dlt_files_with_offset() {
+        let tmp_dir = TempDir::new("dlt_merge").unwrap();
+        let out_path = tmp_dir.path().join("merged.dlt");
+        let written = merge(
+            vec![
+                input(DLT_SAMPLE, None, None),
+                input(DLT_SAMPLE, Some(5000), None),
+            ],
+            &out_path,
+        );
+        assert_eq!(vec![3, 3], written);
+        let start = 1_558_528_570_000i64;
+        let times: Vec<i64> = read_messages(&out_path)
+            .iter()
+            .map(|message| message_timestamp(message, None).unwrap() - start)
+            .collect();
+        assert_eq!(vec![-5000, 0, 5500, 10_500, 35_000, 40_000], times);
+    }
+
+    #[test]
+    fn test_merge_dlt_files_with_offset_only_patches_time() {
+        let tmp_dir = TempDir::new("dlt_merge").unwrap();
+        let out_path = tmp_dir.path().join("merged.dlt");
+        merge(vec![input(DLT_SAMPLE, Some(5000), Some("ECU9"))], &out_path);
+        let original = fs::read(DLT_SAMPLE).unwrap();
+        let merged = fs::read(&out_path).unwrap();
+        assert_eq!(original.len(), merged.len());
+        let changed: Vec<usize> = original
+            .iter()
+            .zip(merged.iter())
+            .enumerate()
+            .filter(|(_, (a, b))| a != b)
+            .map(|(i, _)| i)
+            .collect();
+        let message_starts: Vec<usize> = original
+            .windows(4)
+            .enumerate()
+            .filter(|(_, w)| w == b"DLT\x01")
+            .map(|(i, _)| i)
+            .collect();
+        assert!(changed.iter().all(|i| message_starts
+            .iter()
+            .any(|start| (start + 4..start + 12).contains(i))));
+        assert!(!changed.is_empty());
+    }
+
+    #[test]
+    fn test_merge_dlt_files_fills_missing_ecu_id() {
+        let tmp_dir = TempDir::new("dlt_merge").unwrap();
+        let in_path = tmp_dir.path().join("no_ecu.dlt");
+        let message = Message::new(
+            MessageConfig {
+                version: 1,
+                counter: 0,
+                endianness: Endianness::Big,
+                ecu_id: None,
+                session_id: None,
+                timestamp: None,
+                payload: PayloadContent::Verbose(vec![Argument {
+                    type_info: TypeInfo {
+                        kind: TypeInfoKind::StringType,
+                        coding: StringCoding::UTF8,
+                        has_variable_info: false,
+                        has_trace_info: false,
+                    },
+                    name: None,
+                    unit: None,
+                    fixed_point: None,
+                    value: Value::StringVal("without ecu".to_string()),
+                }]),
+                extended_header_info: Some(ExtendedHeaderConfig {
+                    message_type: MessageType::Log(LogLevel::Info),
+                    app_id: "APP".to_string(),
+                    context_id: "CTX".to_string(),
+                }),
+            },
+            Some(StorageHeader {
+                timestamp: DltTimeStamp::from_ms(1000),
+                ecu_id: String::new(),
+            }),
+        );
+        fs::write(&in_path, message.as_bytes()).unwrap();
+        let out_path = tmp_dir.path().join("merged.dlt");
+        merge(
+            vec![input(&in_path.to_string_lossy(), None, Some("ECU9"))],
+            &out_path,
+        );
+        let merged = read_messages(&out_path);
+        assert_eq!(1, merged.len());
+        assert_eq!(Some("ECU9".to_string()), merged[0].header.ecu_id);
+        assert_eq!(
+            "ECU9",
+            merged[0].storage_header.as_ref().unwrap().ecu_id.as_str()
+        );
+    }
+}
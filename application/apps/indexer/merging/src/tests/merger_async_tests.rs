@@ -0,0 +1,186 @@
+#[cfg(test)]
+mod tests {
+    use crate::{
+        merger::{read_merge_options, FileMergeOptions, IndexOutput},
+        merger_async::*,
+    };
+    use crossbeam_channel as cc;
+    use indexer_base::{chunks::ChunkResults, progress::IndexingProgress, timedline::TimedLine};
+    use pretty_assertions::assert_eq;
+    use std::{fs, path::PathBuf, time::Duration};
+    use tempdir::TempDir;
+    use tokio::sync::mpsc;
+    use tokio_stream::{self as stream, wrappers::ReceiverStream, StreamExt};
+
+    fn line(timestamp: i64, tag: &str) -> TimedLine {
+        TimedLine {
+            timestamp,
+            content: format!("{} {}", tag, timestamp),
+            tag: tag.to_string(),
+            original_length: 1,
+            year_was_missing: false,
+            line_nr: 0,
+        }
+    }
+
+    fn contents(lines: &[(usize, TimedLine)]) -> Vec<String> {
+        lines.iter().map(|(_, l)| l.content.clone()).collect()
+    }
+
+    #[tokio::test]
+    async fn test_merge_streams_in_time_order() {
+        let inputs = vec![
+            stream::iter(vec![line(1, "a"), line(5, "a"), line(9, "a")]),
+            stream::iter(vec![line(2, "b"), line(5, "b")]),
+            stream::iter(vec![]),
+            stream::iter(vec![line(0, "c"), line(10, "c")]),
+        ];
+        let merged: Vec<(usize, TimedLine)> = merge_streams(inputs).collect().await;
+        assert_eq!(
+            vec!["c 0", "a 1", "b 2", "a 5", "b 5", "a 9", "c 10"],
+            contents(&merged)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_merge_waits_for_live_input() {
+        let (tx, rx) = mpsc::channel(1);
+        let inputs: Vec<std::pin::Pin<Box<dyn tokio_stream::Stream<Item = TimedLine> + Send>>> = vec![
+            Box::pin(stream::iter(vec![line(1, "file"), line(3, "file")])),
+            Box::pin(ReceiverStream::new(rx)),
+        ];
+        let mut merged = merge_streams(inputs);
+        // nothing is produced before the live input delivered its first entry
+        assert!(
+            tokio::time::timeout(Duration::from_millis(50), merged.next())
+                .await
+                .is_err()
+        );
+        tx.send(line(2, "live")).await.unwrap();
+        assert_eq!("file 1", merged.next().await.unwrap().1.content);
+        // the live input still has to show it has nothing before 3
+        tx.send(line(4, "live")).await.unwrap();
+        assert_eq!("live 2", merged.next().await.unwrap().1.content);
+        assert_eq!("file 3", merged.next().await.unwrap().1.content);
+        drop(tx);
+        assert_eq!("live 4", merged.next().await.unwrap().1.content);
+        assert!(merged.next().await.is_none());
+    }
+
+    fn stream_input(tag: &str) -> FileMergeOptions {
+        FileMergeOptions {
+            path: format!("{}-source", tag),
+            offset: None,
+            year: None,
+            tag: tag.to_string(),
+            format: String::new(),
+            multiline: false,
+            input_type: Default::default(),
+            ecu_time_reference: None,
+            reorder_window: None,
+            clock_drift: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_merge_pending_stream_with_writer() {
+        let tmp_dir = TempDir::new("test_dir").expect("could not create temp dir");
+        let out_path = tmp_dir.path().join("merged.out");
+        let (tx, _rx): (cc::Sender<ChunkResults>, cc::Receiver<ChunkResults>) = cc::unbounded();
+        let mut writer = IndexOutput::new(false, &out_path, 5, 0, None, tx.clone()).unwrap();
+        assert_eq!(0, writer.add_stream_input(&stream_input("file")));
+        assert_eq!(1, writer.add_stream_input(&stream_input("live")));
+
+        let (live_tx, live_rx) = mpsc::channel(1);
+        let inputs: Vec<std::pin::Pin<Box<dyn tokio_stream::Stream<Item = TimedLine> + Send>>> = vec![
+            Box::pin(stream::iter(vec![line(1, "file"), line(3, "file")])),
+            Box::pin(ReceiverStream::new(live_rx)),
+        ];
+        // the live input is pending until the entries arrive one after the other
+        let live = tokio::spawn(async move {
+            for timestamp in &[2, 4] {
+                tokio::time::sleep(Duration::from_millis(20)).await;
+                live_tx.send(line(*timestamp, "live")).await.unwrap();
+            }
+        });
+        merge_streams_with_writer(&mut writer, inputs, tx, None)
+            .await
+            .unwrap();
+        live.await.unwrap();
+
+        let content = fs::read_to_string(&out_path).unwrap();
+        let positions: Vec<usize> = ["file 1", "live 2", "file 3", "live 4"]
+            .iter()
+            .map(|c| content.find(c).expect("entry missing"))
+            .collect();
+        assert!(positions.windows(2).all(|p| p[0] < p[1]));
+        let reports = writer.input_reports();
+        assert_eq!(
+            vec![("file-source", 2), ("live-source", 2)],
+            reports
+                .iter()
+                .map(|r| (r.path.as_str(), r.entries))
+                .collect::<Vec<(&str, usize)>>()
+        );
+    }
+
+    fn merge_sample(
+        dir_name: &str,
+        shutdown_rx: Option<mpsc::Receiver<()>>,
+    ) -> (String, Vec<IndexingProgress<indexer_base::chunks::Chunk>>) {
+        let tmp_dir = TempDir::new("test_dir").expect("could not create temp dir");
+        let out_file_path = tmp_dir.path().join("merged.out");
+        let config_path = PathBuf::from("test_samples")
+            .join(dir_name)
+            .join("config.json");
+        let options = read_merge_options(
+            &mut fs::File::open(&config_path).unwrap(),
+            config_path.parent(),
+        )
+        .unwrap();
+        let (tx, rx): (cc::Sender<ChunkResults>, cc::Receiver<ChunkResults>) = cc::unbounded();
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(merge_files_use_config_async(
+            options,
+            &out_file_path,
+            false,
+            5,
+            tx,
+            shutdown_rx,
+        ))
+        .expect("async merge should succeed");
+        let progress = rx.try_iter().filter_map(Result::ok).collect();
+        let content = fs::read_to_string(&out_file_path).expect("could not read merged file");
+        (content, progress)
+    }
+
+    #[test]
+    fn test_merge_files_async() {
+        for dir_name in &[
+            "simple_regex_1_regex_2",
+            "dlt_and_text",
+            "compressed_inputs",
+        ] {
+            let (content, progress) = merge_sample(dir_name, None);
+            let expected = fs::read_to_string(
+                PathBuf::from("test_samples")
+                    .join(dir_name)
+                    .join("expected.merged"),
+            )
+            .unwrap();
+            assert_eq!(expected, content);
+            assert!(matches!(progress.last(), Some(IndexingProgress::Finished)));
+        }
+    }
+
+    #[test]
+    fn test_merge_files_async_stopped() {
+        let (shutdown_tx, shutdown_rx) = mpsc::channel(1);
+        shutdown_tx.try_send(()).unwrap();
+        let (content, progress) = merge_sample("simple_regex_1_regex_2", Some(shutdown_rx));
+        assert!(content.is_empty());
+        assert!(progress
+            .iter()
+            .any(|p| matches!(p, IndexingProgress::Stopped)));
+    }
+}
@@ -1,2 +1,9 @@
 #[macro_use]
 mod merger_tests;
+mod alignment_tests;
+mod concatenator_tests;
+mod dlt_merger_tests;
+mod merger_async_tests;
+mod provenance_tests;
+mod reorder_tests;
+mod report_tests;
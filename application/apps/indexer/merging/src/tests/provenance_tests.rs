@@ -0,0 +1,87 @@
+#[cfg(test)]
+mod tests {
+    use crate::{merger::*, provenance::*};
+    use crossbeam_channel as cc;
+    use indexer_base::chunks::ChunkResults;
+    use pretty_assertions::assert_eq;
+    use std::{
+        fs,
+        path::{Path, PathBuf},
+    };
+    use tempdir::TempDir;
+
+    fn merge_sample(dir_name: &str, out_path: &Path, append: bool) -> ProvenanceMap {
+        let config_path = PathBuf::from("test_samples")
+            .join(dir_name)
+            .join("config.json");
+        let (tx, _rx): (cc::Sender<ChunkResults>, cc::Receiver<ChunkResults>) = cc::unbounded();
+        merge_files_use_config_file(&config_path, out_path, append, 5, tx, None)
+            .expect("merge should succeed");
+        ProvenanceMap::load(&ProvenanceMap::path_for(out_path)).expect("no provenance map")
+    }
+
+    fn merged_lines(out_path: &Path) -> Vec<String> {
+        fs::read_to_string(out_path)
+            .unwrap()
+            .lines()
+            .map(|l| l.split('\u{0003}').next().unwrap().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_origin_points_to_input_entry() {
+        let tmp_dir = TempDir::new("test_dir").expect("could not create temp dir");
+        let out_path = tmp_dir.path().join("merged.out");
+        let map = merge_sample("simple_regex_1_regex_2", &out_path, false);
+        let merged = merged_lines(&out_path);
+        assert_eq!(merged.len(), map.len());
+        assert_eq!(2, map.inputs().len());
+        for (output_line, content) in merged.iter().enumerate() {
+            let origin = map.origin(output_line).expect("every line has an origin");
+            let input = fs::read_to_string(&map.inputs()[origin.input]).unwrap();
+            assert_eq!(content, input.lines().nth(origin.line).unwrap());
+            assert!(input[origin.offset as usize..].starts_with(content.as_str()));
+            assert_eq!(
+                Some(output_line),
+                map.output_line(origin.input, origin.line)
+            );
+        }
+        assert_eq!(None, map.origin(merged.len()));
+    }
+
+    #[test]
+    fn test_output_lines_in_input_order() {
+        let tmp_dir = TempDir::new("test_dir").expect("could not create temp dir");
+        let out_path = tmp_dir.path().join("merged.out");
+        let map = merge_sample("reordered_entries", &out_path, false);
+        let merged = merged_lines(&out_path);
+        let a_lines: Vec<&str> = map
+            .output_lines_in_input_order(0)
+            .into_iter()
+            .map(|line| merged[line].as_str())
+            .collect();
+        assert_eq!(
+            fs::read_to_string("test_samples/reordered_entries/a.log")
+                .unwrap()
+                .lines()
+                .collect::<Vec<&str>>(),
+            a_lines
+        );
+        // A2 was logged after A1 but got merged before it
+        assert!(map.output_line(0, 2).unwrap() < map.output_line(0, 1).unwrap());
+    }
+
+    #[test]
+    fn test_provenance_is_extended_when_appending() {
+        let tmp_dir = TempDir::new("test_dir").expect("could not create temp dir");
+        let out_path = tmp_dir.path().join("merged.out");
+        let first = merge_sample("reordered_entries", &out_path, false);
+        let map = merge_sample("simple_regex_1_regex_2", &out_path, true);
+        assert_eq!(4, map.inputs().len());
+        assert_eq!(merged_lines(&out_path).len(), map.len());
+        for line in 0..first.len() {
+            assert_eq!(first.origin(line), map.origin(line));
+        }
+        assert_eq!(2, map.origin(first.len()).unwrap().input);
+    }
+}
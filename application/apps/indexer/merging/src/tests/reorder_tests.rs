@@ -0,0 +1,85 @@
+#[cfg(test)]
+mod tests {
+    use crate::{reorder::*, report::EntryCount};
+    use indexer_base::timedline::TimedLine;
+    use pretty_assertions::assert_eq;
+
+    fn lines(timestamps: &[i64]) -> Vec<TimedLine> {
+        timestamps
+            .iter()
+            .enumerate()
+            .map(|(i, &timestamp)| TimedLine {
+                timestamp,
+                content: format!("{}-{}", timestamp, i),
+                tag: "TAG".to_string(),
+                original_length: 1,
+                year_was_missing: false,
+                line_nr: i,
+            })
+            .collect()
+    }
+
+    fn reorder(timestamps: &[i64], window: ReorderWindow) -> (Vec<i64>, usize) {
+        let count = EntryCount::default();
+        let sorted = Reordered::new(lines(timestamps).into_iter(), window, count.clone())
+            .map(|l| l.timestamp)
+            .collect();
+        (sorted, count.get())
+    }
+
+    #[test]
+    fn test_reorder_within_entries() {
+        assert_eq!(
+            (vec![1, 2, 3, 4, 5, 6], 2),
+            reorder(&[2, 1, 3, 5, 4, 6], ReorderWindow::Entries(1))
+        );
+        // the entry is too far away to be moved in front of 2
+        assert_eq!(
+            (vec![2, 3, 1, 4], 1),
+            reorder(&[2, 3, 4, 1], ReorderWindow::Entries(1))
+        );
+        assert_eq!(
+            (vec![1, 2, 3, 4], 1),
+            reorder(&[2, 3, 4, 1], ReorderWindow::Entries(3))
+        );
+        assert_eq!((vec![], 0), reorder(&[], ReorderWindow::Entries(3)));
+    }
+
+    #[test]
+    fn test_reorder_within_millis() {
+        assert_eq!(
+            (vec![100, 105, 110, 200, 300], 1),
+            reorder(&[100, 110, 105, 200, 300], ReorderWindow::Millis(10))
+        );
+        // 100 is more than 10 ms behind 150
+        assert_eq!(
+            (vec![110, 100, 150], 1),
+            reorder(&[110, 150, 100], ReorderWindow::Millis(10))
+        );
+    }
+
+    #[test]
+    fn test_reorder_keeps_order_of_equal_timestamps() {
+        let count = EntryCount::default();
+        let contents: Vec<String> = Reordered::new(
+            lines(&[5, 5, 3, 5]).into_iter(),
+            ReorderWindow::Entries(4),
+            count,
+        )
+        .map(|l| l.content)
+        .collect();
+        assert_eq!(vec!["3-2", "5-0", "5-1", "5-3"], contents);
+    }
+
+    #[test]
+    fn test_parse_reorder_window() {
+        assert_eq!(
+            ReorderWindow::Entries(10),
+            serde_json::from_str(r#"{"entries": 10}"#).unwrap()
+        );
+        assert_eq!(
+            ReorderWindow::Millis(250),
+            serde_json::from_str(r#"{"millis": 250}"#).unwrap()
+        );
+    }
+}
@@ -0,0 +1,159 @@
+#[cfg(test)]
+mod tests {
+    use crate::{merger::*, report::*};
+    use crossbeam_channel as cc;
+    use indexer_base::{
+        chunks::ChunkResults,
+        progress::{Notification, Severity},
+    };
+    use pretty_assertions::assert_eq;
+    use std::{fs, path::Path};
+    use tempdir::TempDir;
+
+    fn input(path: &Path, tag: &str, format: &str) -> FileMergeOptions {
+        serde_json::from_value(serde_json::json!({
+            "path": path.to_string_lossy(),
+            "offset": 0,
+            "tag": tag,
+            "format": format,
+        }))
+        .unwrap()
+    }
+
+    fn merge(inputs: Vec<FileMergeOptions>, out_path: &Path) -> (Vec<InputReport>, Vec<String>) {
+        let (tx, rx): (cc::Sender<ChunkResults>, cc::Receiver<ChunkResults>) = cc::unbounded();
+        let reports = merge_files_use_config(inputs, out_path, false, 5, tx, None)
+            .expect("merge should succeed");
+        let errors = rx
+            .try_iter()
+            .filter_map(|item| match item {
+                Err(Notification {
+                    severity: Severity::ERROR,
+                    content,
+                    ..
+                }) => Some(content),
+                _ => None,
+            })
+            .collect();
+        (reports, errors)
+    }
+
+    #[test]
+    fn test_report_counts_entries() {
+        let tmp_dir = TempDir::new("report").expect("could not create temp dir");
+        let samples = Path::new("test_samples/missing_timestamp_lines");
+        let (reports, errors) = merge(
+            vec![
+                input(&samples.join("a.log"), "A", "MM-DD-YYYY hh:mm:ss.s"),
+                input(&samples.join("b.log"), "B", "MM-DD-YYYY hh:mm:ss.s"),
+            ],
+            &tmp_dir.path().join("merged.out"),
+        );
+        assert!(errors.is_empty());
+        let a_lines = fs::read_to_string(samples.join("a.log")).unwrap();
+        assert_eq!(2, reports.len());
+        assert_eq!(None, reports[0].error);
+        assert_eq!(a_lines.lines().count(), reports[0].entries);
+        assert_eq!(1, reports[0].without_timestamp);
+        assert_eq!(0, reports[1].without_timestamp);
+        assert!(reports[0].min_time.unwrap() < reports[0].max_time.unwrap());
+        assert_eq!(Some("MM-DD-YYYY hh:mm:ss.s".to_string()), reports[1].format);
+    }
+
+    #[test]
+    fn test_report_reordered_entries() {
+        let tmp_dir = TempDir::new("report").expect("could not create temp dir");
+        let (tx, _rx): (cc::Sender<ChunkResults>, cc::Receiver<ChunkResults>) = cc::unbounded();
+        let reports = merge_files_use_config_file(
+            Path::new("test_samples/reordered_entries/config.json"),
+            &tmp_dir.path().join("merged.out"),
+            false,
+            5,
+            tx,
+            None,
+        )
+        .unwrap();
+        assert_eq!(
+            vec![3, 0],
+            reports.iter().map(|r| r.reordered).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec![3, 0],
+            reports.iter().map(|r| r.out_of_order).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec![7, 3],
+            reports.iter().map(|r| r.entries).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_report_out_of_order_without_reorder_window() {
+        let tmp_dir = TempDir::new("report").expect("could not create temp dir");
+        let (reports, _) = merge(
+            vec![input(
+                Path::new("test_samples/reordered_entries/a.log"),
+                "A",
+                "MM-DD-YYYY hh:mm:ss.s",
+            )],
+            &tmp_dir.path().join("merged.out"),
+        );
+        assert_eq!(3, reports[0].out_of_order);
+        assert_eq!(0, reports[0].reordered);
+    }
+
+    #[test]
+    fn test_detect_missing_format() {
+        let tmp_dir = TempDir::new("report").expect("could not create temp dir");
+        let out_path = tmp_dir.path().join("merged.out");
+        let (reports, errors) = merge(
+            vec![input(
+                Path::new("test_samples/simple_regex_1_regex_2/a.log"),
+                "A",
+                "",
+            )],
+            &out_path,
+        );
+        assert!(errors.is_empty());
+        assert_eq!(Some("MM-DD-YYYY hh:mm:ss.s".to_string()), reports[0].format);
+        assert!(reports[0].entries > 0);
+        assert_eq!(
+            reports[0].entries,
+            fs::read_to_string(&out_path).unwrap().lines().count()
+        );
+    }
+
+    #[test]
+    fn test_failing_inputs_are_reported() {
+        let tmp_dir = TempDir::new("report").expect("could not create temp dir");
+        let out_path = tmp_dir.path().join("merged.out");
+        let not_a_log = tmp_dir.path().join("not_a_log.txt");
+        fs::write(&not_a_log, "no timestamps\nin this file\n").unwrap();
+        let valid = Path::new("test_samples/simple_regex_1_regex_2/a.log");
+        let (reports, errors) = merge(
+            vec![
+                input(
+                    &tmp_dir.path().join("missing.log"),
+                    "MISSING",
+                    "MM-DD-YYYY hh:mm:ss.s",
+                ),
+                input(valid, "A", "MM-DD-YYYY hh:mm:ss.s"),
+                input(&not_a_log, "UNDETECTED", ""),
+                input(valid, "INVALID", "not a format"),
+            ],
+            &out_path,
+        );
+        assert_eq!(
+            vec!["MISSING", "A", "UNDETECTED", "INVALID"],
+            reports.iter().map(|r| r.tag.as_str()).collect::<Vec<_>>()
+        );
+        let failed: Vec<bool> = reports.iter().map(|r| r.error.is_some()).collect();
+        assert_eq!(vec![true, false, true, true], failed);
+        assert_eq!(3, errors.len());
+        assert!(errors[0].contains("missing.log"));
+        assert_eq!(
+            fs::read_to_string(valid).unwrap().lines().count(),
+            fs::read_to_string(&out_path).unwrap().lines().count()
+        );
+    }
+}
@@ -3,16 +3,20 @@ use crate::grabber::{
     LineRange, MetadataSource, Slot,
 };
 use buf_redux::{policy::MinBuffered, BufReader as ReduxReader};
-use dlt::dlt_file::FileMessageProducer;
+use dlt::dlt_file::{FileMessageProducer, MessageSlot};
 use dlt_core::{
     dlt::Message,
     fmt::FormattableMessage,
     parse::{dlt_consume_msg, ParsedMessage},
 };
-use indexer_base::{progress::ComputationResult, utils};
+use indexer_base::{
+    compression::{self, InputFile},
+    progress::ComputationResult,
+    utils,
+};
 use std::{
     fs,
-    io::{BufRead, Cursor, SeekFrom},
+    io::{BufRead, Cursor},
     path::{Path, PathBuf},
 };
 
@@ -60,7 +64,7 @@ impl MetadataSource for DltSource {
         }
         let mut slots = Vec::<Slot>::new();
 
-        let f = fs::File::open(&self.path)?;
+        let f = InputFile::open(&self.path)?;
 
         let mut reader = ReduxReader::with_capacity(REDUX_READER_CAPACITY, f)
             .set_policy(MinBuffered(REDUX_MIN_BUFFER_SPACE));
@@ -134,6 +138,7 @@ impl MetadataSource for DltSource {
         Ok(ComputationResult::Item(GrabMetadata {
             slots,
             line_count: msg_cnt as usize,
+            checkpoints: reader.get_ref().checkpoints(),
         }))
     }
 
@@ -148,7 +153,6 @@ impl MetadataSource for DltSource {
                 context: "range was empty".to_string(),
             });
         }
-        use std::io::prelude::*;
 
         let file_part = identify_byte_range(&metadata.slots, line_range).ok_or_else(|| {
             GrabError::InvalidRange {
@@ -158,11 +162,12 @@ impl MetadataSource for DltSource {
         })?;
         trace!("get_entries, file-part: {:?}", file_part);
 
-        let mut read_buf = vec![0; file_part.length];
-        let mut read_from = fs::File::open(&self.path())?;
-
-        read_from.seek(SeekFrom::Start(file_part.offset_in_file))?;
-        read_from.read_exact(&mut read_buf)?;
+        let read_buf = compression::read_at(
+            self.path(),
+            &metadata.checkpoints,
+            file_part.offset_in_file,
+            file_part.length,
+        )?;
 
         let message_stream = FileMessageProducer::new(Cursor::new(read_buf), None, true, None);
 
@@ -202,3 +207,26 @@ impl MetadataSource for DltSource {
         Ok(GrabbedContent { grabbed_elements })
     }
 }
+
+/// The slots of dlt metadata as message positions, used to export sections of
+/// the file without scanning it from the beginning.
+///
+/// The line range of a slot ends with the index of the first message of the next slot.
+/// For compressed files the slots are decompressed offsets that can't be used to seek
+/// in the file itself, exporting sections of compressed files is not supported.
+pub fn message_slots(metadata: &GrabMetadata) -> Result<Vec<MessageSlot>, GrabError> {
+    if !metadata.checkpoints.is_empty() {
+        return Err(GrabError::Config(
+            "message slots of compressed files can't be used for export".to_string(),
+        ));
+    }
+    Ok(metadata
+        .slots
+        .iter()
+        .map(|slot| MessageSlot {
+            first_message: slot.lines.start(),
+            message_count: slot.lines.end() - slot.lines.start(),
+            offset: slot.bytes.start(),
+        })
+        .collect())
+}
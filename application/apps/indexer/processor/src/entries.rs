@@ -0,0 +1,108 @@
+// Copyright (c) 2019 E.S.R.Labs. All rights reserved.
+//
+// NOTICE:  All information contained herein is, and remains
+// the property of E.S.R.Labs and its suppliers, if any.
+// The intellectual and technical concepts contained herein are
+// proprietary to E.S.R.Labs and its suppliers and may be covered
+// by German and Foreign Patents, patents in process, and are protected
+// by trade secret or copyright law.
+// Dissemination of this information or reproduction of this material
+// is strictly forbidden unless prior written permission is obtained
+// from E.S.R.Labs.
+use indexer_base::utils;
+use regex::Regex;
+use std::io::BufRead;
+
+/// A line starts a new log entry if the timestamp format matches and nothing but
+/// punctuation or whitespace precedes the match (e.g. `[2019-05-02 ...]`).
+/// Lines like `\tat com.example.Foo` or `"time": "2019-05-02..."` in a pretty printed
+/// json blob are continuation lines of the previous entry.
+pub fn line_starts_entry(line: &str, entry_start: &Regex) -> bool {
+    match entry_start.find(line) {
+        Some(m) => !line[..m.start()].chars().any(char::is_alphanumeric),
+        None => false,
+    }
+}
+
+/// One log entry, possibly spanning multiple physical lines
+#[derive(Debug, PartialEq)]
+pub struct Entry {
+    /// the content of all lines of the entry, without trailing newlines
+    pub lines: Vec<String>,
+    /// number of bytes consumed from the input for this entry
+    pub consumed: usize,
+    /// did the last line of the entry end with a newline
+    pub had_newline: bool,
+}
+
+impl Entry {
+    /// join all lines of an entry into one row so it fits into the tagged output format
+    pub fn joined(&self) -> String {
+        self.lines.join(utils::ENTRY_NEWLINE_SENTINAL_STR)
+    }
+}
+
+/// Iterates over the log entries of a reader.
+///
+/// Without an `entry_start` regex each line is an entry on its own. If a regex is
+/// supplied, lines that do not start with a match are attached to the previous entry.
+pub struct EntryReader<R: BufRead> {
+    reader: R,
+    entry_start: Option<Regex>,
+    /// a line that was read ahead and starts the next entry
+    pending: Option<(String, usize, bool)>,
+}
+
+impl<R: BufRead> EntryReader<R> {
+    pub fn new(reader: R, entry_start: Option<Regex>) -> Self {
+        Self {
+            reader,
+            entry_start,
+            pending: None,
+        }
+    }
+}
+
+fn read_line<R: BufRead>(reader: &mut R) -> Option<(String, usize, bool)> {
+    let mut buf = vec![];
+    match reader.read_until(b'\n', &mut buf) {
+        Ok(0) => None,
+        Ok(len) => {
+            let s = unsafe { std::str::from_utf8_unchecked(&buf) };
+            let trimmed_line = s.trim_matches(utils::is_newline);
+            let had_newline = trimmed_line.len() != len;
+            Some((trimmed_line.to_string(), len, had_newline))
+        }
+        Err(e) => {
+            warn!("error reading entry: {}", e);
+            None
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for EntryReader<R> {
+    type Item = Entry;
+    fn next(&mut self) -> Option<Entry> {
+        let (first, mut consumed, mut had_newline) = match self.pending.take() {
+            Some(line) => line,
+            None => read_line(&mut self.reader)?,
+        };
+        let mut lines = vec![first];
+        if let Some(entry_start) = &self.entry_start {
+            while let Some((line, len, newline)) = read_line(&mut self.reader) {
+                if line_starts_entry(&line, entry_start) {
+                    self.pending = Some((line, len, newline));
+                    break;
+                }
+                lines.push(line);
+                consumed += len;
+                had_newline = newline;
+            }
+        }
+        Some(Entry {
+            lines,
+            consumed,
+            had_newline,
+        })
+    }
+}
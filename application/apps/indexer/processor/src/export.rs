@@ -0,0 +1,424 @@
+// Copyright (c) 2019 E.S.R.Labs. All rights reserved.
+//
+// NOTICE:  All information contained herein is, and remains
+// the property of E.S.R.Labs and its suppliers, if any.
+// The intellectual and technical concepts contained herein are
+// proprietary to E.S.R.Labs and its suppliers and may be covered
+// by German and Foreign Patents, patents in process, and are protected
+// by trade secret or copyright law.
+// Dissemination of this information or reproduction of this material
+// is strictly forbidden unless prior written permission is obtained
+// from E.S.R.Labs.
+use crate::map::FilterMatch;
+use crossbeam_channel as cc;
+use dlt::dlt_file::{detect_dlt_file, dlt_messages_with_bytes, export_as_dlt_file};
+use dlt_core::{dlt::Message, fibex::FibexMetadata, fmt::FormattableMessage};
+use indexer_base::{
+    chunks::ChunkResults,
+    config::{IndexSection, SectionConfig},
+    container::{self, ContainerReader},
+    progress::{IndexingProgress, Notification, Severity},
+    utils::restore_line,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    io::{self, BufRead, BufReader, BufWriter, Write},
+    path::Path,
+    str::FromStr,
+};
+use thiserror::Error;
+
+/// separators that dlt-core uses when formatting a message
+const DLT_COLUMN_SENTINAL: char = '\u{0004}';
+const DLT_ARGUMENT_SENTINAL: char = '\u{0005}';
+const DLT_NEWLINE_SENTINAL: char = '\u{0006}';
+
+const DLT_CSV_HEADER: &str = "row,datetime,ecu,session,counter,timestamp,apid,ctid,type,payload";
+const TEXT_CSV_HEADER: &str = "row,content";
+
+#[derive(Error, Debug)]
+pub enum ExportError {
+    #[error("IO error: {0:?}")]
+    Io(#[from] io::Error),
+    #[error("Invalid search result: {0}")]
+    SearchResult(String),
+    #[error("Unknown export format: {0}")]
+    UnknownFormat(String),
+    #[error("Export failed: {0}")]
+    Export(String),
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    /// the original bytes of the dlt messages
+    Dlt,
+    Text,
+    Csv,
+    /// one json object per line
+    Json,
+}
+
+impl FromStr for ExportFormat {
+    type Err = ExportError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "dlt" => Ok(ExportFormat::Dlt),
+            "text" | "txt" => Ok(ExportFormat::Text),
+            "csv" => Ok(ExportFormat::Csv),
+            "json" | "jsonl" => Ok(ExportFormat::Json),
+            _ => Err(ExportError::UnknownFormat(s.to_string())),
+        }
+    }
+}
+
+/// the rows of the matches of a search
+pub fn matched_rows(matches: &[FilterMatch]) -> Vec<u64> {
+    matches.iter().map(|m| m.index).collect()
+}
+
+/// read the rows from the result file of a `SearchHolder` (one row number per line)
+pub fn read_search_result(path: &Path) -> Result<Vec<u64>, ExportError> {
+    BufReader::new(fs::File::open(path)?)
+        .lines()
+        .filter(|line| !matches!(line, Ok(l) if l.trim().is_empty()))
+        .map(|line| {
+            let line = line?;
+            line.trim().parse().map_err(|e| {
+                ExportError::SearchResult(format!("{:?} is no row number ({})", line, e))
+            })
+        })
+        .collect()
+}
+
+/// Sections that contain the rows together with `context` rows before and after
+/// each of them. Overlapping and adjacent sections are joined.
+pub fn sections_with_context(rows: &[u64], context: usize) -> SectionConfig {
+    let mut rows: Vec<usize> = rows.iter().map(|r| *r as usize).collect();
+    rows.sort_unstable();
+    rows.dedup();
+    let mut sections: Vec<IndexSection> = vec![];
+    for row in rows {
+        let first_line = row.saturating_sub(context);
+        let last_line = row + context;
+        match sections.last_mut() {
+            Some(last) if first_line <= last.last_line + 1 => {
+                last.last_line = std::cmp::max(last.last_line, last_line)
+            }
+            _ => sections.push(IndexSection {
+                first_line,
+                last_line,
+            }),
+        }
+    }
+    SectionConfig { sections }
+}
+
+/// Export the rows of a search result (plus `context` rows around each match).
+///
+/// `source` is either a dlt file (rows are messages) or a line based file like
+/// the session file or an indexed container. Raw dlt can only be exported from
+/// dlt files, csv and json have separate columns for the parsed dlt messages.
+/// Returns the number of exported rows (for raw dlt with storage headers the number
+/// of requested rows since the messages are copied without parsing them).
+pub fn export_rows(
+    source: &Path,
+    destination: &Path,
+    rows: &[u64],
+    context: usize,
+    format: ExportFormat,
+    fibex_metadata: Option<&FibexMetadata>,
+    update_channel: cc::Sender<ChunkResults>,
+) -> Result<usize, ExportError> {
+    trace!(
+        "export {} rows of {:?} to {:?} as {:?}",
+        rows.len(),
+        source,
+        destination,
+        format
+    );
+    let sections = sections_with_context(rows, context);
+    let res = if sections.sections.is_empty() {
+        // empty sections would export the whole file
+        fs::File::create(destination)?;
+        let _ = update_channel.send(Ok(IndexingProgress::Finished));
+        Ok(0)
+    } else {
+        match detect_dlt_file(source).map_err(|e| ExportError::Export(e.to_string()))? {
+            Some(with_storage_header) => export_dlt_rows(
+                source,
+                destination,
+                sections,
+                with_storage_header,
+                format,
+                fibex_metadata,
+                update_channel.clone(),
+            ),
+            None => export_text_rows(
+                source,
+                destination,
+                sections,
+                format,
+                update_channel.clone(),
+            ),
+        }
+    };
+    if let Err(e) = &res {
+        let _ = update_channel.send(Err(Notification {
+            severity: Severity::ERROR,
+            content: format!("export of search result failed: {}", e),
+            line: None,
+        }));
+    }
+    res
+}
+
+fn export_dlt_rows(
+    source: &Path,
+    destination: &Path,
+    sections: SectionConfig,
+    with_storage_header: bool,
+    format: ExportFormat,
+    fibex_metadata: Option<&FibexMetadata>,
+    update_channel: cc::Sender<ChunkResults>,
+) -> Result<usize, ExportError> {
+    if format == ExportFormat::Dlt && with_storage_header {
+        let rows = sections
+            .sections
+            .iter()
+            .map(|s| s.last_line - s.first_line + 1)
+            .sum();
+        export_as_dlt_file(
+            source.to_path_buf(),
+            destination.to_path_buf(),
+            sections,
+            update_channel,
+        )
+        .map_err(|e| ExportError::Export(e.to_string()))?;
+        return Ok(rows);
+    }
+    let mut out = BufWriter::new(fs::File::create(destination)?);
+    if format == ExportFormat::Csv {
+        writeln!(out, "{}", DLT_CSV_HEADER)?;
+    }
+    let mut selected = SelectedRows::new(sections);
+    let mut exported = 0usize;
+    for (row, (_, msg, bytes)) in
+        dlt_messages_with_bytes(fs::File::open(source)?, with_storage_header).enumerate()
+    {
+        match selected.contains(row) {
+            Some(true) => (),
+            Some(false) => continue,
+            None => break,
+        }
+        match format {
+            ExportFormat::Dlt => out.write_all(&bytes)?,
+            _ => {
+                let columns = DltColumns::new(row, msg, fibex_metadata);
+                match format {
+                    ExportFormat::Text => writeln!(out, "{}", columns.fields().join(" "))?,
+                    ExportFormat::Csv => writeln!(out, "{}", csv_line(&columns.fields()))?,
+                    _ => writeln!(
+                        out,
+                        "{}",
+                        serde_json::to_string(&columns)
+                            .map_err(|e| ExportError::Export(e.to_string()))?
+                    )?,
+                }
+            }
+        }
+        exported += 1;
+    }
+    out.flush()?;
+    let _ = update_channel.send(Ok(IndexingProgress::Finished));
+    Ok(exported)
+}
+
+fn export_text_rows(
+    source: &Path,
+    destination: &Path,
+    sections: SectionConfig,
+    format: ExportFormat,
+    update_channel: cc::Sender<ChunkResults>,
+) -> Result<usize, ExportError> {
+    if format == ExportFormat::Dlt {
+        return Err(ExportError::Export(format!(
+            "{:?} is not a dlt file",
+            source
+        )));
+    }
+    let mut out = BufWriter::new(fs::File::create(destination)?);
+    if format == ExportFormat::Csv {
+        writeln!(out, "{}", TEXT_CSV_HEADER)?;
+    }
+    let mut selected = SelectedRows::new(sections);
+    let mut exported = 0usize;
+    for (row, content) in text_rows(source)?.enumerate() {
+        match selected.contains(row) {
+            Some(true) => (),
+            Some(false) => continue,
+            None => break,
+        }
+        let content = content?;
+        match format {
+            ExportFormat::Csv => writeln!(out, "{}", csv_line(&[row.to_string(), content]))?,
+            ExportFormat::Json => writeln!(
+                out,
+                "{}",
+                serde_json::to_string(&TextRow { row, content })
+                    .map_err(|e| ExportError::Export(e.to_string()))?
+            )?,
+            _ => writeln!(out, "{}", content)?,
+        }
+        exported += 1;
+    }
+    out.flush()?;
+    let _ = update_channel.send(Ok(IndexingProgress::Finished));
+    Ok(exported)
+}
+
+/// the content of each row of a line based file (without tag and row number)
+fn text_rows(source: &Path) -> Result<Box<dyn Iterator<Item = io::Result<String>>>, ExportError> {
+    if container::is_container(source) {
+        Ok(Box::new(ContainerReader::open(source)?.map(|record| {
+            record.map(|r| r.content_str().into_owned())
+        })))
+    } else {
+        Ok(Box::new(
+            BufReader::new(fs::File::open(source)?)
+                .lines()
+                .map(|line| line.map(|l| restore_line(&l).into_owned())),
+        ))
+    }
+}
+
+/// walks through sorted sections for increasing rows
+struct SelectedRows {
+    sections: std::vec::IntoIter<IndexSection>,
+    current: Option<IndexSection>,
+}
+
+impl SelectedRows {
+    fn new(sections: SectionConfig) -> Self {
+        let mut sections = sections.sections.into_iter();
+        let current = sections.next();
+        SelectedRows { sections, current }
+    }
+
+    /// `None` if there are no more sections after `row`
+    fn contains(&mut self, row: usize) -> Option<bool> {
+        loop {
+            let section = self.current.as_ref()?;
+            if row < section.first_line {
+                return Some(false);
+            }
+            if row <= section.last_line {
+                return Some(true);
+            }
+            self.current = self.sections.next();
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct TextRow {
+    row: usize,
+    content: String,
+}
+
+/// columns of a dlt message, app id, context id, type and payload are taken
+/// from the formatted message so non-verbose messages are resolved with the fibex
+#[derive(Serialize, Debug)]
+struct DltColumns {
+    row: usize,
+    datetime: String,
+    ecu: String,
+    session: Option<u32>,
+    counter: u8,
+    timestamp: Option<u32>,
+    apid: String,
+    ctid: String,
+    #[serde(rename = "type")]
+    message_type: String,
+    payload: String,
+}
+
+impl DltColumns {
+    fn new(row: usize, message: Message, fibex_metadata: Option<&FibexMetadata>) -> Self {
+        let datetime = message
+            .storage_header
+            .as_ref()
+            .map(|h| h.timestamp.utc_string())
+            .unwrap_or_default();
+        let ecu = message
+            .storage_header
+            .as_ref()
+            .map(|h| h.ecu_id.clone())
+            .or_else(|| message.header.ecu_id.clone())
+            .unwrap_or_default();
+        let session = message.header.session_id;
+        let counter = message.header.message_counter;
+        let timestamp = message.header.timestamp;
+        let formatted = FormattableMessage {
+            message,
+            fibex_metadata,
+            options: None,
+        }
+        .to_string();
+        let mut columns = formatted.rsplitn(5, DLT_COLUMN_SENTINAL);
+        let mut next = || columns.next().unwrap_or_default().to_string();
+        let payload = next();
+        let message_type = next();
+        let ctid = next();
+        let apid = next();
+        DltColumns {
+            row,
+            datetime,
+            ecu,
+            session,
+            counter,
+            timestamp,
+            apid,
+            ctid,
+            message_type,
+            payload: payload
+                .trim_start_matches(DLT_ARGUMENT_SENTINAL)
+                .replace(DLT_ARGUMENT_SENTINAL, " ")
+                .replace(DLT_NEWLINE_SENTINAL, "\n")
+                .trim()
+                .to_string(),
+        }
+    }
+
+    fn fields(&self) -> Vec<String> {
+        vec![
+            self.row.to_string(),
+            self.datetime.clone(),
+            self.ecu.clone(),
+            self.session.map(|s| s.to_string()).unwrap_or_default(),
+            self.counter.to_string(),
+            self.timestamp.map(|t| t.to_string()).unwrap_or_default(),
+            self.apid.clone(),
+            self.ctid.clone(),
+            self.message_type.clone(),
+            self.payload.clone(),
+        ]
+    }
+}
+
+/// fields are quoted if they contain a separator, a quote or a line break
+fn csv_line(fields: &[String]) -> String {
+    fields
+        .iter()
+        .map(|field| {
+            if field.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", field.replace('"', "\"\""))
+            } else {
+                field.clone()
+            }
+        })
+        .collect::<Vec<String>>()
+        .join(",")
+}
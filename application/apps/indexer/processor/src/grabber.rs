@@ -1,4 +1,4 @@
-use indexer_base::progress::ComputationResult;
+use indexer_base::{compression::Checkpoint, progress::ComputationResult};
 use serde::{Deserialize, Serialize};
 use std::{
     fmt, fs,
@@ -118,6 +118,10 @@ pub struct GrabMetadata {
     ///         (1000,1999) -> 199 (199 newlines in [1000,1999])
     pub slots: Vec<Slot>,
     pub line_count: usize,
+    /// for compressed files: where decoding can be started to get to the
+    /// (decompressed) byte offsets of the slots without decoding everything before
+    #[serde(default)]
+    pub checkpoints: Vec<Checkpoint>,
 }
 
 /// A trait that defines how indexed content can be retrieved from a certain file type.
@@ -131,6 +135,7 @@ pub trait MetadataSource {
     fn source_id(&self) -> String;
 
     /// This will initialize the cached metadata from a file
+    #[allow(clippy::wrong_self_convention)]
     fn from_file(
         &self,
         shutdown_receiver: Option<cc::Receiver<()>>,
@@ -150,7 +155,7 @@ pub trait MetadataSource {
 
     /// the size of the input content
     fn input_size(&self) -> Result<u64, GrabError> {
-        let input_file_size = std::fs::metadata(&self.path())
+        let input_file_size = std::fs::metadata(self.path())
             .map_err(|e| {
                 GrabError::Config(format!("Could not determine size of input file: {}", e))
             })?
@@ -284,14 +289,7 @@ impl<T: MetadataSource> Grabber<T> {
     /// if the metadata for a path already exists, it can be read
     /// from a file
     pub fn load_metadata(mut self, slots_path: impl AsRef<Path>) -> Result<Self, GrabError> {
-        let mut slots_file = fs::File::open(&slots_path)
-            .map_err(|e| GrabError::Config(format!("Could not open slot file: {}", e)))?;
-        let mut buffer = vec![];
-        let _bytes_read = slots_file.read_to_end(&mut buffer);
-        self.metadata = Some(
-            bincode::deserialize(&buffer)
-                .map_err(|_| GrabError::Config("Could not deserialize metadata".to_string()))?,
-        );
+        self.metadata = Some(read_metadata(slots_path)?);
         Ok(self)
     }
 
@@ -338,6 +336,16 @@ impl<T: MetadataSource> Grabber<T> {
     }
 }
 
+/// read metadata that was written with `Grabber::export_slots`
+pub fn read_metadata(slots_path: impl AsRef<Path>) -> Result<GrabMetadata, GrabError> {
+    let mut slots_file = fs::File::open(&slots_path)
+        .map_err(|e| GrabError::Config(format!("Could not open slot file: {}", e)))?;
+    let mut buffer = vec![];
+    let _bytes_read = slots_file.read_to_end(&mut buffer);
+    bincode::deserialize(&buffer)
+        .map_err(|_| GrabError::Config("Could not deserialize metadata".to_string()))
+}
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct FilePart {
     pub offset_in_file: u64,
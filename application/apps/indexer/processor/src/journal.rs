@@ -0,0 +1,268 @@
+// Copyright (c) 2019 E.S.R.Labs. All rights reserved.
+//
+// NOTICE:  All information contained herein is, and remains
+// the property of E.S.R.Labs and its suppliers, if any.
+// The intellectual and technical concepts contained herein are
+// proprietary to E.S.R.Labs and its suppliers and may be covered
+// by German and Foreign Patents, patents in process, and are protected
+// by trade secret or copyright law.
+// Dissemination of this information or reproduction of this material
+// is strictly forbidden unless prior written permission is obtained
+// from E.S.R.Labs.
+use crate::{
+    processor::{index_timed_rows, TimedRow},
+    structured::{StructuredRecord, SYSLOG_SEVERITIES},
+};
+use anyhow::Result;
+use crossbeam_channel as cc;
+use indexer_base::{
+    chunks::ChunkResults,
+    compression::InputFile,
+    config::IndexingConfig,
+    progress::{IndexingProgress, Notification, Severity},
+    timedline::TimedLine,
+};
+use std::{
+    borrow::Cow,
+    collections::BTreeMap,
+    io::{BufRead, BufReader, Read},
+    time::Instant,
+};
+use thiserror::Error;
+
+pub const REALTIME_TIMESTAMP: &str = "__REALTIME_TIMESTAMP";
+pub const SYSTEMD_UNIT: &str = "_SYSTEMD_UNIT";
+pub const PRIORITY: &str = "PRIORITY";
+pub const MESSAGE: &str = "MESSAGE";
+
+/// biggest binary field that is accepted, the limit of systemd-journal-remote
+const MAX_BINARY_FIELD_SIZE: u64 = 768 * 1024 * 1024;
+
+/// journal fields that are rendered in addition to unit, priority and message
+const EXTRA_FIELDS: &[(&str, &str)] = &[
+    ("_PID", "pid"),
+    ("_HOSTNAME", "host"),
+    ("SYSLOG_IDENTIFIER", "identifier"),
+];
+
+#[derive(Error, Debug)]
+pub enum JournalError {
+    #[error("IO error: {0:?}")]
+    Io(#[from] std::io::Error),
+    #[error("Malformed journal export: {0}")]
+    Malformed(String),
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct JournalEntry {
+    pub fields: BTreeMap<String, Vec<u8>>,
+}
+
+impl JournalEntry {
+    pub fn field(&self, name: &str) -> Option<Cow<'_, str>> {
+        self.fields.get(name).map(|v| String::from_utf8_lossy(v))
+    }
+
+    /// `__REALTIME_TIMESTAMP` is in microseconds, we use milliseconds
+    pub fn timestamp(&self) -> Option<i64> {
+        self.field(REALTIME_TIMESTAMP)
+            .and_then(|v| v.parse::<i64>().ok())
+            .map(|us| us / 1000)
+    }
+
+    pub fn to_record(&self) -> StructuredRecord {
+        let level = self
+            .field(PRIORITY)
+            .and_then(|p| p.parse::<usize>().ok())
+            .and_then(|p| SYSLOG_SEVERITIES.get(p))
+            .map(|s| s.to_string());
+        let mut fields = BTreeMap::new();
+        for (journal_name, name) in EXTRA_FIELDS {
+            if let Some(value) = self.field(journal_name) {
+                fields.insert(name.to_string(), value.into_owned());
+            }
+        }
+        StructuredRecord {
+            timestamp: self.timestamp(),
+            level,
+            source: self.field(SYSTEMD_UNIT).map(Cow::into_owned),
+            message: self.field(MESSAGE).map(Cow::into_owned).unwrap_or_default(),
+            fields,
+        }
+    }
+
+    pub fn to_timed_line(&self, tag: &str, consumed: usize, line_nr: usize) -> TimedLine {
+        let record = self.to_record();
+        TimedLine {
+            timestamp: record.timestamp.unwrap_or(0),
+            content: record.render(),
+            tag: tag.to_string(),
+            original_length: consumed,
+            year_was_missing: false,
+            line_nr,
+        }
+    }
+}
+
+/// Reader for the systemd journal export format (`journalctl -o export`)
+///
+/// Entries are separated by an empty line. Each field is either `KEY=value\n`
+/// or, for binary data, `KEY\n` followed by the length as 64 bit little endian
+/// integer, the data and a newline.
+/// Iterates over the entries together with the number of bytes each entry took.
+pub struct JournalReader<R: BufRead> {
+    reader: R,
+}
+
+impl<R: BufRead> JournalReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self { reader }
+    }
+
+    fn read_entry(&mut self) -> Result<Option<(JournalEntry, usize)>, JournalError> {
+        let mut entry = JournalEntry::default();
+        let mut consumed = 0usize;
+        let mut buf = vec![];
+        loop {
+            buf.clear();
+            let len = self.reader.read_until(b'\n', &mut buf)?;
+            consumed += len;
+            if len == 0 {
+                return Ok(if entry.fields.is_empty() {
+                    None
+                } else {
+                    Some((entry, consumed))
+                });
+            }
+            if buf.last() == Some(&b'\n') {
+                buf.pop();
+            }
+            if buf.is_empty() {
+                if entry.fields.is_empty() {
+                    continue;
+                }
+                return Ok(Some((entry, consumed)));
+            }
+            match buf.iter().position(|&b| b == b'=') {
+                Some(eq) => {
+                    let key = String::from_utf8_lossy(&buf[..eq]).into_owned();
+                    entry.fields.insert(key, buf[eq + 1..].to_vec());
+                }
+                None => {
+                    let key = String::from_utf8_lossy(&buf).into_owned();
+                    let mut size = [0u8; 8];
+                    self.reader.read_exact(&mut size)?;
+                    let size = u64::from_le_bytes(size);
+                    if size > MAX_BINARY_FIELD_SIZE {
+                        return Err(JournalError::Malformed(format!(
+                            "binary field {} too big ({} bytes)",
+                            key, size
+                        )));
+                    }
+                    // the data is read as it comes instead of allocating `size` up front
+                    let mut data = vec![];
+                    (&mut self.reader).take(size + 1).read_to_end(&mut data)?;
+                    if data.len() as u64 != size + 1 {
+                        return Err(JournalError::Io(std::io::Error::new(
+                            std::io::ErrorKind::UnexpectedEof,
+                            format!("binary field {} truncated", key),
+                        )));
+                    }
+                    let size = size as usize;
+                    if data.pop() != Some(b'\n') {
+                        return Err(JournalError::Malformed(format!(
+                            "binary field {} not terminated by newline",
+                            key
+                        )));
+                    }
+                    consumed += 8 + size + 1;
+                    entry.fields.insert(key, data);
+                }
+            }
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for JournalReader<R> {
+    type Item = Result<(JournalEntry, usize), JournalError>;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_entry().transpose()
+    }
+}
+
+/// Iterator over the entries of a journal export as `TimedLine`s, used for merging.
+/// Stops at the first malformed entry.
+pub fn journal_timed_lines(
+    reader: impl BufRead,
+    tag: String,
+    current_line_nr: usize,
+) -> impl Iterator<Item = TimedLine> {
+    JournalReader::new(reader)
+        .map_while(|res| match res {
+            Ok(entry) => Some(entry),
+            Err(e) => {
+                warn!("stop reading journal: {}", e);
+                None
+            }
+        })
+        .enumerate()
+        .map(move |(i, (entry, consumed))| entry.to_timed_line(&tag, consumed, current_line_nr + i))
+}
+
+/// Index a journal export file into the tagged format.
+///
+/// Each entry becomes one row rendered like a structured record
+/// (`time | level=<PRIORITY> | source=<_SYSTEMD_UNIT> | MESSAGE | ...`)
+/// with `__REALTIME_TIMESTAMP` as timestamp of the row.
+pub async fn create_index_and_mapping_journal(
+    config: IndexingConfig,
+    source_file_size: u64,
+    update_channel: cc::Sender<ChunkResults>,
+    shutdown_receiver: Option<cc::Receiver<()>>,
+) -> Result<()> {
+    let start = Instant::now();
+    let in_file = InputFile::open(&config.in_file)?;
+    let compressed_position = in_file.compressed_position();
+    let mut last_timestamp = 0i64;
+    let mut read_error: Option<JournalError> = None;
+    let rows = JournalReader::new(BufReader::new(in_file)).map_while(|res| match res {
+        Ok((entry, consumed)) => {
+            let record = entry.to_record();
+            let timestamp = record.timestamp.unwrap_or(last_timestamp);
+            last_timestamp = timestamp;
+            Some(TimedRow {
+                content: record.render(),
+                timestamp,
+                consumed,
+            })
+        }
+        Err(e) => {
+            read_error = Some(e);
+            None
+        }
+    });
+    let stopped = index_timed_rows(
+        rows,
+        &config,
+        source_file_size,
+        compressed_position,
+        &update_channel,
+        shutdown_receiver.as_ref(),
+    )?;
+    if stopped {
+        return Ok(());
+    }
+    if let Some(e) = read_error {
+        update_channel.send(Err(Notification {
+            severity: Severity::ERROR,
+            content: format!("could not read all journal entries: {}", e),
+            line: None,
+        }))?;
+    }
+    info!(
+        "done indexing journal export in {} ms, sending Finished",
+        start.elapsed().as_millis()
+    );
+    update_channel.send(Ok(IndexingProgress::Finished))?;
+    Ok(())
+}
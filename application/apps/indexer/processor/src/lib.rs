@@ -19,12 +19,19 @@ extern crate log;
 extern crate crossbeam_channel as cc;
 
 pub mod dlt_source;
+pub mod entries;
+pub mod export;
 pub mod grabber;
+pub mod journal;
+pub mod logcat;
 pub mod map;
 pub mod parse;
 pub mod processor;
 pub mod search;
+pub mod sort;
+pub mod structured;
 pub mod text_source;
+pub mod text_to_dlt;
 
 #[cfg(test)]
 mod tests;
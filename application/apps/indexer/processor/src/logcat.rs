@@ -0,0 +1,346 @@
+// Copyright (c) 2019 E.S.R.Labs. All rights reserved.
+//
+// NOTICE:  All information contained herein is, and remains
+// the property of E.S.R.Labs and its suppliers, if any.
+// The intellectual and technical concepts contained herein are
+// proprietary to E.S.R.Labs and its suppliers and may be covered
+// by German and Foreign Patents, patents in process, and are protected
+// by trade secret or copyright law.
+// Dissemination of this information or reproduction of this material
+// is strictly forbidden unless prior written permission is obtained
+// from E.S.R.Labs.
+use crate::{
+    entries::EntryReader,
+    processor::{index_timed_rows, TimedRow},
+    structured::StructuredRecord,
+};
+use anyhow::Result;
+use chrono::{Datelike, NaiveDate, Utc};
+use crossbeam_channel as cc;
+use indexer_base::{
+    chunks::ChunkResults,
+    compression::InputFile,
+    config::IndexingConfig,
+    progress::{IndexingProgress, Notification, Severity},
+    timedline::TimedLine,
+};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    io::{BufReader, Read},
+    path::Path,
+    time::Instant,
+};
+use thiserror::Error;
+
+/// header size of the first version of `logger_entry` (which has no `hdr_size` field)
+const LOGGER_ENTRY_V1_HEADER_SIZE: usize = 20;
+/// a logger entry payload is never bigger than this (`LOGGER_ENTRY_MAX_PAYLOAD`)
+const LOGGER_ENTRY_MAX_PAYLOAD: usize = 4068;
+const PRIORITIES: [&str; 9] = ["?", "?", "V", "D", "I", "W", "E", "F", "S"];
+
+lazy_static! {
+    static ref THREADTIME_REGEX: Regex = Regex::new(
+        r"^(?P<m>\d{2})-(?P<d>\d{2})\s+(?P<H>\d{2}):(?P<M>\d{2}):(?P<S>\d{2})\.(?P<millis>\d{3})\s+(?P<pid>\d+)\s+(?P<tid>\d+)\s+(?P<prio>[VDIWEFS])\s(?P<tag>.*?)\s*: (?P<msg>.*)$"
+    )
+    .expect("static regex failed");
+}
+
+#[derive(Error, Debug)]
+pub enum LogcatError {
+    #[error("IO error: {0:?}")]
+    Io(#[from] std::io::Error),
+    #[error("Invalid logcat entry: {0}")]
+    Parse(String),
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum LogcatFormat {
+    /// `logcat -B`, a sequence of `logger_entry` structs
+    Binary,
+    /// `logcat -v threadtime`
+    Threadtime,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogcatEntry {
+    /// posix timestamp in ms
+    pub timestamp: i64,
+    pub pid: i32,
+    pub tid: i32,
+    /// one of `V D I W E F S`
+    pub priority: String,
+    pub tag: String,
+    pub message: String,
+}
+
+impl LogcatEntry {
+    pub fn to_record(&self) -> StructuredRecord {
+        let mut fields = BTreeMap::new();
+        fields.insert("pid".to_string(), self.pid.to_string());
+        fields.insert("tid".to_string(), self.tid.to_string());
+        StructuredRecord {
+            timestamp: Some(self.timestamp),
+            level: Some(self.priority.clone()),
+            source: Some(self.tag.clone()),
+            message: self.message.clone(),
+            fields,
+        }
+    }
+}
+
+/// Parse a line in `threadtime` format:
+/// `05-02 12:36:04.344  1234  1256 I ActivityManager: Start proc`
+///
+/// The format has no year, so it has to be supplied.
+pub fn parse_threadtime_line(line: &str, year: i32) -> Result<LogcatEntry, LogcatError> {
+    let caps = THREADTIME_REGEX
+        .captures(line)
+        .ok_or_else(|| LogcatError::Parse(format!("not in threadtime format: {}", line)))?;
+    let num = |name: &str| -> Result<u32, LogcatError> {
+        caps[name]
+            .parse::<u32>()
+            .map_err(|e| LogcatError::Parse(format!("invalid {}: {}", name, e)))
+    };
+    let (hour, minute, second, millis) = (num("H")?, num("M")?, num("S")?, num("millis")?);
+    let timestamp = NaiveDate::from_ymd_opt(year, num("m")?, num("d")?)
+        .and_then(|d| d.and_hms_milli_opt(hour, minute, second, millis))
+        .ok_or_else(|| LogcatError::Parse(format!("invalid date in {}", line)))?
+        .timestamp_millis();
+    Ok(LogcatEntry {
+        timestamp,
+        pid: caps["pid"].parse().unwrap_or(0),
+        tid: caps["tid"].parse().unwrap_or(0),
+        priority: caps["prio"].to_string(),
+        tag: caps["tag"].to_string(),
+        message: caps["msg"].to_string(),
+    })
+}
+
+fn i32_at(buf: &[u8], offset: usize) -> i32 {
+    i32::from_le_bytes([
+        buf[offset],
+        buf[offset + 1],
+        buf[offset + 2],
+        buf[offset + 3],
+    ])
+}
+
+/// Reads `logger_entry` records as written by `logcat -B`
+///
+/// ```text
+/// u16 len; u16 hdr_size; i32 pid; i32 tid; i32 sec; i32 nsec; ...
+/// ```
+/// `hdr_size` is 0 for version 1 of the struct (header of 20 bytes). The payload of
+/// `len` bytes contains the priority, the tag and the message (both 0-terminated).
+/// Iterates over the entries together with the number of bytes each entry took.
+pub struct LogcatBinaryReader<R: Read> {
+    reader: R,
+}
+
+impl<R: Read> LogcatBinaryReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self { reader }
+    }
+
+    fn read_entry(&mut self) -> Result<Option<(LogcatEntry, usize)>, LogcatError> {
+        let mut start = [0u8; 4];
+        match self.reader.read_exact(&mut start) {
+            Ok(()) => (),
+            Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(e) => return Err(e.into()),
+        }
+        let payload_len = u16::from_le_bytes([start[0], start[1]]) as usize;
+        let header_size = match u16::from_le_bytes([start[2], start[3]]) as usize {
+            0 => LOGGER_ENTRY_V1_HEADER_SIZE,
+            n if n >= LOGGER_ENTRY_V1_HEADER_SIZE => n,
+            n => return Err(LogcatError::Parse(format!("invalid header size {}", n))),
+        };
+        if payload_len > LOGGER_ENTRY_MAX_PAYLOAD {
+            return Err(LogcatError::Parse(format!(
+                "payload too big ({} bytes)",
+                payload_len
+            )));
+        }
+        let mut header = vec![0u8; header_size - start.len()];
+        self.reader.read_exact(&mut header)?;
+        let mut payload = vec![0u8; payload_len];
+        self.reader.read_exact(&mut payload)?;
+
+        let pid = i32_at(&header, 0);
+        let tid = i32_at(&header, 4);
+        let sec = i32_at(&header, 8) as i64;
+        let nsec = i32_at(&header, 12) as i64;
+
+        let (priority, text) = payload
+            .split_first()
+            .ok_or_else(|| LogcatError::Parse("empty payload".to_string()))?;
+        let mut parts = text.splitn(2, |&b| b == 0);
+        let tag = String::from_utf8_lossy(parts.next().unwrap_or_default());
+        let message = parts.next().unwrap_or_default();
+        let message = message.strip_suffix(&[0]).unwrap_or(message);
+        Ok(Some((
+            LogcatEntry {
+                timestamp: sec * 1000 + nsec / 1_000_000,
+                pid,
+                tid,
+                priority: PRIORITIES
+                    .get(*priority as usize)
+                    .unwrap_or(&"?")
+                    .to_string(),
+                tag: tag.into_owned(),
+                message: String::from_utf8_lossy(message)
+                    .trim_end_matches('\n')
+                    .to_string(),
+            },
+            header_size + payload_len,
+        )))
+    }
+}
+
+impl<R: Read> Iterator for LogcatBinaryReader<R> {
+    type Item = Result<(LogcatEntry, usize), LogcatError>;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_entry().transpose()
+    }
+}
+
+/// Text logcat files start with a readable line, binary ones with a `logger_entry` header
+pub fn detect_logcat_format(path: &Path) -> Result<LogcatFormat, LogcatError> {
+    let mut start = vec![];
+    InputFile::open(path)?.take(256).read_to_end(&mut start)?;
+    let first_line = start.split(|&b| b == b'\n').next().unwrap_or_default();
+    let is_text = !first_line.is_empty()
+        && std::str::from_utf8(first_line)
+            .map(|s| !s.chars().any(|c| c.is_control() && c != '\r' && c != '\t'))
+            .unwrap_or(false);
+    Ok(if is_text {
+        LogcatFormat::Threadtime
+    } else {
+        LogcatFormat::Binary
+    })
+}
+
+fn year_or_current(year: Option<i32>) -> i32 {
+    year.unwrap_or_else(|| Utc::now().year())
+}
+
+/// A logcat entry or, for text files, a line that was no threadtime entry
+/// (e.g. `--------- beginning of main`)
+type LogcatItem = (Result<LogcatEntry, String>, usize);
+type LogcatItems = Box<dyn Iterator<Item = Result<LogcatItem, LogcatError>> + Send>;
+
+fn logcat_items(file: InputFile, format: LogcatFormat, year: Option<i32>) -> LogcatItems {
+    match format {
+        LogcatFormat::Binary => Box::new(
+            LogcatBinaryReader::new(BufReader::new(file))
+                .map(|res| res.map(|(entry, consumed)| (Ok(entry), consumed))),
+        ),
+        LogcatFormat::Threadtime => {
+            let year = year_or_current(year);
+            Box::new(
+                EntryReader::new(BufReader::new(file), None).map(move |entry| {
+                    let line = &entry.lines[0];
+                    Ok((
+                        parse_threadtime_line(line, year).map_err(|_| line.to_string()),
+                        entry.consumed,
+                    ))
+                }),
+            )
+        }
+    }
+}
+
+/// Iterator over the entries of a logcat file (binary or threadtime) as `TimedLine`s,
+/// used for merging. Lines that are no logcat entries are skipped.
+/// `path` is used to detect the format of the content `file` was opened with.
+pub fn logcat_timed_lines(
+    path: &Path,
+    file: InputFile,
+    tag: String,
+    year: Option<i32>,
+    current_line_nr: usize,
+) -> Result<impl Iterator<Item = TimedLine>, LogcatError> {
+    Ok(logcat_items(file, detect_logcat_format(path)?, year)
+        .map_while(|res| match res {
+            Ok(item) => Some(item),
+            Err(e) => {
+                warn!("stop reading logcat: {}", e);
+                None
+            }
+        })
+        .filter_map(|(entry, consumed)| entry.ok().map(|e| (e, consumed)))
+        .enumerate()
+        .map(move |(i, (entry, consumed))| TimedLine {
+            timestamp: entry.timestamp,
+            content: entry.to_record().render(),
+            tag: tag.clone(),
+            original_length: consumed,
+            year_was_missing: false,
+            line_nr: current_line_nr + i,
+        }))
+}
+
+/// Index an android logcat file (binary or threadtime) into the tagged format.
+///
+/// Entries are rendered like structured records with the logcat tag as source and
+/// pid/tid as fields. Threadtime has no year, if `year` is not given the current one is used.
+pub async fn create_index_and_mapping_logcat(
+    config: IndexingConfig,
+    source_file_size: u64,
+    year: Option<i32>,
+    update_channel: cc::Sender<ChunkResults>,
+    shutdown_receiver: Option<cc::Receiver<()>>,
+) -> Result<()> {
+    let start = Instant::now();
+    let mut last_timestamp = 0i64;
+    let mut read_error: Option<LogcatError> = None;
+    let format = detect_logcat_format(&config.in_file)?;
+    let file = InputFile::open(&config.in_file)?;
+    let compressed_position = file.compressed_position();
+    let rows = logcat_items(file, format, year).map_while(|res| match res {
+        Ok((Ok(entry), consumed)) => {
+            last_timestamp = entry.timestamp;
+            Some(TimedRow {
+                content: entry.to_record().render(),
+                timestamp: entry.timestamp,
+                consumed,
+            })
+        }
+        Ok((Err(line), consumed)) => Some(TimedRow {
+            content: line,
+            timestamp: last_timestamp,
+            consumed,
+        }),
+        Err(e) => {
+            read_error = Some(e);
+            None
+        }
+    });
+    let stopped = index_timed_rows(
+        rows,
+        &config,
+        source_file_size,
+        compressed_position,
+        &update_channel,
+        shutdown_receiver.as_ref(),
+    )?;
+    if stopped {
+        return Ok(());
+    }
+    if let Some(e) = read_error {
+        update_channel.send(Err(Notification {
+            severity: Severity::ERROR,
+            content: format!("could not read all logcat entries: {}", e),
+            line: None,
+        }))?;
+    }
+    info!(
+        "done indexing logcat in {} ms, sending Finished",
+        start.elapsed().as_millis()
+    );
+    update_channel.send(Ok(IndexingProgress::Finished))?;
+    Ok(())
+}
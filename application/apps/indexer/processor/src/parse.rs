@@ -13,6 +13,7 @@ use anyhow::{anyhow, Result};
 use chrono::{DateTime, Datelike, NaiveDate, NaiveDateTime, Utc};
 use crossbeam_channel as cc;
 use indexer_base::{
+    compression::InputFile,
     error_reporter::*,
     progress::{Severity, *},
     timedline::TimedLine,
@@ -87,8 +88,8 @@ lazy_static! {
 
 pub fn posix_timestamp_as_string(timestamp_ms: i64) -> String {
     match NaiveDateTime::from_timestamp_opt(
-        (timestamp_ms as f64 / 1000.0) as i64,
-        (timestamp_ms as f64 % 1000.0) as u32 * 1000,
+        timestamp_ms.div_euclid(1000),
+        timestamp_ms.rem_euclid(1000) as u32 * 1_000_000,
     ) {
         Some(naive_datetime_max) => {
             let t: DateTime<Utc> = DateTime::from_utc(naive_datetime_max, Utc);
@@ -896,8 +897,7 @@ pub struct TimestampFormat {
 pub fn detect_timestamp_format_in_file(path: &Path) -> Result<String> {
     use std::time::Instant;
     let now = Instant::now();
-    let f: fs::File = fs::File::open(path)?;
-    let mut reader: BufReader<&std::fs::File> = BufReader::new(&f);
+    let mut reader = BufReader::new(InputFile::open(path)?);
 
     let mut buf = vec![];
     let mut inspected_lines = 0;
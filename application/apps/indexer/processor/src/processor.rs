@@ -10,18 +10,21 @@
 // is strictly forbidden unless prior written permission is obtained
 // from E.S.R.Labs.
 
-use crate::parse;
+use crate::{entries::EntryReader, parse};
 use anyhow::{anyhow, Result};
 use crossbeam_channel as cc;
 use encoding_rs_io::*;
 use indexer_base::{
     chunks::{ChunkFactory, ChunkResults},
+    compression::{CompressedPosition, InputFile},
     config::IndexingConfig,
+    container::RowWriter,
     progress::*,
     utils,
     utils::restore_line,
 };
 use parse::detect_timestamp_in_string;
+use regex::Regex;
 use std::{
     fs,
     io::{BufRead, BufReader, BufWriter, Read, Write},
@@ -29,10 +32,19 @@ use std::{
     time::Instant,
 };
 
+/// Index a text file.
+///
+/// With `multiline` set, the timestamp format of the file is detected first and every
+/// line that does not start with this format is attached to the previous entry
+/// (stack traces, pretty printed json, ...). Such an entry ends up in one row of the output.
+///
+/// Compressed files (gzip, zstd, xz) are decoded on the fly, progress is then
+/// reported relative to the compressed `source_file_size`.
 pub async fn create_index_and_mapping(
     config: IndexingConfig,
     source_file_size: u64,
     parse_timestamps: bool,
+    multiline: bool,
     update_channel: cc::Sender<ChunkResults>,
     shutdown_receiver: Option<cc::Receiver<()>>,
 ) -> Result<()> {
@@ -54,7 +66,7 @@ pub async fn create_index_and_mapping(
     let (out_file, current_out_file_size) =
         utils::get_out_file_and_size(config.append, &config.out_path)?;
 
-    let in_file = match fs::File::open(&config.in_file) {
+    let in_file = match InputFile::open(&config.in_file) {
         Ok(file) => file,
         Err(e) => {
             warn!("could not open {:?}", config.in_file);
@@ -66,6 +78,26 @@ pub async fn create_index_and_mapping(
             return Err(anyhow!("could not open file ({})", e));
         }
     };
+    let entry_start = if multiline {
+        match parse::detect_timestamp_format_in_file(&config.in_file)
+            .and_then(|format| parse::lookup_regex_for_format_str(&format))
+        {
+            Ok(regex) => Some(regex),
+            Err(e) => {
+                let _ = update_channel.send(Err(Notification {
+                    severity: Severity::WARNING,
+                    content: format!(
+                        "no multi-line grouping, treating each line as entry ({})",
+                        e
+                    ),
+                    line: None,
+                }));
+                None
+            }
+        }
+    } else {
+        None
+    };
     let mut decode_builder = DecodeReaderBytesBuilder::new();
     decode_builder
         .utf8_passthru(true)
@@ -73,16 +105,20 @@ pub async fn create_index_and_mapping(
         .bom_override(true)
         .bom_sniffing(true);
     let mut decode_buffer = vec![0; 8 * 1024];
+    let compressed_position = in_file.compressed_position();
     let read_from = decode_builder.build_with_buffer(in_file, &mut *decode_buffer)?;
     index_file(
         read_from,
         &config.tag,
         out_file,
+        &config.out_path,
         current_out_file_size,
         config.chunk_size,
         source_file_size,
+        compressed_position,
         initial_line_nr,
         parse_timestamps,
+        entry_start,
         update_channel,
         shutdown_receiver,
     )
@@ -93,11 +129,14 @@ pub fn index_file<T: Read>(
     read_from: T,
     tag: &str,
     out_file: fs::File,
+    out_path: &Path,
     current_out_file_size: usize,
     chunk_size: usize,
     source_file_size: u64,
+    compressed_position: Option<CompressedPosition>,
     initial_line_nr: usize,
     timestamps: bool,
+    entry_start: Option<Regex>,
     update_channel: cc::Sender<ChunkResults>,
     shutdown_receiver: Option<cc::Receiver<()>>,
 ) -> Result<()> {
@@ -105,31 +144,29 @@ pub fn index_file<T: Read>(
 
     let mut chunk_count = 0usize;
     let mut last_byte_index = 0usize;
-    let mut chunk_factory = ChunkFactory::new(chunk_size, current_out_file_size);
+    let mut chunk_factory =
+        ChunkFactory::new(chunk_size, current_out_file_size).with_index(out_path)?;
 
-    let mut reader = BufReader::new(read_from);
+    let entries = EntryReader::new(BufReader::new(read_from), entry_start);
     let mut line_nr = initial_line_nr;
-    let mut buf_writer = BufWriter::with_capacity(10 * 1024 * 1024, &out_file);
+    let mut row_writer = RowWriter::new(
+        BufWriter::with_capacity(10 * 1024 * 1024, &out_file),
+        out_path,
+        current_out_file_size,
+    )?;
 
-    let mut buf = vec![];
     let mut stopped = false;
 
-    let mut progress_reporter = ProgressReporter::new(source_file_size, update_channel.clone());
-    while let Ok(len) = reader.read_until(b'\n', &mut buf) {
+    let mut progress_reporter = ProgressReporter::new(source_file_size, update_channel.clone())
+        .with_compressed_position(compressed_position);
+    for entry in entries {
         if stopped {
             info!("we where stopped in indexer",);
             break;
         };
-        let s = unsafe { std::str::from_utf8_unchecked(&buf) };
-        let trimmed_line = s.trim_matches(utils::is_newline);
-        let trimmed_len = trimmed_line.len();
-        let had_newline = trimmed_len != len;
-        if len == 0 {
-            // no more content
-            break;
-        };
+        let content = entry.joined();
         let ts = if timestamps {
-            match detect_timestamp_in_string(trimmed_line, None) {
+            match detect_timestamp_in_string(&entry.lines[0], None) {
                 Ok((time, _, _)) => Some(time),
                 Err(_) => Some(0),
             }
@@ -137,7 +174,7 @@ pub fn index_file<T: Read>(
             None
         };
         let additional_bytes: usize =
-            utils::write_tagged_line(tag, &mut buf_writer, trimmed_line, line_nr, had_newline, ts)?;
+            row_writer.write_row(tag, &content, line_nr, entry.had_newline, ts)?;
         line_nr += 1;
 
         match chunk_factory.add_bytes(line_nr, additional_bytes) {
@@ -146,21 +183,20 @@ pub fn index_file<T: Read>(
                 chunk_count += 1;
                 last_byte_index = chunk.b.1;
                 update_channel.send(Ok(IndexingProgress::GotItem { item: chunk }))?;
-                buf_writer.flush()?;
+                row_writer.flush()?;
                 false
             }
             None => false,
         };
 
-        progress_reporter.make_progress(len);
-        buf = vec![];
+        progress_reporter.make_progress(entry.consumed);
     }
     if stopped {
         debug!("sending IndexingProgress::Stopped");
         update_channel.send(Ok(IndexingProgress::Stopped))?;
         Ok(())
     } else {
-        buf_writer.flush()?;
+        row_writer.flush()?;
         if let Some(chunk) = chunk_factory.create_last_chunk(line_nr, chunk_count == 0) {
             last_byte_index = chunk.b.1;
             trace!("index: add last chunk {:?}", chunk);
@@ -187,8 +223,95 @@ pub fn index_file<T: Read>(
     }
 }
 
+/// A row of the tagged output together with the number of input bytes it was made of
+pub struct TimedRow {
+    pub content: String,
+    pub timestamp: i64,
+    pub consumed: usize,
+}
+
+/// Write rows including their timestamp into the tagged output file of `config`
+/// and report the chunks through `update_channel`.
+///
+/// Used for all inputs that are not plain text. Sends `IndexingProgress::Stopped` if
+/// a shutdown was requested but leaves sending `IndexingProgress::Finished` to the caller.
+/// Returns `true` if we were stopped.
+pub(crate) fn index_timed_rows(
+    rows: impl Iterator<Item = TimedRow>,
+    config: &IndexingConfig,
+    source_file_size: u64,
+    compressed_position: Option<CompressedPosition>,
+    update_channel: &cc::Sender<ChunkResults>,
+    shutdown_receiver: Option<&cc::Receiver<()>>,
+) -> Result<bool> {
+    let mut line_nr = utils::next_line_nr(&config.out_path).map_err(|e| {
+        anyhow!(
+            "could not determine last line number of {:?} ({})",
+            config.out_path,
+            e
+        )
+    })?;
+    let (out_file, current_out_file_size) =
+        utils::get_out_file_and_size(config.append, &config.out_path)?;
+    let mut chunk_count = 0usize;
+    let mut last_byte_index = 0usize;
+    let mut chunk_factory =
+        ChunkFactory::new(config.chunk_size, current_out_file_size).with_index(&config.out_path)?;
+    let mut row_writer = RowWriter::new(
+        BufWriter::with_capacity(10 * 1024 * 1024, &out_file),
+        &config.out_path,
+        current_out_file_size,
+    )?;
+    let mut progress_reporter = ProgressReporter::new(source_file_size, update_channel.clone())
+        .with_compressed_position(compressed_position);
+    let mut stopped = false;
+
+    for row in rows {
+        if stopped {
+            info!("we where stopped in indexer",);
+            debug!("sending IndexingProgress::Stopped");
+            update_channel.send(Ok(IndexingProgress::Stopped))?;
+            return Ok(true);
+        };
+        let additional_bytes = row_writer.write_row(
+            &config.tag,
+            &row.content,
+            line_nr,
+            true,
+            Some(row.timestamp),
+        )?;
+        line_nr += 1;
+        if let Some(chunk) = chunk_factory.add_bytes(line_nr, additional_bytes) {
+            stopped = utils::check_if_stop_was_requested(shutdown_receiver, "indexer");
+            chunk_count += 1;
+            last_byte_index = chunk.b.1;
+            update_channel.send(Ok(IndexingProgress::GotItem { item: chunk }))?;
+            row_writer.flush()?;
+        }
+        progress_reporter.make_progress(row.consumed);
+    }
+    row_writer.flush()?;
+    if let Some(chunk) = chunk_factory.create_last_chunk(line_nr, chunk_count == 0) {
+        last_byte_index = chunk.b.1;
+        trace!("index: add last chunk {:?}", chunk);
+        update_channel.send(Ok(IndexingProgress::GotItem { item: chunk }))?;
+        chunk_count += 1;
+    }
+    if chunk_count > 0 {
+        let last_expected_byte_index = out_file.metadata().map(|md| md.len() as usize)?;
+        if last_expected_byte_index != last_byte_index {
+            return Err(anyhow!(
+                "error in computation! last byte in chunks is {} but should be {}",
+                last_byte_index,
+                last_expected_byte_index
+            ));
+        }
+    }
+    Ok(false)
+}
+
 pub fn restore_original_from_indexed_file(indexed_file: &Path, out: &Path) -> Result<()> {
-    let f = fs::File::open(&indexed_file)?;
+    let f = fs::File::open(indexed_file)?;
     let reader = &mut std::io::BufReader::new(f);
     let out_file = std::fs::File::create(out)?;
     trace!("created out_file: {:?}", &out_file);
@@ -238,12 +238,7 @@ impl SearchHolder {
         Ok((
             self.out_file_path.clone(),
             indexes,
-            FilterStats(
-                stats
-                    .into_iter()
-                    .map(|(filter_index, meets)| (filter_index, meets))
-                    .collect(),
-            ),
+            FilterStats(stats.into_iter().collect()),
         ))
     }
 }
@@ -300,6 +295,7 @@ impl MatchesExtractor {
     }
 }
 
+#[allow(dead_code)]
 struct MySink {
     matches: u64,
 }
@@ -328,7 +324,7 @@ mod tests {
     ];
     use super::*;
     // use grep_printer::SummaryBuilder;
-    use std::io::{Error, ErrorKind};
+    use std::io::Error;
     fn as_matches(content: &str) -> Vec<u64> {
         let lines: Vec<&str> = content.lines().collect();
         println!("lines: {:?}", lines);
@@ -346,7 +342,7 @@ mod tests {
         let search_holder = SearchHolder::new(tmp_file.path(), filters.iter());
         let (out_path, _indexes, _stats) = search_holder
             .execute_search()
-            .map_err(|e| Error::new(ErrorKind::Other, format!("Error in search: {}", e)))?;
+            .map_err(|e| Error::other(format!("Error in search: {}", e)))?;
         std::fs::read_to_string(out_path)
     }
 
@@ -0,0 +1,283 @@
+// Copyright (c) 2019 E.S.R.Labs. All rights reserved.
+//
+// NOTICE:  All information contained herein is, and remains
+// the property of E.S.R.Labs and its suppliers, if any.
+// The intellectual and technical concepts contained herein are
+// proprietary to E.S.R.Labs and its suppliers and may be covered
+// by German and Foreign Patents, patents in process, and are protected
+// by trade secret or copyright law.
+// Dissemination of this information or reproduction of this material
+// is strictly forbidden unless prior written permission is obtained
+// from E.S.R.Labs.
+use crate::{
+    entries::EntryReader,
+    parse::{
+        detect_timestamp_format_in_file, extract_posix_timestamp, lookup_regex_for_format_str,
+        DateTimeReplacements,
+    },
+};
+use anyhow::{anyhow, Result};
+use crossbeam_channel as cc;
+use encoding_rs_io::DecodeReaderBytesBuilder;
+use indexer_base::{
+    chunks::{ChunkFactory, ChunkResults},
+    compression::InputFile,
+    config::IndexingConfig,
+    container::RowWriter,
+    progress::*,
+    utils,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    cmp::Reverse,
+    collections::BinaryHeap,
+    fs,
+    io::{BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+/// default for how many bytes of entries are sorted in memory at once
+pub const DEFAULT_RUN_SIZE: usize = 64 * 1024 * 1024;
+
+/// memory that is used for an entry besides its content
+const ENTRY_OVERHEAD: usize = 48;
+
+#[derive(Serialize, Deserialize, Debug)]
+struct SortEntry {
+    timestamp: i64,
+    /// line number of the entry in the input (plus the initial line number)
+    line_nr: usize,
+    content: String,
+}
+
+/// Index a text file with its entries sorted by time.
+///
+/// The timestamp format of the file is detected, entries without a timestamp keep
+/// the time of the entry before so they stay behind it. Entries with the same
+/// timestamp keep the order of the file. Each row of the output has the original
+/// line number of its entry and the timestamp.
+///
+/// At most `run_size` bytes of entries are sorted in memory, bigger files are sorted
+/// in runs that are stored next to the output file and merged afterwards.
+pub async fn create_sorted_index_and_mapping(
+    config: IndexingConfig,
+    source_file_size: u64,
+    multiline: bool,
+    run_size: usize,
+    update_channel: cc::Sender<ChunkResults>,
+    shutdown_receiver: Option<cc::Receiver<()>>,
+) -> Result<()> {
+    let format = detect_timestamp_format_in_file(&config.in_file).map_err(|e| {
+        let _ = update_channel.send(Err(Notification {
+            severity: Severity::ERROR,
+            content: format!("can not sort without timestamps ({})", e),
+            line: None,
+        }));
+        e
+    })?;
+    let regex = lookup_regex_for_format_str(&format)?;
+    // the order does not depend on the timezone
+    let offset = if format.contains("TZD") {
+        None
+    } else {
+        Some(0)
+    };
+    let initial_line_nr = utils::next_line_nr(&config.out_path).map_err(|e| {
+        anyhow!(
+            "could not determine last line number of {:?} ({})",
+            config.out_path,
+            e
+        )
+    })?;
+    let in_file = InputFile::open(&config.in_file)?;
+    let mut progress_reporter = ProgressReporter::new(source_file_size, update_channel.clone())
+        .with_compressed_position(in_file.compressed_position());
+    let read_from = DecodeReaderBytesBuilder::new()
+        .utf8_passthru(true)
+        .strip_bom(true)
+        .bom_override(true)
+        .bom_sniffing(true)
+        .build(in_file);
+    let entry_start = if multiline { Some(regex.clone()) } else { None };
+    let entries = EntryReader::new(BufReader::new(read_from), entry_start);
+
+    let mut runs = SortRuns::new(&config.out_path);
+    let mut run: Vec<SortEntry> = vec![];
+    let mut run_bytes = 0usize;
+    let mut last_timestamp = 0i64;
+    for (index, entry) in entries.enumerate() {
+        if index % 10_000 == 0
+            && utils::check_if_stop_was_requested(shutdown_receiver.as_ref(), "sort")
+        {
+            update_channel.send(Ok(IndexingProgress::Stopped))?;
+            return Ok(());
+        }
+        let replacements = DateTimeReplacements {
+            year: None,
+            month: None,
+            day: None,
+            offset,
+        };
+        if let Ok((timestamp, _)) = extract_posix_timestamp(&entry.lines[0], &regex, replacements) {
+            last_timestamp = timestamp;
+        }
+        let content = entry.joined();
+        run_bytes += content.len() + ENTRY_OVERHEAD;
+        run.push(SortEntry {
+            timestamp: last_timestamp,
+            line_nr: initial_line_nr + index,
+            content,
+        });
+        progress_reporter.make_progress(entry.consumed);
+        if run_bytes >= run_size {
+            runs.store(&mut run)?;
+            run_bytes = 0;
+        }
+    }
+    if runs.paths.is_empty() {
+        sort_run(&mut run);
+        write_sorted(
+            run.into_iter().map(Ok),
+            &config,
+            initial_line_nr,
+            &update_channel,
+        )?;
+    } else {
+        runs.store(&mut run)?;
+        write_sorted(runs.merged()?, &config, initial_line_nr, &update_channel)?;
+    }
+    update_channel.send(Ok(IndexingProgress::Finished))?;
+    Ok(())
+}
+
+fn sort_run(run: &mut [SortEntry]) {
+    // line numbers are unique, so this keeps the order of equal timestamps
+    run.sort_unstable_by_key(|entry| (entry.timestamp, entry.line_nr));
+}
+
+/// runs of sorted entries that are stored on disk, the files are removed on drop
+struct SortRuns {
+    out_path: PathBuf,
+    paths: Vec<PathBuf>,
+}
+
+impl SortRuns {
+    fn new(out_path: &Path) -> Self {
+        SortRuns {
+            out_path: out_path.to_path_buf(),
+            paths: vec![],
+        }
+    }
+
+    /// sort the entries and write them to a new run file, `run` is empty afterwards
+    fn store(&mut self, run: &mut Vec<SortEntry>) -> Result<()> {
+        sort_run(run);
+        let mut name = self.out_path.as_os_str().to_owned();
+        name.push(format!(".run{}", self.paths.len()));
+        let path = PathBuf::from(name);
+        trace!("store {} sorted entries in {:?}", run.len(), path);
+        let mut writer = BufWriter::new(fs::File::create(&path)?);
+        self.paths.push(path);
+        for entry in run.drain(..) {
+            bincode::serialize_into(&mut writer, &entry)
+                .map_err(|e| anyhow!("could not write sorted entries: {}", e))?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// k-way merge of all runs
+    fn merged(&self) -> Result<impl Iterator<Item = Result<SortEntry>>> {
+        let mut readers = self
+            .paths
+            .iter()
+            .map(|path| Ok(BufReader::new(fs::File::open(path)?)))
+            .collect::<Result<Vec<BufReader<fs::File>>>>()?;
+        let mut heads: Vec<Option<SortEntry>> = vec![];
+        let mut heap = BinaryHeap::new();
+        for (index, reader) in readers.iter_mut().enumerate() {
+            let head = read_entry(reader)?;
+            if let Some(entry) = &head {
+                heap.push(Reverse((entry.timestamp, entry.line_nr, index)));
+            }
+            heads.push(head);
+        }
+        Ok(std::iter::from_fn(move || {
+            let Reverse((_, _, index)) = heap.pop()?;
+            let entry = heads[index].take()?;
+            match read_entry(&mut readers[index]) {
+                Ok(next) => {
+                    if let Some(next) = &next {
+                        heap.push(Reverse((next.timestamp, next.line_nr, index)));
+                    }
+                    heads[index] = next;
+                    Some(Ok(entry))
+                }
+                Err(e) => Some(Err(e)),
+            }
+        }))
+    }
+}
+
+impl Drop for SortRuns {
+    fn drop(&mut self) {
+        for path in &self.paths {
+            if let Err(e) = fs::remove_file(path) {
+                warn!("could not remove {:?}: {}", path, e);
+            }
+        }
+    }
+}
+
+fn read_entry(reader: &mut BufReader<fs::File>) -> Result<Option<SortEntry>> {
+    match bincode::deserialize_from(reader) {
+        Ok(entry) => Ok(Some(entry)),
+        Err(e) => match *e {
+            bincode::ErrorKind::Io(ref io) if io.kind() == std::io::ErrorKind::UnexpectedEof => {
+                Ok(None)
+            }
+            _ => Err(anyhow!("could not read sorted entries: {}", e)),
+        },
+    }
+}
+
+fn write_sorted(
+    entries: impl Iterator<Item = Result<SortEntry>>,
+    config: &IndexingConfig,
+    initial_line_nr: usize,
+    update_channel: &cc::Sender<ChunkResults>,
+) -> Result<()> {
+    let (out_file, current_out_file_size) =
+        utils::get_out_file_and_size(config.append, &config.out_path)?;
+    let mut chunk_factory =
+        ChunkFactory::new(config.chunk_size, current_out_file_size).with_index(&config.out_path)?;
+    let mut chunk_count = 0usize;
+    // rows of the output, the rows themselves have the line number of the input
+    let mut rows = initial_line_nr;
+    let mut row_writer = RowWriter::new(
+        BufWriter::with_capacity(10 * 1024 * 1024, &out_file),
+        &config.out_path,
+        current_out_file_size,
+    )?;
+    for entry in entries {
+        let entry = entry?;
+        let additional_bytes = row_writer.write_row(
+            &config.tag,
+            &entry.content,
+            entry.line_nr,
+            true,
+            Some(entry.timestamp),
+        )?;
+        rows += 1;
+        if let Some(chunk) = chunk_factory.add_bytes(rows, additional_bytes) {
+            chunk_count += 1;
+            row_writer.flush()?;
+            update_channel.send(Ok(IndexingProgress::GotItem { item: chunk }))?;
+        }
+    }
+    row_writer.flush()?;
+    if let Some(chunk) = chunk_factory.create_last_chunk(rows, chunk_count == 0) {
+        update_channel.send(Ok(IndexingProgress::GotItem { item: chunk }))?;
+    }
+    Ok(())
+}
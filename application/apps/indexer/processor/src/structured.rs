@@ -0,0 +1,545 @@
+// Copyright (c) 2019 E.S.R.Labs. All rights reserved.
+//
+// NOTICE:  All information contained herein is, and remains
+// the property of E.S.R.Labs and its suppliers, if any.
+// The intellectual and technical concepts contained herein are
+// proprietary to E.S.R.Labs and its suppliers and may be covered
+// by German and Foreign Patents, patents in process, and are protected
+// by trade secret or copyright law.
+// Dissemination of this information or reproduction of this material
+// is strictly forbidden unless prior written permission is obtained
+// from E.S.R.Labs.
+use crate::{
+    entries::EntryReader,
+    parse::{detect_timestamp_in_string, posix_timestamp_as_string},
+    processor::{index_timed_rows, TimedRow},
+    search::SearchFilter,
+};
+use anyhow::Result;
+use crossbeam_channel as cc;
+use indexer_base::{
+    chunks::ChunkResults, compression::InputFile, config::IndexingConfig, progress::*, utils,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{collections::BTreeMap, io::BufReader, str::FromStr, time::Instant};
+use thiserror::Error;
+
+/// separates the columns of a rendered record (same as for dlt messages)
+pub const FIELD_SENTINAL: char = '\u{0004}';
+
+const TIMESTAMP_KEYS: &[&str] = &["timestamp", "@timestamp", "time", "ts", "datetime", "date"];
+const LEVEL_KEYS: &[&str] = &["level", "lvl", "severity", "loglevel"];
+const SOURCE_KEYS: &[&str] = &["source", "logger", "component", "app", "service"];
+const MESSAGE_KEYS: &[&str] = &["message", "msg", "text"];
+
+pub(crate) const SYSLOG_SEVERITIES: [&str; 8] = [
+    "EMERG", "ALERT", "CRIT", "ERR", "WARNING", "NOTICE", "INFO", "DEBUG",
+];
+
+#[derive(Error, Debug)]
+pub enum StructuredError {
+    #[error("Invalid {format} record: {reason}")]
+    Parse {
+        format: &'static str,
+        reason: String,
+    },
+    #[error("Unknown structured format: {0}")]
+    UnknownFormat(String),
+}
+
+fn parse_error(format: &'static str, reason: &str) -> StructuredError {
+    StructuredError::Parse {
+        format,
+        reason: reason.to_string(),
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum StructuredFormat {
+    JsonLines,
+    Logfmt,
+    Syslog,
+}
+
+impl StructuredFormat {
+    pub fn parser(self) -> Box<dyn RecordParser> {
+        match self {
+            StructuredFormat::JsonLines => Box::new(JsonLinesParser),
+            StructuredFormat::Logfmt => Box::new(LogfmtParser),
+            StructuredFormat::Syslog => Box::new(SyslogParser),
+        }
+    }
+
+    /// Try to find out which structured format a line has.
+    /// Syslog and json are tried first since plain text often looks like logfmt.
+    pub fn detect(line: &str) -> Option<StructuredFormat> {
+        if SyslogParser.parse_record(line).is_ok() {
+            Some(StructuredFormat::Syslog)
+        } else if JsonLinesParser.parse_record(line).is_ok() {
+            Some(StructuredFormat::JsonLines)
+        } else if is_pure_logfmt(line) {
+            Some(StructuredFormat::Logfmt)
+        } else {
+            None
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            StructuredFormat::JsonLines => "json",
+            StructuredFormat::Logfmt => "logfmt",
+            StructuredFormat::Syslog => "syslog",
+        }
+    }
+}
+
+impl FromStr for StructuredFormat {
+    type Err = StructuredError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "json" | "jsonl" | "jsonlines" => Ok(StructuredFormat::JsonLines),
+            "logfmt" => Ok(StructuredFormat::Logfmt),
+            "syslog" | "rfc5424" => Ok(StructuredFormat::Syslog),
+            _ => Err(StructuredError::UnknownFormat(s.to_string())),
+        }
+    }
+}
+
+/// A log record of a structured format
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct StructuredRecord {
+    /// posix timestamp in ms
+    pub timestamp: Option<i64>,
+    pub level: Option<String>,
+    pub source: Option<String>,
+    pub message: String,
+    /// all remaining fields of the record
+    pub fields: BTreeMap<String, String>,
+}
+
+impl StructuredRecord {
+    /// Render the record into a single row.
+    ///
+    /// The columns are separated by `FIELD_SENTINAL`:
+    /// `time | level=.. | source=.. | message | name=value | ...`
+    /// Everything but the time and the message is rendered as `name=value`
+    /// so a search can address fields by name (see `field_filter`).
+    pub fn render(&self) -> String {
+        let mut columns: Vec<String> = vec![
+            match self.timestamp {
+                Some(ts) => posix_timestamp_as_string(ts),
+                None => "-".to_string(),
+            },
+            format!("level={}", self.level.as_deref().unwrap_or("-")),
+            format!("source={}", self.source.as_deref().unwrap_or("-")),
+            self.message.clone(),
+        ];
+        for (name, value) in &self.fields {
+            columns.push(format!("{}={}", name, value));
+        }
+        columns
+            .join(&FIELD_SENTINAL.to_string())
+            .replace('\n', utils::ENTRY_NEWLINE_SENTINAL_STR)
+    }
+}
+
+/// A search filter that matches rows where the field `name` has exactly `value`
+pub fn field_filter(name: &str, value: &str) -> SearchFilter {
+    SearchFilter::new(&format!(
+        r"\x04{}={}(\x04|\x03)",
+        regex::escape(name),
+        regex::escape(value)
+    ))
+    .regex(true)
+}
+
+/// Turns one line of a structured log format into a `StructuredRecord`
+pub trait RecordParser {
+    fn format(&self) -> StructuredFormat;
+    fn parse_record(&self, line: &str) -> Result<StructuredRecord, StructuredError>;
+}
+
+/// Parse a timestamp value: numbers are seconds or milliseconds since epoch,
+/// strings are tried as RFC 3339 and then with the known timestamp formats
+pub(crate) fn parse_timestamp_value(value: &str) -> Option<i64> {
+    if let Ok(n) = value.parse::<f64>() {
+        return Some(if n.abs() > 100_000_000_000.0 {
+            n as i64
+        } else {
+            (n * 1000.0) as i64
+        });
+    }
+    if let Ok(dt) = chrono::DateTime::parse_from_rfc3339(value) {
+        return Some(dt.timestamp_millis());
+    }
+    detect_timestamp_in_string(value, None)
+        .ok()
+        .map(|(ts, _, _)| ts)
+}
+
+fn take_first(fields: &mut BTreeMap<String, String>, keys: &[&str]) -> Option<String> {
+    keys.iter().find_map(|k| fields.remove(*k))
+}
+
+/// json and logfmt records share the same key conventions
+fn record_from_fields(mut fields: BTreeMap<String, String>) -> StructuredRecord {
+    let timestamp = TIMESTAMP_KEYS
+        .iter()
+        .find_map(|k| {
+            fields
+                .get(*k)
+                .and_then(|v| parse_timestamp_value(v))
+                .map(|ts| (*k, ts))
+        })
+        .map(|(k, ts)| {
+            fields.remove(k);
+            ts
+        });
+    StructuredRecord {
+        timestamp,
+        level: take_first(&mut fields, LEVEL_KEYS),
+        source: take_first(&mut fields, SOURCE_KEYS),
+        message: take_first(&mut fields, MESSAGE_KEYS).unwrap_or_default(),
+        fields,
+    }
+}
+
+pub struct JsonLinesParser;
+
+fn flatten_json(prefix: &str, value: &Value, fields: &mut BTreeMap<String, String>) {
+    match value {
+        Value::Object(map) => {
+            for (k, v) in map {
+                let key = if prefix.is_empty() {
+                    k.to_string()
+                } else {
+                    format!("{}.{}", prefix, k)
+                };
+                flatten_json(&key, v, fields);
+            }
+        }
+        Value::String(s) => {
+            fields.insert(prefix.to_string(), s.to_string());
+        }
+        other => {
+            fields.insert(prefix.to_string(), other.to_string());
+        }
+    }
+}
+
+impl RecordParser for JsonLinesParser {
+    fn format(&self) -> StructuredFormat {
+        StructuredFormat::JsonLines
+    }
+
+    fn parse_record(&self, line: &str) -> Result<StructuredRecord, StructuredError> {
+        let value: Value =
+            serde_json::from_str(line).map_err(|e| parse_error("json", &e.to_string()))?;
+        if !value.is_object() {
+            return Err(parse_error("json", "not a json object"));
+        }
+        let mut fields = BTreeMap::new();
+        flatten_json("", &value, &mut fields);
+        Ok(record_from_fields(fields))
+    }
+}
+
+pub struct LogfmtParser;
+
+/// split a logfmt line into (key, value) pairs, bare keys get an empty value
+fn logfmt_pairs(line: &str) -> Result<Vec<(String, Option<String>)>, StructuredError> {
+    let mut pairs = vec![];
+    let mut chars = line.chars().peekable();
+    loop {
+        while chars.peek().is_some_and(|c| c.is_whitespace()) {
+            chars.next();
+        }
+        if chars.peek().is_none() {
+            break;
+        }
+        let mut key = String::new();
+        while let Some(&c) = chars.peek() {
+            if c == '=' || c.is_whitespace() {
+                break;
+            }
+            key.push(c);
+            chars.next();
+        }
+        if key.is_empty() {
+            return Err(parse_error("logfmt", "empty key"));
+        }
+        if chars.peek() != Some(&'=') {
+            pairs.push((key, None));
+            continue;
+        }
+        chars.next();
+        let mut value = String::new();
+        if chars.peek() == Some(&'"') {
+            chars.next();
+            let mut closed = false;
+            while let Some(c) = chars.next() {
+                match c {
+                    '"' => {
+                        closed = true;
+                        break;
+                    }
+                    '\\' => match chars.next() {
+                        Some('n') => value.push('\n'),
+                        Some('t') => value.push('\t'),
+                        Some(escaped) => value.push(escaped),
+                        None => break,
+                    },
+                    c => value.push(c),
+                }
+            }
+            if !closed {
+                return Err(parse_error("logfmt", "unterminated quoted value"));
+            }
+        } else {
+            while let Some(&c) = chars.peek() {
+                if c.is_whitespace() {
+                    break;
+                }
+                value.push(c);
+                chars.next();
+            }
+        }
+        pairs.push((key, Some(value)));
+    }
+    Ok(pairs)
+}
+
+fn is_pure_logfmt(line: &str) -> bool {
+    match logfmt_pairs(line) {
+        Ok(pairs) => !pairs.is_empty() && pairs.iter().all(|(_, v)| v.is_some()),
+        Err(_) => false,
+    }
+}
+
+impl RecordParser for LogfmtParser {
+    fn format(&self) -> StructuredFormat {
+        StructuredFormat::Logfmt
+    }
+
+    fn parse_record(&self, line: &str) -> Result<StructuredRecord, StructuredError> {
+        let pairs = logfmt_pairs(line)?;
+        if !pairs.iter().any(|(_, v)| v.is_some()) {
+            return Err(parse_error("logfmt", "no key=value pair found"));
+        }
+        let fields = pairs
+            .into_iter()
+            .map(|(k, v)| (k, v.unwrap_or_default()))
+            .collect();
+        Ok(record_from_fields(fields))
+    }
+}
+
+/// RFC 5424 syslog
+///
+/// `<PRI>VERSION TIMESTAMP HOSTNAME APP-NAME PROCID MSGID STRUCTURED-DATA [MSG]`
+pub struct SyslogParser;
+
+const NIL_VALUE: &str = "-";
+
+fn parse_structured_data(
+    input: &str,
+    fields: &mut BTreeMap<String, String>,
+) -> Result<usize, StructuredError> {
+    let invalid = |reason: &str| parse_error("syslog", reason);
+    let bytes = input.as_bytes();
+    let mut pos = 0usize;
+    while pos < bytes.len() && bytes[pos] == b'[' {
+        pos += 1;
+        let id_end = input[pos..]
+            .find([' ', ']'])
+            .ok_or_else(|| invalid("unterminated structured data"))?;
+        let id = &input[pos..pos + id_end];
+        pos += id_end;
+        loop {
+            match bytes.get(pos) {
+                Some(b']') => {
+                    pos += 1;
+                    break;
+                }
+                Some(b' ') => {
+                    pos += 1;
+                    let eq = input[pos..]
+                        .find('=')
+                        .ok_or_else(|| invalid("structured data param without value"))?;
+                    let name = &input[pos..pos + eq];
+                    pos += eq + 1;
+                    if bytes.get(pos) != Some(&b'"') {
+                        return Err(invalid("structured data value not quoted"));
+                    }
+                    pos += 1;
+                    let mut value = String::new();
+                    let mut chars = input[pos..].char_indices();
+                    let mut consumed = None;
+                    while let Some((i, c)) = chars.next() {
+                        match c {
+                            '"' => {
+                                consumed = Some(i + 1);
+                                break;
+                            }
+                            '\\' => {
+                                if let Some((_, escaped)) = chars.next() {
+                                    value.push(escaped);
+                                }
+                            }
+                            c => value.push(c),
+                        }
+                    }
+                    pos += consumed.ok_or_else(|| invalid("unterminated param value"))?;
+                    fields.insert(format!("{}.{}", id, name), value);
+                }
+                _ => return Err(invalid("malformed structured data")),
+            }
+        }
+    }
+    if pos == 0 {
+        return Err(invalid("missing structured data"));
+    }
+    Ok(pos)
+}
+
+impl RecordParser for SyslogParser {
+    fn format(&self) -> StructuredFormat {
+        StructuredFormat::Syslog
+    }
+
+    fn parse_record(&self, line: &str) -> Result<StructuredRecord, StructuredError> {
+        let invalid = |reason: &str| parse_error("syslog", reason);
+        let rest = line
+            .strip_prefix('<')
+            .ok_or_else(|| invalid("missing priority"))?;
+        let pri_end = rest.find('>').ok_or_else(|| invalid("missing priority"))?;
+        let pri: u8 = rest[..pri_end]
+            .parse()
+            .map_err(|_| invalid("invalid priority"))?;
+        if pri > 191 {
+            return Err(invalid("invalid priority"));
+        }
+        let mut header = rest[pri_end + 1..].splitn(7, ' ');
+        let mut next_part = || header.next().ok_or_else(|| invalid("incomplete header"));
+        let version = next_part()?;
+        if version.is_empty() || !version.chars().all(|c| c.is_ascii_digit()) {
+            return Err(invalid("invalid version"));
+        }
+        let timestamp = next_part()?;
+        let hostname = next_part()?;
+        let app_name = next_part()?;
+        let proc_id = next_part()?;
+        let msg_id = next_part()?;
+        let remaining = next_part()?;
+
+        let timestamp = if timestamp == NIL_VALUE {
+            None
+        } else {
+            Some(
+                chrono::DateTime::parse_from_rfc3339(timestamp)
+                    .map_err(|_| invalid("invalid timestamp"))?
+                    .timestamp_millis(),
+            )
+        };
+        let mut fields = BTreeMap::new();
+        fields.insert("facility".to_string(), (pri / 8).to_string());
+        for (name, value) in &[("host", hostname), ("procid", proc_id), ("msgid", msg_id)] {
+            if *value != NIL_VALUE {
+                fields.insert(name.to_string(), value.to_string());
+            }
+        }
+        let msg = if let Some(msg) = remaining.strip_prefix(NIL_VALUE) {
+            msg
+        } else {
+            let sd_len = parse_structured_data(remaining, &mut fields)?;
+            &remaining[sd_len..]
+        };
+        let message = match msg.strip_prefix(' ') {
+            Some(m) => m.trim_start_matches('\u{feff}').to_string(),
+            None if msg.is_empty() => String::new(),
+            None => return Err(invalid("missing space before message")),
+        };
+        Ok(StructuredRecord {
+            timestamp,
+            level: Some(SYSLOG_SEVERITIES[(pri % 8) as usize].to_string()),
+            source: if app_name == NIL_VALUE {
+                None
+            } else {
+                Some(app_name.to_string())
+            },
+            message,
+            fields,
+        })
+    }
+}
+
+/// Index a file in a structured log format.
+///
+/// Each record is rendered with `StructuredRecord::render` and its own timestamp is
+/// added to the tagged line. Lines that cannot be parsed are kept as they are and
+/// get the timestamp of the previous record.
+pub async fn create_index_and_mapping_structured(
+    config: IndexingConfig,
+    source_file_size: u64,
+    format: StructuredFormat,
+    update_channel: cc::Sender<ChunkResults>,
+    shutdown_receiver: Option<cc::Receiver<()>>,
+) -> Result<()> {
+    let start = Instant::now();
+    let in_file = InputFile::open(&config.in_file)?;
+    let compressed_position = in_file.compressed_position();
+    let parser = format.parser();
+    let mut last_timestamp = 0i64;
+    let mut unparsed_lines = 0usize;
+    let rows = EntryReader::new(BufReader::new(in_file), None).map(|entry| {
+        let line = &entry.lines[0];
+        let (content, timestamp) = match parser.parse_record(line) {
+            Ok(record) => {
+                let ts = record.timestamp.unwrap_or(last_timestamp);
+                (record.render(), ts)
+            }
+            Err(e) => {
+                trace!("line not parsed: {}", e);
+                unparsed_lines += 1;
+                (line.to_string(), last_timestamp)
+            }
+        };
+        last_timestamp = timestamp;
+        TimedRow {
+            content,
+            timestamp,
+            consumed: entry.consumed,
+        }
+    });
+    let stopped = index_timed_rows(
+        rows,
+        &config,
+        source_file_size,
+        compressed_position,
+        &update_channel,
+        shutdown_receiver.as_ref(),
+    )?;
+    if stopped {
+        return Ok(());
+    }
+    if unparsed_lines > 0 {
+        update_channel.send(Err(Notification {
+            severity: Severity::WARNING,
+            content: format!(
+                "{} lines could not be parsed as {}",
+                unparsed_lines,
+                format.name()
+            ),
+            line: None,
+        }))?;
+    }
+    info!(
+        "done indexing {} in {} ms, sending Finished",
+        format.name(),
+        start.elapsed().as_millis()
+    );
+    update_channel.send(Ok(IndexingProgress::Finished))?;
+    Ok(())
+}
@@ -0,0 +1,177 @@
+#[cfg(test)]
+mod tests {
+    use crate::{
+        export::*,
+        search::{SearchFilter, SearchHolder},
+    };
+    use crossbeam_channel as cc;
+    use dlt::dlt_file::{dlt_messages, dlt_messages_with_bytes, STORAGE_HEADER_LENGTH};
+    use indexer_base::{
+        chunks::ChunkResults,
+        utils::{PLUGIN_ID_SENTINAL, ROW_NUMBER_SENTINAL},
+    };
+    use pretty_assertions::assert_eq;
+    use std::{fs, path::PathBuf};
+    use tempfile::tempdir;
+
+    fn timed_messages() -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../dlt/test_samples/timed_messages.dlt")
+    }
+
+    #[test]
+    fn test_sections_with_context() {
+        let sections: Vec<(usize, usize)> = sections_with_context(&[10, 1, 4, 20, 10], 1)
+            .sections
+            .iter()
+            .map(|s| (s.first_line, s.last_line))
+            .collect();
+        // 3..5 is adjacent to 0..2
+        assert_eq!(vec![(0, 5), (9, 11), (19, 21)], sections);
+        assert!(sections_with_context(&[], 3).sections.is_empty());
+    }
+
+    #[test]
+    fn test_export_search_result_of_session_file() {
+        let tmp_dir = tempdir().expect("could not create temp dir");
+        let session_file = tmp_dir.path().join("session.log");
+        let lines = ["start", "error: one", "ok", "ok", "error: \"two\", again"];
+        let content: String = lines
+            .iter()
+            .enumerate()
+            .map(|(i, l)| {
+                format!(
+                    "{}{}TAG{}{}{}{}\n",
+                    l,
+                    PLUGIN_ID_SENTINAL,
+                    PLUGIN_ID_SENTINAL,
+                    ROW_NUMBER_SENTINAL,
+                    i,
+                    ROW_NUMBER_SENTINAL
+                )
+            })
+            .collect();
+        fs::write(&session_file, content).unwrap();
+        let filters = [SearchFilter::new("error")];
+        let search = SearchHolder::new(&session_file, filters.iter());
+        let (result_file, matches, _) = search.execute_search().unwrap();
+        let rows = read_search_result(&result_file).unwrap();
+        assert_eq!(matched_rows(&matches), rows);
+
+        let export = |format: ExportFormat, context: usize| {
+            let destination = tmp_dir.path().join("export");
+            let (tx, _rx): (cc::Sender<ChunkResults>, cc::Receiver<ChunkResults>) = cc::unbounded();
+            let exported = export_rows(
+                &session_file,
+                &destination,
+                &rows,
+                context,
+                format,
+                None,
+                tx,
+            )
+            .unwrap();
+            (exported, fs::read_to_string(&destination).unwrap())
+        };
+        assert_eq!(
+            (2, "error: one\nerror: \"two\", again\n".to_string()),
+            export(ExportFormat::Text, 0)
+        );
+        assert_eq!(
+            (
+                5,
+                "row,content\n0,start\n1,error: one\n2,ok\n3,ok\n4,\"error: \"\"two\"\", again\"\n"
+                    .to_string()
+            ),
+            export(ExportFormat::Csv, 1)
+        );
+        assert_eq!(
+            (
+                2,
+                "{\"row\":1,\"content\":\"error: one\"}\n\
+                 {\"row\":4,\"content\":\"error: \\\"two\\\", again\"}\n"
+                    .to_string()
+            ),
+            export(ExportFormat::Json, 0)
+        );
+        let (tx, _rx): (cc::Sender<ChunkResults>, cc::Receiver<ChunkResults>) = cc::unbounded();
+        assert!(export_rows(
+            &session_file,
+            &tmp_dir.path().join("export.dlt"),
+            &rows,
+            0,
+            ExportFormat::Dlt,
+            None,
+            tx
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_export_rows_of_dlt_file() {
+        let tmp_dir = tempdir().expect("could not create temp dir");
+        let source = timed_messages();
+        let bytes = fs::read(&source).unwrap();
+        let first_message_len = dlt_messages(fs::File::open(&source).unwrap(), true)
+            .next()
+            .unwrap()
+            .0;
+        let export = |rows: &[u64], context: usize, format: ExportFormat| {
+            let destination = tmp_dir.path().join("export");
+            let (tx, _rx): (cc::Sender<ChunkResults>, cc::Receiver<ChunkResults>) = cc::unbounded();
+            let exported =
+                export_rows(&source, &destination, rows, context, format, None, tx).unwrap();
+            (exported, fs::read(&destination).unwrap())
+        };
+
+        // the section reaches past the last message
+        let (_, raw) = export(&[2], 1, ExportFormat::Dlt);
+        assert_eq!(bytes[first_message_len..].to_vec(), raw);
+
+        let (exported, csv) = export(&[0, 2], 0, ExportFormat::Csv);
+        assert_eq!(2, exported);
+        let csv = String::from_utf8(csv).unwrap();
+        let csv_lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(3, csv_lines.len());
+        assert_eq!(
+            "row,datetime,ecu,session,counter,timestamp,apid,ctid,type,payload",
+            csv_lines[0]
+        );
+        assert!(csv_lines[1].starts_with("0,2019-05-22T12:36:10"));
+        assert!(csv_lines[2].starts_with("2,2019-05-22T12:36:50"));
+
+        let (exported, json) = export(&[1], 0, ExportFormat::Json);
+        assert_eq!(1, exported);
+        let json: serde_json::Value =
+            serde_json::from_str(String::from_utf8(json).unwrap().trim()).unwrap();
+        assert_eq!(1, json["row"]);
+        assert!(json["payload"].as_str().unwrap().contains("dlt message 1"));
+    }
+
+    #[test]
+    fn test_export_rows_of_dlt_file_without_storage_headers() {
+        let tmp_dir = tempdir().expect("could not create temp dir");
+        let messages: Vec<Vec<u8>> =
+            dlt_messages_with_bytes(fs::File::open(timed_messages()).unwrap(), true)
+                .map(|(_, _, bytes)| bytes[STORAGE_HEADER_LENGTH..].to_vec())
+                .collect();
+        let source = tmp_dir.path().join("no_storage_header.dlt");
+        fs::write(&source, messages.concat()).unwrap();
+        let destination = tmp_dir.path().join("export");
+        let (tx, _rx): (cc::Sender<ChunkResults>, cc::Receiver<ChunkResults>) = cc::unbounded();
+        let exported = export_rows(
+            &source,
+            &destination,
+            &[0, 2],
+            0,
+            ExportFormat::Dlt,
+            None,
+            tx,
+        )
+        .unwrap();
+        assert_eq!(2, exported);
+        assert_eq!(
+            [messages[0].clone(), messages[2].clone()].concat(),
+            fs::read(&destination).unwrap()
+        );
+    }
+}
@@ -2,6 +2,7 @@
 mod tests {
     extern crate rand;
     use crate::{
+        dlt_source::{message_slots, DltSource},
         grabber::{
             identify_byte_range, identify_end_slot_simple, identify_start_slot,
             identify_start_slot_simple, ByteRange, FilePart, GrabMetadata, Grabber, LineRange,
@@ -361,4 +362,123 @@ mod tests {
         assert_eq!(c1, c2);
         Ok(())
     }
+
+    #[test]
+    fn test_get_multiline_entries() -> Result<()> {
+        use crate::parse::lookup_regex_for_format_str;
+        let entries: Vec<String> = (0..2000)
+            .map(|i| {
+                format!(
+                    "05-02-2019 12:{:02}:{:02}.000 entry {}\n\tat com.example.Foo.bar(Foo.java:{})\n\tat com.example.Foo.main(Foo.java:3)",
+                    i / 60 % 60,
+                    i % 60,
+                    i,
+                    i
+                )
+            })
+            .collect();
+        let p = write_content_to_tmp_file(&entries)?;
+        let regex = lookup_regex_for_format_str("MM-DD-YYYY hh:mm:ss.s")?;
+        let source = TextFileSource::new(&p, "sourceA").multiline(regex);
+        let grabber = Grabber::new(source)?;
+        assert_eq!(Some(2000), grabber.log_entry_count());
+        let metadata = grabber.metadata.as_ref().expect("no metadata");
+        assert!(metadata.slots.len() > 1);
+        let r = LineRange::from(995..=1005);
+        let grabbed = grabber
+            .get_entries(&r)?
+            .grabbed_elements
+            .into_iter()
+            .map(|e| e.content)
+            .collect::<Vec<String>>();
+        assert_eq!(grabbed, entries[995..=1005].to_vec());
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_entries_from_compressed_file() -> Result<()> {
+        use std::io::Write;
+        let lines: Vec<String> = (0..2000).map(|i| format!("line {}", i)).collect();
+        let tmp_dir = tempfile::tempdir()?;
+        let path = tmp_dir.path().join("compressed.log.gz");
+        let mut compressed = vec![];
+        for part in lines.chunks(1000) {
+            let mut encoder = flate2::write::GzEncoder::new(vec![], flate2::Compression::default());
+            for line in part {
+                writeln!(encoder, "{}", line)?;
+            }
+            compressed.extend(encoder.finish()?);
+        }
+        std::fs::write(&path, compressed)?;
+
+        let source = TextFileSource::new(&path, "sourceA");
+        assert_eq!(2000, source.count_lines()?);
+        let grabber = Grabber::new(source)?;
+        assert_eq!(Some(2000), grabber.log_entry_count());
+        let metadata = grabber.metadata.as_ref().expect("no metadata");
+        assert_eq!(0, metadata.checkpoints[0].decompressed);
+        let grabbed = grabber
+            .get_entries(&LineRange::from(995..=1005))?
+            .grabbed_elements
+            .into_iter()
+            .map(|e| e.content)
+            .collect::<Vec<String>>();
+        assert_eq!(grabbed, lines[995..=1005].to_vec());
+        Ok(())
+    }
+    #[test]
+    fn test_export_dlt_sections_with_metadata() -> Result<()> {
+        use dlt::dlt_file::{dlt_messages, export_as_dlt_file, export_as_dlt_file_with_slots};
+        use indexer_base::config::{IndexSection, SectionConfig};
+        let sample = std::fs::read(
+            std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+                .join("../dlt/test_samples/timed_messages.dlt"),
+        )?;
+        // 3 messages per copy, enough for several slots
+        let tmp_dir = tempfile::tempdir()?;
+        let path = tmp_dir.path().join("many.dlt");
+        std::fs::write(&path, sample.repeat(1200))?;
+
+        let grabber = Grabber::new(DltSource::new(&path, "sourceA"))?;
+        let metadata = grabber.metadata.as_ref().expect("no metadata");
+        let slots = message_slots(metadata)?;
+        assert!(slots.len() > 2);
+        assert_eq!(
+            3600,
+            slots
+                .last()
+                .map(|s| s.first_message + s.message_count)
+                .unwrap()
+        );
+
+        let sections = || SectionConfig {
+            sections: [(5, 7), (8, 8), (2000, 2002), (3598, 3700)]
+                .iter()
+                .map(|(first_line, last_line)| IndexSection {
+                    first_line: *first_line,
+                    last_line: *last_line,
+                })
+                .collect(),
+        };
+        let mut message_bytes = vec![];
+        let mut offset = 0;
+        for (consumed, _) in dlt_messages(std::io::Cursor::new(sample.clone()), true) {
+            message_bytes.push(&sample[offset..offset + consumed]);
+            offset += consumed;
+        }
+        let expected: Vec<u8> = (5..=8)
+            .chain(2000..=2002)
+            .chain(3598..3600)
+            .flat_map(|i| message_bytes[i % 3].to_vec())
+            .collect();
+
+        let scanned = tmp_dir.path().join("scanned.dlt");
+        let with_slots = tmp_dir.path().join("with_slots.dlt");
+        let (tx, _rx) = crossbeam_channel::unbounded();
+        export_as_dlt_file(path.clone(), scanned.clone(), sections(), tx.clone())?;
+        export_as_dlt_file_with_slots(path, with_slots.clone(), sections(), slots, None, tx)?;
+        assert_eq!(expected, std::fs::read(&scanned)?);
+        assert_eq!(expected, std::fs::read(&with_slots)?);
+        Ok(())
+    }
 }
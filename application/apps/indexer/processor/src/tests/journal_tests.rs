@@ -0,0 +1,103 @@
+#[cfg(test)]
+mod tests {
+    use crate::journal::*;
+    use anyhow::Result;
+    use crossbeam_channel::unbounded;
+    use indexer_base::{chunks::ChunkResults, config::IndexingConfig, progress::IndexingProgress};
+    use pretty_assertions::assert_eq;
+    use std::fs;
+    use tempfile::tempdir;
+
+    fn export_sample() -> Vec<u8> {
+        let mut sample: Vec<u8> = vec![];
+        sample.extend_from_slice(
+            b"__REALTIME_TIMESTAMP=1556800570000123\n\
+              _SYSTEMD_UNIT=network.service\n\
+              PRIORITY=4\n\
+              MESSAGE=a=b\n\
+              \n",
+        );
+        let binary_message = b"line 1\nline 2";
+        sample.extend_from_slice(b"__REALTIME_TIMESTAMP=1556800571000000\nMESSAGE\n");
+        sample.extend_from_slice(&(binary_message.len() as u64).to_le_bytes());
+        sample.extend_from_slice(binary_message);
+        sample.extend_from_slice(b"\n_PID=42\n\n");
+        sample
+    }
+
+    #[test]
+    fn test_read_journal_export() -> Result<()> {
+        let sample = export_sample();
+        let entries = JournalReader::new(&sample[..]).collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(2, entries.len());
+        assert_eq!(sample.len(), entries.iter().map(|(_, c)| c).sum::<usize>());
+
+        let first = entries[0].0.to_record();
+        assert_eq!(Some(1_556_800_570_000), first.timestamp);
+        assert_eq!(Some("WARNING".to_string()), first.level);
+        assert_eq!(Some("network.service".to_string()), first.source);
+        assert_eq!("a=b", first.message);
+
+        let second = entries[1].0.to_record();
+        assert_eq!("line 1\nline 2", second.message);
+        assert_eq!(None, second.source);
+        assert_eq!(Some(&"42".to_string()), second.fields.get("pid"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_truncated_binary_field() {
+        let sample = b"MESSAGE\n\x10\x00\x00\x00\x00\x00\x00\x00short\n";
+        let res = JournalReader::new(&sample[..]).next();
+        assert!(matches!(res, Some(Err(JournalError::Io(_)))));
+    }
+
+    #[test]
+    fn test_read_huge_binary_field() {
+        for size in [u64::MAX, 1 << 40] {
+            let mut sample = b"MESSAGE\n".to_vec();
+            sample.extend_from_slice(&size.to_le_bytes());
+            sample.extend_from_slice(b"short\n");
+            let res = JournalReader::new(&sample[..]).next();
+            assert!(matches!(res, Some(Err(JournalError::Malformed(_)))));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_index_journal_export() -> Result<()> {
+        let tmp_dir = tempdir()?;
+        let in_path = tmp_dir.path().join("journal.export");
+        let out_path = tmp_dir.path().join("journal.export.out");
+        fs::write(&in_path, export_sample())?;
+        let (tx, rx) = unbounded::<ChunkResults>();
+        create_index_and_mapping_journal(
+            IndexingConfig {
+                tag: "TAG".to_owned(),
+                chunk_size: 1,
+                in_file: in_path.clone(),
+                out_path: out_path.clone(),
+                append: false,
+                watch: false,
+            },
+            fs::metadata(&in_path)?.len(),
+            tx,
+            None,
+        )
+        .await?;
+        let mut chunks = vec![];
+        while let Ok(msg) = rx.recv() {
+            match msg {
+                Ok(IndexingProgress::GotItem { item }) => chunks.push(item),
+                Ok(IndexingProgress::Finished) => break,
+                _ => (),
+            }
+        }
+        assert_eq!(2, chunks.len());
+        let out = fs::read_to_string(&out_path)?;
+        let rows: Vec<&str> = out.lines().collect();
+        assert_eq!(2, rows.len());
+        assert!(rows[0].ends_with("\u{2}0\u{2}1556800570000\u{2}"));
+        assert!(rows[1].contains("line 1\u{6}line 2"));
+        Ok(())
+    }
+}
@@ -0,0 +1,137 @@
+#[cfg(test)]
+mod tests {
+    use crate::logcat::*;
+    use anyhow::Result;
+    use crossbeam_channel::unbounded;
+    use indexer_base::{chunks::ChunkResults, config::IndexingConfig, progress::IndexingProgress};
+    use pretty_assertions::assert_eq;
+    use std::fs;
+    use tempfile::tempdir;
+
+    fn binary_entry(
+        header_size: u16,
+        pid: i32,
+        sec: i32,
+        prio: u8,
+        tag: &str,
+        msg: &str,
+    ) -> Vec<u8> {
+        let mut payload = vec![prio];
+        payload.extend_from_slice(tag.as_bytes());
+        payload.push(0);
+        payload.extend_from_slice(msg.as_bytes());
+        payload.push(0);
+        let mut entry = vec![];
+        entry.extend_from_slice(&(payload.len() as u16).to_le_bytes());
+        entry.extend_from_slice(&header_size.to_le_bytes());
+        entry.extend_from_slice(&pid.to_le_bytes());
+        entry.extend_from_slice(&(pid + 1).to_le_bytes());
+        entry.extend_from_slice(&sec.to_le_bytes());
+        entry.extend_from_slice(&250_000_000i32.to_le_bytes());
+        let real_header_size = if header_size == 0 { 20 } else { header_size };
+        entry.resize(real_header_size as usize, 0);
+        entry.extend_from_slice(&payload);
+        entry
+    }
+
+    #[test]
+    fn test_parse_threadtime() -> Result<()> {
+        let entry = parse_threadtime_line(
+            "05-02 12:36:04.344  1234  1256 I ActivityManager: Start proc: com.example",
+            2019,
+        )?;
+        assert_eq!(
+            LogcatEntry {
+                timestamp: 1_556_800_564_344,
+                pid: 1234,
+                tid: 1256,
+                priority: "I".to_string(),
+                tag: "ActivityManager".to_string(),
+                message: "Start proc: com.example".to_string(),
+            },
+            entry
+        );
+        let padded_tag = parse_threadtime_line("05-02 12:36:04.344  1  2 W Tag     : msg", 2019)?;
+        assert_eq!("Tag", padded_tag.tag);
+        assert!(parse_threadtime_line("--------- beginning of main", 2019).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_binary_entries() -> Result<()> {
+        let mut sample = binary_entry(0, 100, 1_556_800_564, 4, "v1", "first");
+        sample.extend(binary_entry(28, 200, 1_556_800_565, 6, "v4", "second\n"));
+        let entries = LogcatBinaryReader::new(&sample[..]).collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(2, entries.len());
+        assert_eq!(sample.len(), entries.iter().map(|(_, c)| c).sum::<usize>());
+        let (first, _) = &entries[0];
+        assert_eq!(1_556_800_564_250, first.timestamp);
+        assert_eq!((100, 101), (first.pid, first.tid));
+        assert_eq!(
+            ("I", "v1", "first"),
+            (&first.priority[..], &first.tag[..], &first.message[..])
+        );
+        let (second, _) = &entries[1];
+        assert_eq!(
+            ("E", "v4", "second"),
+            (&second.priority[..], &second.tag[..], &second.message[..])
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_detect_logcat_format() -> Result<()> {
+        let tmp_dir = tempdir()?;
+        let text = tmp_dir.path().join("logcat.txt");
+        fs::write(
+            &text,
+            "--------- beginning of main\n05-02 12:36:04.344  1  2 W Tag: msg\n",
+        )?;
+        assert_eq!(LogcatFormat::Threadtime, detect_logcat_format(&text)?);
+        let binary = tmp_dir.path().join("logcat.bin");
+        fs::write(&binary, binary_entry(24, 1, 1, 4, "tag", "msg"))?;
+        assert_eq!(LogcatFormat::Binary, detect_logcat_format(&binary)?);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_index_threadtime_with_year() -> Result<()> {
+        let tmp_dir = tempdir()?;
+        let in_path = tmp_dir.path().join("logcat.txt");
+        let out_path = tmp_dir.path().join("logcat.txt.out");
+        fs::write(
+            &in_path,
+            "--------- beginning of main\n05-02 12:36:04.344  1234  1256 I AM: started\n",
+        )?;
+        let (tx, rx) = unbounded::<ChunkResults>();
+        create_index_and_mapping_logcat(
+            IndexingConfig {
+                tag: "TAG".to_owned(),
+                chunk_size: 10,
+                in_file: in_path.clone(),
+                out_path: out_path.clone(),
+                append: false,
+                watch: false,
+            },
+            fs::metadata(&in_path)?.len(),
+            Some(2019),
+            tx,
+            None,
+        )
+        .await?;
+        while let Ok(msg) = rx.recv() {
+            if let Ok(IndexingProgress::Finished) = msg {
+                break;
+            }
+        }
+        let out = fs::read_to_string(&out_path)?;
+        let rows: Vec<&str> = out.lines().collect();
+        assert_eq!(2, rows.len());
+        assert!(rows[0].starts_with("--------- beginning of main\u{3}"));
+        assert_eq!(
+            "2019-05-02 12:36:04.344 UTC\u{4}level=I\u{4}source=AM\u{4}started\u{4}pid=1234\u{4}tid=1256\u{3}TAG\u{3}\u{2}1\u{2}1556800564344\u{2}",
+            rows[1]
+        );
+        Ok(())
+    }
+}
@@ -1,4 +1,10 @@
 #[macro_use]
 mod parse_tests;
+mod export_tests;
 mod grabber_tests;
+mod journal_tests;
+mod logcat_tests;
 mod processor_tests;
+mod sort_tests;
+mod structured_tests;
+mod text_to_dlt_tests;
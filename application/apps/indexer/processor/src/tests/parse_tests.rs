@@ -847,10 +847,10 @@ mod tests {
     test_generator::test_expand_paths! { test_detect_regex; "processor/test_samples/detecting/*" }
 
     fn test_detect_regex(dir_name: &str) {
-        let in_path = PathBuf::from("..").join(&dir_name).join("in.log");
+        let in_path = PathBuf::from("..").join(dir_name).join("in.log");
         let res = detect_timestamp_format_in_file(&in_path).expect("could not detect regex type");
 
-        let mut format_path = PathBuf::from("..").join(&dir_name);
+        let mut format_path = PathBuf::from("..").join(dir_name);
         format_path.push("expected.format");
         let contents =
             fs::read_to_string(format_path).expect("Something went wrong reading the file");
@@ -41,6 +41,7 @@ mod tests {
             },
             source_file_size,
             false,
+            false,
             tx,
             None,
         )
@@ -103,6 +104,106 @@ mod tests {
             && row_pairs.iter().all(|&(p1, p2)| p1.1 + 1 == p2.0)
     }
 
+    #[tokio::test]
+    async fn test_multiline_entries() -> Result<()> {
+        let tmp_dir = tempdir().expect("could not create temp dir");
+        let in_path = tmp_dir.path().join("multiline.log");
+        let out_path = tmp_dir.path().join("multiline.log.out");
+        let restored_path = tmp_dir.path().join("multiline.log.restored");
+        let content = "05-02-2019 12:36:04.344 A0\n\
+                       05-02-2019 12:36:05.344 A1 exception\n\
+                       \tat com.example.Foo.bar(Foo.java:12)\n\
+                       \tat com.example.Foo.main(Foo.java:3)\n\
+                       05-02-2019 12:36:06.344 A2\n\
+                       05-02-2019 12:36:07.344 A3 {\n\
+                       \x20 \"time\": \"05-02-2019 12:36:07.344\"\n\
+                       }\n\
+                       05-02-2019 12:36:08.344 A4\n";
+        fs::write(&in_path, content)?;
+        let (tx, rx): (cc::Sender<ChunkResults>, cc::Receiver<ChunkResults>) = unbounded();
+        create_index_and_mapping(
+            IndexingConfig {
+                tag: "TAG".to_owned(),
+                chunk_size: 2,
+                in_file: in_path.clone(),
+                out_path: out_path.clone(),
+                append: false,
+                watch: false,
+            },
+            fs::metadata(&in_path)?.len(),
+            false,
+            true,
+            tx,
+            None,
+        )
+        .await?;
+        let mut chunks: Vec<Chunk> = vec![];
+        while let Ok(msg) = rx.recv() {
+            match msg {
+                Ok(IndexingProgress::GotItem { item: chunk }) => chunks.push(chunk),
+                Ok(IndexingProgress::Finished) => break,
+                _ => (),
+            }
+        }
+        let out_content = fs::read_to_string(&out_path)?;
+        assert_eq!(5, out_content.lines().count());
+        assert!(chunks_fit_together(&chunks));
+        assert_eq!((4, 4), chunks.last().expect("no chunks").r);
+
+        restore_original_from_indexed_file(&out_path, &restored_path)?;
+        assert_eq!(content, fs::read_to_string(&restored_path)?);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_index_gzip_compressed_file() -> Result<()> {
+        use std::io::Write;
+        let tmp_dir = tempdir().expect("could not create temp dir");
+        let in_path = tmp_dir.path().join("compressed.log.gz");
+        let out_path = tmp_dir.path().join("compressed.log.out");
+        let restored_path = tmp_dir.path().join("compressed.log.restored");
+        let content: String = (0..100)
+            .map(|i| format!("05-02-2019 12:36:{:02}.344 line {}\n", i % 60, i))
+            .collect();
+        let mut encoder = flate2::write::GzEncoder::new(vec![], flate2::Compression::default());
+        encoder.write_all(content.as_bytes())?;
+        fs::write(&in_path, encoder.finish()?)?;
+        let compressed_size = fs::metadata(&in_path)?.len();
+        let (tx, rx): (cc::Sender<ChunkResults>, cc::Receiver<ChunkResults>) = unbounded();
+        create_index_and_mapping(
+            IndexingConfig {
+                tag: "TAG".to_owned(),
+                chunk_size: 10,
+                in_file: in_path.clone(),
+                out_path: out_path.clone(),
+                append: false,
+                watch: false,
+            },
+            compressed_size,
+            false,
+            false,
+            tx,
+            None,
+        )
+        .await?;
+        let mut last_ticks = None;
+        while let Ok(msg) = rx.recv() {
+            match msg {
+                Ok(IndexingProgress::Progress { ticks }) => last_ticks = Some(ticks),
+                Ok(IndexingProgress::Finished) => break,
+                _ => (),
+            }
+        }
+        // progress is relative to the compressed size, not to the decompressed content
+        let (count, total) = last_ticks.expect("no progress");
+        assert_eq!(compressed_size, total);
+        assert!(count > 0 && count <= total);
+        assert_eq!(100, fs::read_to_string(&out_path)?.lines().count());
+        restore_original_from_indexed_file(&out_path, &restored_path)?;
+        assert_eq!(content, fs::read_to_string(&restored_path)?);
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_append_to_empty_output() -> Result<()> {
         let tmp_dir = tempdir().expect("could not create temp dir");
@@ -126,6 +227,7 @@ mod tests {
             },
             source_file_size,
             false,
+            false,
             tx,
             None,
         )
@@ -190,6 +292,7 @@ mod tests {
             },
             source_file_size,
             false,
+            false,
             tx,
             None,
         )
@@ -265,7 +368,7 @@ mod tests {
         println!("chunks: {:?}", chunks);
         println!(
             "content ({} bytes): {:02X?}",
-            content.as_bytes().len(),
+            content.len(),
             content.as_bytes()
         );
         assert_eq!(2, chunks.len());
@@ -299,7 +402,7 @@ mod tests {
     test_generator::test_expand_paths! { test_input_output; "processor/test_samples/indexing/*" }
 
     fn test_input_output(dir_name: &str) {
-        let in_path = PathBuf::from("..").join(&dir_name).join("in.txt");
+        let in_path = PathBuf::from("..").join(dir_name).join("in.txt");
         let tmp_dir = tempdir().expect("could not create temp dir");
         let out_file_path = tmp_dir.path().join("tmpTestFile.txt.out");
         let restored_file_path = tmp_dir.path().join("restoredTestFile.txt.out");
@@ -312,7 +415,7 @@ mod tests {
         // let last_in_ms = since_the_epoch.as_micros() as i64;
         // let out_file_path = home_dir.join(format!("{}_sample_test.test.out", last_in_ms));
 
-        let append_to_this = PathBuf::from("..").join(&dir_name).join("append_here.log");
+        let append_to_this = PathBuf::from("..").join(dir_name).join("append_here.log");
         let append_use_case = append_to_this.exists();
 
         if append_use_case {
@@ -335,6 +438,7 @@ mod tests {
                 },
                 fs::metadata(&in_path).expect("metadata not found").len(),
                 false,
+                false,
                 tx,
                 None,
             )
@@ -344,12 +448,12 @@ mod tests {
         let mut chunks: Vec<Chunk> = vec![];
         loop {
             match rx.recv() {
-                Ok(Ok(IndexingProgress::Finished { .. })) => {
+                Ok(Ok(IndexingProgress::Finished)) => {
                     trace!("finished...");
                     let out_file_content_bytes =
                         fs::read(&out_file_path).expect("could not read file");
                     let out_file_content = String::from_utf8_lossy(&out_file_content_bytes[..]);
-                    let expected_path = PathBuf::from("..").join(&dir_name).join("expected.output");
+                    let expected_path = PathBuf::from("..").join(dir_name).join("expected.output");
                     let expected_content_bytes =
                         fs::read(expected_path).expect("could not read expected file");
                     let expected_content = String::from_utf8_lossy(&expected_content_bytes[..]);
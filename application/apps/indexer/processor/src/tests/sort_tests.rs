@@ -0,0 +1,105 @@
+#[cfg(test)]
+mod tests {
+    use crate::sort::*;
+    use crossbeam_channel as cc;
+    use indexer_base::{
+        chunks::{Chunk, ChunkResults},
+        config::IndexingConfig,
+        progress::IndexingProgress,
+        utils::{PLUGIN_ID_SENTINAL, ROW_NUMBER_SENTINAL},
+    };
+    use pretty_assertions::assert_eq;
+    use std::fs;
+    use tempfile::tempdir;
+
+    /// sort `content` and return (content, original line number) of each row and the chunks
+    async fn sort(content: &str, run_size: usize) -> (Vec<(String, usize)>, Vec<Chunk>) {
+        let tmp_dir = tempdir().expect("could not create temp dir");
+        let in_file = tmp_dir.path().join("shuffled.log");
+        let out_path = tmp_dir.path().join("shuffled.log.out");
+        fs::write(&in_file, content).unwrap();
+        let (tx, rx): (cc::Sender<ChunkResults>, cc::Receiver<ChunkResults>) = cc::unbounded();
+        create_sorted_index_and_mapping(
+            IndexingConfig {
+                tag: "TAG".to_string(),
+                chunk_size: 2,
+                in_file: in_file.clone(),
+                out_path: out_path.clone(),
+                append: false,
+                watch: false,
+            },
+            content.len() as u64,
+            false,
+            run_size,
+            tx,
+            None,
+        )
+        .await
+        .expect("sorting should succeed");
+        let rows = fs::read_to_string(&out_path)
+            .unwrap()
+            .lines()
+            .map(|row| {
+                let mut parts = row.split(PLUGIN_ID_SENTINAL);
+                let content = parts.next().unwrap().to_string();
+                let line_nr = parts
+                    .nth(1)
+                    .unwrap()
+                    .split(ROW_NUMBER_SENTINAL)
+                    .nth(1)
+                    .unwrap()
+                    .parse()
+                    .unwrap();
+                (content, line_nr)
+            })
+            .collect();
+        let chunks = rx
+            .try_iter()
+            .filter_map(|item| match item {
+                Ok(IndexingProgress::GotItem { item }) => Some(item),
+                _ => None,
+            })
+            .collect();
+        // the runs were removed, only input, output and its chunk index are left
+        assert_eq!(3, fs::read_dir(tmp_dir.path()).unwrap().count());
+        (rows, chunks)
+    }
+
+    const SHUFFLED: &str = "\
+05-22-2019 12:36:04.300 third
+05-22-2019 12:36:04.100 first
+05-22-2019 12:36:04.300 fourth
+no timestamp, belongs to fourth
+05-22-2019 12:36:04.200 second
+05-22-2019 12:36:05.000 fifth
+";
+
+    fn expected() -> Vec<(String, usize)> {
+        vec![
+            ("05-22-2019 12:36:04.100 first".to_string(), 1),
+            ("05-22-2019 12:36:04.200 second".to_string(), 4),
+            ("05-22-2019 12:36:04.300 third".to_string(), 0),
+            ("05-22-2019 12:36:04.300 fourth".to_string(), 2),
+            ("no timestamp, belongs to fourth".to_string(), 3),
+            ("05-22-2019 12:36:05.000 fifth".to_string(), 5),
+        ]
+    }
+
+    #[tokio::test]
+    async fn test_sort_in_memory() {
+        let (rows, chunks) = sort(SHUFFLED, DEFAULT_RUN_SIZE).await;
+        assert_eq!(expected(), rows);
+        assert_eq!(3, chunks.len());
+        assert_eq!((0, 1), chunks[0].r);
+        assert_eq!((4, 5), chunks[2].r);
+    }
+
+    #[tokio::test]
+    async fn test_sort_in_runs_on_disk() {
+        // every entry gets a run of its own
+        let (rows, _) = sort(SHUFFLED, 1).await;
+        assert_eq!(expected(), rows);
+        let (rows, _) = sort(SHUFFLED, 100).await;
+        assert_eq!(expected(), rows);
+    }
+}
@@ -0,0 +1,172 @@
+#[cfg(test)]
+mod tests {
+    use crate::structured::*;
+    use anyhow::Result;
+    use crossbeam_channel::unbounded;
+    use indexer_base::{
+        chunks::ChunkResults, config::IndexingConfig, progress::IndexingProgress, utils,
+    };
+    use pretty_assertions::assert_eq;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_parse_json_line() -> Result<()> {
+        let record = JsonLinesParser.parse_record(
+            r#"{"time":"2021-03-04T10:11:12.123Z","level":"ERROR","logger":"db","msg":"connection lost","ctx":{"retry":3,"host":"a"}}"#,
+        )?;
+        assert_eq!(Some(1_614_852_672_123), record.timestamp);
+        assert_eq!(Some("ERROR".to_string()), record.level);
+        assert_eq!(Some("db".to_string()), record.source);
+        assert_eq!("connection lost", record.message);
+        assert_eq!(Some(&"3".to_string()), record.fields.get("ctx.retry"));
+        assert_eq!(Some(&"a".to_string()), record.fields.get("ctx.host"));
+        assert!(JsonLinesParser.parse_record("[1, 2]").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_json_numeric_timestamps() -> Result<()> {
+        let seconds = JsonLinesParser.parse_record(r#"{"ts":1614852672.5,"msg":"a"}"#)?;
+        assert_eq!(Some(1_614_852_672_500), seconds.timestamp);
+        let millis = JsonLinesParser.parse_record(r#"{"ts":1614852672123,"msg":"a"}"#)?;
+        assert_eq!(Some(1_614_852_672_123), millis.timestamp);
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_logfmt() -> Result<()> {
+        let record = LogfmtParser.parse_record(
+            r#"ts=2021-03-04T10:11:12Z level=info component=api msg="request \"done\"" status=200 cached"#,
+        )?;
+        assert_eq!(Some(1_614_852_672_000), record.timestamp);
+        assert_eq!(Some("info".to_string()), record.level);
+        assert_eq!(Some("api".to_string()), record.source);
+        assert_eq!("request \"done\"", record.message);
+        assert_eq!(Some(&"200".to_string()), record.fields.get("status"));
+        assert_eq!(Some(&"".to_string()), record.fields.get("cached"));
+        assert!(LogfmtParser.parse_record("just some text").is_err());
+        assert!(LogfmtParser.parse_record(r#"msg="unterminated"#).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_syslog() -> Result<()> {
+        let record = SyslogParser.parse_record(
+            r#"<165>1 2003-10-11T22:14:15.003Z mymachine.example.com evntslog - ID47 [exampleSDID@32473 iut="3" eventSource="Application"] An application event"#,
+        )?;
+        assert_eq!(Some(1_065_910_455_003), record.timestamp);
+        assert_eq!(Some("NOTICE".to_string()), record.level);
+        assert_eq!(Some("evntslog".to_string()), record.source);
+        assert_eq!("An application event", record.message);
+        assert_eq!(Some(&"20".to_string()), record.fields.get("facility"));
+        assert_eq!(
+            Some(&"mymachine.example.com".to_string()),
+            record.fields.get("host")
+        );
+        assert_eq!(Some(&"ID47".to_string()), record.fields.get("msgid"));
+        assert_eq!(None, record.fields.get("procid"));
+        assert_eq!(
+            Some(&"Application".to_string()),
+            record.fields.get("exampleSDID@32473.eventSource")
+        );
+
+        let no_sd = SyslogParser.parse_record("<34>1 - host su - - - 'su root' failed")?;
+        assert_eq!(None, no_sd.timestamp);
+        assert_eq!(Some("CRIT".to_string()), no_sd.level);
+        assert_eq!("'su root' failed", no_sd.message);
+        assert!(SyslogParser
+            .parse_record("<34>Oct 11 22:14:15 mymachine su: 'su root' failed")
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_detect_format() {
+        assert_eq!(
+            Some(StructuredFormat::Syslog),
+            StructuredFormat::detect("<34>1 - host su - - - message")
+        );
+        assert_eq!(
+            Some(StructuredFormat::JsonLines),
+            StructuredFormat::detect(r#"{"msg": "hello"}"#)
+        );
+        assert_eq!(
+            Some(StructuredFormat::Logfmt),
+            StructuredFormat::detect("level=info msg=hello")
+        );
+        assert_eq!(None, StructuredFormat::detect("hello world"));
+    }
+
+    #[test]
+    fn test_render_and_field_filter() -> Result<()> {
+        let record = LogfmtParser.parse_record("level=warn msg=\"a\\nb\" user=bob")?;
+        let rendered = record.render();
+        assert_eq!(
+            "-\u{4}level=warn\u{4}source=-\u{4}a\u{6}b\u{4}user=bob",
+            rendered
+        );
+        let tagged = format!("{}\u{3}TAG\u{3}\u{2}0\u{2}", rendered);
+        let filter = field_filter("user", "bob");
+        let regex = regex::Regex::new(&filter.value)?;
+        assert!(regex.is_match(&tagged));
+        let other = regex::Regex::new(&field_filter("user", "bo").value)?;
+        assert!(!other.is_match(&tagged));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_index_structured_file() -> Result<()> {
+        let tmp_dir = tempdir()?;
+        let in_path = tmp_dir.path().join("structured.log");
+        let out_path = tmp_dir.path().join("structured.log.out");
+        fs::write(
+            &in_path,
+            "{\"ts\":1000,\"msg\":\"first\"}\nnot json\n{\"ts\":3000,\"msg\":\"third\"}\n",
+        )?;
+        let (tx, rx) = unbounded::<ChunkResults>();
+        create_index_and_mapping_structured(
+            IndexingConfig {
+                tag: "TAG".to_owned(),
+                chunk_size: 2,
+                in_file: in_path.clone(),
+                out_path: out_path.clone(),
+                append: false,
+                watch: false,
+            },
+            fs::metadata(&in_path)?.len(),
+            StructuredFormat::JsonLines,
+            tx,
+            None,
+        )
+        .await?;
+        let mut warnings = 0;
+        while let Ok(msg) = rx.recv() {
+            match msg {
+                Ok(IndexingProgress::Finished) => break,
+                Err(_) => warnings += 1,
+                _ => (),
+            }
+        }
+        assert_eq!(1, warnings);
+        let out = fs::read_to_string(&out_path)?;
+        let timestamps: Vec<Option<i64>> = out
+            .lines()
+            .map(|l| {
+                l.rsplit(utils::ROW_NUMBER_SENTINAL)
+                    .nth(1)
+                    .and_then(|ts| ts.parse().ok())
+            })
+            .collect();
+        assert_eq!(
+            vec![Some(1_000_000), Some(1_000_000), Some(3_000_000)],
+            timestamps
+        );
+        assert!(out
+            .lines()
+            .nth(1)
+            .expect("no second line")
+            .starts_with("not json"));
+        Ok(())
+    }
+}
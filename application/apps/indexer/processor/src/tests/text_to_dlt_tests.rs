@@ -0,0 +1,126 @@
+#[cfg(test)]
+mod tests {
+    use crate::text_to_dlt::*;
+    use crossbeam_channel as cc;
+    use dlt::dlt_file::FileMessageProducer;
+    use dlt_core::{
+        dlt::{DltTimeStamp, LogLevel, Message, MessageType, PayloadContent, Value},
+        parse::ParsedMessage,
+    };
+    use indexer_base::chunks::VoidResults;
+    use pretty_assertions::assert_eq;
+    use std::{fs, path::Path};
+    use tempfile::tempdir;
+
+    const LOG: &str = "2019-05-22T12:36:10.250 ERROR [NAV1] route: failed to compute\n  \
+                       in step 3\n\
+                       2019-05-22T12:36:11.000 INFO [HMI] ui: started\n\
+                       no time here\n";
+
+    fn config(multiline: bool) -> TextToDltConfig {
+        TextToDltConfig {
+            format: Some("YYYY-MM-DDThh:mm:ss.s".to_string()),
+            offset: Some(0),
+            multiline,
+            app_id: IdRule {
+                pattern: Some(r"\[(\w+)\]".to_string()),
+                default: "NONE".to_string(),
+            },
+            context_id: IdRule {
+                pattern: Some(r"\] (\w+):".to_string()),
+                default: "CTX".to_string(),
+            },
+            log_levels: vec![LogLevelRule {
+                pattern: "ERROR".to_string(),
+                level: TextLogLevel::Error,
+            }],
+            ..Default::default()
+        }
+    }
+
+    fn convert(input: &Path, config: &TextToDltConfig) -> Vec<Message> {
+        let output = input.with_extension("dlt");
+        let (tx, _rx): (cc::Sender<VoidResults>, cc::Receiver<VoidResults>) = cc::unbounded();
+        let written = convert_text_to_dlt(input, &output, config, tx).unwrap();
+        let messages: Vec<Message> =
+            FileMessageProducer::new(fs::File::open(&output).unwrap(), None, true, None)
+                .map(|parsed| match parsed {
+                    ParsedMessage::Item(msg) => msg,
+                    other => panic!("unexpected {:?}", other),
+                })
+                .collect();
+        assert_eq!(written, messages.len());
+        messages
+    }
+
+    fn payload(msg: &Message) -> &str {
+        match &msg.payload {
+            PayloadContent::Verbose(args) => match &args[0].value {
+                Value::StringVal(s) => s,
+                other => panic!("unexpected {:?}", other),
+            },
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_convert_multiline_text_to_dlt() {
+        let tmp_dir = tempdir().unwrap();
+        let input = tmp_dir.path().join("app.log");
+        fs::write(&input, LOG).unwrap();
+        let messages = convert(&input, &config(true));
+        assert_eq!(2, messages.len());
+
+        let first = &messages[0];
+        assert_eq!(
+            "2019-05-22T12:36:10.250 ERROR [NAV1] route: failed to compute\n  in step 3",
+            payload(first)
+        );
+        let storage_header = first.storage_header.as_ref().unwrap();
+        assert_eq!(
+            DltTimeStamp::from_ms(1_558_528_570_250),
+            storage_header.timestamp
+        );
+        assert_eq!("ECU", storage_header.ecu_id);
+        let extended_header = first.extended_header.as_ref().unwrap();
+        assert_eq!(
+            ("NAV1", "rout", &MessageType::Log(LogLevel::Error), true),
+            (
+                extended_header.application_id.as_str(),
+                extended_header.context_id.as_str(),
+                &extended_header.message_type,
+                extended_header.verbose
+            )
+        );
+        let extended_header = messages[1].extended_header.as_ref().unwrap();
+        assert_eq!(
+            ("HMI", "ui", &MessageType::Log(LogLevel::Info)),
+            (
+                extended_header.application_id.as_str(),
+                extended_header.context_id.as_str(),
+                &extended_header.message_type,
+            )
+        );
+        assert_eq!(1, messages[1].header.message_counter);
+    }
+
+    #[test]
+    fn test_lines_without_time_get_previous_time() {
+        let tmp_dir = tempdir().unwrap();
+        let input = tmp_dir.path().join("app.log");
+        fs::write(&input, LOG).unwrap();
+        let messages = convert(&input, &config(false));
+        assert_eq!(4, messages.len());
+        let times: Vec<DltTimeStamp> = messages
+            .iter()
+            .map(|m| m.storage_header.as_ref().unwrap().timestamp.clone())
+            .collect();
+        assert_eq!(times[0], times[1]);
+        assert_eq!(times[2], times[3]);
+        assert_eq!("no time here", payload(&messages[3]));
+        assert_eq!(
+            "NONE",
+            messages[3].extended_header.as_ref().unwrap().application_id
+        );
+    }
+}
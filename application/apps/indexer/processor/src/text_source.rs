@@ -1,22 +1,32 @@
-use crate::grabber::{
-    identify_byte_range, ByteRange, GrabError, GrabMetadata, GrabbedContent, GrabbedElement,
-    LineRange, MetadataSource, Slot,
+use crate::{
+    entries::{line_starts_entry, EntryReader},
+    grabber::{
+        identify_byte_range, ByteRange, GrabError, GrabMetadata, GrabbedContent, GrabbedElement,
+        LineRange, MetadataSource, Slot,
+    },
 };
 use buf_redux::{policy::MinBuffered, BufReader as ReduxReader};
-use indexer_base::{progress::ComputationResult, utils};
+use indexer_base::{
+    compression::{self, InputFile},
+    progress::ComputationResult,
+    utils,
+};
+use regex::Regex;
 use std::{
-    fs,
-    io::{BufRead, Read, SeekFrom},
+    io::{BufRead, BufReader, Read},
     path::{Path, PathBuf},
 };
 
 const REDUX_READER_CAPACITY: usize = 1024 * 1024;
 const REDUX_MIN_BUFFER_SPACE: usize = 10 * 1024;
+/// approximate size of a slot when grouping multi-line entries
+const ENTRY_SLOT_SIZE: u64 = 64 * 1024;
 
 #[derive(Debug)]
 pub struct TextFileSource {
     source_id: String,
     path: PathBuf,
+    entry_start: Option<Regex>,
 }
 
 impl TextFileSource {
@@ -24,7 +34,65 @@ impl TextFileSource {
         Self {
             source_id: id.to_string(),
             path: PathBuf::from(p),
+            entry_start: None,
+        }
+    }
+
+    /// Group multi-line entries: lines that do not start with `entry_start`
+    /// belong to the previous entry. Slots never split such an entry and
+    /// line numbers refer to entries instead of physical lines.
+    pub fn multiline(mut self, entry_start: Regex) -> Self {
+        self.entry_start = Some(entry_start);
+        self
+    }
+
+    fn entries_from_file(
+        &self,
+        entry_start: &Regex,
+        shutdown_receiver: Option<cc::Receiver<()>>,
+    ) -> Result<ComputationResult<GrabMetadata>, GrabError> {
+        let f = InputFile::open(&self.path)?;
+        let mut reader = BufReader::with_capacity(REDUX_READER_CAPACITY, f);
+        let mut slots = Vec::<Slot>::new();
+        let mut byte_index = 0u64;
+        let mut entry_index = 0u64;
+        let mut slot_start_byte = 0u64;
+        let mut slot_start_entry = 0u64;
+        let mut buf = vec![];
+        loop {
+            buf.clear();
+            let len = reader.read_until(b'\n', &mut buf)? as u64;
+            if len == 0 {
+                break;
+            }
+            let s = unsafe { std::str::from_utf8_unchecked(&buf) };
+            if byte_index == 0 || line_starts_entry(s, entry_start) {
+                if byte_index - slot_start_byte >= ENTRY_SLOT_SIZE {
+                    if utils::check_if_stop_was_requested(shutdown_receiver.as_ref(), "grabber") {
+                        return Ok(ComputationResult::Stopped);
+                    }
+                    slots.push(Slot {
+                        bytes: ByteRange::from(slot_start_byte..=byte_index - 1),
+                        lines: LineRange::from(slot_start_entry..=entry_index - 1),
+                    });
+                    slot_start_byte = byte_index;
+                    slot_start_entry = entry_index;
+                }
+                entry_index += 1;
+            }
+            byte_index += len;
         }
+        if byte_index > slot_start_byte {
+            slots.push(Slot {
+                bytes: ByteRange::from(slot_start_byte..=byte_index - 1),
+                lines: LineRange::from(slot_start_entry..=entry_index - 1),
+            });
+        }
+        Ok(ComputationResult::Item(GrabMetadata {
+            slots,
+            line_count: entry_index as usize,
+            checkpoints: reader.get_ref().checkpoints(),
+        }))
     }
 }
 
@@ -38,23 +106,23 @@ impl MetadataSource for TextFileSource {
     }
 
     fn count_lines(&self) -> Result<usize, GrabError> {
+        if let Some(entry_start) = &self.entry_start {
+            let f = InputFile::open(&self.path)?;
+            let entries = EntryReader::new(BufReader::new(f), Some(entry_start.clone()));
+            return Ok(entries.count());
+        }
         let chunk_size = 100 * 1024usize;
-        let mut f = fs::File::open(&self.path)?;
+        let mut f = InputFile::open(&self.path)?;
         let mut count = 0usize;
         let mut buffer = vec![0; chunk_size];
 
         loop {
+            // decoders of compressed files may return less than requested before the end
             let n = f.read(&mut buffer)?;
-            if n < chunk_size {
-                buffer.resize(n, 0);
-            }
             if n == 0 {
                 break;
             }
-            count += bytecount::count(&buffer, b'\n');
-            if n < chunk_size {
-                break;
-            }
+            count += bytecount::count(&buffer[..n], b'\n');
         }
         Ok(count)
     }
@@ -63,7 +131,10 @@ impl MetadataSource for TextFileSource {
         &self,
         shutdown_receiver: Option<cc::Receiver<()>>,
     ) -> Result<ComputationResult<GrabMetadata>, GrabError> {
-        let f = fs::File::open(&self.path)?;
+        if let Some(entry_start) = &self.entry_start {
+            return self.entries_from_file(entry_start, shutdown_receiver);
+        }
+        let f = InputFile::open(&self.path)?;
         let mut slots = Vec::<Slot>::new();
         let mut byte_index = 0u64;
         let mut line_index = 0u64;
@@ -118,6 +189,7 @@ impl MetadataSource for TextFileSource {
         Ok(ComputationResult::Item(GrabMetadata {
             slots,
             line_count: line_index as usize,
+            checkpoints: reader.get_ref().checkpoints(),
         }))
     }
 
@@ -135,7 +207,6 @@ impl MetadataSource for TextFileSource {
                 context: "Get entries of empty range is invalid".to_string(),
             });
         }
-        use std::io::prelude::*;
         let file_part = identify_byte_range(&metadata.slots, line_range).ok_or_else(|| {
             GrabError::InvalidRange {
                 range: line_range.clone(),
@@ -148,15 +219,31 @@ impl MetadataSource for TextFileSource {
         //     file_part.total_lines - file_part.lines_to_skip - file_part.lines_to_drop
         // );
 
-        let mut read_buf = vec![0; file_part.length];
-        let mut read_from = fs::File::open(&self.path())?;
-        read_from.seek(SeekFrom::Start(file_part.offset_in_file))?;
+        let read_buf = compression::read_at(
+            self.path(),
+            &metadata.checkpoints,
+            file_part.offset_in_file,
+            file_part.length,
+        )?;
 
-        read_from.read_exact(&mut read_buf)?;
+        if let Some(entry_start) = &self.entry_start {
+            let entries = EntryReader::new(&read_buf[..], Some(entry_start.clone()));
+            let grabbed_elements = entries
+                .take(file_part.total_lines - file_part.lines_to_drop)
+                .skip(file_part.lines_to_skip)
+                .map(|entry| GrabbedElement {
+                    source_id: self.source_id.clone(),
+                    content: entry.lines.join("\n"),
+                    row: None,
+                    pos: None,
+                })
+                .collect::<Vec<GrabbedElement>>();
+            return Ok(GrabbedContent { grabbed_elements });
+        }
 
         let s = unsafe { std::str::from_utf8_unchecked(&read_buf) };
 
-        let all_lines = s.split(|c| c == '\n');
+        let all_lines = s.split('\n');
         let lines_minus_end = all_lines.take(file_part.total_lines - file_part.lines_to_drop);
         let pure_lines = lines_minus_end.skip(file_part.lines_to_skip);
         let grabbed_elements = pure_lines
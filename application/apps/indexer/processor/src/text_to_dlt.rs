@@ -0,0 +1,271 @@
+// Copyright (c) 2019 E.S.R.Labs. All rights reserved.
+//
+// NOTICE:  All information contained herein is, and remains
+// the property of E.S.R.Labs and its suppliers, if any.
+// The intellectual and technical concepts contained herein are
+// proprietary to E.S.R.Labs and its suppliers and may be covered
+// by German and Foreign Patents, patents in process, and are protected
+// by trade secret or copyright law.
+// Dissemination of this information or reproduction of this material
+// is strictly forbidden unless prior written permission is obtained
+// from E.S.R.Labs.
+use crate::{
+    entries::EntryReader,
+    parse::{detect_timestamp_format_in_file, line_to_timed_line, lookup_regex_for_format_str},
+};
+use anyhow::Result;
+use crossbeam_channel as cc;
+use dlt_core::dlt::{
+    Argument, DltTimeStamp, Endianness, ExtendedHeaderConfig, LogLevel, Message, MessageConfig,
+    MessageType, PayloadContent, StorageHeader, StringCoding, TypeInfo, TypeInfoKind, Value,
+};
+use indexer_base::{
+    chunks::VoidResults, compression::InputFile, error_reporter::Reporter, progress::*, utils,
+};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::{
+    io::{BufReader, BufWriter, Write},
+    path::Path,
+};
+
+/// the longest string that still fits into a message with ecu id and extended header
+const MAX_STRING_LENGTH: usize = u16::MAX as usize - 4 - 4 - 10 - 4 - 2 - 1;
+const PROGRESS_THRESHOLD: usize = 1024 * 1024;
+
+/// Where an id (ECU, APID or CTID) of the messages comes from: the first capture
+/// group (or the whole match) of `pattern` in the line, `default` if it does not match.
+/// Ids are cut to 4 ASCII characters.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct IdRule {
+    pub pattern: Option<String>,
+    pub default: String,
+}
+
+impl IdRule {
+    fn fixed(id: &str) -> Self {
+        IdRule {
+            pattern: None,
+            default: id.to_string(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum TextLogLevel {
+    Fatal,
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Verbose,
+}
+
+impl From<TextLogLevel> for LogLevel {
+    fn from(level: TextLogLevel) -> Self {
+        match level {
+            TextLogLevel::Fatal => LogLevel::Fatal,
+            TextLogLevel::Error => LogLevel::Error,
+            TextLogLevel::Warn => LogLevel::Warn,
+            TextLogLevel::Info => LogLevel::Info,
+            TextLogLevel::Debug => LogLevel::Debug,
+            TextLogLevel::Verbose => LogLevel::Verbose,
+        }
+    }
+}
+
+/// lines matching `pattern` get `level`, the first matching rule wins
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct LogLevelRule {
+    pub pattern: String,
+    pub level: TextLogLevel,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct TextToDltConfig {
+    /// format of the timestamps, detected from the file if not set
+    pub format: Option<String>,
+    /// used if the timestamps have no year
+    pub year: Option<i32>,
+    /// offset in ms, used instead of a timezone
+    pub offset: Option<i64>,
+    /// lines that don't start with a timestamp belong to the previous message
+    pub multiline: bool,
+    pub ecu_id: IdRule,
+    pub app_id: IdRule,
+    pub context_id: IdRule,
+    pub log_levels: Vec<LogLevelRule>,
+    /// level for lines that match no rule
+    pub default_level: TextLogLevel,
+}
+
+impl Default for TextToDltConfig {
+    fn default() -> Self {
+        TextToDltConfig {
+            format: None,
+            year: None,
+            offset: None,
+            multiline: false,
+            ecu_id: IdRule::fixed("ECU"),
+            app_id: IdRule::fixed("TEXT"),
+            context_id: IdRule::fixed("LOG"),
+            log_levels: vec![],
+            default_level: TextLogLevel::Info,
+        }
+    }
+}
+
+struct CompiledIdRule {
+    regex: Option<Regex>,
+    default: String,
+}
+
+impl CompiledIdRule {
+    fn new(rule: &IdRule) -> Result<Self> {
+        Ok(CompiledIdRule {
+            regex: match &rule.pattern {
+                Some(pattern) => Some(Regex::new(pattern)?),
+                None => None,
+            },
+            default: dlt_id(&rule.default),
+        })
+    }
+
+    fn id_for(&self, line: &str) -> String {
+        self.regex
+            .as_ref()
+            .and_then(|regex| regex.captures(line))
+            .and_then(|caps| caps.get(1).or_else(|| caps.get(0)))
+            .map(|id| dlt_id(id.as_str()))
+            .unwrap_or_else(|| self.default.clone())
+    }
+}
+
+fn dlt_id(id: &str) -> String {
+    id.chars().filter(|c| c.is_ascii()).take(4).collect()
+}
+
+/// the longest prefix of `s` that fits into a dlt string argument
+fn truncate_to_fit(s: &mut String) {
+    if s.len() > MAX_STRING_LENGTH {
+        let mut end = MAX_STRING_LENGTH;
+        while !s.is_char_boundary(end) {
+            end -= 1;
+        }
+        s.truncate(end);
+    }
+}
+
+/// Convert a text log with timestamps into a dlt file.
+///
+/// Every line (or entry with `multiline`) becomes a verbose log message with the
+/// line as string argument. The storage header carries the time extracted from the line,
+/// lines without a readable time get the time of the previous line.
+/// Returns the number of written messages.
+pub fn convert_text_to_dlt(
+    input: &Path,
+    output: &Path,
+    config: &TextToDltConfig,
+    update_channel: cc::Sender<VoidResults>,
+) -> Result<usize> {
+    let format = match &config.format {
+        Some(format) => format.clone(),
+        None => detect_timestamp_format_in_file(input)?,
+    };
+    let regex = lookup_regex_for_format_str(&format)?;
+    let ecu_id = CompiledIdRule::new(&config.ecu_id)?;
+    let app_id = CompiledIdRule::new(&config.app_id)?;
+    let context_id = CompiledIdRule::new(&config.context_id)?;
+    let log_levels = config
+        .log_levels
+        .iter()
+        .map(|rule| Ok((Regex::new(&rule.pattern)?, rule.level)))
+        .collect::<Result<Vec<(Regex, TextLogLevel)>>>()?;
+
+    let input_size = input.metadata()?.len();
+    let entry_start = if config.multiline {
+        Some(regex.clone())
+    } else {
+        None
+    };
+    let entries = EntryReader::new(BufReader::new(InputFile::open(input)?), entry_start);
+    let (out_file, _) = utils::get_out_file_and_size(false, output)?;
+    let mut out = BufWriter::new(out_file);
+    let mut reporter = Reporter::default();
+
+    let mut last_timestamp = 0i64;
+    let mut written = 0usize;
+    let mut processed_bytes = 0usize;
+    let mut unreported_bytes = 0usize;
+    for (line_nr, entry) in entries.enumerate() {
+        let first_line = &entry.lines[0];
+        if let Ok(timed_line) = line_to_timed_line(
+            first_line,
+            entry.consumed,
+            "",
+            &regex,
+            config.year,
+            config.offset,
+            line_nr,
+            &mut reporter,
+        ) {
+            last_timestamp = timed_line.timestamp;
+        }
+        let level = log_levels
+            .iter()
+            .find(|(regex, _)| regex.is_match(first_line))
+            .map(|(_, level)| *level)
+            .unwrap_or(config.default_level);
+        let ecu = ecu_id.id_for(first_line);
+        let mut content = entry.lines.join("\n");
+        truncate_to_fit(&mut content);
+        let message = Message::new(
+            MessageConfig {
+                version: 1,
+                counter: (written % 256) as u8,
+                endianness: Endianness::Big,
+                ecu_id: Some(ecu.clone()),
+                session_id: None,
+                timestamp: None,
+                payload: PayloadContent::Verbose(vec![Argument {
+                    type_info: TypeInfo {
+                        kind: TypeInfoKind::StringType,
+                        coding: StringCoding::UTF8,
+                        has_variable_info: false,
+                        has_trace_info: false,
+                    },
+                    name: None,
+                    unit: None,
+                    fixed_point: None,
+                    value: Value::StringVal(content),
+                }]),
+                extended_header_info: Some(ExtendedHeaderConfig {
+                    message_type: MessageType::Log(level.into()),
+                    app_id: app_id.id_for(first_line),
+                    context_id: context_id.id_for(first_line),
+                }),
+            },
+            Some(StorageHeader {
+                timestamp: DltTimeStamp::from_ms(last_timestamp.max(0) as u64),
+                ecu_id: ecu,
+            }),
+        );
+        out.write_all(&message.as_bytes())?;
+        written += 1;
+
+        processed_bytes += entry.consumed;
+        unreported_bytes += entry.consumed;
+        if unreported_bytes > PROGRESS_THRESHOLD {
+            unreported_bytes = 0;
+            let _ = update_channel.send(Ok(IndexingProgress::Progress {
+                ticks: (processed_bytes as u64, input_size),
+            }));
+        }
+    }
+    out.flush()?;
+    reporter.flush();
+    let _ = update_channel.send(Ok(IndexingProgress::Finished));
+    Ok(written)
+}